@@ -1,23 +1,66 @@
-use crate::util::{AppState, Picture, APP_AUTHORS, APP_ICON, APP_NAME, APP_TITLE, APP_VERSION};
+use crate::{
+  config::Config,
+  theme::Color,
+  update::{self, Release},
+  util::{AppState, Picture, APP_AUTHORS, APP_ICON, APP_NAME, APP_TITLE, APP_VERSION},
+};
+use chrono::{Duration, Local, NaiveDateTime};
 use eframe::{egui, emath::Align2, epaint::Color32};
-use egui::{Context, Key, RichText, Window};
+use egui::{Button, Context, Key, OpenUrl, RichText, Spinner, Window};
+use futures::{channel::mpsc, executor::ThreadPool};
+use std::borrow::Cow;
 
 pub struct AboutDlg {
+  config: Config,
   logo: Picture,
   state: AppState,
+  threads: ThreadPool,
+  tx: mpsc::UnboundedSender<Message>,
+  rx: mpsc::UnboundedReceiver<Message>,
+  update: Option<UpdateStatus>,
+
+  /// When the next automatic check is due, if they're enabled.
+  next_check: Option<NaiveDateTime>,
+
+  /// Open the dialog when the UI is no longer disabled (an automatic check found a new release).
+  notify: bool,
   visible: bool,
 }
 
 impl AboutDlg {
-  pub fn new(state: AppState) -> Self {
+  pub fn new(config: Config, state: AppState, threads: ThreadPool) -> Self {
+    let (tx, rx) = mpsc::unbounded();
+    let next_check = get_next_check(&config);
     Self {
+      config,
       logo: Picture::new(format!("{APP_NAME}_logo"), APP_ICON),
       state,
+      threads,
+      tx,
+      rx,
+      update: None,
+      next_check,
+      notify: false,
       visible: false,
     }
   }
 
   pub fn show(&mut self, ctx: &Context) -> bool {
+    self.check_messages();
+    if self
+      .next_check
+      .is_some_and(|next_check| Local::now().naive_local() >= next_check)
+    {
+      self.next_check = None;
+      self.check_for_update(ctx, true);
+    }
+
+    // Show the new release once nothing else is using the UI.
+    if self.notify && !self.visible && !self.state.is_disabled() {
+      self.notify = false;
+      self.open();
+    }
+
     if self.visible {
       self.handle_hotkeys(ctx);
 
@@ -39,6 +82,42 @@ impl AboutDlg {
             ui.label(RichText::new(APP_TITLE).heading().color(Color32::GOLD));
             ui.label(format!("Version {APP_VERSION}"));
             ui.label(format!("Copyright © 2022-present {APP_AUTHORS}"));
+            ui.add_space(4.0);
+
+            // Show where the settings are stored so that portable mode can be confirmed.
+            let mode = if self.config.is_portable() {
+              "Portable"
+            } else {
+              "Standard"
+            };
+            ui.label(format!("Settings ({mode}): {}", self.config.path().display()));
+
+            match &self.update {
+              Some(UpdateStatus::Checking) => {
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                  ui.add(Spinner::new());
+                  ui.label("Checking for updates...");
+                });
+              }
+              Some(UpdateStatus::UpToDate) => {
+                ui.add_space(4.0);
+                ui.label("You're up to date");
+              }
+              Some(UpdateStatus::Available(release)) => {
+                ui.add_space(4.0);
+                let text = format!("Version {} is available", release.version);
+                ui.label(RichText::from(text).color(Color32::GOLD));
+                if ui.button("Open Release Page").on_hover_text(&release.url).clicked() {
+                  ctx.open_url(OpenUrl::new_tab(&release.url));
+                }
+              }
+              Some(UpdateStatus::Failed(err)) => {
+                ui.add_space(4.0);
+                ui.label(RichText::from(err.as_ref()).color(Color::Error.get()));
+              }
+              None => (),
+            }
           });
           ui.add_space(8.0);
           ui.separator();
@@ -46,6 +125,20 @@ impl AboutDlg {
             if ui.button("Close").clicked() {
               self.close();
             }
+
+            let enabled = !matches!(self.update, Some(UpdateStatus::Checking));
+            if ui.add_enabled(enabled, Button::new("Check for Updates")).clicked() {
+              self.check_for_update(ctx, false);
+            }
+
+            let mut check = self.config.get_update_check();
+            let response = ui
+              .checkbox(&mut check, "Check Weekly")
+              .on_hover_text("Check for a new release once a week");
+            if response.changed() {
+              self.config.set_update_check(check);
+              self.next_check = get_next_check(&self.config);
+            }
           });
         });
       if !open {
@@ -70,9 +163,89 @@ impl AboutDlg {
     }
   }
 
+  /// Fetch the latest release on a pooled thread. Failures of automatic checks aren't shown.
+  fn check_for_update(&mut self, ctx: &Context, automatic: bool) {
+    if !automatic {
+      self.update = Some(UpdateStatus::Checking);
+    }
+
+    let tx = self.tx.clone();
+    let ctx = ctx.clone();
+    let future = async move {
+      let msg = Message {
+        automatic,
+        result: update::get_latest_release(),
+      };
+      tx.unbounded_send(msg).unwrap();
+      ctx.request_repaint();
+    };
+
+    self.threads.spawn_ok(future);
+  }
+
+  fn check_messages(&mut self) {
+    while let Ok(msg) = self.rx.try_recv() {
+      let now = Local::now().naive_local();
+      let release = match msg.result {
+        Ok(release) => release,
+        Err(err) => {
+          if msg.automatic {
+            // Try again later.
+            if self.config.get_update_check() {
+              self.next_check = Some(now + Duration::hours(RETRY_HOURS));
+            }
+          } else {
+            self.update = Some(UpdateStatus::Failed(err));
+          }
+          continue;
+        }
+      };
+
+      self.config.set_update_checked(now);
+      self.next_check = get_next_check(&self.config);
+
+      if update::is_newer(&release.version, APP_VERSION) == Some(true) {
+        self.notify = msg.automatic && !self.visible;
+        self.update = Some(UpdateStatus::Available(release));
+      } else if !msg.automatic {
+        self.update = Some(UpdateStatus::UpToDate);
+      }
+    }
+  }
+
   fn handle_hotkeys(&mut self, ctx: &Context) {
     if ctx.input(|state| state.key_pressed(Key::Escape)) {
       self.close();
     }
   }
 }
+
+struct Message {
+  automatic: bool,
+  result: Result<Release, Cow<'static, str>>,
+}
+
+enum UpdateStatus {
+  Checking,
+  UpToDate,
+  Available(Release),
+  Failed(Cow<'static, str>),
+}
+
+/// Get when the next automatic check is due, or `None` if automatic checks are disabled.
+fn get_next_check(config: &Config) -> Option<NaiveDateTime> {
+  if !config.get_update_check() {
+    return None;
+  }
+
+  match config.get_update_checked() {
+    Some(checked) => Some(checked + Duration::days(CHECK_DAYS)),
+    None => Some(NaiveDateTime::MIN),
+  }
+}
+
+/// Days between automatic checks for a new release.
+const CHECK_DAYS: i64 = 7;
+
+/// Hours to wait before retrying a failed automatic check.
+const RETRY_HOURS: i64 = 1;
@@ -5,42 +5,44 @@ use crate::{
   confirm_dlg::{Choice, ConfirmDlg, Hence},
   experience::Experience,
   farming::Farming,
+  folders_dlg::FoldersDlg,
+  game_folders::GameFolder,
+  keymap::{Action, Keymap},
+  keymap_dlg::KeymapDlg,
+  log_watcher::LogWatcher,
+  message_dlg::MessageDlg,
   offline::Offline,
+  override_dlg::OverrideDlg,
+  skill_info,
   stats::{Stats, StatsFilter},
-  util,
+  theme, util,
 };
 use eframe::{
   egui::{
-    menu, Button, CentralPanel, Context, CursorIcon, Event, Frame, Key, Margin, TextWrapMode, TopBottomPanel, Ui,
+    menu, Button, CentralPanel, Context, CursorIcon, DragValue, Event, Frame, Margin, TextWrapMode, TopBottomPanel, Ui,
     ViewportCommand, Visuals,
   },
   emath::Align2,
   epaint, glow,
 };
-use epaint::{Color32, Vec2};
+use epaint::{Color32, Pos2, Rect, Vec2};
 use futures::executor::ThreadPoolBuilder;
-use std::{ffi::OsStr, path::Path};
+use std::{
+  ffi::OsStr,
+  path::{Path, PathBuf},
+};
 use util::{AppState, Page};
 
-#[cfg(target_os = "macos")]
-macro_rules! cmd {
-  ($key:literal) => {
-    concat!("⌘ + ", $key)
-  };
-}
-
-#[cfg(not(target_os = "macos"))]
-macro_rules! cmd {
-  ($key:literal) => {
-    concat!("Ctrl + ", $key)
-  };
-}
-
 pub struct App {
   // State.
   config: Config,
   state: AppState,
   page: Page,
+  window_size: Option<Vec2>,
+  window_pos: Option<(Pos2, Vec2)>,
+  check_window_pos: bool,
+  keymap: Keymap,
+  watcher: LogWatcher,
 
   // Tab pages.
   chronometer: Chronometer,
@@ -52,15 +54,37 @@ pub struct App {
   // Dialogs.
   about_dlg: AboutDlg,
   confirm_dlg: ConfirmDlg,
+  hence: Option<Hence>,
+  folders_dlg: FoldersDlg,
+  keymap_dlg: KeymapDlg,
+  message_dlg: MessageDlg,
+  override_dlg: OverrideDlg,
   file_dlg: Option<egui_file::FileDialog>,
+  file_request: Option<FileRequest>,
 }
 
 impl App {
+  /// Default and minimum inner window size.
   pub const fn inner_window_size() -> Vec2 {
     epaint::vec2(480.0, 640.0)
   }
 
-  pub fn new(cc: &eframe::CreationContext<'_>, config: Config) -> Self {
+  /// Check if a remembered window position could be on a screen.
+  pub fn is_window_pos_sane(pos: (f32, f32)) -> bool {
+    // Same limit that eframe uses when it can't get the monitor sizes.
+    const MAX_POS: f32 = 16000.0;
+    pos.0.abs() < MAX_POS && pos.1.abs() < MAX_POS
+  }
+
+  /// Check if enough of the window is on the monitor for it to be moved with the mouse. Window positions are relative
+  /// to the top-left corner of the main monitor.
+  fn is_on_monitor(outer_rect: Rect, monitor_size: Vec2) -> bool {
+    const MIN_VISIBLE: f32 = 32.0;
+    let visible = outer_rect.intersect(Rect::from_min_size(Pos2::ZERO, monitor_size));
+    visible.width() >= MIN_VISIBLE && visible.height() >= MIN_VISIBLE
+  }
+
+  pub fn new(cc: &eframe::CreationContext<'_>, mut config: Config) -> Self {
     egui_extras::install_image_loaders(&cc.egui_ctx);
 
     cc.egui_ctx.set_visuals(Visuals::dark());
@@ -81,32 +105,93 @@ impl App {
     let threads = ThreadPoolBuilder::new().pool_size(count).create().unwrap();
 
     // State.
+    theme::set_color_blind(config.get_color_blind());
     let locale = util::get_locale();
     let state = AppState::default();
     let page = config.get_page().unwrap_or(Page::Chronometer);
+    let keymap = config.get_keymap();
+    let check_window_pos = config
+      .get_window_pos()
+      .is_some_and(|(pos, _)| App::is_window_pos_sane(pos));
+
+    // The skill data override is given to the pages that parse the skills.
+    let override_path = config.skills_override_path();
+    let (skill_override, override_error) = match skill_info::load_override(&override_path) {
+      Ok(skill_override) => (skill_override, None),
+      Err(err) => (Default::default(), Some(err)),
+    };
 
     // Tab pages.
-    let log_path = config.get_log_path().unwrap_or_default();
-    let mut chronometer = Chronometer::new(threads.clone(), state.clone());
-    let experience = Experience::new(log_path.clone(), threads.clone(), config.clone(), state.clone(), locale);
+    let log_paths = config.get_log_paths();
+    let mut chronometer = Chronometer::new(threads.clone(), config.clone(), state.clone());
+    let mut experience = Experience::new(
+      log_paths.clone(),
+      threads.clone(),
+      config.clone(),
+      state.clone(),
+      locale,
+      skill_override,
+    );
     let farming = Farming::new(cc.egui_ctx.clone(), config.clone(), state.clone());
-    let offline = Offline::new(state.clone());
-    let stats = Stats::new(log_path, threads, config.clone(), state.clone(), locale);
-
-    if page == Page::Chronometer {
-      // Start the chronometer timer.
-      chronometer.start_timer(cc.egui_ctx.clone());
-    }
+    let offline = Offline::new(
+      cc.egui_ctx.clone(),
+      threads.clone(),
+      config.clone(),
+      state.clone(),
+      skill_override,
+    );
+    let mut stats = Stats::new(
+      log_paths.clone(),
+      threads.clone(),
+      config.clone(),
+      state.clone(),
+      locale,
+    );
+
+    // The pages that refresh from the chat logs share a watcher.
+    let watcher = LogWatcher::new(log_paths);
+    experience.watch_logs(&cc.egui_ctx, &watcher);
+    stats.watch_logs(&cc.egui_ctx, &watcher);
+
+    chronometer.set_active(&cc.egui_ctx, page == Page::Chronometer);
+    experience.set_active(page == Page::Experience);
 
     // Dialog windows.
-    let about_dlg = AboutDlg::new(state.clone());
+    let about_dlg = AboutDlg::new(config.clone(), state.clone(), threads);
     let confirm_dlg = ConfirmDlg::new(state.clone());
+    let mut folders_dlg = FoldersDlg::new(state.clone());
+    let keymap_dlg = KeymapDlg::new(state.clone());
+    let mut message_dlg = MessageDlg::new(state.clone());
+    let mut override_dlg = OverrideDlg::new(state.clone(), override_path, override_error.clone(), skill_override);
     let file_dlg = None;
 
+    // Report a malformed skill data override.
+    if override_error.is_some() {
+      override_dlg.open();
+    }
+
+    // Report a damaged configuration file.
+    if let Some(warning) = config.take_warning() {
+      message_dlg.open("Settings", warning);
+    }
+
+    // Offer the detected game folders on the first run if the chat logs aren't in the standard location.
+    if !config.get_folders_offered() {
+      config.set_folders_offered();
+      if !config.has_log_path() && !config.has_default_log_path() {
+        folders_dlg.open_if_found();
+      }
+    }
+
     App {
       config,
       state,
       page,
+      window_size: None,
+      window_pos: None,
+      check_window_pos,
+      keymap,
+      watcher,
       chronometer,
       experience,
       farming,
@@ -114,20 +199,30 @@ impl App {
       stats,
       about_dlg,
       confirm_dlg,
+      hence: None,
+      folders_dlg,
+      keymap_dlg,
+      message_dlg,
+      override_dlg,
       file_dlg,
+      file_request: None,
     }
   }
 
   fn handle_input(&mut self, ctx: &Context) -> bool {
     let mut close_status = CloseStatus::None;
+    let mut switch_page = None;
     let mut handled = false;
+
+    // Leave the keys to text fields that are being edited.
+    let text_input = ctx.wants_keyboard_input();
     ctx.input(|state| {
       if state.viewport().close_requested() {
         if self.offline.changed() {
           self.offline.on_close_event();
           if !self.confirm_dlg.visible() {
             let file_name = self.offline.file_name().unwrap();
-            self.confirm_dlg.open(file_name, Hence::Exit);
+            self.confirm_dlg.open(file_name, self.offline.changes(), Hence::Exit);
           }
           close_status = CloseStatus::CancelClose;
         }
@@ -143,52 +238,21 @@ impl App {
           modifiers,
         } = event
         {
-          if *pressed && !*repeat && !self.state.is_disabled() {
-            match key {
-              Key::Escape if self.page == Page::Stats && !self.stats.filter().is_none() => {
-                self.stats.set_filter(StatsFilter::None);
-                handled = true;
-              }
-              Key::D if modifiers.command_only() && self.page == Page::Stats && !self.stats.avatar().is_empty() => {
-                self.stats.show_dps_dlg();
-                handled = true;
-              }
-              Key::F if modifiers.command_only() && self.page == Page::Stats && !self.stats.stats().is_empty() => {
-                self.stats.show_filter_dlg();
-                handled = true;
-              }
-              Key::L if modifiers.command_only() && self.page == Page::Stats && !self.stats.avatar().is_empty() => {
-                self.stats.show_search_dlg();
-                handled = true;
-              }
-              Key::Q if modifiers.command_only() => {
-                close_status = CloseStatus::Close;
-                handled = true;
-              }
-              Key::R
-                if modifiers.command_only()
-                  && self.page == Page::Stats
-                  && !self.stats.stats().is_empty()
-                  && !self.stats.filter().is_resists() =>
-              {
-                self.stats.set_filter(StatsFilter::Resists);
-                handled = true;
-              }
-              Key::S if modifiers.command_only() && self.offline.changed() => {
-                self.offline.store();
-                handled = true;
-              }
-              Key::F5 if self.page == Page::Stats => {
-                self.stats.reload(ctx);
+          if *pressed && !*repeat && !self.state.is_disabled() && !text_input {
+            if let Some(action) = self.keymap.find(*key, modifiers) {
+              if self.run_action(ctx, action, &mut close_status, &mut switch_page) {
                 handled = true;
               }
-              _ => (),
             }
           }
         }
       }
     });
 
+    if let Some(page) = switch_page {
+      self.switch_page(ctx, page);
+    }
+
     match close_status {
       CloseStatus::None => (),
       CloseStatus::Close => ctx.send_viewport_cmd(ViewportCommand::Close),
@@ -198,8 +262,51 @@ impl App {
     handled
   }
 
-  fn choose_folder_path(&mut self, ctx: &Context) {
-    let path = Some(self.stats.log_path().into());
+  /// Run a hotkey action. Returns false if the action doesn't currently apply.
+  fn run_action(
+    &mut self,
+    ctx: &Context,
+    action: Action,
+    close_status: &mut CloseStatus,
+    switch_page: &mut Option<Page>,
+  ) -> bool {
+    let stats_page = self.page == Page::Stats;
+    let has_avatar = !self.stats.avatar().is_empty();
+    let has_stats = !self.stats.stats().is_empty();
+    let can_edit = self.page == Page::Offline;
+    let index = PAGES.iter().position(|page| *page == self.page).unwrap_or(0);
+    match action {
+      Action::StoreSaveGame if self.offline.changed() => self.offline.store(),
+      Action::SearchLogs if stats_page && has_avatar => self.stats.show_search_dlg(),
+      Action::TallyDps if stats_page && has_avatar => self.stats.show_dps_dlg(),
+      Action::ReloadStats if stats_page => self.stats.reload(ctx),
+      Action::Quit => *close_status = CloseStatus::Close,
+      Action::Undo if can_edit => self.offline.undo(),
+      Action::Redo if can_edit => self.offline.redo(),
+      Action::EffectiveResists if stats_page && has_stats && !self.stats.filter().is_resists() => {
+        self.stats.set_filter(StatsFilter::Resists)
+      }
+      // The search results use the same key for finding text.
+      Action::FilterStats if stats_page && has_stats && !self.stats.is_showing_results() => {
+        self.stats.show_filter_dlg()
+      }
+      Action::ClearStatsFilter if stats_page && !self.stats.filter().is_none() => {
+        self.stats.set_filter(StatsFilter::None)
+      }
+      Action::NextPage => *switch_page = Some(PAGES[(index + 1) % PAGES.len()]),
+      Action::PreviousPage => *switch_page = Some(PAGES[(index + PAGES.len() - 1) % PAGES.len()]),
+      Action::ChronometerPage => *switch_page = Some(Page::Chronometer),
+      Action::ExperiencePage => *switch_page = Some(Page::Experience),
+      Action::FarmingPage => *switch_page = Some(Page::Farming),
+      Action::OfflinePage => *switch_page = Some(Page::Offline),
+      Action::StatsPage => *switch_page = Some(Page::Stats),
+      _ => return false,
+    }
+    true
+  }
+
+  fn choose_folder_path(&mut self, ctx: &Context, add: bool) {
+    let path = self.stats.log_paths().last().cloned();
     let filter = Box::new({
       let ext = Some(OsStr::new("txt"));
       move |path: &Path| {
@@ -212,51 +319,69 @@ impl App {
       }
     });
 
-    let available = ctx.available_rect().size();
-    let mut file_dlg = egui_file::FileDialog::select_folder(path)
-      .anchor(Align2::CENTER_TOP, [0.0, 0.0])
-      .current_pos([0.0, 24.0])
-      .default_size([available.x, available.y * 0.5])
+    let file_dlg = egui_file::FileDialog::select_folder(path)
       .show_files_filter(filter)
-      .show_new_folder(false)
-      .show_rename(false)
-      .resizable(false);
-    file_dlg.open();
+      .show_rename(false);
+    self.open_file_dlg(ctx, file_dlg, FileRequest::LogFolder { add });
+  }
 
-    self.state.set_disabled(true);
-    self.file_dlg = Some(file_dlg);
+  fn set_log_paths(&mut self, ctx: &Context, log_paths: Vec<PathBuf>) {
+    self.config.set_log_paths(&log_paths);
+    self.watcher.set_log_paths(log_paths.clone());
+    self.experience.set_log_paths(ctx, log_paths.clone());
+    self.stats.set_log_paths(ctx, log_paths);
+  }
+
+  /// Use the chat logs and save-games from a detected game folder.
+  fn use_game_folder(&mut self, ctx: &Context, folder: &GameFolder) {
+    if let Some(path) = folder.log_path() {
+      // Replace the main log folder, keeping any others.
+      let extra = self.stats.log_paths().iter().skip(1);
+      let extra: Vec<PathBuf> = extra.filter(|extra| **extra != path).cloned().collect();
+      let mut log_paths = vec![path];
+      log_paths.extend(extra);
+      self.set_log_paths(ctx, log_paths);
+    }
+
+    if let Some(path) = folder.save_game_path() {
+      self.config.set_save_game_path(&path);
+    }
   }
 
   fn choose_load_path(&mut self, ctx: &Context) {
     if self.offline.changed() {
       // Current save-game is modified, deal with that first.
       if let Some(file_name) = self.offline.file_name() {
-        self.confirm_dlg.open(file_name, Hence::Load);
+        self.confirm_dlg.open(file_name, self.offline.changes(), Hence::Load);
         return;
       }
     }
 
+    self.open_save_game_dlg(ctx, FileRequest::Load);
+  }
+
+  fn restore_backup(&mut self, backup: PathBuf) {
+    if self.offline.changed() {
+      // Current save-game is modified, deal with that first.
+      if let Some(file_name) = self.offline.file_name() {
+        self
+          .confirm_dlg
+          .open(file_name, self.offline.changes(), Hence::Restore(backup));
+        return;
+      }
+    }
+
+    self.offline.restore_backup(&backup);
+  }
+
+  /// Open the file dialog for choosing a save-game. Nothing is opened if there's no save-game folder.
+  fn open_save_game_dlg(&mut self, ctx: &Context, request: FileRequest) {
     let Some(path) = self.config.get_save_game_path() else {
       return;
     };
 
-    let filter = Box::new({
-      let ext = Some(OsStr::new("sota"));
-      move |path: &Path| path.extension() == ext
-    });
-
-    let available = ctx.available_rect().size();
-    let mut file_dlg = egui_file::FileDialog::open_file(Some(path))
-      .anchor(Align2::CENTER_TOP, [0.0, 0.0])
-      .current_pos([0.0, 24.0])
-      .default_size([available.x, available.y * 0.5])
-      .show_files_filter(filter)
-      .show_new_folder(false)
-      .resizable(false);
-    file_dlg.open();
-
-    self.state.set_disabled(true);
-    self.file_dlg = Some(file_dlg);
+    let file_dlg = egui_file::FileDialog::open_file(Some(path)).show_files_filter(ext_filter(&["sota"]));
+    self.open_file_dlg(ctx, file_dlg, request);
   }
 
   fn choose_store_path(&mut self, ctx: &Context) {
@@ -264,23 +389,83 @@ impl App {
       return;
     };
 
-    let filter = Box::new({
-      let ext = Some(OsStr::new("sota"));
-      move |path: &Path| path.extension() == ext
-    });
+    let file_dlg = egui_file::FileDialog::save_file(Some(path)).show_files_filter(ext_filter(&["sota"]));
+    self.open_file_dlg(ctx, file_dlg, FileRequest::StoreAs);
+  }
+
+  fn choose_plan_import_path(&mut self, ctx: &Context) {
+    let file_dlg = egui_file::FileDialog::open_file(dirs::home_dir())
+      .title("Import Skill Plan")
+      .show_files_filter(ext_filter(&["ron", "json"]))
+      .show_rename(false);
+    self.open_file_dlg(ctx, file_dlg, FileRequest::ImportPlan);
+  }
 
+  /// Open the file dialog for saving a file to the home folder.
+  fn file_dlg(
+    &mut self,
+    ctx: &Context,
+    title: &str,
+    filter: &'static [&'static str],
+    default_name: String,
+    request: FileRequest,
+  ) {
+    let file_dlg = egui_file::FileDialog::save_file(dirs::home_dir())
+      .title(title)
+      .default_filename(default_name)
+      .show_files_filter(ext_filter(filter));
+    self.open_file_dlg(ctx, file_dlg, request);
+  }
+
+  /// Show the file dialog below the menu-bar. What's done with the chosen path depends on the request.
+  fn open_file_dlg(&mut self, ctx: &Context, file_dlg: egui_file::FileDialog, request: FileRequest) {
     let available = ctx.available_rect().size();
-    let mut file_dlg = egui_file::FileDialog::save_file(Some(path))
+    let mut file_dlg = file_dlg
       .anchor(Align2::CENTER_TOP, [0.0, 0.0])
       .current_pos([0.0, 24.0])
       .default_size([available.x, available.y * 0.5])
-      .show_files_filter(filter)
       .show_new_folder(false)
       .resizable(false);
     file_dlg.open();
 
     self.state.set_disabled(true);
     self.file_dlg = Some(file_dlg);
+    self.file_request = Some(request);
+  }
+
+  /// Switch to a different page.
+  fn switch_page(&mut self, ctx: &Context, page: Page) {
+    self.chronometer.set_active(ctx, page == Page::Chronometer);
+    self.experience.set_active(page == Page::Experience);
+
+    match page {
+      Page::Experience => self.config.set_avatar_page(self.experience.avatar(), page),
+      Page::Stats => self.config.set_avatar_page(self.stats.avatar(), page),
+      _ => (),
+    }
+
+    self.config.set_page(page);
+    self.page = page;
+  }
+
+  /// Switch to the page that was last used with the avatar.
+  fn avatar_changed(&mut self, ctx: &Context, avatar: &str) {
+    if avatar.is_empty() {
+      return;
+    }
+
+    let page = self.config.get_avatar_state(avatar).page;
+    let selected = match page {
+      Some(Page::Experience) if self.page != Page::Experience => self.experience.select_avatar(avatar),
+      Some(Page::Stats) if self.page != Page::Stats => self.stats.select_avatar(ctx, avatar),
+      _ => false,
+    };
+
+    if let (true, Some(page)) = (selected, page) {
+      self.switch_page(ctx, page);
+    } else {
+      self.config.set_avatar_page(avatar, self.page);
+    }
   }
 }
 
@@ -291,6 +476,85 @@ impl eframe::App for App {
       self.choose_load_path(ctx);
     }
 
+    // Storing was held back because the save-game was modified after it was loaded.
+    if self.offline.modified_request() {
+      if let Some(file_name) = self.offline.file_name() {
+        self.confirm_dlg.open_modified(file_name, None);
+      }
+    }
+
+    // Process copy skills request from the offline page.
+    if self.offline.copy_request() {
+      self.open_save_game_dlg(ctx, FileRequest::CopySkills);
+    }
+
+    // Process import request from the experience page.
+    if self.experience.import_request() {
+      self.open_save_game_dlg(ctx, FileRequest::ImportSkills);
+    }
+
+    // Process skill plan requests from the experience page.
+    if self.experience.import_plan_request() {
+      self.choose_plan_import_path(ctx);
+    }
+
+    if self.experience.export_plan_request() {
+      let name = self.experience.plan_file_name();
+      self.file_dlg(ctx, "Export Skill Plan", &["ron"], name, FileRequest::ExportPlan);
+    }
+
+    // Process log folder requests from the pages that read the chat logs.
+    if self.experience.choose_folder_request() || self.stats.choose_folder_request() {
+      self.choose_folder_path(ctx, false);
+    }
+
+    // Process CSV request from the stats page.
+    if self.stats.csv_request() {
+      let name = self.stats.csv_file_name();
+      self.file_dlg(ctx, "Export Stats", &["csv"], name, FileRequest::ExportCsv);
+    }
+
+    if self.stats.history_csv_request() {
+      let name = self.stats.history_csv_file_name();
+      self.file_dlg(ctx, "Export Stat History", &["csv"], name, FileRequest::ExportHistory);
+    }
+
+    // Process schedule export request from the chronometer page.
+    if self.chronometer.export_request() {
+      let name = self.chronometer.schedule_file_name();
+      self.file_dlg(ctx, "Export Schedule", &["ics"], name, FileRequest::ExportSchedule);
+    }
+
+    // Keep track of the window size and position so that they can be restored. Ignore them when maximized or
+    // full-screen, and ignore the position when minimized.
+    ctx.input(|state| {
+      let viewport = state.viewport();
+      if viewport.maximized != Some(true) && viewport.fullscreen != Some(true) {
+        if let Some(rect) = viewport.inner_rect {
+          self.window_size = Some(rect.size());
+        }
+
+        if viewport.minimized != Some(true) {
+          if let (Some(rect), Some(monitor_size)) = (viewport.outer_rect, viewport.monitor_size) {
+            self.window_pos = Some((rect.min, monitor_size));
+          }
+        }
+      }
+    });
+
+    // The monitor that the window was restored to may be gone or have a lower resolution. Move the window to the
+    // middle of the monitor if it isn't on it.
+    if self.check_window_pos {
+      let (outer_rect, monitor_size) = ctx.input(|state| (state.viewport().outer_rect, state.viewport().monitor_size));
+      if let (Some(outer_rect), Some(monitor_size)) = (outer_rect, monitor_size) {
+        self.check_window_pos = false;
+        if !App::is_on_monitor(outer_rect, monitor_size) {
+          let pos = ((monitor_size - outer_rect.size()) * 0.5).max(Vec2::ZERO);
+          ctx.send_viewport_cmd(ViewportCommand::OuterPosition(pos.to_pos2()));
+        }
+      }
+    }
+
     // Set the progress cursor if the app is busy.
     if self.state.is_busy() {
       ctx.output_mut(|output| output.cursor_icon = CursorIcon::Progress);
@@ -309,7 +573,32 @@ impl eframe::App for App {
         menu::bar(ui, |ui| {
           ui.menu_button("File", |ui| {
             if menu_item(ui, close_menu, "Set Log Folder...", None) {
-              self.choose_folder_path(ctx);
+              self.choose_folder_path(ctx, false);
+            }
+
+            if menu_item(ui, close_menu, "Add Another Log Folder...", None) {
+              self.choose_folder_path(ctx, true);
+            }
+
+            if self.stats.log_paths().len() > 1 {
+              ui.menu_button("Remove Log Folder", |ui| {
+                let mut remove = None;
+                for (index, path) in self.stats.log_paths().iter().enumerate() {
+                  if menu_item(ui, close_menu, &path.to_string_lossy(), None) {
+                    remove = Some(index);
+                  }
+                }
+
+                if let Some(index) = remove {
+                  let mut log_paths = self.stats.log_paths().to_vec();
+                  log_paths.remove(index);
+                  self.set_log_paths(ctx, log_paths);
+                }
+              });
+            }
+
+            if menu_item(ui, close_menu, "Detect Game Folders...", None) {
+              self.folders_dlg.open();
             }
 
             match self.page {
@@ -322,7 +611,12 @@ impl eframe::App for App {
 
                 let enabled = self.offline.changed();
                 ui.add_enabled_ui(enabled, |ui| {
-                  if menu_item(ui, close_menu, "Store Save-game...", Some(cmd!("S"))) {
+                  if menu_item(
+                    ui,
+                    close_menu,
+                    "Store Save-game...",
+                    self.keymap.text(Action::StoreSaveGame).as_deref(),
+                  ) {
                     self.offline.store();
                   }
                 });
@@ -333,56 +627,163 @@ impl eframe::App for App {
                     self.choose_store_path(ctx);
                   }
                 });
+
+                ui.add_enabled_ui(enabled, |ui| {
+                  if menu_item(ui, close_menu, "Export Character Data...", None) {
+                    let name = self.offline.export_file_name();
+                    let request = FileRequest::ExportCharacter;
+                    self.file_dlg(ctx, "Export Character Data", &["json"], name, request);
+                  }
+                });
+
+                ui.add_enabled_ui(enabled, |ui| {
+                  ui.menu_button("Restore Backup", |ui| {
+                    let backups = self.offline.backups();
+                    if backups.is_empty() {
+                      ui.add_enabled_ui(false, |ui| {
+                        ui.label("No backups");
+                      });
+                    }
+
+                    for (time, path) in backups {
+                      let text = time.format("%Y-%m-%d %H:%M:%S").to_string();
+                      if menu_item(ui, close_menu, &text, None) {
+                        self.restore_backup(path);
+                      }
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                      ui.label("Backups to keep");
+                      let mut backups = self.config.get_save_backups();
+                      let widget = DragValue::new(&mut backups).range(0..=50);
+                      if ui.add(widget).on_hover_text("Zero disables backups").changed() {
+                        self.config.set_save_backups(backups);
+                      }
+                    });
+                  });
+                });
               }
               Page::Stats => {
                 ui.separator();
 
                 let enabled = !self.stats.avatar().is_empty();
                 ui.add_enabled_ui(enabled, |ui| {
-                  if menu_item(ui, close_menu, "Search Logs...", Some(cmd!("L"))) {
+                  if menu_item(
+                    ui,
+                    close_menu,
+                    "Search Logs...",
+                    self.keymap.text(Action::SearchLogs).as_deref(),
+                  ) {
                     self.stats.show_search_dlg();
                   }
                 });
 
                 ui.add_enabled_ui(enabled, |ui| {
-                  if menu_item(ui, close_menu, "Tally DPS...", Some(cmd!("D"))) {
+                  if menu_item(
+                    ui,
+                    close_menu,
+                    "Tally DPS...",
+                    self.keymap.text(Action::TallyDps).as_deref(),
+                  ) {
                     self.stats.show_dps_dlg();
                   }
                 });
 
-                if menu_item(ui, close_menu, "Reload Stats", Some("F5")) {
+                if menu_item(
+                  ui,
+                  close_menu,
+                  "Reload Stats",
+                  self.keymap.text(Action::ReloadStats).as_deref(),
+                ) {
                   self.stats.reload(ui.ctx());
                 }
+
+                ui.separator();
+
+                ui.add_enabled_ui(!self.stats.log_paths().is_empty(), |ui| {
+                  if menu_item(ui, close_menu, "Open Log Folder", None) {
+                    self.stats.open_log_folder();
+                  }
+                });
+
+                ui.add_enabled_ui(self.stats.can_open_log_file(), |ui| {
+                  if menu_item(ui, close_menu, "Open Log File", None) {
+                    self.stats.open_log_file();
+                  }
+                });
               }
               _ => (),
             }
 
             ui.separator();
 
-            if menu_item(ui, close_menu, "Quit", Some(cmd!("Q"))) {
+            let mut color_blind = theme::is_color_blind();
+            let response = ui
+              .checkbox(&mut color_blind, "Color-blind Friendly")
+              .on_hover_text("Use blue and orange instead of red, yellow and green");
+            if response.changed() {
+              theme::set_color_blind(color_blind);
+              self.config.set_color_blind(color_blind);
+            }
+
+            ui.separator();
+
+            if menu_item(ui, close_menu, "Quit", self.keymap.text(Action::Quit).as_deref()) {
               ctx.send_viewport_cmd(ViewportCommand::Close);
             }
           });
 
+          if self.page == Page::Offline {
+            ui.menu_button("Edit", |ui| {
+              ui.add_enabled_ui(self.offline.can_undo(), |ui| {
+                if menu_item(ui, close_menu, "Undo", self.keymap.text(Action::Undo).as_deref()) {
+                  self.offline.undo();
+                }
+              });
+
+              ui.add_enabled_ui(self.offline.can_redo(), |ui| {
+                if menu_item(ui, close_menu, "Redo", self.keymap.text(Action::Redo).as_deref()) {
+                  self.offline.redo();
+                }
+              });
+            });
+          }
+
           if self.page == Page::Stats {
             ui.menu_button("View", |ui| {
               let enabled = !self.stats.filter().is_resists() && !self.stats.stats().is_empty();
               ui.add_enabled_ui(enabled, |ui| {
-                if menu_item(ui, close_menu, "Effective Resists", Some(cmd!("R"))) {
+                if menu_item(
+                  ui,
+                  close_menu,
+                  "Effective Resists",
+                  self.keymap.text(Action::EffectiveResists).as_deref(),
+                ) {
                   self.stats.set_filter(StatsFilter::Resists);
                 }
               });
 
               let enabled = !self.stats.stats().is_empty();
               ui.add_enabled_ui(enabled, |ui| {
-                if menu_item(ui, close_menu, "Filter Stats...", Some(cmd!("F"))) {
+                if menu_item(
+                  ui,
+                  close_menu,
+                  "Filter Stats...",
+                  self.keymap.text(Action::FilterStats).as_deref(),
+                ) {
                   self.stats.show_filter_dlg();
                 }
               });
 
               let enabled = !self.stats.filter().is_none();
               ui.add_enabled_ui(enabled, |ui| {
-                if menu_item(ui, close_menu, "Reset View", Some("Esc")) {
+                if menu_item(
+                  ui,
+                  close_menu,
+                  "Reset View",
+                  self.keymap.text(Action::ClearStatsFilter).as_deref(),
+                ) {
                   self.stats.set_filter(StatsFilter::None);
                 }
               });
@@ -390,6 +791,14 @@ impl eframe::App for App {
           }
 
           ui.menu_button("Help", |ui| {
+            if menu_item(ui, close_menu, "Hotkeys...", None) {
+              self.keymap_dlg.open();
+            }
+
+            if menu_item(ui, close_menu, "Skill Data Override...", None) {
+              self.override_dlg.open();
+            }
+
             if menu_item(ui, close_menu, "About...", None) {
               self.about_dlg.open();
             }
@@ -401,43 +810,98 @@ impl eframe::App for App {
     // Put the dialogs here so that they're anchored below the menu-bar.
     if let Some(file_dlg) = &mut self.file_dlg {
       if !file_dlg.show(ctx).visible() {
-        if file_dlg.selected() {
-          if let Some(path) = file_dlg.path() {
-            match file_dlg.dialog_type() {
-              egui_file::DialogType::SelectFolder => {
-                self.config.set_log_path(path);
-                self.experience.set_log_path(ctx, path.to_owned());
-                self.stats.set_log_path(ctx, path.to_owned());
-              }
-              egui_file::DialogType::OpenFile => {
-                let folder = path.with_file_name(String::default());
-                if self.offline.load(path.to_owned()) {
-                  self.config.set_save_game_path(&folder);
-                }
+        let path = file_dlg
+          .selected()
+          .then(|| file_dlg.path())
+          .flatten()
+          .map(Path::to_owned);
+        if let (Some(path), Some(request)) = (path, self.file_request.take()) {
+          match request {
+            FileRequest::LogFolder { add } => {
+              let mut log_paths = Vec::new();
+              if add {
+                log_paths.extend(
+                  self
+                    .stats
+                    .log_paths()
+                    .iter()
+                    .filter(|log_path| **log_path != path)
+                    .cloned(),
+                );
               }
-              egui_file::DialogType::SaveFile => self.offline.store_as(path.to_owned()),
+              log_paths.push(path);
+              self.set_log_paths(ctx, log_paths);
+            }
+            FileRequest::Load => self.offline.load(path),
+            FileRequest::ImportSkills => {
+              let folder = path.with_file_name(String::default());
+              self.experience.import_skills(path);
+              self.config.set_save_game_path(&folder);
             }
+            FileRequest::CopySkills => self.offline.copy_skills_from(path),
+            FileRequest::ImportPlan => self.experience.import_plan(path),
+            FileRequest::StoreAs => self.offline.store_as(path),
+            FileRequest::ExportPlan => self.experience.export_plan(path),
+            FileRequest::ExportCsv => self.stats.save_csv(path),
+            FileRequest::ExportHistory => self.stats.save_history_csv(path),
+            FileRequest::ExportSchedule => self.chronometer.export_schedule(path),
+            FileRequest::ExportCharacter => self.offline.export_character(path),
           }
         }
         self.state.set_disabled(false);
+        self.file_request = None;
         self.file_dlg = None;
       }
     }
 
     if !self.confirm_dlg.show(ctx) {
       match self.confirm_dlg.take_choice() {
-        Some(Choice::Save) => self.offline.store(),
+        Some(Choice::Save) => {
+          self.offline.store();
+          if self.offline.modified_request() {
+            // Deal with the modified file before doing whatever was next. The dialog keeps what's next until the
+            // user picks Overwrite or Reload.
+            if let Some(file_name) = self.offline.file_name() {
+              let hence = self.confirm_dlg.take_hence();
+              self.confirm_dlg.open_modified(file_name, hence);
+            }
+          }
+        }
         Some(Choice::Discard) => self.offline.discard(),
-        _ => (),
+        Some(Choice::Overwrite) => self.offline.overwrite(),
+        Some(Choice::Reload) => self.offline.reload(),
+        // Show the unsaved changes.
+        Some(Choice::Cancel) if self.page != Page::Offline => self.switch_page(ctx, Page::Offline),
+        Some(Choice::Cancel) | None => (),
       }
-      match self.confirm_dlg.take_hence() {
+      if let Some(hence) = self.confirm_dlg.take_hence() {
+        self.hence = Some(hence);
+      }
+    }
+
+    // Carry on once the save-game is done being stored.
+    if !self.offline.is_busy() {
+      match self.hence.take() {
         Some(Hence::Load) => self.choose_load_path(ctx),
+        Some(Hence::Restore(backup)) => self.restore_backup(backup),
         Some(Hence::Exit) => ctx.send_viewport_cmd(ViewportCommand::Close),
         None => (),
       }
     }
 
     self.about_dlg.show(ctx);
+    self.override_dlg.show(ctx);
+    self.message_dlg.show(ctx);
+
+    if !self.folders_dlg.show(ctx) {
+      if let Some(folder) = self.folders_dlg.take_result() {
+        self.use_game_folder(ctx, &folder);
+      }
+    }
+
+    if self.keymap_dlg.show(&mut self.keymap, ctx) {
+      self.config.set_keymap(&self.keymap);
+    }
 
     // Bottom panel for the status. This needs to be done before
     // the central panel so that we know how much space is left.
@@ -448,6 +912,18 @@ impl eframe::App for App {
         }
         self.chronometer.show_status(ui);
       }),
+      Page::Experience => bottom_panel(Page::Experience, ctx, |ui| {
+        if !enabled {
+          ui.disable();
+        }
+        self.experience.show_status(ui);
+      }),
+      Page::Farming => bottom_panel(Page::Farming, ctx, |ui| {
+        if !enabled {
+          ui.disable();
+        }
+        self.farming.show_status(ui);
+      }),
       Page::Offline => bottom_panel(Page::Offline, ctx, |ui| {
         if !enabled {
           ui.disable();
@@ -460,7 +936,6 @@ impl eframe::App for App {
         }
         self.stats.show_status(ui);
       }),
-      _ => (),
     }
 
     // Central panel for the tab pages.
@@ -471,34 +946,11 @@ impl eframe::App for App {
 
       // Tab control.
       ui.horizontal(|ui| {
-        let button = ui.selectable_value(&mut self.page, Page::Chronometer, "Chronometer");
-        if button.clicked() {
-          self.chronometer.start_timer(ctx.clone());
-          self.config.set_page(Page::Chronometer);
-        }
-
-        let button = ui.selectable_value(&mut self.page, Page::Experience, "Experience");
-        if button.clicked() {
-          self.chronometer.stop_timer();
-          self.config.set_page(Page::Experience);
-        }
-
-        let button = ui.selectable_value(&mut self.page, Page::Farming, "Farming");
-        if button.clicked() {
-          self.chronometer.stop_timer();
-          self.config.set_page(Page::Farming);
-        }
-
-        let button = ui.selectable_value(&mut self.page, Page::Offline, "Offline");
-        if button.clicked() {
-          self.chronometer.stop_timer();
-          self.config.set_page(Page::Offline);
-        }
-
-        let button = ui.selectable_value(&mut self.page, Page::Stats, "Stats");
-        if button.clicked() {
-          self.chronometer.stop_timer();
-          self.config.set_page(Page::Stats);
+        for page in PAGES {
+          let text = format!("{page:?}");
+          if ui.selectable_label(self.page == page, text).clicked() && self.page != page {
+            self.switch_page(ctx, page);
+          }
         }
       });
 
@@ -513,22 +965,73 @@ impl eframe::App for App {
         Page::Stats => self.stats.show(ui),
       }
     });
+
+    if self.chronometer.is_mini() {
+      let crop = self.farming.next_event();
+      self.chronometer.show_mini(ctx, crop);
+    }
+
+    // Restore the page that was last used with a newly selected avatar.
+    if self.experience.take_avatar_changed() {
+      let avatar = self.experience.avatar().to_owned();
+      self.avatar_changed(ctx, &avatar);
+    }
+
+    if self.stats.take_avatar_changed() {
+      let avatar = self.stats.avatar().to_owned();
+      self.avatar_changed(ctx, &avatar);
+    }
   }
 
   fn on_exit(&mut self, _: Option<&glow::Context>) {
+    if let Some(size) = self.window_size {
+      self.config.set_window_size((size.x, size.y));
+    }
+
+    if let Some((pos, monitor_size)) = self.window_pos {
+      self
+        .config
+        .set_window_pos((pos.x, pos.y), (monitor_size.x, monitor_size.y));
+    }
+
     self.chronometer.on_exit();
     self.experience.on_exit();
     self.farming.on_exit();
+    self.offline.on_exit();
     self.stats.on_exit();
+    self.watcher.stop();
   }
 }
 
+/// What to do with the path chosen in the file dialog.
+enum FileRequest {
+  /// Use the folder for the chat logs, keeping the other folders if `add` is true.
+  LogFolder {
+    add: bool,
+  },
+  Load,
+  ImportSkills,
+  CopySkills,
+  ImportPlan,
+  StoreAs,
+  ExportPlan,
+  ExportCsv,
+  ExportHistory,
+  ExportSchedule,
+  ExportCharacter,
+}
+
 enum CloseStatus {
   None,
   Close,
   CancelClose,
 }
 
+/// File dialog filter that shows the files with one of the extensions.
+fn ext_filter(exts: &'static [&'static str]) -> egui_file::Filter<PathBuf> {
+  Box::new(move |path: &Path| exts.iter().any(|ext| path.extension() == Some(OsStr::new(ext))))
+}
+
 fn top_panel<R>(ctx: &Context, contents: impl FnOnce(&mut Ui) -> R) {
   const MENU: &str = "Menu";
   TopBottomPanel::top(MENU)
@@ -554,10 +1057,8 @@ fn bottom_panel<R>(page: Page, ctx: &Context, contents: impl FnOnce(&mut Ui) ->
   let (id, margin) = match page {
     // We need a little more vertical space for the chronometer status area so that it looks good.
     Page::Chronometer => ("chronometer_status", Margin::symmetric(8.0, 6.0)),
-    // The experience page doesn't have a status area.
-    Page::Experience => unreachable!(),
-    // The farming page doesn't have a status area.
-    Page::Farming => unreachable!(),
+    Page::Experience => ("experience_status", Margin::symmetric(8.0, 2.0)),
+    Page::Farming => ("farming_status", Margin::symmetric(8.0, 2.0)),
     Page::Offline => ("offline_status", Margin::symmetric(8.0, 2.0)),
     Page::Stats => ("stats_status", Margin::symmetric(8.0, 2.0)),
   };
@@ -567,6 +1068,15 @@ fn bottom_panel<R>(page: Page, ctx: &Context, contents: impl FnOnce(&mut Ui) ->
     .show(ctx, contents);
 }
 
+/// Pages in tab order.
+const PAGES: [Page; 5] = [
+  Page::Chronometer,
+  Page::Experience,
+  Page::Farming,
+  Page::Offline,
+  Page::Stats,
+];
+
 fn menu_item(ui: &mut Ui, close: bool, text: &str, hotkey: Option<&str>) -> bool {
   let widget = if let Some(hotkey) = hotkey {
     Button::new(text).wrap_mode(TextWrapMode::Extend).shortcut_text(hotkey)
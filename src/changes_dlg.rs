@@ -0,0 +1,106 @@
+use crate::{offline::ChangeGroup, util::AppState};
+use eframe::{
+  egui::{scroll_area::ScrollBarVisibility, Context, Grid, Key, RichText, ScrollArea, Ui, Window},
+  emath::Align2,
+  epaint::Color32,
+};
+use std::mem;
+
+/// Dialog window listing the edits that haven't been stored yet.
+pub struct ChangesDlg {
+  state: AppState,
+  groups: Vec<ChangeGroup>,
+  visible: bool,
+}
+
+impl ChangesDlg {
+  pub fn new(state: AppState) -> Self {
+    Self {
+      state,
+      groups: Vec::new(),
+      visible: false,
+    }
+  }
+
+  pub fn show(&mut self, ctx: &Context) {
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      let available = ctx.available_rect();
+      let mut open = true;
+      Window::new(RichText::from("📝  Changes").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          show_changes(ui, &self.groups, available.height() * 0.6);
+          ui.separator();
+          ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+              self.close();
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+  }
+
+  pub fn open(&mut self, groups: Vec<ChangeGroup>) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      self.groups = groups;
+      self.visible = true;
+    }
+  }
+
+  pub fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      mem::take(&mut self.groups);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
+
+/// Show a table of the changes, one section per group, in a scroll area with the specified maximum height.
+pub fn show_changes(ui: &mut Ui, groups: &[ChangeGroup], max_height: f32) {
+  const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+  const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+  const OLD_COLOR: Color32 = Color32::from_gray(154);
+
+  if groups.is_empty() {
+    ui.label("There are no changes.");
+    return;
+  }
+
+  ScrollArea::vertical()
+    .max_height(max_height)
+    .scroll_bar_visibility(ScrollBarVisibility::VisibleWhenNeeded)
+    .show(ui, |ui| {
+      for group in groups {
+        ui.label(RichText::from(group.name).color(HEADER_COLOR));
+        Grid::new(format!("{}_changes_grid", group.name.to_lowercase()))
+          .striped(true)
+          .show(ui, |ui| {
+            for change in &group.changes {
+              ui.label(RichText::from(&change.name).color(NAME_COLOR));
+              ui.label(RichText::from(&change.old).color(OLD_COLOR));
+              ui.label("→");
+              ui.label(&change.new);
+              ui.end_row();
+            }
+          });
+        ui.add_space(ui.spacing().item_spacing.y);
+      }
+    });
+}
@@ -1,30 +1,79 @@
 use crate::{
+  config::Config,
   ethos::{Siege, Virtue, CABALISTS, PLANETARY_ORBITS, TOWNS, VIRTUES},
+  ntp,
+  plant_info::Event,
+  rift_dlg::RiftDlg,
+  theme::Color,
   towns_dlg::TownsDlg,
   util,
 };
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
 use eframe::{
-  egui::{Context, Grid, Layout, RichText, Ui},
-  emath::Align,
+  egui::{
+    Button, CentralPanel, Checkbox, Context, DragValue, Grid, Label, Layout, RichText, Sense, Ui, ViewportBuilder,
+    ViewportClass, ViewportId, Window,
+  },
+  emath::{Align, Pos2},
   epaint::Color32,
 };
-use futures::executor::ThreadPool;
-use std::time::Duration;
+use futures::{channel::mpsc, executor::ThreadPool};
+use notify_rust::Notification;
+use std::{
+  borrow::Cow,
+  collections::BTreeSet,
+  fs,
+  path::PathBuf,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 use util::{AppState, Cancel, FORTNIGHT_SECS, HOUR_SECS};
 
 pub struct Chronometer {
+  config: Config,
+  clock_12h: bool,
   towns_dlg: TownsDlg,
+  rift_dlg: RiftDlg,
   threads: ThreadPool,
   timer_cancel: Option<Cancel>,
+  active: bool,
+  mini: Option<MiniState>,
+  mini_crops: bool,
+  alerts: Arc<Mutex<RiftAlerts>>,
+  pinned: BTreeSet<String>,
+  export_request: bool,
+  error: Option<String>,
+  clock_tx: mpsc::UnboundedSender<Result<f64, Cow<'static, str>>>,
+  clock_rx: mpsc::UnboundedReceiver<Result<f64, Cow<'static, str>>>,
+  clock_check: Option<ClockCheck>,
 }
 
 impl Chronometer {
-  pub fn new(threads: ThreadPool, state: AppState) -> Self {
+  pub fn new(threads: ThreadPool, config: Config, state: AppState) -> Self {
+    let rifts = config.get_rift_alerts().unwrap_or_default();
+    let minutes = config.get_rift_alert_minutes().unwrap_or(DEFAULT_ALERT_MINUTES);
+    let alerts = Arc::new(Mutex::new(RiftAlerts { rifts, minutes }));
+    let clock_12h = config.get_clock_12h();
+    let pinned = config.get_pinned_rifts().unwrap_or_default();
+    let mini_crops = config.get_mini_chronometer_crops();
+    let (clock_tx, clock_rx) = mpsc::unbounded();
     Self {
-      towns_dlg: TownsDlg::new(state),
+      config,
+      clock_12h,
+      towns_dlg: TownsDlg::new(state.clone()),
+      rift_dlg: RiftDlg::new(state),
       threads,
       timer_cancel: None,
+      active: false,
+      mini: None,
+      mini_crops,
+      alerts,
+      pinned,
+      export_request: false,
+      error: None,
+      clock_tx,
+      clock_rx,
+      clock_check: None,
     }
   }
 
@@ -38,9 +87,36 @@ impl Chronometer {
     let now = Utc::now();
     let sieges = get_sieges(now);
 
-    self.towns_dlg.show(ui.ctx(), &sieges);
+    self.towns_dlg.show(ui.ctx(), &sieges, now, self.clock_12h);
+    if let Some(rift) = self.rift_dlg.rift() {
+      let openings = get_rift_openings(now, rift, RIFT_OPENINGS_COUNT);
+      let (name, _) = LUNAR_RIFTS[rift];
+      self.rift_dlg.show(ui.ctx(), name, &openings, self.clock_12h);
+    }
 
+    // In-game clock.
     ui.add_space(4.0);
+    ui.horizontal(|ui| {
+      const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+      let clock = get_game_clock(now);
+      ui.label(RichText::from("New Britannia").color(LABEL_COLOR));
+      ui.label(clock.time_text(self.clock_12h));
+      ui.separator();
+      ui.label(format!("Year {}, Month {}, Day {}", clock.year, clock.month, clock.day));
+      ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+        if clock.is_day() {
+          ui.label(RichText::from("☀ Day").color(Color32::from_rgb(229, 229, 154)));
+        } else {
+          ui.label(RichText::from("☾ Night").color(Color32::from_rgb(154, 154, 229)));
+        }
+      });
+    });
+
+    ui.scope(|ui| {
+      ui.visuals_mut().widgets.noninteractive.bg_stroke.color = Color32::from_rgb(40, 40, 40);
+      ui.separator();
+    });
+
     Grid::new("lunar_rifts_grid")
       .min_col_width((width - spacing.x * 2.0) / 3.0)
       .show(ui, |ui| {
@@ -54,28 +130,32 @@ impl Chronometer {
         });
         ui.end_row();
 
-        const LUNAR_RIFTS: [(&str, &str); RIFT_COUNT] = [
-          ("Blood River", "New Moon"),
-          ("Solace Bridge", "Waxing Crescent"),
-          ("Highvale", "First Quarter"),
-          ("Brookside", "Waxing Gibbous"),
-          ("Owl's Head", "Full Moon"),
-          ("Westend", "Wanning Gibbous"),
-          ("Brittany Graveyard", "Third Quarter"),
-          ("Etceter", "Wanning Crescent"),
-        ];
-
-        // Rifts.
+        // Rifts, pinned first. The rows map back to the indexes of the countdowns.
+        let mut alerts = self.alerts.lock().unwrap();
+        let mut alerts_changed = false;
+        let mut pinned_changed = false;
         let rift_countdowns = get_rift_countdowns(now);
-        for idx in 0..RIFT_COUNT {
+        let (order, pinned_count) = get_rift_order(&self.pinned);
+        for (row, idx) in order.into_iter().enumerate() {
+          if row == pinned_count && pinned_count > 0 {
+            // Separate the pinned rifts from the rest.
+            for _ in 0..3 {
+              ui.separator();
+            }
+            ui.end_row();
+          }
+
           let countdown = rift_countdowns[idx];
           let (name, phase) = LUNAR_RIFTS[idx];
-          let (rift_color, color, status) = if countdown < 0 {
+          let pinned = row < pinned_count;
+          let (rift_color, color, status, time) = if countdown < 0 {
             const OPEN_RIFT_COLOR: Color32 = Color32::from_rgb(154, 229, 255);
+            const OPEN_PINNED_COLOR: Color32 = Color32::from_rgb(204, 245, 255);
             (
-              OPEN_RIFT_COLOR,
+              if pinned { OPEN_PINNED_COLOR } else { OPEN_RIFT_COLOR },
               ACTIVE_PORTAL_COLOR,
               util::get_countdown_text("Closes: ", -countdown),
+              format!("Closes at {}", util::get_clock_text(-countdown, self.clock_12h)),
             )
           } else {
             const CLOSED_RIFT_COLOR: Color32 = Color32::from_rgb(102, 154, 180);
@@ -83,20 +163,82 @@ impl Chronometer {
               CLOSED_RIFT_COLOR,
               INACTIVE_PORTAL_COLOR,
               util::get_countdown_text("Opens: ", countdown),
+              format!("Opens at {}", util::get_clock_text(countdown, self.clock_12h)),
             )
           };
 
-          ui.label(RichText::from(name).color(rift_color));
+          ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = spacing.x * 0.5;
+
+            // Pin toggle.
+            let (star, hover_text) = if pinned {
+              (RichText::from("★").color(PINNED_COLOR), "Unpin this rift")
+            } else {
+              (
+                RichText::from("☆").color(INACTIVE_PORTAL_COLOR),
+                "Pin this rift to the top",
+              )
+            };
+            let label = Label::new(star).sense(Sense::click());
+            if ui.add(label).on_hover_text(hover_text).clicked() {
+              if pinned {
+                self.pinned.remove(name);
+              } else {
+                self.pinned.insert(name.to_owned());
+              }
+              pinned_changed = true;
+            }
+
+            // Alert checkbox.
+            let mut checked = alerts.rifts.contains(name);
+            let response = ui.add(Checkbox::new(&mut checked, ""));
+            if response.on_hover_text("Notify before this rift opens").changed() {
+              if checked {
+                alerts.rifts.insert(name.to_owned());
+              } else {
+                alerts.rifts.remove(name);
+              }
+              alerts_changed = true;
+            }
+
+            let text = RichText::from(name).color(rift_color);
+            let text = if pinned && countdown < 0 { text.strong() } else { text };
+            let label = Label::new(text).sense(Sense::click());
+            if ui.add(label).on_hover_text("Click to show upcoming openings").clicked() {
+              self.rift_dlg.open(idx);
+            }
+          });
           ui.centered_and_justified(|ui| {
             ui.label(RichText::from(phase).color(color));
           });
           ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-            ui.label(RichText::from(status).color(color));
+            ui.label(RichText::from(status).color(color)).on_hover_text(time);
           });
           ui.end_row();
         }
+
+        if alerts_changed {
+          self.config.set_rift_alerts(&alerts.rifts);
+        }
+
+        if pinned_changed {
+          self.config.set_pinned_rifts(&self.pinned);
+        }
       });
 
+    // Alert lead time.
+    ui.horizontal(|ui| {
+      const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+      let mut alerts = self.alerts.lock().unwrap();
+      ui.spacing_mut().item_spacing.x = spacing.x * 0.5;
+      ui.label(RichText::from("Notify").color(LABEL_COLOR));
+      let widget = DragValue::new(&mut alerts.minutes).range(1..=60).speed(0.125);
+      if ui.add(widget).changed() {
+        self.config.set_rift_alert_minutes(alerts.minutes);
+      }
+      ui.label(RichText::from("minutes before a checked rift opens").color(LABEL_COLOR));
+    });
+
     ui.scope(|ui| {
       ui.visuals_mut().widgets.noninteractive.bg_stroke.color = Color32::from_rgb(40, 40, 40);
       ui.separator();
@@ -108,12 +250,13 @@ impl Chronometer {
         const LOST_VALE: &str = "Lost Vale";
 
         let countdown = get_lost_vale_countdown(now);
-        let (vale_color, status_color, status) = if countdown < 0 {
+        let (vale_color, status_color, status, time) = if countdown < 0 {
           const OPEN_VALE_COLOR: Color32 = Color32::from_rgb(187, 187, 255);
           (
             OPEN_VALE_COLOR,
             ACTIVE_PORTAL_COLOR,
             util::get_countdown_text("Closes: ", -countdown),
+            format!("Closes at {}", util::get_clock_text(-countdown, self.clock_12h)),
           )
         } else {
           const CLOSED_VALE_COLOR: Color32 = Color32::from_rgb(140, 140, 187);
@@ -121,21 +264,59 @@ impl Chronometer {
             CLOSED_VALE_COLOR,
             INACTIVE_PORTAL_COLOR,
             util::get_countdown_text("Opens: ", countdown),
+            format!("Opens at {}", util::get_clock_text(countdown, self.clock_12h)),
           )
         };
 
         ui.label(RichText::from(LOST_VALE).color(vale_color));
         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-          ui.label(RichText::from(status).color(status_color));
+          ui.label(RichText::from(status).color(status_color)).on_hover_text(time);
         });
         ui.end_row();
       });
 
     ui.add_space(4.0);
     ui.separator();
-    if ui.button("Towns").clicked() {
-      self.towns_dlg.open();
-    }
+    ui.horizontal(|ui| {
+      if ui.button("Towns").clicked() {
+        self.towns_dlg.open();
+      }
+
+      let response = ui.add_enabled(self.mini.is_none(), Button::new("Mini Mode"));
+      if response
+        .on_hover_text("Show the rift and Lost Vale countdowns in a small window that stays on top")
+        .clicked()
+      {
+        self.open_mini();
+      }
+
+      let response = ui.button("Export Schedule...");
+      if response
+        .on_hover_text("Save the next week of Lost Vale and checked rift openings as a calendar file")
+        .clicked()
+      {
+        self.export_request = true;
+      }
+
+      let checking = matches!(self.clock_check, Some(ClockCheck::Checking));
+      let response = ui.add_enabled(!checking, Button::new("Check Clock"));
+      if response
+        .on_hover_text("Compare the system clock with network time")
+        .clicked()
+      {
+        self.check_clock(ui.ctx());
+      }
+
+      ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+        let response = ui.checkbox(&mut self.clock_12h, "12-hour Clock");
+        if response
+          .on_hover_text("Format the opening and closing times using a 12-hour clock")
+          .changed()
+        {
+          self.config.set_clock_12h(self.clock_12h);
+        }
+      });
+    });
     ui.add_space(4.0);
 
     Grid::new("cabalists_grid")
@@ -161,9 +342,9 @@ impl Chronometer {
           let (cabalist_color, town_color, remain_color) = if siege.virtue() != Virtue::Ethos {
             let town_color = match counts[siege.virtue() as usize] {
               0 => unreachable!(),
-              1 => Color32::from_rgb(192, 164, 24),
-              2 => Color32::from_rgb(208, 96, 32),
-              _ => Color32::from_rgb(224, 48, 48),
+              1 => Color::SiegeLow.get(),
+              2 => Color::SiegeMedium.get(),
+              _ => Color::SiegeHigh.get(),
             };
             (Color32::from_rgb(240, 140, 178), town_color, Color32::from_gray(204))
           } else {
@@ -187,7 +368,10 @@ impl Chronometer {
           // Remaining time.
           ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
             let text = util::get_countdown_text(Default::default(), siege.remain_secs());
-            ui.label(RichText::from(text).color(remain_color)).on_hover_text(next);
+            let time = util::get_clock_text(siege.remain_secs(), self.clock_12h);
+            let hover_text = format!("{next}\nMoves at {time}");
+            ui.label(RichText::from(text).color(remain_color))
+              .on_hover_text(hover_text);
           });
           ui.end_row();
         }
@@ -195,44 +379,464 @@ impl Chronometer {
   }
 
   pub fn show_status(&mut self, ui: &mut Ui) {
+    while let Ok(result) = self.clock_rx.try_recv() {
+      self.clock_check = Some(match result {
+        Ok(offset) => ClockCheck::Offset(offset),
+        Err(err) => ClockCheck::Failed(err),
+      });
+    }
+
     ui.centered_and_justified(|ui| {
+      if let Some(error) = &self.error {
+        ui.label(RichText::from(error).color(Color::Error.get()));
+        return;
+      }
+
+      // The result of the last clock check is shown for the rest of the session.
+      match &self.clock_check {
+        Some(ClockCheck::Checking) => {
+          ui.label("Checking the system clock...");
+          return;
+        }
+        Some(ClockCheck::Offset(offset)) => {
+          let (text, warning) = get_clock_text(*offset);
+          if warning {
+            ui.label(RichText::from(text).color(Color::Error.get()));
+          } else {
+            ui.label(text);
+          }
+          return;
+        }
+        Some(ClockCheck::Failed(err)) => {
+          ui.label(RichText::from(format!("Unable to check the clock: {err}")).color(Color::Error.get()));
+          return;
+        }
+        None => (),
+      }
       ui.label("The accuracy of this chronometer depends entirely on your system clock.\nFor best results, set your system clock to synchronize with Internet time.");
     });
   }
 
-  pub fn start_timer(&mut self, ctx: Context) {
-    self.stop_timer();
+  /// Query network time on a pooled thread. This is only done when asked.
+  fn check_clock(&mut self, ctx: &Context) {
+    self.clock_check = Some(ClockCheck::Checking);
+
+    let tx = self.clock_tx.clone();
+    let ctx = ctx.clone();
+    self.threads.spawn_ok(async move {
+      tx.unbounded_send(ntp::get_clock_offset()).unwrap();
+      ctx.request_repaint();
+    });
+  }
+
+  pub fn export_request(&mut self) -> bool {
+    std::mem::take(&mut self.export_request)
+  }
+
+  pub fn schedule_file_name(&self) -> String {
+    format!("SotA Schedule {}.ics", Utc::now().format("%Y-%m-%d"))
+  }
+
+  /// Save the upcoming Lost Vale and checked rift openings as an iCalendar file.
+  pub fn export_schedule(&mut self, path: PathBuf) {
+    // Use the "ics" extension if there isn't one.
+    let path = if path.extension().is_none() {
+      path.with_extension("ics")
+    } else {
+      path
+    };
+
+    let now = Utc::now();
+    let rifts = self.alerts.lock().unwrap().rifts.clone();
+    let events = get_schedule(now, SCHEDULE_DAYS, &rifts);
+    self.error = match fs::write(&path, get_icalendar(&events, now)) {
+      Ok(()) => None,
+      Err(err) => Some(format!("Unable to save to {path:?}: {err}")),
+    };
+  }
+
+  /// Show or hide the mini chronometer window. The crop event is only shown if it's enabled.
+  pub fn show_mini(&mut self, ctx: &Context, crop: Option<(Event, NaiveDateTime, String)>) {
+    let Some(mini) = &self.mini else {
+      return;
+    };
+
+    let mut builder = ViewportBuilder::default()
+      .with_title(format!("{} - Mini", util::APP_TITLE))
+      .with_inner_size([MINI_WIDTH, 0.0])
+      .with_resizable(false)
+      .with_always_on_top();
+
+    // The builder must be the same every frame or the window is moved back to where it started.
+    if let Some(pos) = mini.start_pos {
+      builder = builder.with_position(pos);
+    }
+
+    let crop = crop.filter(|_| self.mini_crops);
+    let mut close = false;
+    ctx.show_viewport_immediate(ViewportId::from_hash_of(MINI_ID), builder, |ctx, class| {
+      if class == ViewportClass::Embedded {
+        // Multiple windows aren't supported, so show it in the main window instead.
+        let mut open = true;
+        Window::new("Mini Chronometer")
+          .open(&mut open)
+          .collapsible(false)
+          .resizable(false)
+          .show(ctx, |ui| close = self.show_mini_contents(ui, crop.as_ref()));
+        close |= !open;
+        return;
+      }
+
+      CentralPanel::default().show(ctx, |ui| {
+        close = self.show_mini_contents(ui, crop.as_ref());
+      });
+
+      ctx.input(|state| {
+        let viewport = state.viewport();
+        if viewport.minimized != Some(true) {
+          if let (Some(rect), Some(mini)) = (viewport.outer_rect, &mut self.mini) {
+            mini.pos = Some(rect.min);
+          }
+        }
+        close |= viewport.close_requested();
+      });
+    });
+
+    if close {
+      self.close_mini(ctx);
+    }
+  }
+
+  /// Check if the mini chronometer is showing.
+  pub fn is_mini(&self) -> bool {
+    self.mini.is_some()
+  }
+
+  /// Set whether the chronometer page is showing. The timer keeps running while the mini chronometer is open.
+  pub fn set_active(&mut self, ctx: &Context, active: bool) {
+    self.active = active;
+    self.update_timer(ctx);
+  }
+
+  fn show_mini_contents(&mut self, ui: &mut Ui, crop: Option<&(Event, NaiveDateTime, String)>) -> bool {
+    const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+    const OPEN_COLOR: Color32 = Color32::from_rgb(154, 229, 255);
+    const CLOSED_COLOR: Color32 = Color32::from_rgb(102, 154, 180);
+
+    let now = Utc::now();
+    let rift_countdowns = get_rift_countdowns(now);
+    let open = rift_countdowns.iter().position(|&countdown| countdown < 0);
+    let next = (0..RIFT_COUNT)
+      .filter(|&idx| rift_countdowns[idx] >= 0)
+      .min_by_key(|&idx| rift_countdowns[idx]);
+
+    let response = ui
+      .scope(|ui| {
+        Grid::new("mini_chronometer_grid").num_columns(2).show(ui, |ui| {
+          let row = |ui: &mut Ui, name: &str, color: Color32, countdown: i32| {
+            ui.label(RichText::from(name).color(color));
+            let (prefix, countdown) = if countdown < 0 {
+              ("Closes: ", -countdown)
+            } else {
+              ("Opens: ", countdown)
+            };
+            let time = util::get_clock_text(countdown, self.clock_12h);
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+              ui.label(util::get_countdown_text(prefix, countdown))
+                .on_hover_text(time);
+            });
+            ui.end_row();
+          };
+
+          if let Some(idx) = open {
+            row(ui, LUNAR_RIFTS[idx].0, OPEN_COLOR, rift_countdowns[idx]);
+          }
+
+          if let Some(idx) = next {
+            row(ui, LUNAR_RIFTS[idx].0, CLOSED_COLOR, rift_countdowns[idx]);
+          }
+
+          let countdown = get_lost_vale_countdown(now);
+          let color = if countdown < 0 { OPEN_COLOR } else { CLOSED_COLOR };
+          row(ui, "Lost Vale", color, countdown);
+
+          if let Some((event, date_time, text)) = crop {
+            let secs = (*date_time - Local::now().naive_local()).num_seconds() as i32;
+            ui.label(RichText::from(format!("{event:?}")).color(LABEL_COLOR))
+              .on_hover_text(text);
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+              let time = date_time.format(util::get_time_format(self.clock_12h));
+              ui.label(util::get_countdown_text(Default::default(), secs))
+                .on_hover_text(format!("{text}\nDue at {time}"));
+            });
+            ui.end_row();
+          }
+        });
+      })
+      .response;
+
+    let mut close = false;
+    response.interact(Sense::click()).context_menu(|ui| {
+      if ui.checkbox(&mut self.mini_crops, "Show Next Crop Event").changed() {
+        self.config.set_mini_chronometer_crops(self.mini_crops);
+        ui.close_menu();
+      }
+
+      if ui.button("Close").clicked() {
+        close = true;
+        ui.close_menu();
+      }
+    });
+    close
+  }
+
+  fn open_mini(&mut self) {
+    if self.mini.is_none() {
+      let start_pos = self.config.get_mini_chronometer_pos().map(|(x, y)| Pos2::new(x, y));
+      self.mini = Some(MiniState { start_pos, pos: None });
+    }
+  }
+
+  fn close_mini(&mut self, ctx: &Context) {
+    if let Some(mini) = self.mini.take() {
+      self.save_mini_pos(&mini);
+      self.update_timer(ctx);
+    }
+  }
+
+  fn save_mini_pos(&mut self, mini: &MiniState) {
+    if let Some(pos) = mini.pos {
+      self.config.set_mini_chronometer_pos((pos.x, pos.y));
+    }
+  }
+
+  /// Run the timer while the page or the mini chronometer is showing.
+  fn update_timer(&mut self, ctx: &Context) {
+    if self.active || self.mini.is_some() {
+      self.start_timer(ctx.clone());
+    } else {
+      self.stop_timer();
+    }
+  }
+
+  fn start_timer(&mut self, ctx: Context) {
+    if self.timer_cancel.is_some() {
+      // Already running.
+      return;
+    }
 
     let cancel = Cancel::default();
     self.timer_cancel = Some(cancel.clone());
 
+    let alerts = self.alerts.clone();
     self.threads.spawn_ok(async move {
+      let mut _notification = None;
+      let mut notified = [false; RIFT_COUNT];
       while !cancel.is_canceled() {
         // Request a repaint every quarter-second.
         std::thread::sleep(Duration::from_millis(250));
         ctx.request_repaint();
+
+        // Check for rifts that are about to open.
+        // Copy the settings so that the lock isn't held while showing a notification.
+        let (rifts, minutes) = {
+          let alerts = alerts.lock().unwrap();
+          (alerts.rifts.clone(), alerts.minutes)
+        };
+
+        let lead_secs = minutes as i32 * 60;
+        let rift_countdowns = get_rift_countdowns(Utc::now());
+        for idx in 0..RIFT_COUNT {
+          let countdown = rift_countdowns[idx];
+          let (name, _) = LUNAR_RIFTS[idx];
+          if countdown < 0 || countdown > lead_secs {
+            // Reset so that the next opening is notified.
+            notified[idx] = false;
+            continue;
+          }
+
+          if notified[idx] || !rifts.contains(name) {
+            continue;
+          }
+
+          // Popup a desktop notification.
+          notified[idx] = true;
+          let minutes = (countdown + 59) / 60;
+          let body = if minutes == 1 {
+            format!("{name} rift opens in 1 minute")
+          } else {
+            format!("{name} rift opens in {minutes} minutes")
+          };
+
+          match Notification::new().summary("Lunar Rift").body(&body).show() {
+            Ok(handle) => {
+              // Discarding the handle closes the notification on Wayland, so keep it around until the next notification.
+              _notification = Some(handle);
+            }
+            Err(err) => println!("{err:?}"),
+          };
+        }
       }
     });
   }
 
-  pub fn stop_timer(&mut self) {
+  fn stop_timer(&mut self) {
     if let Some(mut timer_cancel) = self.timer_cancel.take() {
       timer_cancel.cancel();
     }
   }
 
   pub fn on_exit(&mut self) {
+    if let Some(mini) = self.mini.take() {
+      self.save_mini_pos(&mini);
+    }
     self.stop_timer();
   }
 }
 
+/// State of the system clock check.
+enum ClockCheck {
+  Checking,
+
+  /// Seconds that the system clock is behind network time.
+  Offset(f64),
+  Failed(Cow<'static, str>),
+}
+
+/// Describe how far off the system clock is. Returns the text and whether it's far enough off to affect the times.
+fn get_clock_text(offset: f64) -> (String, bool) {
+  if offset.abs() < 1.0 {
+    return (String::from("System clock is within a second of network time"), false);
+  }
+
+  let secs = offset.abs().round().min(i32::MAX as f64) as i32;
+  let off = if secs < 60 {
+    format!("{secs}s")
+  } else {
+    util::get_countdown_text("", secs)
+  };
+  let direction = if offset > 0.0 { "behind" } else { "ahead of" };
+  let text = format!("System clock is {off} {direction} network time");
+  if offset.abs() < CLOCK_WARNING_SECS {
+    return (text, false);
+  }
+
+  (format!("{text} — rift times may be off"), true)
+}
+
+/// Seconds that the system clock can be off before the times are affected.
+const CLOCK_WARNING_SECS: f64 = 2.0;
+
+/// Position of the mini chronometer window.
+struct MiniState {
+  /// Where the window was placed when it was opened.
+  start_pos: Option<Pos2>,
+
+  /// Where the window is now.
+  pos: Option<Pos2>,
+}
+
+const MINI_ID: &str = "mini_chronometer";
+const MINI_WIDTH: f32 = 240.0;
+
 const RIFT_COUNT: usize = 8;
 
+const LUNAR_RIFTS: [(&str, &str); RIFT_COUNT] = [
+  ("Blood River", "New Moon"),
+  ("Solace Bridge", "Waxing Crescent"),
+  ("Highvale", "First Quarter"),
+  ("Brookside", "Waxing Gibbous"),
+  ("Owl's Head", "Full Moon"),
+  ("Westend", "Wanning Gibbous"),
+  ("Brittany Graveyard", "Third Quarter"),
+  ("Etceter", "Wanning Crescent"),
+];
+
+/// Color of the pin toggle for pinned rifts.
+const PINNED_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+
+/// Get the display order of the rifts as indexes into `LUNAR_RIFTS`, along with the number of pinned rifts. Pinned
+/// rifts are first; otherwise the order is unchanged.
+fn get_rift_order(pinned: &BTreeSet<String>) -> (Vec<usize>, usize) {
+  let (mut order, rest): (Vec<usize>, Vec<usize>) =
+    (0..RIFT_COUNT).partition(|&idx| pinned.contains(LUNAR_RIFTS[idx].0));
+  let count = order.len();
+  order.extend(rest);
+  (order, count)
+}
+
+/// Default number of minutes before a rift opens to notify.
+const DEFAULT_ALERT_MINUTES: u32 = 5;
+
+/// Rift opening notification settings.
+struct RiftAlerts {
+  /// Names of the rifts to notify for.
+  rifts: BTreeSet<String>,
+
+  /// Number of minutes before opening to notify.
+  minutes: u32,
+}
+
+/// Number of upcoming openings to show in the rift dialog.
+const RIFT_OPENINGS_COUNT: usize = 10;
+
+/// Duration of each lunar phase (rift opening) in seconds.
+const PHASE_SECS: i32 = 525;
+
+/// Number of in-game days in an in-game month. A month is 28 real hours, the same as the original Lost Vale window.
+const GAME_MONTH_DAYS: i64 = 28;
+
+/// In-game hours that count as daytime. The game doesn't report sunrise or sunset, so the in-game day is split
+/// evenly at 6:00 and 18:00.
+const GAME_DAY_HOURS: std::ops::Range<u32> = 6..18;
+
+/// New Britannia date and time of day.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GameClock {
+  pub year: i64,
+
+  /// Month within the year, twelve per year.
+  pub month: i64,
+
+  /// Day within the month.
+  pub day: i64,
+  pub hour: u32,
+  pub minute: u32,
+}
+
+impl GameClock {
+  pub fn is_day(&self) -> bool {
+    GAME_DAY_HOURS.contains(&self.hour)
+  }
+
+  fn time_text(&self, clock_12h: bool) -> String {
+    let time = NaiveTime::from_hms_opt(self.hour, self.minute, 0).unwrap();
+    util::get_time_text(time, clock_12h)
+  }
+}
+
+/// Get the in-game date and time. One real hour is one in-game day and one fortnight is one in-game year.
+pub fn get_game_clock(now: DateTime<Utc>) -> GameClock {
+  let epoch_secs = (now - util::get_epoch()).num_seconds();
+  let year = epoch_secs.div_euclid(FORTNIGHT_SECS) + 1;
+  let days = epoch_secs.rem_euclid(FORTNIGHT_SECS) / HOUR_SECS;
+
+  // Each real second is 24 in-game seconds.
+  let game_secs = epoch_secs.rem_euclid(HOUR_SECS) * 24;
+  GameClock {
+    year,
+    month: days / GAME_MONTH_DAYS + 1,
+    day: days % GAME_MONTH_DAYS + 1,
+    hour: (game_secs / HOUR_SECS) as u32,
+    minute: (game_secs % HOUR_SECS / 60) as u32,
+  }
+}
+
+/// Duration of a full lunar cycle in seconds.
+const CYCLE_SECS: i64 = PHASE_SECS as i64 * RIFT_COUNT as i64;
+
 // Get the number of seconds for each rift.
 fn get_rift_countdowns(now: DateTime<Utc>) -> [i32; RIFT_COUNT] {
-  const PHASE_SECS: i32 = 525;
-  const CYCLE_SECS: i64 = 4200;
-
   // Get the number of seconds since epoch.
   let delta_secs = (now - util::get_epoch()).num_seconds();
 
@@ -263,6 +867,25 @@ fn get_rift_countdowns(now: DateTime<Utc>) -> [i32; RIFT_COUNT] {
   secs
 }
 
+/// Get the open and close times, in seconds from now, of the next openings for a rift. The first entry is the current
+/// opening if the rift is already open.
+fn get_rift_openings(now: DateTime<Utc>, rift: usize, count: usize) -> Vec<(i32, i32)> {
+  let countdown = get_rift_countdowns(now)[rift];
+  let begin = if countdown < 0 {
+    // Currently open, so it opened a phase before it closes.
+    -countdown - PHASE_SECS
+  } else {
+    countdown
+  };
+
+  (0..count as i32)
+    .map(|index| {
+      let begin = begin + index * CYCLE_SECS as i32;
+      (begin, begin + PHASE_SECS)
+    })
+    .collect()
+}
+
 /// Get the current Lost Vale countdown as seconds.
 fn get_lost_vale_countdown(now: DateTime<Utc>) -> i32 {
   // Get the number of seconds since 2018/02/23 13:00:00 UTC (first sighting).
@@ -286,6 +909,97 @@ fn get_lost_vale_countdown(now: DateTime<Utc>) -> i32 {
   }
 }
 
+/// Number of days of openings to include in an exported schedule.
+const SCHEDULE_DAYS: i64 = 7;
+
+/// Lost Vale or lunar rift opening.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScheduleEvent {
+  pub summary: String,
+  pub begin: DateTime<Utc>,
+  pub end: DateTime<Utc>,
+}
+
+/// Get the Lost Vale openings, and the openings of the specified rifts, that end after `start` and begin within `days`
+/// of it. The openings are projected forward using the same countdowns as the live display.
+pub fn get_schedule(start: DateTime<Utc>, days: i64, rifts: &BTreeSet<String>) -> Vec<ScheduleEvent> {
+  let limit = start + TimeDelta::days(days);
+  let mut events = Vec::new();
+
+  // Lost Vale.
+  let mut now = start;
+  while now < limit {
+    let countdown = get_lost_vale_countdown(now) as i64;
+    let (begin, end) = if countdown < 0 {
+      // Currently open, so it opened an hour before it closes.
+      let end = now + TimeDelta::seconds(-countdown);
+      (end - TimeDelta::seconds(HOUR_SECS), end)
+    } else {
+      let begin = now + TimeDelta::seconds(countdown);
+      (begin, begin + TimeDelta::seconds(HOUR_SECS))
+    };
+
+    if begin < limit {
+      events.push(ScheduleEvent {
+        summary: String::from("Lost Vale open"),
+        begin,
+        end,
+      });
+    }
+
+    now = end;
+  }
+
+  // Lunar rifts.
+  let count = (days * 24 * HOUR_SECS / CYCLE_SECS) as usize + 2;
+  for (idx, (name, _)) in LUNAR_RIFTS.iter().enumerate() {
+    if !rifts.contains(*name) {
+      continue;
+    }
+
+    for (begin, end) in get_rift_openings(start, idx, count) {
+      let begin = start + TimeDelta::seconds(begin as i64);
+      if begin < limit {
+        events.push(ScheduleEvent {
+          summary: format!("{name} lunar rift open"),
+          begin,
+          end: start + TimeDelta::seconds(end as i64),
+        });
+      }
+    }
+  }
+
+  events.sort_by(|a, b| a.begin.cmp(&b.begin).then_with(|| a.summary.cmp(&b.summary)));
+  events
+}
+
+/// Format the schedule as an iCalendar file.
+pub fn get_icalendar(events: &[ScheduleEvent], stamp: DateTime<Utc>) -> String {
+  const FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+  // iCalendar lines end with CRLF.
+  let mut lines = vec![
+    String::from("BEGIN:VCALENDAR"),
+    String::from("VERSION:2.0"),
+    format!("PRODID:-//{}//Chronometer//EN", util::APP_NAME),
+  ];
+
+  for event in events {
+    let id: String = event.summary.chars().filter(|ch| ch.is_ascii_alphanumeric()).collect();
+    lines.push(String::from("BEGIN:VEVENT"));
+    lines.push(format!("UID:{}-{id}@{}", event.begin.timestamp(), util::APP_NAME));
+    lines.push(format!("DTSTAMP:{}", stamp.format(FORMAT)));
+    lines.push(format!("DTSTART:{}", event.begin.format(FORMAT)));
+    lines.push(format!("DTEND:{}", event.end.format(FORMAT)));
+    lines.push(format!("SUMMARY:{}", event.summary.replace(',', "\\,")));
+    lines.push(String::from("END:VEVENT"));
+  }
+
+  lines.push(String::from("END:VCALENDAR"));
+  lines.push(String::new());
+  lines.join("\r\n")
+}
+
 /// Calculate the virtue/town and number of seconds remaining in a siege for each cabalist.
 pub fn get_sieges(now: DateTime<Utc>) -> [Siege; CABALISTS.len()] {
   PLANETARY_ORBITS.map(|(orbit_secs, zone_secs)| {
@@ -313,6 +1027,46 @@ pub fn get_sieges(now: DateTime<Utc>) -> [Siege; CABALISTS.len()] {
   })
 }
 
+/// Projected siege of a town by a cabalist.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SiegeForecast {
+  /// Index into `CABALISTS`.
+  pub cabalist: usize,
+  pub begin: DateTime<Utc>,
+  pub end: DateTime<Utc>,
+}
+
+/// Get the next `count` sieges of the town for `virtue` that end after `start`, by stepping each cabalist forward
+/// through the zones. Sieges that are underway at `start` are included.
+pub fn get_siege_forecast(start: DateTime<Utc>, virtue: Virtue, count: usize) -> Vec<SiegeForecast> {
+  let limit = start + TimeDelta::seconds(FORTNIGHT_SECS);
+  let mut forecast = Vec::new();
+  for (cabalist, &(_, zone_secs)) in PLANETARY_ORBITS.iter().enumerate() {
+    let mut now = start;
+    let mut found = 0;
+    while now < limit && found < count {
+      let siege = &get_sieges(now)[cabalist];
+      let end = now + TimeDelta::seconds(siege.remain_secs() as i64);
+      if siege.virtue() == virtue {
+        // After the first step, `now` is the start of a zone.
+        let begin = if now == start {
+          end - TimeDelta::seconds(zone_secs.ceil() as i64)
+        } else {
+          now
+        };
+
+        forecast.push(SiegeForecast { cabalist, begin, end });
+        found += 1;
+      }
+      now = end;
+    }
+  }
+
+  forecast.sort_by(|a, b| a.begin.cmp(&b.begin).then_with(|| a.cabalist.cmp(&b.cabalist)));
+  forecast.truncate(count);
+  forecast
+}
+
 fn count_cabalists(sieges: &[Siege; CABALISTS.len()]) -> [u32; VIRTUES.len()] {
   let mut counts: [u32; VIRTUES.len()] = Default::default();
   for siege in sieges {
@@ -320,3 +1074,177 @@ fn count_cabalists(sieges: &[Siege; CABALISTS.len()]) -> [u32; VIRTUES.len()] {
   }
   counts
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rift_order() {
+    let (order, count) = get_rift_order(&BTreeSet::new());
+    assert_eq!(order, (0..RIFT_COUNT).collect::<Vec<_>>());
+    assert_eq!(count, 0);
+
+    // Unknown names are ignored.
+    let pinned = BTreeSet::from([
+      String::from("Etceter"),
+      String::from("Highvale"),
+      String::from("Nowhere"),
+    ]);
+    let (order, count) = get_rift_order(&pinned);
+    assert_eq!(order, [2, 7, 0, 1, 3, 4, 5, 6]);
+    assert_eq!(count, 2);
+  }
+
+  #[test]
+  fn test_game_clock() {
+    let epoch = util::get_epoch();
+    let clock = |secs| get_game_clock(epoch + chrono::Duration::seconds(secs));
+
+    let expected = GameClock {
+      year: 1,
+      month: 1,
+      day: 1,
+      hour: 0,
+      minute: 0,
+    };
+    assert_eq!(clock(0), expected);
+    assert!(!clock(0).is_day());
+
+    // Half a real hour is noon.
+    let noon = clock(HOUR_SECS / 2);
+    assert_eq!((noon.hour, noon.minute), (12, 0));
+    assert!(noon.is_day());
+
+    // 2.5 real minutes is one in-game hour.
+    let time = clock(150 + 15);
+    assert_eq!((time.hour, time.minute), (1, 6));
+
+    // One in-game month is 28 real hours.
+    let time = clock(28 * HOUR_SECS + HOUR_SECS / 4);
+    assert_eq!((time.month, time.day, time.hour), (2, 1, 6));
+    assert!(time.is_day());
+
+    // The last day of the year is in the twelfth month.
+    let time = clock(FORTNIGHT_SECS - HOUR_SECS / 8);
+    assert_eq!((time.month, time.day, time.hour), (12, 28, 21));
+    assert!(!time.is_day());
+
+    // One in-game year is a fortnight.
+    let time = clock(FORTNIGHT_SECS + HOUR_SECS * 3);
+    assert_eq!((time.year, time.month, time.day), (2, 1, 4));
+    assert_eq!(time.time_text(true), "12:00 AM");
+    assert_eq!(clock(HOUR_SECS * 3 / 4).time_text(true), "6:00 PM");
+  }
+
+  #[test]
+  fn test_siege_forecast() {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 12, 34, 56).unwrap();
+    let forecast = get_siege_forecast(start, Virtue::Love, 10);
+    assert_eq!(forecast.len(), 10);
+    assert!(forecast.windows(2).all(|pair| pair[0].begin <= pair[1].begin));
+    for siege in &forecast {
+      assert!(siege.end > start);
+
+      // The live display agrees with the forecast throughout each siege.
+      let zone_secs = PLANETARY_ORBITS[siege.cabalist].1;
+      assert!(((siege.end - siege.begin).num_seconds() as f64 - zone_secs).abs() <= 1.0);
+      for time in [
+        siege.begin.max(start) + TimeDelta::seconds(1),
+        siege.end - TimeDelta::seconds(2),
+      ] {
+        let live = &get_sieges(time)[siege.cabalist];
+        assert_eq!(live.virtue(), Virtue::Love);
+      }
+
+      // The cabalist moves on when the siege ends.
+      assert_ne!(
+        get_sieges(siege.end + TimeDelta::seconds(1))[siege.cabalist].virtue(),
+        Virtue::Love
+      );
+    }
+
+    // Current sieges are first.
+    let current = get_sieges(start);
+    for (cabalist, siege) in current.iter().enumerate() {
+      if siege.virtue() == Virtue::Love {
+        assert!(forecast
+          .iter()
+          .any(|forecast| forecast.cabalist == cabalist && forecast.begin <= start));
+      }
+    }
+  }
+
+  #[test]
+  fn test_clock_text() {
+    let within = String::from("System clock is within a second of network time");
+    assert_eq!(get_clock_text(0.4), (within.clone(), false));
+    assert_eq!(get_clock_text(-0.9), (within, false));
+    assert_eq!(
+      get_clock_text(1.4),
+      (String::from("System clock is 1s behind network time"), false)
+    );
+    assert_eq!(
+      get_clock_text(37.2),
+      (
+        String::from("System clock is 37s behind network time — rift times may be off"),
+        true
+      )
+    );
+    assert_eq!(
+      get_clock_text(-125.0),
+      (
+        String::from("System clock is 02m 05s ahead of network time — rift times may be off"),
+        true
+      )
+    );
+  }
+
+  #[test]
+  fn test_schedule() {
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 12, 34, 56).unwrap();
+    let rifts = BTreeSet::from([String::from("Highvale")]);
+    let events = get_schedule(start, 7, &rifts);
+    let limit = start + TimeDelta::days(7);
+
+    let vale: Vec<_> = events
+      .iter()
+      .filter(|event| event.summary == "Lost Vale open")
+      .collect();
+    let rift: Vec<_> = events
+      .iter()
+      .filter(|event| event.summary == "Highvale lunar rift open")
+      .collect();
+    assert_eq!(vale.len() + rift.len(), events.len());
+
+    // Three openings every 28 hours.
+    assert!((17..=19).contains(&vale.len()));
+    for event in &vale {
+      assert!(event.end > start && event.begin < limit);
+      assert_eq!((event.end - event.begin).num_seconds(), HOUR_SECS);
+
+      // The live display shows the vale as open with an hour left just as it opens.
+      assert_eq!(get_lost_vale_countdown(event.begin), -HOUR_SECS as i32);
+      assert!(get_lost_vale_countdown(event.begin - TimeDelta::seconds(1)) > 0);
+    }
+
+    // One opening per lunar cycle.
+    let cycles = 7 * 24 * HOUR_SECS / CYCLE_SECS;
+    assert!((cycles..=cycles + 1).contains(&(rift.len() as i64)));
+    for event in &rift {
+      assert!(event.end > start && event.begin < limit);
+      assert_eq!((event.end - event.begin).num_seconds(), PHASE_SECS as i64);
+      assert_eq!(get_rift_countdowns(event.begin)[2], -PHASE_SECS);
+    }
+
+    // Events are in order.
+    assert!(events.windows(2).all(|pair| pair[0].begin <= pair[1].begin));
+
+    let text = get_icalendar(&events, start);
+    assert!(text.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(text.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(text.matches("BEGIN:VEVENT").count(), events.len());
+    let begin = events[0].begin.format("%Y%m%dT%H%M%SZ");
+    assert!(text.contains(&format!("DTSTART:{begin}\r\n")));
+  }
+}
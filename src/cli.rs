@@ -0,0 +1,332 @@
+use crate::{
+  config::Config,
+  log_data::{self, AttackTally, DPSTally, Span},
+  stats_cache::StatsCache,
+  util::{self, AppState, Cancel, APP_NAME},
+};
+use chrono::NaiveDateTime;
+use serde_json::{json, Map, Value};
+use std::{borrow::Cow, path::PathBuf, process::ExitCode};
+
+/// Headless command given on the command line.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+  /// Tally the DPS between two times, or for the most recent fight if no times are given.
+  TallyDps {
+    avatar: String,
+    span: Option<(NaiveDateTime, NaiveDateTime)>,
+  },
+
+  /// Get the most recent `/stats` output.
+  LatestStats { avatar: String },
+
+  /// Show the usage.
+  Help,
+}
+
+/// Headless command and its options.
+#[derive(Debug, PartialEq)]
+pub struct Invocation {
+  pub command: Command,
+
+  /// Log folder to use instead of the configured ones.
+  pub log_path: Option<PathBuf>,
+}
+
+/// Parse the command-line arguments (sans the executable name). Returns `None` if there's no headless command, in
+/// which case the GUI is shown.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Invocation>, Cow<'static, str>> {
+  let mut command = None;
+  let mut avatar = None;
+  let mut from = None;
+  let mut to = None;
+  let mut log_path = None;
+  let mut unknown = None;
+  let mut args = args.into_iter();
+  while let Some(arg) = args.next() {
+    let mut value = |name: &str| args.next().ok_or_else(|| Cow::from(format!("{name} requires a value")));
+    match arg.as_str() {
+      "--tally-dps" | "--latest-stats" | "--help" | "-h" => {
+        if command.replace(arg).is_some() {
+          return Err(Cow::from("Only one command can be given"));
+        }
+      }
+      "--avatar" => avatar = Some(value("--avatar")?),
+      "--from" => from = Some(parse_time(&value("--from")?)?),
+      "--to" => to = Some(parse_time(&value("--to")?)?),
+      "--log-path" => log_path = Some(PathBuf::from(value("--log-path")?)),
+      "--portable" => (),
+      _ => {
+        unknown.get_or_insert(arg);
+      }
+    }
+  }
+
+  // Without a command, everything else is left to the GUI.
+  let Some(command) = command else {
+    return Ok(None);
+  };
+
+  if let Some(arg) = unknown {
+    return Err(Cow::from(format!("Unknown argument: {arg}")));
+  }
+
+  let command = match command.as_str() {
+    "--tally-dps" => {
+      let span = match (from, to) {
+        (Some(from), Some(to)) if from < to => Some((from, to)),
+        (Some(_), Some(_)) => return Err(Cow::from("--from must be before --to")),
+        (None, None) => None,
+        _ => return Err(Cow::from("--from and --to must be used together")),
+      };
+      Command::TallyDps {
+        avatar: get_avatar(avatar)?,
+        span,
+      }
+    }
+    "--latest-stats" => Command::LatestStats {
+      avatar: get_avatar(avatar)?,
+    },
+    _ => Command::Help,
+  };
+
+  Ok(Some(Invocation { command, log_path }))
+}
+
+/// Run a headless command, printing the result as JSON.
+pub fn run(invocation: Invocation, config: &Config) -> ExitCode {
+  let result = match invocation.command {
+    Command::TallyDps { avatar, span } => {
+      get_log_paths(invocation.log_path, config).and_then(|log_paths| tally_dps(log_paths, avatar, span))
+    }
+    Command::LatestStats { avatar } => {
+      get_log_paths(invocation.log_path, config).and_then(|log_paths| latest_stats(log_paths, avatar, config))
+    }
+    Command::Help => {
+      println!("{USAGE}");
+      return ExitCode::SUCCESS;
+    }
+  };
+
+  match result {
+    Ok(value) => {
+      println!("{value:#}");
+      ExitCode::SUCCESS
+    }
+    Err(err) => {
+      eprintln!("{APP_NAME}: {err}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn tally_dps(
+  log_paths: Vec<PathBuf>,
+  avatar: String,
+  span: Option<(NaiveDateTime, NaiveDateTime)>,
+) -> Result<Value, Cow<'static, str>> {
+  let span = match span {
+    Some((begin, end)) => Span { begin, end },
+    None => {
+      let future = log_data::find_last_fight_span(log_paths.clone(), avatar.clone(), FIGHT_GAP, Cancel::default());
+      futures::executor::block_on(future).ok_or_else(|| Cow::from(format!("No fights found for {avatar}")))?
+    }
+  };
+
+  let future = log_data::tally_dps(log_paths, avatar.clone(), span, Cancel::default(), AppState::default());
+  let tally = futures::executor::block_on(future);
+  if tally.avatar == 0 && tally.pet == 0 {
+    return Err(Cow::from(format!("No damage found for {avatar}")));
+  }
+
+  Ok(tally_json(&avatar, &tally))
+}
+
+fn tally_json(avatar: &str, tally: &DPSTally) -> Value {
+  let per_sec = |value: u64, secs: u64| value as f64 / secs.max(1) as f64;
+  let attack_json = |attack: &AttackTally| {
+    json!({
+      "name": attack.name,
+      "damage": attack.damage,
+      "hits": attack.hits,
+      "max_hit": attack.max_hit,
+    })
+  };
+  let attacks: Vec<Value> = tally.attacks.iter().map(attack_json).collect();
+  let pets: Vec<Value> = tally.pets.iter().map(attack_json).collect();
+
+  json!({
+    "avatar": avatar,
+    "begin": tally.span.begin.format(TIME_FORMAT).to_string(),
+    "end": tally.span.end.format(TIME_FORMAT).to_string(),
+    "secs": tally.secs,
+    "combat_secs": tally.combat_secs,
+    "damage": {
+      "avatar": tally.avatar,
+      "pet": tally.pet,
+    },
+    "dps": {
+      "total": per_sec(tally.avatar + tally.pet, tally.secs),
+      "avatar": per_sec(tally.avatar, tally.secs),
+      "pet": per_sec(tally.pet, tally.secs),
+    },
+    "damage_taken": {
+      "avatar": tally.avatar_taken,
+      "pet": tally.pet_taken,
+    },
+    "healing_done": tally.healing_done,
+    "healing_received": tally.healing_received,
+    "attacks": attacks,
+    "pets": pets,
+  })
+}
+
+fn latest_stats(log_paths: Vec<PathBuf>, avatar: String, config: &Config) -> Result<Value, Cow<'static, str>> {
+  let cache = StatsCache::new(config.stats_cache_path());
+  let future = log_data::get_stats_timestamps(
+    log_paths.clone(),
+    avatar.clone(),
+    Cancel::default(),
+    None,
+    cache,
+    AppState::default(),
+  );
+
+  // The most recent is first.
+  let Some(&ts) = futures::executor::block_on(future).first() else {
+    return Err(Cow::from(format!("No stats found for {avatar}")));
+  };
+
+  let stats = futures::executor::block_on(log_data::get_stats(log_paths, avatar.clone(), ts, Cancel::default()));
+  let stats: Map<String, Value> = stats
+    .iter()
+    .map(|(name, value)| (name.to_owned(), json!(value)))
+    .collect();
+  Ok(json!({
+    "avatar": avatar,
+    "date": util::timestamp_to_string(Some(ts)),
+    "timestamp": ts,
+    "stats": stats,
+  }))
+}
+
+fn get_log_paths(log_path: Option<PathBuf>, config: &Config) -> Result<Vec<PathBuf>, Cow<'static, str>> {
+  let log_paths = match log_path {
+    Some(log_path) => vec![log_path],
+    None => config.get_log_paths(),
+  };
+
+  if let Some(path) = log_paths.iter().find(|path| !path.is_dir()) {
+    return Err(Cow::from(format!("Log folder not found: {}", path.display())));
+  }
+
+  if log_paths.is_empty() {
+    return Err(Cow::from("No log folder is configured, use --log-path"));
+  }
+
+  Ok(log_paths)
+}
+
+fn get_avatar(avatar: Option<String>) -> Result<String, Cow<'static, str>> {
+  avatar
+    .filter(|avatar| !avatar.is_empty())
+    .ok_or_else(|| Cow::from("--avatar is required"))
+}
+
+fn parse_time(text: &str) -> Result<NaiveDateTime, Cow<'static, str>> {
+  NaiveDateTime::parse_from_str(text, TIME_FORMAT)
+    .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M"))
+    .map_err(|_| Cow::from(format!("Invalid time (expected \"YYYY-MM-DD HH:MM\"): {text}")))
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Maximum gap between attacks, in seconds, when finding the most recent fight. Same as the DPS dialog's default.
+const FIGHT_GAP: i64 = 30;
+
+const USAGE: &str = "\
+Usage:
+  cota [--portable]
+  cota --tally-dps --avatar NAME [--from \"YYYY-MM-DD HH:MM\" --to \"YYYY-MM-DD HH:MM\"] [--log-path PATH]
+  cota --latest-stats --avatar NAME [--log-path PATH]
+
+Without --from and --to, --tally-dps tallies the most recent fight. Results are printed as JSON.";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  fn parse(args: &[&str]) -> Result<Option<Invocation>, Cow<'static, str>> {
+    parse_args(args.iter().map(|arg| arg.to_string()))
+  }
+
+  #[test]
+  fn test_parse_args() {
+    // The GUI is the default.
+    assert_eq!(parse(&[]), Ok(None));
+    assert_eq!(parse(&["--portable"]), Ok(None));
+
+    let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let args = [
+      "--tally-dps",
+      "--avatar",
+      "Mira",
+      "--from",
+      "2024-05-01 20:00",
+      "--to",
+      "2024-05-01 21:00:30",
+    ];
+    assert_eq!(
+      parse(&args),
+      Ok(Some(Invocation {
+        command: Command::TallyDps {
+          avatar: String::from("Mira"),
+          span: Some((
+            date.and_hms_opt(20, 0, 0).unwrap(),
+            date.and_hms_opt(21, 0, 30).unwrap()
+          )),
+        },
+        log_path: None,
+      }))
+    );
+
+    assert_eq!(
+      parse(&["--latest-stats", "--avatar", "Mira", "--log-path", "/tmp/logs"]),
+      Ok(Some(Invocation {
+        command: Command::LatestStats {
+          avatar: String::from("Mira"),
+        },
+        log_path: Some(PathBuf::from("/tmp/logs")),
+      }))
+    );
+
+    // Errors.
+    assert!(parse(&["--latest-stats"]).is_err());
+    assert!(parse(&["--latest-stats", "--avatar"]).is_err());
+    assert!(parse(&["--latest-stats", "--tally-dps", "--avatar", "Mira"]).is_err());
+    assert!(parse(&["--latest-stats", "--avatar", "Mira", "--bogus"]).is_err());
+    assert!(parse(&["--bogus", "--latest-stats", "--avatar", "Mira"]).is_err());
+    assert!(parse(&["--tally-dps", "--avatar", "Mira", "--from", "2024-05-01 20:00"]).is_err());
+    assert!(parse(&[
+      "--tally-dps",
+      "--avatar",
+      "Mira",
+      "--from",
+      "yesterday",
+      "--to",
+      "today"
+    ])
+    .is_err());
+    let args = [
+      "--tally-dps",
+      "--avatar",
+      "Mira",
+      "--from",
+      "2024-05-01 21:00",
+      "--to",
+      "2024-05-01 20:00",
+    ];
+    assert!(parse(&args).is_err());
+  }
+}
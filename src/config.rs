@@ -1,28 +1,83 @@
 use crate::{
-  plant_info::CropTimer,
+  keymap::{Action, Hotkey, Keymap},
+  log_data::Span,
+  plant_info::{CropOrder, CropTimer},
+  search_dlg::SearchTerm,
   storage::Storage,
   util::{Page, APP_NAME},
 };
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
 use std::{
   collections::{BTreeMap, BTreeSet, HashMap},
   path::{Path, PathBuf},
 };
 
+/// Named skill plans, each mapping skill ids to current and target levels.
+pub type SkillPlans = BTreeMap<String, BTreeMap<u32, (i32, i32)>>;
+
+/// Per-avatar UI state.
+#[derive(Default, Serialize, Deserialize)]
+pub struct AvatarState {
+  /// Last page used with the avatar.
+  pub page: Option<Page>,
+
+  /// Last selected stats date.
+  pub stats_date: Option<i64>,
+
+  /// Expanded skill groups on the experience page.
+  pub expanded: BTreeSet<String>,
+}
+
 /// Companion of the Avatar configuration storage.
 #[derive(Clone)]
 pub struct Config {
   storage: Storage,
+  path: PathBuf,
+
+  /// Executable's folder when running in portable mode.
+  portable_dir: Option<PathBuf>,
 }
 
 impl Config {
-  pub fn new() -> Option<Self> {
-    let path = Self::path()?;
-    let storage = Storage::new(path)?;
-    Some(Self { storage })
+  /// Create the configuration. Portable mode is used if `portable` is true or if a `cota.portable` file exists next to
+  /// the executable.
+  pub fn new(portable: bool) -> Option<Self> {
+    let exe_dir = std::env::current_exe()
+      .ok()
+      .and_then(|path| Some(path.parent()?.to_path_buf()));
+    let portable_dir = get_portable_dir(exe_dir.as_deref(), portable);
+    let path = get_storage_path(portable_dir.as_deref(), dirs::config_dir().as_deref())?;
+    let storage = Storage::new(path.clone())?;
+    Some(Self {
+      storage,
+      path,
+      portable_dir,
+    })
+  }
+
+  /// Path of the configuration file.
+  pub fn path(&self) -> &Path {
+    &self.path
   }
 
-  fn path() -> Option<PathBuf> {
-    dirs::config_dir().map(|path| path.join(APP_NAME).with_extension("ron"))
+  /// Path of the `/stats` timestamps cache, next to the configuration file.
+  pub fn stats_cache_path(&self) -> PathBuf {
+    self.path.with_file_name(format!("{APP_NAME}_stats_cache.ron"))
+  }
+
+  /// Path of the skill data override, next to the configuration file.
+  pub fn skills_override_path(&self) -> PathBuf {
+    self.path.with_file_name("skills_override.json")
+  }
+
+  /// Take the warning about the configuration file being damaged when it was loaded. This is only returned once.
+  pub fn take_warning(&self) -> Option<String> {
+    self.storage.take_warning()
+  }
+
+  pub fn is_portable(&self) -> bool {
+    self.portable_dir.is_some()
   }
 
   fn get_sota_config_path() -> Option<PathBuf> {
@@ -30,24 +85,20 @@ impl Config {
     Some(path.join("Portalarium").join("Shroud of the Avatar"))
   }
 
-  fn get_default_log_path() -> Option<PathBuf> {
-    if let Some(path) = Self::get_sota_config_path() {
-      let path = path.join("ChatLogs");
-      if path.is_dir() {
-        return Some(path);
-      }
-    }
-    dirs::home_dir()
+  /// Check if the chat logs are in the standard location.
+  pub fn has_default_log_path(&self) -> bool {
+    let sota_path = Self::get_sota_config_path();
+    find_default_path(self.portable_dir.as_deref(), sota_path.as_deref(), "ChatLogs").is_some()
   }
 
-  fn get_default_save_game_path() -> Option<PathBuf> {
-    if let Some(path) = Self::get_sota_config_path() {
-      let path = path.join("SavedGames");
-      if path.is_dir() {
-        return Some(path);
-      }
-    }
-    dirs::home_dir()
+  fn get_default_log_path(&self) -> Option<PathBuf> {
+    let sota_path = Self::get_sota_config_path();
+    find_default_path(self.portable_dir.as_deref(), sota_path.as_deref(), "ChatLogs").or_else(dirs::home_dir)
+  }
+
+  fn get_default_save_game_path(&self) -> Option<PathBuf> {
+    let sota_path = Self::get_sota_config_path();
+    find_default_path(self.portable_dir.as_deref(), sota_path.as_deref(), "SavedGames").or_else(dirs::home_dir)
   }
 
   pub fn get_page(&self) -> Option<Page> {
@@ -59,21 +110,86 @@ impl Config {
     self.storage.persist();
   }
 
-  pub fn get_log_path(&self) -> Option<PathBuf> {
+  /// Get the hotkeys. Actions that haven't been changed use their default hotkey.
+  pub fn get_keymap(&self) -> Keymap {
+    let bindings: Option<BTreeMap<Action, Option<Hotkey>>> = self.storage.get_as(Config::KEYMAP_KEY);
+    Keymap::with_bindings(bindings.unwrap_or_default())
+  }
+
+  pub fn set_keymap(&mut self, keymap: &Keymap) {
+    // Remove the entry if the hotkeys are the defaults.
+    if *keymap == Keymap::default() {
+      self.storage.remove(Config::KEYMAP_KEY);
+    } else {
+      self.storage.set_as(Config::KEYMAP_KEY, keymap.bindings());
+    }
+
+    self.storage.persist();
+  }
+
+  pub fn get_window_size(&self) -> Option<(f32, f32)> {
+    self.storage.get_as(Config::WINDOW_SIZE_KEY)
+  }
+
+  pub fn set_window_size(&mut self, size: (f32, f32)) {
+    self.storage.set_as(Config::WINDOW_SIZE_KEY, &size);
+    self.storage.persist();
+  }
+
+  /// Get the window's outer position and the size of the monitor it was on.
+  pub fn get_window_pos(&self) -> Option<((f32, f32), (f32, f32))> {
+    self.storage.get_as(Config::WINDOW_POS_KEY)
+  }
+
+  pub fn set_window_pos(&mut self, pos: (f32, f32), monitor_size: (f32, f32)) {
+    self.storage.set_as(Config::WINDOW_POS_KEY, &(pos, monitor_size));
+    self.storage.persist();
+  }
+
+  /// Get the log folders. The first folder is stored the same way as when only one folder was supported.
+  pub fn get_log_paths(&self) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
     if let Some(path) = self.storage.get(Config::LOG_PATH_KEY) {
-      return Some(PathBuf::from(path));
+      paths.push(PathBuf::from(path));
+    } else if let Some(path) = self.get_default_log_path() {
+      paths.push(path);
     }
 
-    Self::get_default_log_path()
+    if let Some(extra) = self.storage.get_as::<Vec<String>>(Config::EXTRA_LOG_PATHS_KEY) {
+      paths.extend(extra.into_iter().map(PathBuf::from));
+    }
+
+    paths
   }
 
-  pub fn set_log_path(&mut self, path: &Path) {
-    if let Some(path) = path.to_str() {
-      self.storage.set(Config::LOG_PATH_KEY, path.to_owned());
-      self.storage.persist();
+  /// Check if a log folder has been chosen.
+  pub fn has_log_path(&self) -> bool {
+    self.storage.get(Config::LOG_PATH_KEY).is_some()
+  }
+
+  pub fn set_log_paths(&mut self, paths: &[PathBuf]) {
+    let mut items = Vec::with_capacity(paths.len());
+    for path in paths {
+      if let Some(path) = path.to_str() {
+        items.push(path.to_owned());
+      } else {
+        println!("Invalid unicode in path: {path:?}");
+      }
+    }
+
+    let mut items = items.into_iter();
+    match items.next() {
+      Some(path) => self.storage.set(Config::LOG_PATH_KEY, path),
+      None => self.storage.remove(Config::LOG_PATH_KEY),
+    }
+
+    let extra: Vec<String> = items.collect();
+    if extra.is_empty() {
+      self.storage.remove(Config::EXTRA_LOG_PATHS_KEY);
     } else {
-      println!("Invalid unicode in path: {path:?}");
+      self.storage.set_as(Config::EXTRA_LOG_PATHS_KEY, &extra);
     }
+    self.storage.persist();
   }
 
   pub fn get_save_game_path(&self) -> Option<PathBuf> {
@@ -81,7 +197,7 @@ impl Config {
       return Some(PathBuf::from(path));
     }
 
-    Self::get_default_save_game_path()
+    self.get_default_save_game_path()
   }
 
   pub fn set_save_game_path(&mut self, path: &Path) {
@@ -93,6 +209,16 @@ impl Config {
     }
   }
 
+  /// Check if the detected game folders have been offered on startup.
+  pub fn get_folders_offered(&self) -> bool {
+    self.storage.get_as(Config::FOLDERS_OFFERED_KEY).unwrap_or(false)
+  }
+
+  pub fn set_folders_offered(&mut self) {
+    self.storage.set_as(Config::FOLDERS_OFFERED_KEY, &true);
+    self.storage.persist();
+  }
+
   pub fn get_stats_avatar(&self) -> Option<String> {
     self.storage.get(Config::STATS_AVATAR_KEY)
   }
@@ -144,6 +270,155 @@ impl Config {
     self.storage.persist();
   }
 
+  /// Template inserted by the notes dialog's "New from Template" button.
+  pub fn get_notes_template(&self) -> String {
+    self
+      .storage
+      .get(Config::NOTES_TEMPLATE_KEY)
+      .unwrap_or_else(|| String::from(DEFAULT_NOTES_TEMPLATE))
+  }
+
+  pub fn set_notes_template(&mut self, template: String) {
+    // Remove the entry if template is the default.
+    if template == DEFAULT_NOTES_TEMPLATE {
+      self.storage.remove(Config::NOTES_TEMPLATE_KEY);
+    } else {
+      self.storage.set(Config::NOTES_TEMPLATE_KEY, template);
+    }
+
+    self.storage.persist();
+  }
+
+  /// Get the custom template for copying a DPS summary.
+  pub fn get_dps_summary_template(&self) -> Option<String> {
+    self.storage.get(Config::DPS_SUMMARY_TEMPLATE_KEY)
+  }
+
+  pub fn set_dps_summary_template(&mut self, template: &str) {
+    if template.is_empty() {
+      self.storage.remove(Config::DPS_SUMMARY_TEMPLATE_KEY);
+    } else {
+      self.storage.set(Config::DPS_SUMMARY_TEMPLATE_KEY, template.to_owned());
+    }
+    self.storage.persist();
+  }
+
+  pub fn get_dps_spans(&self, avatar: &str) -> Option<BTreeMap<String, Span>> {
+    if avatar.is_empty() {
+      return None;
+    }
+
+    let key = format!("{avatar} {}", Config::DPS_SPANS_KEY);
+    self.storage.get_as(&key)
+  }
+
+  pub fn set_dps_spans(&mut self, avatar: &str, spans: &BTreeMap<String, Span>) {
+    if avatar.is_empty() {
+      return;
+    }
+
+    // Remove the entry if spans is empty.
+    let key = format!("{avatar} {}", Config::DPS_SPANS_KEY);
+    if spans.is_empty() {
+      self.storage.remove(&key);
+    } else {
+      self.storage.set_as(&key, spans);
+    }
+
+    self.storage.persist();
+  }
+
+  pub fn get_avatar_state(&self, avatar: &str) -> AvatarState {
+    if avatar.is_empty() {
+      return AvatarState::default();
+    }
+
+    let key = format!("{avatar} {}", Config::AVATAR_STATE_KEY);
+    self.storage.get_as(&key).unwrap_or_default()
+  }
+
+  pub fn set_avatar_page(&mut self, avatar: &str, page: Page) {
+    self.update_avatar_state(avatar, |state| state.page = Some(page));
+  }
+
+  pub fn set_avatar_stats_date(&mut self, avatar: &str, date: Option<i64>) {
+    self.update_avatar_state(avatar, |state| state.stats_date = date);
+  }
+
+  pub fn set_avatar_expanded(&mut self, avatar: &str, expanded: &BTreeSet<String>) {
+    self.update_avatar_state(avatar, |state| state.expanded.clone_from(expanded));
+  }
+
+  /// Get the `/xp` samples (timestamp and adventurer experience) recorded for the avatar, oldest first.
+  pub fn get_exp_samples(&self, avatar: &str) -> Vec<(i64, i64)> {
+    if avatar.is_empty() {
+      return Vec::new();
+    }
+
+    let key = format!("{avatar} {}", Config::EXP_SAMPLES_KEY);
+    self.storage.get_as(&key).unwrap_or_default()
+  }
+
+  pub fn set_exp_samples(&mut self, avatar: &str, samples: &[(i64, i64)]) {
+    if avatar.is_empty() {
+      return;
+    }
+
+    let key = format!("{avatar} {}", Config::EXP_SAMPLES_KEY);
+    self.storage.set_as(&key, &samples);
+    self.storage.persist();
+  }
+
+  /// Remove the UI state and experience samples for avatars that are no longer in the list.
+  pub fn remove_avatar_states(&mut self, avatars: &[String]) {
+    let suffixes = [Config::AVATAR_STATE_KEY, Config::EXP_SAMPLES_KEY].map(|key| format!(" {key}"));
+    let mut removed = false;
+    for key in self.storage.keys() {
+      let avatar = suffixes.iter().find_map(|suffix| key.strip_suffix(suffix.as_str()));
+      if let Some(avatar) = avatar {
+        if !avatars.iter().any(|name| name == avatar) {
+          self.storage.remove(&key);
+          removed = true;
+        }
+      }
+    }
+
+    if removed {
+      self.storage.persist();
+    }
+  }
+
+  fn update_avatar_state<F: FnOnce(&mut AvatarState)>(&mut self, avatar: &str, update: F) {
+    if avatar.is_empty() {
+      return;
+    }
+
+    let mut state = self.get_avatar_state(avatar);
+    update(&mut state);
+
+    let key = format!("{avatar} {}", Config::AVATAR_STATE_KEY);
+    self.storage.set_as(&key, &state);
+    self.storage.persist();
+  }
+
+  pub fn get_filter_history(&self) -> Option<Vec<SearchTerm>> {
+    self.storage.get_as(Config::FILTER_HISTORY_KEY)
+  }
+
+  pub fn set_filter_history(&mut self, history: &Vec<SearchTerm>) {
+    self.storage.set_as(Config::FILTER_HISTORY_KEY, history);
+    self.storage.persist();
+  }
+
+  pub fn get_log_search_history(&self) -> Option<Vec<SearchTerm>> {
+    self.storage.get_as(Config::LOG_SEARCH_HISTORY_KEY)
+  }
+
+  pub fn set_log_search_history(&mut self, history: &Vec<SearchTerm>) {
+    self.storage.set_as(Config::LOG_SEARCH_HISTORY_KEY, history);
+    self.storage.persist();
+  }
+
   pub fn get_crop_timers(&self) -> Option<Vec<CropTimer>> {
     self.storage.get_as(Config::CROP_TIMERS_KEY)
   }
@@ -159,6 +434,34 @@ impl Config {
     self.storage.persist();
   }
 
+  /// When the crop timers were last checked.
+  pub fn get_crops_checked(&self) -> Option<NaiveDateTime> {
+    self.storage.get_as(Config::CROPS_CHECKED_KEY)
+  }
+
+  pub fn set_crops_checked(&mut self, date_time: NaiveDateTime) {
+    self.storage.set_as(Config::CROPS_CHECKED_KEY, &date_time);
+    self.storage.persist();
+  }
+
+  pub fn get_crop_order(&self) -> CropOrder {
+    self.storage.get_as(Config::CROP_ORDER_KEY).unwrap_or_default()
+  }
+
+  pub fn set_crop_order(&mut self, order: CropOrder) {
+    self.storage.set_as(Config::CROP_ORDER_KEY, &order);
+    self.storage.persist();
+  }
+
+  pub fn get_group_crops(&self) -> bool {
+    self.storage.get_as(Config::GROUP_CROPS_KEY).unwrap_or(false)
+  }
+
+  pub fn set_group_crops(&mut self, group: bool) {
+    self.storage.set_as(Config::GROUP_CROPS_KEY, &group);
+    self.storage.persist();
+  }
+
   pub fn get_crop_descriptions(&self) -> Option<BTreeSet<String>> {
     self.storage.get_as(Config::CROP_DESCRIPTIONS_KEY)
   }
@@ -174,6 +477,169 @@ impl Config {
     self.storage.persist();
   }
 
+  pub fn get_crop_lots(&self) -> Option<BTreeSet<String>> {
+    self.storage.get_as(Config::CROP_LOTS_KEY)
+  }
+
+  pub fn set_crop_lots(&mut self, lots: &BTreeSet<String>) {
+    // Remove the entry if lots is empty.
+    if lots.is_empty() {
+      self.storage.remove(Config::CROP_LOTS_KEY);
+    } else {
+      self.storage.set_as(Config::CROP_LOTS_KEY, lots);
+    }
+
+    self.storage.persist();
+  }
+
+  /// Check if the pooled experience rates are used for producer skills.
+  pub fn get_exp_pooled(&self) -> bool {
+    self.storage.get_as(Config::EXP_POOLED_KEY).unwrap_or(false)
+  }
+
+  pub fn set_exp_pooled(&mut self, pooled: bool) {
+    if pooled {
+      self.storage.set_as(Config::EXP_POOLED_KEY, &pooled);
+    } else {
+      self.storage.remove(Config::EXP_POOLED_KEY);
+    }
+    self.storage.persist();
+  }
+
+  pub fn get_clock_12h(&self) -> bool {
+    self.storage.get_as(Config::CLOCK_12H_KEY).unwrap_or(false)
+  }
+
+  pub fn set_clock_12h(&mut self, clock_12h: bool) {
+    self.storage.set_as(Config::CLOCK_12H_KEY, &clock_12h);
+    self.storage.persist();
+  }
+
+  /// Check if the color-blind friendly palette is used.
+  pub fn get_color_blind(&self) -> bool {
+    self.storage.get_as(Config::COLOR_BLIND_KEY).unwrap_or(false)
+  }
+
+  pub fn set_color_blind(&mut self, color_blind: bool) {
+    if color_blind {
+      self.storage.set_as(Config::COLOR_BLIND_KEY, &color_blind);
+    } else {
+      self.storage.remove(Config::COLOR_BLIND_KEY);
+    }
+    self.storage.persist();
+  }
+
+  /// Get the mini chronometer's outer position.
+  pub fn get_mini_chronometer_pos(&self) -> Option<(f32, f32)> {
+    self.storage.get_as(Config::MINI_CHRONOMETER_POS_KEY)
+  }
+
+  pub fn set_mini_chronometer_pos(&mut self, pos: (f32, f32)) {
+    self.storage.set_as(Config::MINI_CHRONOMETER_POS_KEY, &pos);
+    self.storage.persist();
+  }
+
+  /// Check if the mini chronometer shows the next crop event.
+  pub fn get_mini_chronometer_crops(&self) -> bool {
+    self.storage.get_as(Config::MINI_CHRONOMETER_CROPS_KEY).unwrap_or(false)
+  }
+
+  pub fn set_mini_chronometer_crops(&mut self, crops: bool) {
+    self.storage.set_as(Config::MINI_CHRONOMETER_CROPS_KEY, &crops);
+    self.storage.persist();
+  }
+
+  /// Check if new releases are checked for once a week.
+  pub fn get_update_check(&self) -> bool {
+    self.storage.get_as(Config::UPDATE_CHECK_KEY).unwrap_or(false)
+  }
+
+  pub fn set_update_check(&mut self, check: bool) {
+    if check {
+      self.storage.set_as(Config::UPDATE_CHECK_KEY, &check);
+    } else {
+      self.storage.remove(Config::UPDATE_CHECK_KEY);
+    }
+    self.storage.persist();
+  }
+
+  /// Get when new releases were last checked for automatically.
+  pub fn get_update_checked(&self) -> Option<NaiveDateTime> {
+    self.storage.get_as(Config::UPDATE_CHECKED_KEY)
+  }
+
+  pub fn set_update_checked(&mut self, date_time: NaiveDateTime) {
+    self.storage.set_as(Config::UPDATE_CHECKED_KEY, &date_time);
+    self.storage.persist();
+  }
+
+  pub fn get_rift_alerts(&self) -> Option<BTreeSet<String>> {
+    self.storage.get_as(Config::RIFT_ALERTS_KEY)
+  }
+
+  pub fn set_rift_alerts(&mut self, rifts: &BTreeSet<String>) {
+    // Remove the entry if rifts is empty.
+    if rifts.is_empty() {
+      self.storage.remove(Config::RIFT_ALERTS_KEY);
+    } else {
+      self.storage.set_as(Config::RIFT_ALERTS_KEY, rifts);
+    }
+
+    self.storage.persist();
+  }
+
+  pub fn get_pinned_rifts(&self) -> Option<BTreeSet<String>> {
+    self.storage.get_as(Config::PINNED_RIFTS_KEY)
+  }
+
+  pub fn set_pinned_rifts(&mut self, rifts: &BTreeSet<String>) {
+    // Remove the entry if rifts is empty.
+    if rifts.is_empty() {
+      self.storage.remove(Config::PINNED_RIFTS_KEY);
+    } else {
+      self.storage.set_as(Config::PINNED_RIFTS_KEY, rifts);
+    }
+
+    self.storage.persist();
+  }
+
+  pub fn get_rift_alert_minutes(&self) -> Option<u32> {
+    self.storage.get_as(Config::RIFT_ALERT_MINUTES_KEY)
+  }
+
+  pub fn set_rift_alert_minutes(&mut self, minutes: u32) {
+    self.storage.set_as(Config::RIFT_ALERT_MINUTES_KEY, &minutes);
+    self.storage.persist();
+  }
+
+  /// Number of save-game backups to keep when storing.
+  pub fn get_save_backups(&self) -> usize {
+    self
+      .storage
+      .get_as(Config::SAVE_BACKUPS_KEY)
+      .unwrap_or(DEFAULT_SAVE_BACKUPS)
+  }
+
+  pub fn set_save_backups(&mut self, backups: usize) {
+    self.storage.set_as(Config::SAVE_BACKUPS_KEY, &backups);
+    self.storage.persist();
+  }
+
+  pub fn get_offline_expanded(&self) -> BTreeSet<String> {
+    self.storage.get_as(Config::OFFLINE_EXPANDED_KEY).unwrap_or_default()
+  }
+
+  pub fn set_offline_expanded(&mut self, expanded: &BTreeSet<String>) {
+    // Remove the entry if expanded is empty.
+    if expanded.is_empty() {
+      self.storage.remove(Config::OFFLINE_EXPANDED_KEY);
+    } else {
+      self.storage.set_as(Config::OFFLINE_EXPANDED_KEY, expanded);
+    }
+
+    self.storage.persist();
+  }
+
   pub fn get_avatar_skills(&self, avatar: &str) -> Option<HashMap<u32, (i32, i32)>> {
     if avatar.is_empty() {
       return None;
@@ -206,13 +672,158 @@ impl Config {
     self.storage.persist();
   }
 
+  pub fn get_skill_plans(&self, avatar: &str) -> Option<SkillPlans> {
+    if avatar.is_empty() {
+      return None;
+    }
+
+    let key = format!("{avatar} {}", Config::SKILL_PLANS_KEY);
+    self.storage.get_as(&key)
+  }
+
+  pub fn set_skill_plans(&mut self, avatar: &str, plans: &SkillPlans) {
+    if avatar.is_empty() {
+      return;
+    }
+
+    // Remove the entry if plans is empty.
+    let key = format!("{avatar} {}", Config::SKILL_PLANS_KEY);
+    if plans.is_empty() {
+      self.storage.remove(&key);
+    } else {
+      self.storage.set_as(&key, plans);
+    }
+
+    self.storage.persist();
+  }
+
   const LOG_PATH_KEY: &'static str = "log_path";
+  const EXTRA_LOG_PATHS_KEY: &'static str = "extra_log_paths";
   const SAVE_PATH_KEY: &'static str = "save_path";
+  const SAVE_BACKUPS_KEY: &'static str = "save_backups";
+  const FOLDERS_OFFERED_KEY: &'static str = "folders_offered";
   const STATS_AVATAR_KEY: &'static str = "stats_avatar";
   const EXP_AVATAR_KEY: &'static str = "experience_avatar";
+  const EXP_POOLED_KEY: &'static str = "experience_pooled";
   const AVATAR_SKILLS: &'static str = "skills";
+  const SKILL_PLANS_KEY: &'static str = "skill_plans";
   const CROP_TIMERS_KEY: &'static str = "plants";
   const CROP_DESCRIPTIONS_KEY: &'static str = "crop_descriptions";
+  const CROP_LOTS_KEY: &'static str = "crop_lots";
+  const CROPS_CHECKED_KEY: &'static str = "crops_checked";
+  const CROP_ORDER_KEY: &'static str = "crop_order";
+  const GROUP_CROPS_KEY: &'static str = "group_crops";
+  const RIFT_ALERTS_KEY: &'static str = "rift_alerts";
+  const PINNED_RIFTS_KEY: &'static str = "pinned_rifts";
+  const CLOCK_12H_KEY: &'static str = "clock_12h";
+  const COLOR_BLIND_KEY: &'static str = "color_blind";
+  const RIFT_ALERT_MINUTES_KEY: &'static str = "rift_alert_minutes";
+  const MINI_CHRONOMETER_POS_KEY: &'static str = "mini_chronometer_pos";
+  const MINI_CHRONOMETER_CROPS_KEY: &'static str = "mini_chronometer_crops";
+  const UPDATE_CHECK_KEY: &'static str = "update_check";
+  const UPDATE_CHECKED_KEY: &'static str = "update_checked";
   const NOTES_KEY: &'static str = "notes";
+  const NOTES_TEMPLATE_KEY: &'static str = "notes_template";
+  const OFFLINE_EXPANDED_KEY: &'static str = "offline_expanded";
+  const AVATAR_STATE_KEY: &'static str = "ui_state";
+  const EXP_SAMPLES_KEY: &'static str = "exp_samples";
+  const DPS_SPANS_KEY: &'static str = "dps_spans";
+  const DPS_SUMMARY_TEMPLATE_KEY: &'static str = "dps_summary_template";
+  const FILTER_HISTORY_KEY: &'static str = "filter_history";
+  const LOG_SEARCH_HISTORY_KEY: &'static str = "log_search_history";
+  const KEYMAP_KEY: &'static str = "keymap";
   const PAGE_KEY: &'static str = "page";
+  const WINDOW_SIZE_KEY: &'static str = "window_size";
+  const WINDOW_POS_KEY: &'static str = "window_pos";
+}
+
+/// Notes template used until a different one is saved.
+const DEFAULT_NOTES_TEMPLATE: &str = "# Build\n\n- \n\n# Reagents\n\n- \n\n# Links\n\n- https://\n";
+
+/// Number of save-game backups kept by default.
+const DEFAULT_SAVE_BACKUPS: usize = 5;
+
+/// Name of the file that enables portable mode when it's next to the executable.
+const PORTABLE_FILE: &str = "cota.portable";
+
+/// Get the executable's folder if portable mode is enabled.
+fn get_portable_dir(exe_dir: Option<&Path>, portable: bool) -> Option<PathBuf> {
+  let exe_dir = exe_dir?;
+  if portable || exe_dir.join(PORTABLE_FILE).is_file() {
+    return Some(exe_dir.to_path_buf());
+  }
+  None
+}
+
+/// Get the path of the configuration file, next to the executable in portable mode.
+fn get_storage_path(portable_dir: Option<&Path>, config_dir: Option<&Path>) -> Option<PathBuf> {
+  let dir = portable_dir.or(config_dir)?;
+  Some(dir.join(APP_NAME).with_extension("ron"))
+}
+
+/// Find a default SotA folder. In portable mode, the executable's folder is checked before the SotA config folder.
+fn find_default_path(portable_dir: Option<&Path>, sota_dir: Option<&Path>, name: &str) -> Option<PathBuf> {
+  [portable_dir, sota_dir]
+    .into_iter()
+    .flatten()
+    .map(|dir| dir.join(name))
+    .find(|path| path.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::TempDir;
+  use std::fs;
+
+  fn temp_dir(name: &str) -> TempDir {
+    TempDir::new(&format!("config_{name}"))
+  }
+
+  #[test]
+  fn test_standard_paths() {
+    let exe_dir = temp_dir("standard_exe");
+    let config_dir = temp_dir("standard_config");
+    let sota_dir = config_dir.join("Portalarium");
+    fs::create_dir_all(sota_dir.join("ChatLogs")).unwrap();
+
+    // Without the flag or the marker file the OS config folder is used.
+    let portable_dir = get_portable_dir(Some(&exe_dir), false);
+    assert_eq!(portable_dir, None);
+    let path = get_storage_path(portable_dir.as_deref(), Some(&config_dir));
+    assert_eq!(path, Some(config_dir.join("cota.ron")));
+
+    // Folders next to the executable are ignored.
+    fs::create_dir_all(exe_dir.join("ChatLogs")).unwrap();
+    let path = find_default_path(portable_dir.as_deref(), Some(&sota_dir), "ChatLogs");
+    assert_eq!(path, Some(sota_dir.join("ChatLogs")));
+    assert_eq!(find_default_path(None, Some(&sota_dir), "SavedGames"), None);
+  }
+
+  #[test]
+  fn test_portable_paths() {
+    let exe_dir = temp_dir("portable_exe");
+    let config_dir = temp_dir("portable_config");
+    let sota_dir = config_dir.join("Portalarium");
+    fs::create_dir_all(sota_dir.join("ChatLogs")).unwrap();
+    fs::create_dir_all(sota_dir.join("SavedGames")).unwrap();
+
+    // The flag enables portable mode.
+    assert_eq!(get_portable_dir(Some(&exe_dir), true), Some(exe_dir.to_path_buf()));
+    assert_eq!(get_portable_dir(None, true), None);
+
+    // So does the marker file.
+    fs::write(exe_dir.join(PORTABLE_FILE), "").unwrap();
+    let portable_dir = get_portable_dir(Some(&exe_dir), false);
+    assert_eq!(portable_dir, Some(exe_dir.to_path_buf()));
+    let path = get_storage_path(portable_dir.as_deref(), Some(&config_dir));
+    assert_eq!(path, Some(exe_dir.join("cota.ron")));
+
+    // Folders next to the executable are preferred, otherwise the SotA folders are used.
+    fs::create_dir_all(exe_dir.join("ChatLogs")).unwrap();
+    let path = find_default_path(portable_dir.as_deref(), Some(&sota_dir), "ChatLogs");
+    assert_eq!(path, Some(exe_dir.join("ChatLogs")));
+    let path = find_default_path(portable_dir.as_deref(), Some(&sota_dir), "SavedGames");
+    assert_eq!(path, Some(sota_dir.join("SavedGames")));
+  }
 }
@@ -1,23 +1,41 @@
-use crate::util::AppState;
+use crate::{
+  changes_dlg,
+  offline::ChangeGroup,
+  theme::Color,
+  util::{AppState, APP_NAME},
+};
 use eframe::{
   egui::{Context, Key, RichText, Window},
   emath::Align2,
-  epaint::Color32,
 };
+use std::path::PathBuf;
 
 #[derive(Clone, Copy)]
 pub enum Choice {
   Save,
   Discard,
+  Cancel,
+
+  /// Store over a save-game that was modified after it was loaded.
+  Overwrite,
+
+  /// Load the modified save-game again.
+  Reload,
 }
 
 pub enum Hence {
   Load,
+  Restore(PathBuf),
   Exit,
 }
 
 pub struct ConfirmDlg {
   file: String,
+  // Set when asking about a save-game that was modified outside of the app, rather than about unsaved changes.
+  modified: bool,
+
+  // Changes that would be discarded.
+  changes: Vec<ChangeGroup>,
   state: AppState,
   choice: Option<Choice>,
   hence: Option<Hence>,
@@ -29,6 +47,8 @@ impl ConfirmDlg {
   pub fn new(state: AppState) -> Self {
     Self {
       file: String::new(),
+      modified: false,
+      changes: Vec::new(),
       state,
       choice: None,
       hence: None,
@@ -53,42 +73,78 @@ impl ConfirmDlg {
         .show(ctx, |ui| {
           ui.add_space(8.0);
           ui.vertical_centered(|ui| {
-            let text = RichText::from(format!(
-              "Changes have been made to {}.\nWhat would you like to do?",
-              self.file
-            ))
-            .color(Color32::LIGHT_RED);
-            ui.label(text);
+            let text = if self.modified {
+              format!(
+                "{} was modified outside of {APP_NAME} after it was loaded.\nWhat would you like to do?",
+                self.file
+              )
+            } else {
+              format!("Changes have been made to {}.\nWhat would you like to do?", self.file)
+            };
+            ui.label(RichText::from(text).color(Color::Error.get()));
           });
+          if !self.changes.is_empty() {
+            ui.add_space(8.0);
+            changes_dlg::show_changes(ui, &self.changes, available.height() * 0.4);
+          }
           ui.add_space(8.0);
           ui.separator();
           ui.horizontal(|ui| {
-            if ui.button("Save").clicked() {
-              self.close(Some(Choice::Save));
+            if self.modified {
+              if ui.button("Overwrite").clicked() {
+                self.close(Choice::Overwrite);
+              }
+              if ui.button("Reload").on_hover_text("Discard the changes").clicked() {
+                self.close(Choice::Reload);
+              }
+            } else {
+              if ui.button("Save").clicked() {
+                self.close(Choice::Save);
+              }
+              if ui.button("Discard").clicked() {
+                self.close(Choice::Discard);
+              }
             }
-            if ui.button("Discard").clicked() {
-              self.close(Some(Choice::Discard));
+            if ui.button("Cancel").clicked() {
+              self.close(Choice::Cancel);
             }
           });
         });
       if !open {
-        self.close(None);
+        self.close(Choice::Cancel);
       }
     }
 
     self.visible
   }
 
-  pub fn open(&mut self, file: String, hence: Hence) {
+  /// Ask what to do about unsaved changes. `changes` lists what would be discarded.
+  pub fn open(&mut self, file: String, changes: Vec<ChangeGroup>, hence: Hence) {
     if !self.visible {
       self.state.set_disabled(false);
       self.file = file;
+      self.modified = false;
+      self.changes = changes;
       self.hence = Some(hence);
       self.choice = None;
       self.visible = true;
     }
   }
 
+  /// Ask what to do about a save-game that was modified after it was loaded. `hence` is what to do after it's been
+  /// dealt with, if anything.
+  pub fn open_modified(&mut self, file: String, hence: Option<Hence>) {
+    if !self.visible {
+      self.state.set_disabled(false);
+      self.file = file;
+      self.modified = true;
+      self.changes.clear();
+      self.hence = hence;
+      self.choice = None;
+      self.visible = true;
+    }
+  }
+
   pub fn visible(&self) -> bool {
     self.visible
   }
@@ -97,27 +153,68 @@ impl ConfirmDlg {
     self.choice.take()
   }
 
+  /// Take what to do next. Nothing is taken while the dialog is showing, since the user hasn't answered yet.
   pub fn take_hence(&mut self) -> Option<Hence> {
+    if self.visible {
+      return None;
+    }
     self.hence.take()
   }
 
-  fn close(&mut self, choice: Option<Choice>) {
+  fn close(&mut self, choice: Choice) {
     if self.visible {
       self.state.set_disabled(false);
-      if choice.is_none() {
-        // If choice is None then hence is None.
+      if let Choice::Cancel = choice {
+        // Abort whatever was going to happen next.
         self.hence = None;
       }
-      self.choice = choice;
+      self.choice = Some(choice);
       self.visible = false;
     }
   }
 
   fn handle_hotkeys(&mut self, ctx: &Context) {
-    if ctx.input(|state| state.key_pressed(Key::Enter)) {
-      self.close(Some(Choice::Save));
+    if !self.modified && ctx.input(|state| state.key_pressed(Key::Enter)) {
+      self.close(Choice::Save);
     } else if ctx.input(|state| state.key_pressed(Key::Escape)) {
-      self.close(None);
+      self.close(Choice::Cancel);
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hence_after_modified() {
+    let mut dlg = ConfirmDlg::new(AppState::default());
+    dlg.open("test.sota".into(), Vec::new(), Hence::Exit);
+    assert!(dlg.take_hence().is_none());
+
+    // Save, which finds that the save-game was modified on disk.
+    dlg.close(Choice::Save);
+    assert!(matches!(dlg.take_choice(), Some(Choice::Save)));
+    let hence = dlg.take_hence();
+    dlg.open_modified("test.sota".into(), hence);
+
+    // Exiting waits for the user to answer.
+    assert!(dlg.take_hence().is_none());
+    dlg.close(Choice::Overwrite);
+    assert!(matches!(dlg.take_choice(), Some(Choice::Overwrite)));
+    assert!(matches!(dlg.take_hence(), Some(Hence::Exit)));
+  }
+
+  #[test]
+  fn test_hence_canceled() {
+    let mut dlg = ConfirmDlg::new(AppState::default());
+    dlg.open("test.sota".into(), Vec::new(), Hence::Load);
+    dlg.close(Choice::Save);
+    let hence = dlg.take_hence();
+    dlg.open_modified("test.sota".into(), hence);
+
+    // Canceling aborts the load.
+    dlg.close(Choice::Cancel);
+    assert!(dlg.take_hence().is_none());
+  }
+}
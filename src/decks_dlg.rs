@@ -0,0 +1,146 @@
+use crate::{
+  game_data::{Deck, SkillLvl},
+  theme::Color,
+  util::{self, AppState},
+};
+use eframe::{
+  egui::{ComboBox, Context, Grid, Key, Label, RichText, ScrollArea, Ui, Window},
+  emath::Align2,
+  epaint::Color32,
+};
+
+pub struct DecksDlg {
+  state: AppState,
+  visible: bool,
+}
+
+impl DecksDlg {
+  pub fn new(state: AppState) -> Self {
+    Self { state, visible: false }
+  }
+
+  /// Show the combat decks. Slots can only be assigned to skills that are trained.
+  pub fn show(&mut self, decks: &mut [Deck], skills: &[&SkillLvl], editable: bool, ctx: &Context) -> bool {
+    let mut modified = false;
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      // Trained skills, sorted by name.
+      let mut trained: Vec<&SkillLvl> = skills.iter().copied().filter(|skill| skill.level > 0).collect();
+      trained.sort_by_key(|skill| skill.info.name);
+
+      let mut open = true;
+      Window::new(RichText::from("🂠  Decks").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          ScrollArea::vertical().max_height(DECKS_HEIGHT).show(ui, |ui| {
+            for (deck_idx, deck) in decks.iter_mut().enumerate() {
+              const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+              ui.label(RichText::from(&deck.name).color(HEADER_COLOR));
+              Grid::new(format!("deck_grid_{deck_idx}")).striped(true).show(ui, |ui| {
+                for (slot_idx, slot) in deck.slots.iter_mut().enumerate() {
+                  if show_slot(ui, deck_idx, slot_idx, slot, skills, &trained, editable) {
+                    modified = true;
+                  }
+                  ui.end_row();
+                }
+              });
+              ui.add_space(ui.spacing().item_spacing.y);
+            }
+          });
+
+          if !editable {
+            ui.label("Slots can't be edited because the skills are unavailable.");
+          }
+
+          ui.separator();
+          ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+              self.close();
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+    modified
+  }
+
+  pub fn open(&mut self) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      self.visible = true;
+    }
+  }
+
+  pub fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
+
+/// Show a deck slot and return true if the assignment was changed.
+fn show_slot(
+  ui: &mut Ui,
+  deck_idx: usize,
+  slot_idx: usize,
+  slot: &mut Option<u32>,
+  skills: &[&SkillLvl],
+  trained: &[&SkillLvl],
+  editable: bool,
+) -> bool {
+  const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+  ui.label(RichText::from(format!("{}", slot_idx + 1)).color(NAME_COLOR));
+
+  let skill = slot.and_then(|id| skills.iter().find(|skill| skill.info.id == id));
+  let text = match (*slot, skill) {
+    (None, _) => String::from(EMPTY_TEXT),
+    (Some(_), Some(skill)) => String::from(skill.info.name),
+    (Some(id), None) => format!("Unknown ({id})"),
+  };
+
+  let mut changed = false;
+  if editable {
+    let before = *slot;
+    ComboBox::from_id_salt(("deck_slot", deck_idx, slot_idx))
+      .width(SLOT_WIDTH)
+      .selected_text(text)
+      .height(util::button_size(ui) * 12.0)
+      .show_ui(ui, |ui| {
+        ui.selectable_value(slot, None, EMPTY_TEXT);
+        for skill in trained {
+          ui.selectable_value(slot, Some(skill.info.id), skill.info.name);
+        }
+      });
+    changed = *slot != before;
+  } else {
+    ui.add_sized([SLOT_WIDTH, ui.spacing().interact_size.y], Label::new(text));
+  }
+
+  // Flag glyphs that aren't trained.
+  if editable && slot.is_some() && skill.is_none_or(|skill| skill.level <= 0) {
+    ui.label(RichText::from("⚠").color(Color::Error.get()))
+      .on_hover_text("This skill isn't trained");
+  } else {
+    ui.label("");
+  }
+
+  changed
+}
+
+const EMPTY_TEXT: &str = "Empty";
+const SLOT_WIDTH: f32 = 200.0;
+const DECKS_HEIGHT: f32 = 480.0;
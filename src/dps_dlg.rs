@@ -1,34 +1,44 @@
 use crate::{
+  config::Config,
   log_data::{self, DPSTally, Span},
-  util::{AppState, Cancel},
+  theme::Color,
+  util::{self, AppState, Cancel},
 };
 use chrono::{Local, NaiveDateTime, NaiveTime, Timelike};
 use eframe::{
-  egui::{Context, DragValue, Grid, Key, RichText, Ui, Window},
+  egui::{
+    Button, CollapsingHeader, ComboBox, Context, DragValue, Grid, Key, RichText, ScrollArea, TextEdit, Ui, Window,
+  },
   emath::Align2,
   epaint::Color32,
 };
 use egui_extras::DatePickerButton;
 use futures::{channel::mpsc, executor::ThreadPool};
 use mpsc::{UnboundedReceiver, UnboundedSender};
-use num_format::Locale;
-use std::path::{Path, PathBuf};
+use num_format::{Locale, ToFormattedString};
+use std::{collections::BTreeMap, path::PathBuf};
 
 pub struct DPSDlg {
+  config: Config,
   state: AppState,
   threads: ThreadPool,
   locale: Locale,
-  log_path: PathBuf,
+  log_paths: Vec<PathBuf>,
   title: String,
   avatar: String,
   span: Span,
+  spans: BTreeMap<String, Span>,
+  span_name: String,
+  gap: i64,
   channel: Channel,
   tally: Option<DPSTally>,
+  template: String,
+  error: Option<String>,
   visible: bool,
 }
 
 impl DPSDlg {
-  pub fn new(state: AppState, threads: ThreadPool, locale: Locale) -> Self {
+  pub fn new(config: Config, state: AppState, threads: ThreadPool, locale: Locale) -> Self {
     let (tx, rx) = mpsc::unbounded();
     let cancel = Some(Cancel::default());
     let channel = Channel { tx, rx, cancel };
@@ -38,37 +48,54 @@ impl DPSDlg {
     let begin = NaiveDateTime::new(date, NaiveTime::from_hms_opt(0, 0, 0).unwrap());
     let end = NaiveDateTime::new(date, NaiveTime::from_hms_opt(23, 59, 59).unwrap());
     let span = Span { begin, end };
+    let template = config.get_dps_summary_template().unwrap_or_default();
 
     DPSDlg {
+      config,
       state,
       threads,
       locale,
-      log_path: PathBuf::default(),
+      log_paths: Vec::new(),
       title: String::new(),
       avatar: String::new(),
       span,
+      spans: BTreeMap::new(),
+      span_name: String::new(),
+      gap: 30,
       channel,
       tally: None,
+      template,
+      error: None,
       visible: false,
     }
   }
 
-  pub fn open(&mut self, avatar: &str, path_buf: &Path) {
+  pub fn open(&mut self, avatar: &str, log_paths: &[PathBuf]) {
     if !avatar.is_empty() && !self.visible {
-      path_buf.clone_into(&mut self.log_path);
+      log_paths.clone_into(&mut self.log_paths);
       avatar.clone_into(&mut self.avatar);
       self.title = format!("⚔  Tally DPS ({avatar})");
+      self.spans = self.config.get_dps_spans(avatar).unwrap_or_default();
+      self.span_name.clear();
       self.state.set_disabled(true);
       self.tally = None;
+      self.error = None;
       self.visible = true;
     }
   }
 
   pub fn show(&mut self, ctx: &Context) {
-    while let Ok(Some(tally)) = self.channel.rx.try_next() {
-      // Update the date/time span and store the tally.
-      self.span = tally.span.clone();
-      self.tally = Some(tally);
+    while let Ok(msg) = self.channel.rx.try_recv() {
+      match msg {
+        Message::Tally(tally) => {
+          // Update the date/time span and store the tally.
+          self.span = tally.span.clone();
+          self.tally = Some(tally);
+        }
+        Message::NoFight => {
+          self.error = Some(format!("No recent fight found for {}", self.avatar));
+        }
+      }
       self.state.set_busy(false);
     }
 
@@ -110,6 +137,43 @@ impl DPSDlg {
             }
           });
 
+          // Saved spans.
+          ui.horizontal(|ui| {
+            let mut selected = None;
+            ComboBox::from_id_salt("dps_span_combo")
+              .selected_text("Saved Spans")
+              .show_ui(ui, |ui| {
+                for (name, span) in &self.spans {
+                  if ui.selectable_label(self.span_name == *name, name).clicked() {
+                    selected = Some((name.clone(), span.clone()));
+                  }
+                }
+              });
+            if let Some((name, span)) = selected {
+              self.span_name = name;
+              self.span = span;
+              self.tally = None;
+            }
+
+            let widget = TextEdit::singleline(&mut self.span_name)
+              .hint_text("span name")
+              .desired_width(ui.available_width() * 0.5);
+            ui.add(widget);
+
+            let name = self.span_name.trim();
+            if ui.add_enabled(!name.is_empty(), Button::new("Save")).clicked() {
+              self.spans.insert(name.to_owned(), self.span.clone());
+              self.config.set_dps_spans(&self.avatar, &self.spans);
+            }
+
+            let contains = self.spans.contains_key(name);
+            let response = ui.add_enabled(contains, Button::new("🗑"));
+            if response.on_hover_text("Delete the saved span").clicked() {
+              self.spans.remove(name);
+              self.config.set_dps_spans(&self.avatar, &self.spans);
+            }
+          });
+
           ui.separator();
 
           if let Some(tally) = &self.tally {
@@ -128,27 +192,158 @@ impl DPSDlg {
 
                   // Total damage.
                   let total_damage = tally.avatar + tally.pet;
-                  let text = format!("{total_damage}");
+                  let text = total_damage.to_formatted_string(&self.locale);
                   ui.label(text);
 
                   // Total DPS.
                   let val = total_damage as f64 / tally.secs as f64;
-                  let text = f64_to_string!(val, 2, self.locale);
+                  let text = util::format_f64(val, 2, &self.locale);
                   ui.label(text);
 
                   // Avatar DPS.
                   let val = tally.avatar as f64 / tally.secs as f64;
-                  let text = f64_to_string!(val, 2, self.locale);
+                  let text = util::format_f64(val, 2, &self.locale);
                   ui.label(text);
 
-                  // Pet DPS.
+                  // Pet DPS, broken down by pet if more than one contributed.
                   let val = tally.pet as f64 / tally.secs as f64;
-                  let text = f64_to_string!(val, 2, self.locale);
+                  let text = util::format_f64(val, 2, &self.locale);
+                  if tally.pets.len() > 1 {
+                    let text = format!("{text} ({} pets)", tally.pets.len());
+                    ui.label(text).on_hover_ui(|ui| {
+                      Grid::new("pet_dps_grid").show(ui, |ui| {
+                        const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+                        for pet in &tally.pets {
+                          ui.label(RichText::from(&pet.name).color(NAME_COLOR));
+                          ui.label(pet.damage.to_formatted_string(&self.locale));
+                          let val = pet.damage as f64 / tally.secs as f64;
+                          ui.label(format!("{} DPS", util::format_f64(val, 2, &self.locale)));
+                          ui.end_row();
+                        }
+                      });
+                    });
+                  } else {
+                    ui.label(text);
+                  }
+                });
+            });
+
+            ui.separator();
+
+            // Damage taken output.
+            ui.horizontal(|ui| {
+              Grid::new("taken_grid")
+                .min_col_width((ui.available_width() - ui.spacing().item_spacing.x * 3.0) / 4.0)
+                .show(ui, |ui| {
+                  // Header.
+                  const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+                  ui.label(RichText::from("Total Taken").color(HEADER_COLOR));
+                  ui.label(RichText::from("Taken/sec").color(HEADER_COLOR));
+                  ui.label(RichText::from("Avatar Taken/sec").color(HEADER_COLOR));
+                  ui.label(RichText::from("Pet Taken/sec").color(HEADER_COLOR));
+                  ui.end_row();
+
+                  // Total damage taken.
+                  let total_taken = tally.avatar_taken + tally.pet_taken;
+                  let text = total_taken.to_formatted_string(&self.locale);
+                  ui.label(text);
+
+                  // Total damage taken per second.
+                  let val = total_taken as f64 / tally.combat_secs as f64;
+                  let text = util::format_f64(val, 2, &self.locale);
+                  ui.label(text);
+
+                  // Avatar damage taken per second.
+                  let val = tally.avatar_taken as f64 / tally.combat_secs as f64;
+                  let text = util::format_f64(val, 2, &self.locale);
+                  ui.label(text);
+
+                  // Pet damage taken per second.
+                  let val = tally.pet_taken as f64 / tally.combat_secs as f64;
+                  let text = util::format_f64(val, 2, &self.locale);
+                  ui.label(text);
+                });
+            });
+
+            ui.separator();
+
+            // Healing output.
+            ui.horizontal(|ui| {
+              Grid::new("healing_grid")
+                .min_col_width((ui.available_width() - ui.spacing().item_spacing.x * 3.0) / 4.0)
+                .show(ui, |ui| {
+                  // Header.
+                  const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+                  ui.label(RichText::from("Healing Done").color(HEADER_COLOR));
+                  ui.label(RichText::from("HPS").color(HEADER_COLOR));
+                  ui.label(RichText::from("Healing Recv").color(HEADER_COLOR));
+                  ui.label(RichText::from("Recv/sec").color(HEADER_COLOR));
+                  ui.end_row();
+
+                  // Healing done.
+                  let text = tally.healing_done.to_formatted_string(&self.locale);
+                  ui.label(text);
+
+                  // Healing done per second.
+                  let val = tally.healing_done as f64 / tally.combat_secs as f64;
+                  let text = util::format_f64(val, 2, &self.locale);
+                  ui.label(text);
+
+                  // Healing received.
+                  let text = tally.healing_received.to_formatted_string(&self.locale);
+                  ui.label(text);
+
+                  // Healing received per second.
+                  let val = tally.healing_received as f64 / tally.combat_secs as f64;
+                  let text = util::format_f64(val, 2, &self.locale);
                   ui.label(text);
                 });
             });
 
             ui.separator();
+
+            // Avatar damage by attack.
+            if !tally.attacks.is_empty() {
+              CollapsingHeader::new("Attacks").id_salt("dps_attacks").show(ui, |ui| {
+                ScrollArea::vertical()
+                  .max_height(available.height() * 0.4)
+                  .show(ui, |ui| {
+                    Grid::new("attacks_grid")
+                      .min_col_width((ui.available_width() - ui.spacing().item_spacing.x * 4.0) / 5.0)
+                      .striped(true)
+                      .show(ui, |ui| {
+                        // Header.
+                        const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+                        ui.label(RichText::from("Attack").color(HEADER_COLOR));
+                        ui.label(RichText::from("Damage").color(HEADER_COLOR));
+                        ui.label(RichText::from("Hits").color(HEADER_COLOR));
+                        ui.label(RichText::from("Max Hit").color(HEADER_COLOR));
+                        ui.label(RichText::from("Share").color(HEADER_COLOR));
+                        ui.end_row();
+
+                        const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+                        for attack in &tally.attacks {
+                          ui.label(RichText::from(&attack.name).color(NAME_COLOR));
+                          ui.label(attack.damage.to_formatted_string(&self.locale));
+                          ui.label(attack.hits.to_formatted_string(&self.locale));
+                          ui.label(attack.max_hit.to_formatted_string(&self.locale));
+
+                          // Share of the avatar's total damage.
+                          let val = attack.damage as f64 * 100.0 / tally.avatar.max(1) as f64;
+                          ui.label(format!("{}%", util::format_f64(val, 1, &self.locale)));
+                          ui.end_row();
+                        }
+                      });
+                  });
+              });
+
+              ui.separator();
+            }
+          }
+
+          if let Some(err) = &self.error {
+            ui.label(RichText::from(err).color(Color::Error.get()));
+            ui.separator();
           }
 
           ui.horizontal(|ui| {
@@ -156,6 +351,52 @@ impl DPSDlg {
               self.request_dps_tally(ctx);
             }
 
+            let hover_text = "Tally the most recent fight in the newest log";
+            if ui.button("Last Fight").on_hover_text(hover_text).clicked() {
+              self.request_last_fight_tally(ctx);
+            }
+
+            // Gap between attacks that ends a fight.
+            let widget = DragValue::new(&mut self.gap).range(5..=600).suffix("s");
+            ui.add(widget).on_hover_text("Maximum gap between attacks");
+
+            // Copy the summary for pasting into chat.
+            ui.add_enabled_ui(self.tally.is_some(), |ui| {
+              ui.menu_button("Copy Summary", |ui| {
+                let Some(tally) = &self.tally else {
+                  return;
+                };
+
+                let values = get_summary_values(&self.avatar, tally, &self.locale);
+                if ui.button("Single Line").clicked() {
+                  util::set_clipboard_contents(format_summary(SINGLE_LINE_SUMMARY, &values));
+                  ui.close_menu();
+                }
+
+                if ui.button("Multiple Lines").clicked() {
+                  util::set_clipboard_contents(format_summary(MULTI_LINE_SUMMARY, &values));
+                  ui.close_menu();
+                }
+
+                ui.separator();
+
+                let enabled = !self.template.is_empty();
+                if ui.add_enabled(enabled, Button::new("Custom Template")).clicked() {
+                  util::set_clipboard_contents(format_summary(&self.template, &values));
+                  ui.close_menu();
+                }
+
+                let placeholders: Vec<String> = values.iter().map(|(name, _)| format!("{{{name}}}")).collect();
+                let hover_text = format!("Placeholders: {}", placeholders.join(" "));
+                let widget = TextEdit::multiline(&mut self.template)
+                  .hint_text("custom template")
+                  .desired_rows(2);
+                if ui.add(widget).on_hover_text(hover_text).changed() {
+                  self.config.set_dps_summary_template(&self.template);
+                }
+              });
+            });
+
             if ui.button("Close").clicked() {
               self.close();
             }
@@ -169,6 +410,7 @@ impl DPSDlg {
 
   fn request_dps_tally(&mut self, ctx: &Context) {
     self.tally = None;
+    self.error = None;
 
     // Cancel any previous request.
     if let Some(mut cancel) = self.channel.cancel.take() {
@@ -184,12 +426,50 @@ impl DPSDlg {
     // Setup the future.
     let tx = self.channel.tx.clone();
     let ctx = ctx.clone();
-    let log_path = self.log_path.clone();
+    let log_paths = self.log_paths.clone();
     let avatar = self.avatar.clone();
     let span = self.span.clone();
-    let future = log_data::tally_dps(log_path, avatar, span, cancel);
+    let state = self.state.clone();
+    let future = log_data::tally_dps(log_paths, avatar, span, cancel, state);
+    let future = async move {
+      tx.unbounded_send(Message::Tally(future.await)).unwrap();
+      ctx.request_repaint();
+    };
+
+    // Execute the future on a pooled thread.
+    self.threads.spawn_ok(future);
+  }
+
+  fn request_last_fight_tally(&mut self, ctx: &Context) {
+    self.tally = None;
+    self.error = None;
+
+    // Cancel any previous request.
+    if let Some(mut cancel) = self.channel.cancel.take() {
+      cancel.cancel();
+    }
+
+    let cancel = Cancel::default();
+    self.channel.cancel = Some(cancel.clone());
+
+    // Show the busy cursor.
+    self.state.set_busy(true);
+
+    // Setup the future.
+    let tx = self.channel.tx.clone();
+    let ctx = ctx.clone();
+    let log_paths = self.log_paths.clone();
+    let avatar = self.avatar.clone();
+    let gap = self.gap;
+    let state = self.state.clone();
     let future = async move {
-      tx.unbounded_send(future.await).unwrap();
+      // Find the span of the last fight and then tally it.
+      let span = log_data::find_last_fight_span(log_paths.clone(), avatar.clone(), gap, cancel.clone()).await;
+      let msg = match span {
+        Some(span) => Message::Tally(log_data::tally_dps(log_paths, avatar, span, cancel, state).await),
+        None => Message::NoFight,
+      };
+      tx.unbounded_send(msg).unwrap();
       ctx.request_repaint();
     };
 
@@ -268,8 +548,113 @@ fn show_date_time(ui: &mut Ui, date_time: &NaiveDateTime, id: &str) -> Option<Na
   result
 }
 
+/// Get the values for the summary placeholders.
+fn get_summary_values(avatar: &str, tally: &DPSTally, locale: &Locale) -> Vec<(&'static str, String)> {
+  const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+  let per_sec = |value: u64| util::format_f64(value as f64 / tally.secs.max(1) as f64, 2, locale);
+  let secs = tally.secs;
+  vec![
+    ("avatar", avatar.to_owned()),
+    ("begin", tally.span.begin.format(TIME_FORMAT).to_string()),
+    ("end", tally.span.end.format(TIME_FORMAT).to_string()),
+    (
+      "duration",
+      format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    ),
+    ("secs", secs.to_string()),
+    ("total_damage", (tally.avatar + tally.pet).to_formatted_string(locale)),
+    ("avatar_damage", tally.avatar.to_formatted_string(locale)),
+    ("pet_damage", tally.pet.to_formatted_string(locale)),
+    ("total_dps", per_sec(tally.avatar + tally.pet)),
+    ("avatar_dps", per_sec(tally.avatar)),
+    ("pet_dps", per_sec(tally.pet)),
+  ]
+}
+
+/// Replace the `{name}` placeholders in the template. Unknown placeholders are left as they are.
+fn format_summary(template: &str, values: &[(&str, String)]) -> String {
+  let mut text = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(pos) = rest.find('{') {
+    text.push_str(&rest[..pos]);
+    rest = &rest[pos..];
+
+    let value = rest.find('}').and_then(|end| {
+      let (_, value) = values.iter().find(|(name, _)| *name == &rest[1..end])?;
+      Some((value, end))
+    });
+
+    match value {
+      Some((value, end)) => {
+        text.push_str(value);
+        rest = &rest[end + 1..];
+      }
+      None => {
+        text.push('{');
+        rest = &rest[1..];
+      }
+    }
+  }
+  text.push_str(rest);
+  text
+}
+
+const SINGLE_LINE_SUMMARY: &str = "{avatar} DPS | {begin} - {end} ({duration}) | Damage: {total_damage} | \
+  DPS: {total_dps} (Avatar: {avatar_dps}, Pet: {pet_dps})";
+const MULTI_LINE_SUMMARY: &str = "{avatar} DPS
+Span: {begin} - {end}
+Duration: {duration}
+Damage: {total_damage}
+Total DPS: {total_dps}
+Avatar DPS: {avatar_dps}
+Pet DPS: {pet_dps}";
+
+enum Message {
+  Tally(DPSTally),
+  NoFight,
+}
+
 struct Channel {
-  tx: UnboundedSender<DPSTally>,
-  rx: UnboundedReceiver<DPSTally>,
+  tx: UnboundedSender<Message>,
+  rx: UnboundedReceiver<Message>,
   cancel: Option<Cancel>,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  #[test]
+  fn test_format_summary() {
+    let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let tally = DPSTally {
+      span: Span {
+        begin: date.and_hms_opt(20, 0, 0).unwrap(),
+        end: date.and_hms_opt(21, 2, 5).unwrap(),
+      },
+      avatar: 1_200_000,
+      pet: 30_000,
+      secs: 3725,
+      avatar_taken: 0,
+      pet_taken: 0,
+      healing_done: 0,
+      healing_received: 0,
+      combat_secs: 0,
+      attacks: Vec::new(),
+      pets: Vec::new(),
+    };
+    let values = get_summary_values("Mira", &tally, &Locale::en);
+
+    assert_eq!(
+      format_summary(SINGLE_LINE_SUMMARY, &values),
+      "Mira DPS | 2024-05-01 20:00:00 - 2024-05-01 21:02:05 (1:02:05) | Damage: 1,230,000 | \
+       DPS: 330.2 (Avatar: 322.15, Pet: 8.05)"
+    );
+    assert!(!format_summary(MULTI_LINE_SUMMARY, &values).contains('{'));
+
+    // Unknown and unclosed placeholders are kept.
+    let text = format_summary("{avatar}: {total_dps} {bogus} {secs", &values);
+    assert_eq!(text, "Mira: 330.2 {bogus} {secs");
+  }
+}
@@ -1,61 +1,134 @@
-use crate::{config::Config, log_data, skill_info, util};
+use crate::{
+  config::{Config, SkillPlans},
+  game_data::{GameData, Section},
+  log_data::{self, AvatarExp, AvatarLogs, SessionExp},
+  log_watcher::{LogChange, LogEvent, LogSubscription, LogWatcher},
+  skill_info,
+  theme::Color,
+  util,
+};
+use chrono::{Local, Utc};
 use eframe::{
   egui::{
-    scroll_area::ScrollBarVisibility, ComboBox, Context, DragValue, Label, Layout, RichText, ScrollArea, Sense,
-    TextWrapMode, Ui, Widget,
+    pos2, scroll_area::ScrollBarVisibility, vec2, Button, CollapsingHeader, ComboBox, Context, DragValue, Label,
+    Layout, ProgressBar, RichText, ScrollArea, Sense, Shape, Stroke, TextEdit, TextWrapMode, Ui, Widget,
   },
-  emath::{Align, Vec2},
+  emath::Align,
   epaint::Color32,
 };
 use egui_extras::{Column, TableBuilder};
 use futures::{channel::mpsc, executor::ThreadPool};
 use num_format::{Locale, ToFormattedString};
-use skill_info::{SkillCategory, SkillInfo, SkillInfoGroup};
-use std::{collections::HashMap, mem, path::PathBuf};
-use util::{AppState, Cancel, LEVEL_EXP, SKILL_EXP};
+use serde::{Deserialize, Serialize};
+use skill_info::{SkillCategory, SkillInfo, SkillInfoGroup, SkillOverride};
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashMap, HashSet},
+  ffi::OsStr,
+  fs, mem,
+  path::PathBuf,
+  time::Duration,
+};
+use util::{AppState, Cancel, ExpandedGroups, SKILL_EXP};
 
 pub struct Experience {
   config: Config,
   state: AppState,
   threads: ThreadPool,
   channel: Channel,
-  log_path: PathBuf,
+  watcher: Option<LogSubscription>,
+  log_paths: Vec<PathBuf>,
+  log_path_error: Option<PathBuf>,
+  choose_folder_request: bool,
   avatar: String,
   avatars: Vec<String>,
+  active_avatar: Option<String>,
   adventurer_skills: Vec<SkillInfoGroup>,
   producer_skills: Vec<SkillInfoGroup>,
+  skill_override: SkillOverride,
   level_info: LevelInfo,
+  exp_samples: Vec<(i64, i64)>,
+  exp_ts: Option<i64>,
+  record_exp: bool,
+  session: Option<Session>,
   selected: SkillInfo,
   locale: Locale,
+  import: Option<Import>,
+  import_request: bool,
+  plans: SkillPlans,
+  plan: Option<String>,
+  plan_name: String,
+  import_plan_request: bool,
+  export_plan_request: bool,
+  expanded: ExpandedGroups,
+  pooled: bool,
+  untrain: bool,
+  overview: bool,
+  overview_rows: Vec<(String, AvatarExp)>,
+  avatar_changed: bool,
+  active: bool,
   init: bool,
 }
 
 impl Experience {
-  pub fn new(log_path: PathBuf, threads: ThreadPool, config: Config, state: AppState, locale: Locale) -> Self {
+  pub fn new(
+    log_paths: Vec<PathBuf>,
+    threads: ThreadPool,
+    config: Config,
+    state: AppState,
+    locale: Locale,
+    skill_override: SkillOverride,
+  ) -> Self {
     let (tx, rx) = mpsc::unbounded();
     let channel = Channel {
       tx,
       rx,
       cancel_avatars: None,
-      cancel_adv_exp: None,
+      cancel_exp: None,
+      cancel_overview: None,
+      cancel_session: None,
     };
 
-    let adventurer_skills = skill_info::parse_skill_info_groups(SkillCategory::Adventurer);
-    let producer_skills = skill_info::parse_skill_info_groups(SkillCategory::Producer);
+    let adventurer_skills = skill_info::parse_skill_info_groups(SkillCategory::Adventurer, skill_override);
+    let producer_skills = skill_info::parse_skill_info_groups(SkillCategory::Producer, skill_override);
+    let pooled = config.get_exp_pooled();
 
     Experience {
       config,
       state,
       threads,
       channel,
-      log_path,
+      watcher: None,
+      log_paths,
+      log_path_error: None,
+      choose_folder_request: false,
       avatar: String::new(),
       avatars: Vec::new(),
+      active_avatar: None,
       adventurer_skills,
       producer_skills,
+      skill_override,
       level_info: LevelInfo::new(),
+      exp_samples: Vec::new(),
+      exp_ts: None,
+      record_exp: false,
+      session: None,
       selected: Default::default(),
       locale,
+      import: None,
+      import_request: false,
+      plans: BTreeMap::new(),
+      plan: None,
+      plan_name: String::new(),
+      import_plan_request: false,
+      export_plan_request: false,
+      expanded: ExpandedGroups::default(),
+      pooled,
+      untrain: false,
+      overview: false,
+      overview_rows: Vec::new(),
+      avatar_changed: false,
+      active: true,
       init: true,
     }
   }
@@ -66,11 +139,14 @@ impl Experience {
     }
 
     // Process messages.
-    while let Ok(Some(msg)) = self.channel.rx.try_next() {
+    while let Ok(msg) = self.channel.rx.try_recv() {
       self.state.set_busy(false);
       match msg {
-        Message::Avatars(avatars) => {
-          self.avatars = avatars;
+        Message::Avatars((avatars, inaccessible)) => {
+          // Avatars from the other folders are still shown.
+          self.log_path_error = inaccessible;
+          self.active_avatar = log_data::find_active_avatar(&avatars).map(String::from);
+          self.avatars = avatars.into_iter().map(|avatar| avatar.name).collect();
 
           let mut avatar = self.avatar.clone();
           if avatar.is_empty() {
@@ -85,19 +161,73 @@ impl Experience {
           }
 
           if avatar.is_empty() {
-            // Get the first avatar.
+            // Get the most recently active avatar, or the first one.
             if let Some(first) = self.avatars.first() {
-              avatar.clone_from(first);
+              avatar.clone_from(self.active_avatar.as_ref().unwrap_or(first));
             }
           }
 
           self.set_avatar(avatar);
+          if self.overview {
+            self.request_overview(ui.ctx());
+          }
+        }
+        Message::Exp(exp) => {
+          if exp.ts.is_some() {
+            self.exp_ts = exp.ts;
+          }
+          if let Some(adv) = exp.adv {
+            self.level_info.adv_exp = adv;
+
+            // The pooled experience from during the session is used to estimate how much was spent.
+            if let (Some(session), Some(ts)) = (&mut self.session, exp.ts) {
+              if ts >= session.start {
+                session.first_exp.get_or_insert((ts, adv));
+                session.last_exp = Some((ts, adv));
+              }
+            }
+
+            // Keep track of the adventurer experience each time it's refreshed from the tool bar.
+            if let Some(ts) = exp.ts {
+              if mem::take(&mut self.record_exp) && add_exp_sample(&mut self.exp_samples, (ts, adv)) {
+                self.config.set_exp_samples(&self.avatar, &self.exp_samples);
+              }
+            }
+          }
+          if let Some(prd) = exp.prd {
+            self.level_info.prd_exp = prd;
+          }
+        }
+        Message::Overview(rows) => {
+          self.overview_rows = rows;
         }
-        Message::AdvExp(exp) => {
-          if let Some(exp) = exp {
-            self.level_info.adv_exp = exp;
+        Message::SessionExp(scan) => {
+          if let Some(session) = &mut self.session {
+            session.path = scan.path;
+            session.offset = scan.offset;
+            session.gains.extend(scan.gains);
           }
         }
+        Message::LogChange(change) => {
+          // Check for new experience gains.
+          if change.avatar == self.avatar && self.session.is_some() {
+            self.request_session_exp(ui.ctx());
+          }
+
+          // Refresh the experience if `/xp` was used by the current avatar.
+          if change.avatar == self.avatar && log_data::has_exp(&change.text) {
+            self.request_exp(ui.ctx());
+          }
+
+          // Refresh the overview if `/xp` was used by any avatar.
+          if self.overview && log_data::has_exp(&change.text) {
+            self.request_overview(ui.ctx());
+          }
+        }
+        Message::LogsAvailable => {
+          // The log folders are back, so get the avatars again.
+          self.request_avatars(ui.ctx());
+        }
       }
     }
 
@@ -108,10 +238,11 @@ impl Experience {
         let mut avatar_changed = None;
         ComboBox::from_id_salt("exp_avatar_combo")
           .selected_text(&self.avatar)
-          .width(234.0)
+          .width(ui.available_width() * 0.5)
           .show_ui(ui, |ui| {
             for avatar in &self.avatars {
-              let response = ui.selectable_label(self.avatar == *avatar, avatar);
+              let text = util::avatar_text(ui, avatar, self.active_avatar.as_deref());
+              let response = ui.selectable_label(self.avatar == *avatar, text);
               if response.clicked() && self.avatar != *avatar {
                 avatar_changed = Some(avatar.clone());
               }
@@ -119,64 +250,396 @@ impl Experience {
           });
 
         if let Some(avatar) = avatar_changed {
-          self.set_avatar(avatar)
+          self.set_avatar(avatar);
+          self.avatar_changed = true;
+        }
+
+        // Levels of all the avatars.
+        let response = ui.selectable_label(self.overview, "Overview");
+        if response.on_hover_text("Compare the levels of all avatars").clicked() {
+          self.overview = !self.overview;
+          if self.overview {
+            self.request_overview(ui.ctx());
+          }
+        }
+      });
+
+      if self.overview {
+        return;
+      }
+
+      ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
+        // Import from save-game.
+        let hover_text = "Import current levels from a save-game";
+        if ui.button("📥").on_hover_text(hover_text).clicked() {
+          self.import = None;
+          self.import_request = true;
         }
       });
       ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
         // Adventurer level.
-        let (button_text, exp) = if let Some(adv_info) = self.get_adv_info() {
-          (format!("Adv Lvl {}", adv_info.lvl), adv_info.exp)
-        } else {
-          (String::from("Adv Lvl ?"), None)
-        };
-
-        let hover_text = "Type /xp in-game then click this button";
-        if ui.button(button_text).on_hover_text(hover_text).clicked() {
-          self.request_adv_exp(ui.ctx());
+        let adv_info = get_exp_info(self.level_info.adv_exp);
+        if show_level(ui, "Adv", adv_info, &self.locale) {
+          self.record_exp = true;
+          self.request_exp(ui.ctx());
         }
 
-        if let Some(exp) = exp {
-          ui.spacing_mut().item_spacing.x *= 0.5;
-          ui.label("Next");
+        // When the `/xp` values are from.
+        if let Some(ts) = self.exp_ts {
+          show_exp_time(ui, ts);
+        }
 
-          let text = exp.to_formatted_string(&self.locale);
-          let response = Label::new(text).sense(Sense::click()).ui(ui);
-          if response.on_hover_text("Click to copy").clicked() {
-            util::set_clipboard_contents(format!("{}", exp));
+        // Change since the previous refresh.
+        if let Some(trend) = get_exp_trend(&self.exp_samples) {
+          show_exp_trend(ui, &trend, &self.exp_samples, &self.locale);
+        }
+      });
+      ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
+        // Producer level.
+        let prd_info = get_exp_info(self.level_info.prd_exp);
+        if show_level(ui, "Prd", prd_info, &self.locale) {
+          self.record_exp = true;
+          self.request_exp(ui.ctx());
+        }
+      });
+      ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
+        // Experience earned session.
+        let response = ui.selectable_label(self.session.is_some(), "Session");
+        if response
+          .on_hover_text("Track the experience earned from the chat log")
+          .clicked()
+        {
+          if self.session.is_some() {
+            self.stop_session();
+          } else {
+            self.start_session(ui.ctx());
           }
         }
       });
     });
 
+    if self.overview {
+      self.show_overview(ui);
+      return;
+    }
+
+    if let Some(session) = &self.session {
+      show_session(ui, session, get_log_now(), &self.locale);
+
+      // Keep the elapsed time current.
+      ui.ctx().request_repaint_after(Duration::from_secs(1));
+    }
+
+    // Skill plans.
+    self.show_plans(ui);
+
+    // Import warnings and errors.
+    if let Some(import) = &self.import {
+      let mut accept = false;
+      let mut dismiss = false;
+      ui.horizontal(|ui| {
+        let (text, color) = match import {
+          Import::Mismatch { name, .. } => (
+            Cow::from(format!("Save-game avatar {name} doesn't match {}", self.avatar)),
+            Color::Error.get(),
+          ),
+          Import::Warning(warning) => (Cow::from(warning.as_str()), Color32::YELLOW),
+          Import::Error(err) => (Cow::from(err.as_str()), Color::Error.get()),
+        };
+        ui.label(RichText::from(text).color(color));
+        if matches!(import, Import::Mismatch { .. }) && ui.button("Import Anyway").clicked() {
+          accept = true;
+        }
+        if ui.button("Dismiss").clicked() {
+          dismiss = true;
+        }
+      });
+
+      if accept {
+        if let Some(Import::Mismatch { levels, .. }) = self.import.take() {
+          self.set_current_levels(levels);
+        }
+      } else if dismiss {
+        self.import = None;
+      }
+    }
+
     ui.separator();
 
-    // Divide the remaining space evenly between adventurer and producer.
-    let available = ui.available_size();
-    let spacing = ui.spacing().item_spacing.y;
-    let size = Vec2::new(available.x, available.y * 0.5 - spacing * 4.0);
+    // Experience totals.
+    ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
+      ui.horizontal(|ui| {
+        const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+        let adv = get_total_exp(&self.adventurer_skills, &self.level_info.skill_lvls, self.pooled);
+        let prd = get_total_exp(&self.producer_skills, &self.level_info.skill_lvls, self.pooled);
+        let total = ExpTotal {
+          needed: adv.needed + prd.needed,
+          refund: adv.refund + prd.refund,
+        };
+
+        let x_spacing = ui.spacing().item_spacing.x;
+        for (label, exp_total) in [("Adv", adv), ("Prd", prd), ("Total", total)] {
+          if label != "Adv" {
+            ui.separator();
+          }
+
+          ui.spacing_mut().item_spacing.x = x_spacing * 0.5;
+          ui.label(RichText::from(label).color(LABEL_COLOR));
+          ui.spacing_mut().item_spacing.x = x_spacing;
+          if self.untrain {
+            // Pooled experience recovered from un-training.
+            let text = RichText::from(exp_total.refund.to_formatted_string(&self.locale)).color(Color::Untrain.get());
+            let response = Label::new(text).sense(Sense::click()).ui(ui);
+            if response.on_hover_text("Click to copy").clicked() {
+              util::set_clipboard_contents(format!("{}", exp_total.refund));
+            }
+            continue;
+          }
+
+          ui.label(exp_total.to_string(&self.locale));
+        }
 
-    ui.add_space(spacing);
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+          let response = ui.checkbox(&mut self.pooled, "Pooled");
+          let hover_text = "Use the rates for training producer skills from the pooled experience";
+          if response.on_hover_text(hover_text).changed() {
+            self.config.set_exp_pooled(self.pooled);
+          }
 
-    // Adventurer skills.
-    ui.allocate_ui(size, |ui| {
-      self.show_skill_category(ui, SkillCategory::Adventurer);
+          let response = ui.checkbox(&mut self.untrain, "Untrain");
+          response
+            .on_hover_text("Only show the skills with a target below the current level, and the experience recovered");
+        });
+      });
     });
 
-    ui.add_space(spacing);
     ui.separator();
-    ui.add_space(spacing);
 
-    // Producer skills.
-    ui.allocate_ui(size, |ui| {
-      self.show_skill_category(ui, SkillCategory::Producer);
+    // Divide the remaining space evenly between adventurer and producer.
+    util::split_evenly(ui, |ui, index| {
+      let category = if index == 0 {
+        SkillCategory::Adventurer
+      } else {
+        SkillCategory::Producer
+      };
+      self.show_skill_category(ui, category);
     });
+
+    // The expanded groups only need to be restored once.
+    self.expanded.restored();
+  }
+
+  pub fn avatar(&self) -> &str {
+    &self.avatar
+  }
+
+  /// Check if the user selected a different avatar.
+  pub fn take_avatar_changed(&mut self) -> bool {
+    mem::take(&mut self.avatar_changed)
+  }
+
+  /// Select an avatar if it's in the list.
+  pub fn select_avatar(&mut self, avatar: &str) -> bool {
+    if self.avatars.iter().any(|name| name == avatar) {
+      self.set_avatar(avatar.to_owned());
+      return true;
+    }
+    false
   }
 
-  pub fn set_log_path(&mut self, ctx: &Context, log_path: PathBuf) {
-    self.log_path = log_path;
+  /// Only watch the logs while the page is showing. Changes from while it wasn't are reported when it is again.
+  pub fn set_active(&mut self, active: bool) {
+    self.active = active;
+    if let Some(watcher) = &self.watcher {
+      watcher.pause(!active);
+    }
+  }
+
+  /// Refresh from the chat log changes reported by the log watcher.
+  pub fn watch_logs(&mut self, ctx: &Context, watcher: &LogWatcher) {
+    let watcher = watch_logs(ctx, &self.channel, watcher);
+    watcher.pause(!self.active);
+    self.watcher = Some(watcher);
+  }
+
+  pub fn set_log_paths(&mut self, ctx: &Context, log_paths: Vec<PathBuf>) {
+    self.log_paths = log_paths;
+    self.stop_session();
     self.request_avatars(ctx);
   }
 
+  /// Start tracking the experience earned from the chat log.
+  fn start_session(&mut self, ctx: &Context) {
+    self.session = Some(Session::new(get_log_now()));
+    self.request_session_exp(ctx);
+  }
+
+  fn stop_session(&mut self) {
+    if let Some(mut cancel) = self.channel.cancel_session.take() {
+      cancel.cancel();
+    }
+    self.session = None;
+  }
+
+  fn show_overview(&mut self, ui: &mut Ui) {
+    ui.separator();
+
+    const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+    const HINT_COLOR: Color32 = Color32::from_rgb(154, 154, 154);
+    let spacing = ui.spacing().item_spacing;
+    let row_size = util::button_size(ui) + spacing[1];
+    let mut selected = None;
+    TableBuilder::new(ui)
+      .cell_layout(Layout::left_to_right(Align::Center))
+      .striped(true)
+      .sense(Sense::click())
+      .column(Column::auto())
+      .column(Column::auto())
+      .column(Column::auto())
+      .column(Column::auto())
+      .column(Column::remainder())
+      .header(row_size, |mut header| {
+        for text in ["Avatar", "Adv", "Next", "Prd", "Updated"] {
+          header.col(|ui| {
+            ui.label(RichText::from(text).color(HEADER_COLOR));
+          });
+        }
+      })
+      .body(|mut body| {
+        for (avatar, exp) in &self.overview_rows {
+          body.row(row_size, |mut row| {
+            row.col(|ui| {
+              ui.add(Label::new(avatar).selectable(false));
+            });
+
+            let adv_info = exp.adv.and_then(get_exp_info);
+            let prd_info = exp.prd.and_then(get_exp_info);
+            if adv_info.is_none() && prd_info.is_none() {
+              row.col(|ui| {
+                let text = RichText::from("Type /xp in-game").color(HINT_COLOR);
+                ui.add(Label::new(text).wrap_mode(TextWrapMode::Extend).selectable(false));
+              });
+              row.col(|_| ());
+              row.col(|_| ());
+              row.col(|_| ());
+            } else {
+              row.col(|ui| {
+                if let Some(info) = &adv_info {
+                  ui.add(Label::new(format!("{}", info.lvl)).selectable(false));
+                }
+              });
+              row.col(|ui| {
+                if let Some(exp) = adv_info.and_then(|info| info.exp) {
+                  ui.add(Label::new(exp.to_formatted_string(&self.locale)).selectable(false));
+                }
+              });
+              row.col(|ui| {
+                if let Some(info) = &prd_info {
+                  ui.add(Label::new(format!("{}", info.lvl)).selectable(false));
+                }
+              });
+              row.col(|ui| {
+                ui.add(Label::new(util::timestamp_to_string(exp.ts)).selectable(false));
+              });
+            }
+
+            let hover_text = "Click to show this avatar's experience";
+            if row.response().on_hover_text(hover_text).clicked() {
+              selected = Some((avatar.clone(), *exp));
+            }
+          });
+        }
+      });
+
+    // Show the full experience view for the selected avatar.
+    if let Some((avatar, exp)) = selected {
+      if self.avatar != avatar {
+        self.set_avatar(avatar);
+        self.avatar_changed = true;
+      }
+      self.level_info.adv_exp = exp.adv.unwrap_or_default();
+      self.level_info.prd_exp = exp.prd.unwrap_or_default();
+      self.exp_ts = exp.ts;
+      self.overview = false;
+    }
+  }
+
+  fn show_plans(&mut self, ui: &mut Ui) {
+    ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
+      ui.horizontal(|ui| {
+        // Plan combo-box.
+        let mut selected = None;
+        ComboBox::from_id_salt("exp_plan_combo")
+          .selected_text(self.plan.as_deref().unwrap_or_default())
+          .width(ui.available_width() * 0.3)
+          .show_ui(ui, |ui| {
+            for name in self.plans.keys() {
+              let checked = self.plan.as_ref() == Some(name);
+              if ui.selectable_label(checked, name).clicked() && !checked {
+                selected = Some(name.clone());
+              }
+            }
+          });
+
+        if let Some(name) = selected {
+          self.load_plan(name);
+        }
+
+        // Plan name.
+        let widget = TextEdit::singleline(&mut self.plan_name)
+          .hint_text("Plan name")
+          .desired_width(ui.available_width() * 0.3);
+        ui.add(widget);
+
+        let name = self.plan_name.trim().to_owned();
+        let enabled = !name.is_empty();
+        let hover_text = "Save the current levels as a plan";
+        if ui
+          .add_enabled(enabled, Button::new("Save"))
+          .on_hover_text(hover_text)
+          .clicked()
+        {
+          self.save_plan(name.clone());
+        }
+
+        let enabled = self.plan.is_some() && !name.is_empty() && !self.plans.contains_key(&name);
+        let hover_text = "Rename the selected plan";
+        if ui
+          .add_enabled(enabled, Button::new("Rename"))
+          .on_hover_text(hover_text)
+          .clicked()
+        {
+          self.rename_plan(name);
+        }
+
+        let enabled = self.plan.is_some();
+        let hover_text = "Delete the selected plan";
+        if ui
+          .add_enabled(enabled, Button::new("🗑"))
+          .on_hover_text(hover_text)
+          .clicked()
+        {
+          self.delete_plan();
+        }
+
+        let hover_text = "Export the selected plan to a file";
+        if ui
+          .add_enabled(enabled, Button::new("📤"))
+          .on_hover_text(hover_text)
+          .clicked()
+        {
+          self.export_plan_request = true;
+        }
+
+        let hover_text = "Import a plan from a file";
+        if ui.button("📂").on_hover_text(hover_text).clicked() {
+          self.import = None;
+          self.import_plan_request = true;
+        }
+      });
+    });
+  }
+
   fn show_skill_category(&mut self, ui: &mut Ui, category: SkillCategory) {
     let (scroll_id, groups) = match category {
       SkillCategory::Adventurer => ("adventurer_skills", &self.adventurer_skills),
@@ -184,6 +647,7 @@ impl Experience {
     };
 
     let mut save = false;
+    let untrain = self.untrain;
     ui.vertical(|ui| {
       ui.add_enabled_ui(!self.avatar.is_empty(), |ui| {
         ScrollArea::vertical()
@@ -191,93 +655,123 @@ impl Experience {
           .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
           .show(ui, |ui| {
             for skill_group in groups {
+              let levels = &self.level_info.skill_lvls;
+              let untraining = |skill: &SkillInfo| levels.get(&skill.id).is_some_and(is_untraining);
+              if untrain && !skill_group.skills.iter().any(untraining) {
+                continue;
+              }
+
               // Use a single column in order to force the scroll area to fill the entire available width.
               ui.columns(1, |col| {
-                let response = col[0].collapsing(skill_group.name, |ui| {
-                  let spacing = ui.spacing().item_spacing;
-                  let row_size = util::button_size(ui) + spacing[1];
-                  TableBuilder::new(ui)
-                    .cell_layout(Layout::left_to_right(Align::Center))
-                    .striped(true)
-                    .vscroll(false)
-                    .column(Column::auto())
-                    .column(Column::auto())
-                    .column(Column::auto())
-                    .column(Column::auto())
-                    .column(Column::remainder())
-                    .header(row_size, |mut header| {
-                      const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
-                      header.col(|ui| {
-                        ui.label(RichText::from("Skill").color(HEADER_COLOR));
-                      });
-                      header.col(|ui| {
-                        ui.label(RichText::from("Cur").color(HEADER_COLOR));
-                      });
-                      header.col(|ui| {
-                        ui.label(RichText::from("Tgt").color(HEADER_COLOR));
-                      });
-                      header.col(|ui| {
-                        ui.label(RichText::from("Mul").color(HEADER_COLOR));
-                      });
-                      header.col(|ui| {
-                        ui.label(RichText::from("Exp").color(HEADER_COLOR));
-                      });
-                    })
-                    .body(|mut body| {
-                      for skill in &skill_group.skills {
-                        let level = get_skill_lvl_mut(&mut self.level_info.skill_lvls, skill.id);
-                        body.row(row_size, |mut row| {
-                          row.col(|ui| {
-                            let text = RichText::from(skill.name);
-                            let text = text.color(Color32::from_rgb(102, 154, 180));
-                            let widget = Label::new(text).wrap_mode(TextWrapMode::Extend);
-                            ui.add(widget);
-                          });
-                          row.col(|ui| {
-                            let range = 0..=200;
-                            let value = &mut level.0;
-                            let widget = DragValue::new(value).range(range);
-                            let response = ui.add(widget);
-                            if response.drag_stopped() || response.lost_focus() {
-                              save = true;
-                            }
-                          });
-                          row.col(|ui| {
-                            let range = 0..=200;
-                            let value = &mut level.1;
-                            let widget = DragValue::new(value).range(range);
-                            let response = ui.add(widget);
-                            if response.drag_stopped() || response.lost_focus() {
-                              save = true;
-                            }
-                          });
-                          row.col(|ui| {
-                            ui.label(format!("{}x", skill.mul));
-                          });
-                          row.col(|ui| {
-                            if let Some(exp) = get_needed_exp(level, skill.mul) {
-                              let (text, exp) = if exp < 0 {
-                                // Half experience returned for un-training.
-                                let exp = exp / 2;
-                                let text = exp.abs().to_formatted_string(&self.locale);
-                                let text = format!("({})", text);
-                                (text, exp)
+                // Restore the avatar's expanded groups. Use a different ID while only showing un-training so that the
+                // groups are expanded without disturbing their normal state.
+                let (id_salt, open) = if untrain {
+                  (format!("{}_untrain", skill_group.name), None)
+                } else {
+                  (skill_group.name.to_owned(), self.expanded.open(skill_group.name))
+                };
+                let response = CollapsingHeader::new(skill_group.name)
+                  .id_salt(id_salt)
+                  .default_open(untrain)
+                  .open(open)
+                  .show(&mut col[0], |ui| {
+                    let spacing = ui.spacing().item_spacing;
+                    let row_size = util::button_size(ui) + spacing[1];
+                    let pooled = self.pooled;
+                    TableBuilder::new(ui)
+                      .cell_layout(Layout::left_to_right(Align::Center))
+                      .striped(true)
+                      .vscroll(false)
+                      .column(Column::auto())
+                      .column(Column::auto())
+                      .column(Column::auto())
+                      .column(Column::auto())
+                      .column(Column::remainder())
+                      .header(row_size, |mut header| {
+                        const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+                        header.col(|ui| {
+                          ui.label(RichText::from("Skill").color(HEADER_COLOR));
+                        });
+                        header.col(|ui| {
+                          ui.label(RichText::from("Cur").color(HEADER_COLOR));
+                        });
+                        header.col(|ui| {
+                          ui.label(RichText::from("Tgt").color(HEADER_COLOR));
+                        });
+                        header.col(|ui| {
+                          ui.label(RichText::from("Mul").color(HEADER_COLOR));
+                        });
+                        header.col(|ui| {
+                          ui.label(RichText::from("Exp").color(HEADER_COLOR));
+                        });
+                      })
+                      .body(|mut body| {
+                        for skill in &skill_group.skills {
+                          let level = get_skill_lvl_mut(&mut self.level_info.skill_lvls, skill.id);
+                          if untrain && !is_untraining(level) {
+                            continue;
+                          }
+
+                          body.row(row_size, |mut row| {
+                            row.col(|ui| {
+                              let color = if untrain {
+                                Color::Untrain.get()
                               } else {
-                                let text = exp.to_formatted_string(&self.locale);
-                                (text, exp)
+                                Color::SkillName.get()
                               };
-                              let response = Label::new(text).sense(Sense::click()).ui(ui);
-                              if response.on_hover_text("Click to copy").clicked() {
-                                util::set_clipboard_contents(format!("{exp}"));
+                              let text = RichText::from(skill.name).color(color);
+                              let widget = Label::new(text).wrap_mode(TextWrapMode::Extend);
+                              ui.add(widget);
+                            });
+                            row.col(|ui| {
+                              let range = 0..=skill.level_cap();
+                              let value = &mut level.0;
+                              let widget = DragValue::new(value).range(range);
+                              let response = ui.add(widget);
+                              if response.drag_stopped() || response.lost_focus() {
+                                save = true;
                               }
-                            }
+                            });
+                            row.col(|ui| {
+                              let range = 0..=skill.level_cap();
+                              let value = &mut level.1;
+                              let widget = DragValue::new(value).range(range);
+                              let response = ui.add(widget);
+                              if response.drag_stopped() || response.lost_focus() {
+                                save = true;
+                              }
+                            });
+                            row.col(|ui| {
+                              ui.label(format!("{}x", skill.get_mul(pooled)));
+                            });
+                            row.col(|ui| {
+                              if let Some(exp) = get_needed_exp(level, skill, pooled) {
+                                let (text, exp) = if exp < 0 {
+                                  // Half experience returned for un-training.
+                                  let exp = exp / 2;
+                                  let text = exp.abs().to_formatted_string(&self.locale);
+                                  let text = format!("({})", text);
+                                  (text, exp)
+                                } else {
+                                  let text = exp.to_formatted_string(&self.locale);
+                                  (text, exp)
+                                };
+                                let response = Label::new(text).sense(Sense::click()).ui(ui);
+                                let hover_text = get_exp_hover_text(level, skill, &self.locale);
+                                if response.on_hover_text(hover_text).clicked() {
+                                  util::set_clipboard_contents(format!("{exp}"));
+                                }
+                              }
+                            });
                           });
-                        });
-                      }
-                    });
-                });
+                        }
+                      });
+                  });
+
+                if !untrain && response.header_response.clicked() {
+                  // Keep track of the expanded groups.
+                  self.expanded.toggle(skill_group.name);
 
-                if response.header_response.clicked() {
                   // Check if this group contains the selected skill.
                   if skill_group
                     .skills
@@ -297,6 +791,246 @@ impl Experience {
     if save {
       self.save();
     }
+
+    if self.expanded.take_changed() {
+      self.config.set_avatar_expanded(&self.avatar, self.expanded.names());
+    }
+  }
+
+  pub fn show_status(&mut self, ui: &mut Ui) {
+    if let Some(progress) = self.state.progress() {
+      // The progress is updated from other threads, so keep repainting while it's shown.
+      ui.ctx().request_repaint_after(PROGRESS_REPAINT);
+      ui.centered_and_justified(|ui| {
+        ui.add(ProgressBar::new(progress.fraction()).text(progress.text()));
+      });
+      return;
+    }
+
+    if let Some(path) = &self.log_path_error {
+      if util::show_log_path_error(ui, path) {
+        self.choose_folder_request = true;
+      }
+      return;
+    }
+
+    if self.avatar.is_empty() {
+      return;
+    }
+
+    let mut parts = vec![self.avatar.clone()];
+    if let Some(info) = get_exp_info(self.level_info.adv_exp) {
+      let mut text = format!("Adv Lvl {}", info.lvl);
+      if let Some(exp) = info.exp {
+        text = format!("{text} ({} to next)", exp.to_formatted_string(&self.locale));
+      }
+      parts.push(text);
+    }
+
+    let adv = get_total_exp(&self.adventurer_skills, &self.level_info.skill_lvls, self.pooled);
+    let prd = get_total_exp(&self.producer_skills, &self.level_info.skill_lvls, self.pooled);
+    let needed = adv.needed + prd.needed;
+    if needed > 0 {
+      parts.push(format!("Planned {}", needed.to_formatted_string(&self.locale)));
+    }
+
+    ui.centered_and_justified(|ui| {
+      ui.label(parts.join(" - "));
+    });
+  }
+
+  /// Check if the user requested choosing a different log folder.
+  pub fn choose_folder_request(&mut self) -> bool {
+    mem::take(&mut self.choose_folder_request)
+  }
+
+  /// Check if the user requested importing levels from a save-game.
+  pub fn import_request(&mut self) -> bool {
+    mem::take(&mut self.import_request)
+  }
+
+  /// Import the current skill levels from a save-game.
+  pub fn import_skills(&mut self, path: PathBuf) {
+    let data = match GameData::load(path) {
+      Ok(data) => data,
+      Err(err) => {
+        self.import = Some(Import::Error(err.into_owned()));
+        return;
+      }
+    };
+
+    if let Some(err) = data.report().error(Section::Skills) {
+      self.import = Some(Import::Error(format!("Unable to read the skills: {err}")));
+      return;
+    }
+
+    let mut levels = HashMap::new();
+    for category in [SkillCategory::Adventurer, SkillCategory::Producer] {
+      for group in data.get_skills(category, self.skill_override) {
+        for skill in group.skills {
+          levels.insert(skill.info.id, skill.level);
+        }
+      }
+    }
+
+    // Warn if the save-game is for a different avatar.
+    let name = data.avatar_name();
+    if name != self.avatar {
+      let name = name.to_owned();
+      self.import = Some(Import::Mismatch { name, levels });
+      return;
+    }
+
+    self.set_current_levels(levels);
+  }
+
+  /// Check if the user requested importing a skill plan.
+  pub fn import_plan_request(&mut self) -> bool {
+    mem::take(&mut self.import_plan_request)
+  }
+
+  /// Check if the user requested exporting the selected skill plan.
+  pub fn export_plan_request(&mut self) -> bool {
+    mem::take(&mut self.export_plan_request)
+  }
+
+  /// Default file name for exporting the selected skill plan.
+  pub fn plan_file_name(&self) -> String {
+    format!("{}.ron", self.plan.as_deref().unwrap_or("plan"))
+  }
+
+  /// Export the selected skill plan to a RON file.
+  pub fn export_plan(&mut self, path: PathBuf) {
+    let Some(name) = &self.plan else {
+      return;
+    };
+
+    let Some(skills) = self.plans.get(name) else {
+      return;
+    };
+
+    // Use the "ron" extension if there isn't one.
+    let path = if path.extension().is_none() {
+      path.with_extension("ron")
+    } else {
+      path
+    };
+
+    let plan = PlanFile {
+      name: name.clone(),
+      skills: skills.clone(),
+    };
+
+    let result = ron::ser::to_string_pretty(&plan, Default::default()).map_err(|err| err.to_string());
+    if let Err(err) = result.and_then(|text| fs::write(&path, text).map_err(|err| err.to_string())) {
+      self.import = Some(Import::Error(format!("Unable to export to {path:?}: {err}")));
+    }
+  }
+
+  /// Import a skill plan from a RON or JSON file. Unknown skill ids are ignored.
+  pub fn import_plan(&mut self, path: PathBuf) {
+    let text = match fs::read_to_string(&path) {
+      Ok(text) => text,
+      Err(err) => {
+        self.import = Some(Import::Error(format!("Unable to load file: {err}")));
+        return;
+      }
+    };
+
+    let result = if path.extension() == Some(OsStr::new("json")) {
+      serde_json::from_str::<PlanFile>(&text).map_err(|err| err.to_string())
+    } else {
+      ron::from_str::<PlanFile>(&text).map_err(|err| err.to_string())
+    };
+
+    let mut plan = match result {
+      Ok(plan) => plan,
+      Err(err) => {
+        self.import = Some(Import::Error(format!("Unable to parse plan: {err}")));
+        return;
+      }
+    };
+
+    // Ignore skill ids that don't exist.
+    let ids: HashSet<u32> = self
+      .adventurer_skills
+      .iter()
+      .chain(self.producer_skills.iter())
+      .flat_map(|group| group.skills.iter().map(|skill| skill.id))
+      .collect();
+    let count = plan.skills.len();
+    plan.skills.retain(|id, _| ids.contains(id));
+    let unknown = count - plan.skills.len();
+
+    // Use the file name if the plan doesn't have a name.
+    let mut name = plan.name.trim().to_owned();
+    if name.is_empty() {
+      name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    }
+
+    self.plans.insert(name.clone(), plan.skills);
+    self.config.set_skill_plans(&self.avatar, &self.plans);
+    self.load_plan(name);
+
+    if unknown > 0 {
+      let name = self.plan.as_deref().unwrap_or_default();
+      self.import = Some(Import::Warning(format!(
+        "Ignored {unknown} unknown skill id(s) in {name}"
+      )));
+    }
+  }
+
+  /// Replace the current levels with the levels from a plan.
+  fn load_plan(&mut self, name: String) {
+    let Some(skills) = self.plans.get(&name) else {
+      return;
+    };
+
+    self.level_info.skill_lvls = skills.iter().map(|(id, levels)| (*id, *levels)).collect();
+    self.plan_name.clone_from(&name);
+    self.plan = Some(name);
+    self.save();
+  }
+
+  /// Save the current levels as a plan.
+  fn save_plan(&mut self, name: String) {
+    let skills = get_plan_skills(&self.level_info.skill_lvls);
+    self.plans.insert(name.clone(), skills);
+    self.config.set_skill_plans(&self.avatar, &self.plans);
+    self.plan_name.clone_from(&name);
+    self.plan = Some(name);
+  }
+
+  fn rename_plan(&mut self, name: String) {
+    let Some(old) = self.plan.take() else {
+      return;
+    };
+
+    if let Some(skills) = self.plans.remove(&old) {
+      self.plans.insert(name.clone(), skills);
+      self.config.set_skill_plans(&self.avatar, &self.plans);
+    }
+
+    self.plan_name.clone_from(&name);
+    self.plan = Some(name);
+  }
+
+  fn delete_plan(&mut self) {
+    let Some(name) = self.plan.take() else {
+      return;
+    };
+
+    self.plans.remove(&name);
+    self.config.set_skill_plans(&self.avatar, &self.plans);
+    self.plan_name.clear();
+  }
+
+  /// Set the current skill levels, leaving the targets untouched.
+  fn set_current_levels(&mut self, levels: HashMap<u32, i32>) {
+    for (id, level) in levels {
+      get_skill_lvl_mut(&mut self.level_info.skill_lvls, id).0 = level;
+    }
+    self.save();
   }
 
   pub fn save(&mut self) {
@@ -310,11 +1044,19 @@ impl Experience {
     self.save();
 
     // Cancel all async operations on exit.
-    let cancelers = [self.channel.cancel_avatars.take(), self.channel.cancel_adv_exp.take()];
+    let cancelers = [
+      self.channel.cancel_avatars.take(),
+      self.channel.cancel_exp.take(),
+      self.channel.cancel_overview.take(),
+      self.channel.cancel_session.take(),
+    ];
 
     for mut cancel in cancelers.into_iter().flatten() {
       cancel.cancel();
     }
+
+    // Stop receiving log changes.
+    self.watcher = None;
   }
 
   fn request_avatars(&mut self, ctx: &Context) {
@@ -332,7 +1074,7 @@ impl Experience {
     // Setup the future.
     let tx = self.channel.tx.clone();
     let ctx = ctx.clone();
-    let future = log_data::get_avatars(self.log_path.clone(), cancel);
+    let future = log_data::get_avatars(self.log_paths.clone(), cancel);
     let future = async move {
       let avatars = Message::Avatars(future.await);
       tx.unbounded_send(avatars).unwrap();
@@ -348,8 +1090,8 @@ impl Experience {
       return;
     }
 
-    // Cancel any previous adventurer experience request.
-    if let Some(mut cancel) = self.channel.cancel_adv_exp.take() {
+    // Cancel any previous experience request.
+    if let Some(mut cancel) = self.channel.cancel_exp.take() {
       cancel.cancel();
     }
 
@@ -359,26 +1101,43 @@ impl Experience {
     // Store the new avatar name.
     self.config.set_exp_avatar(avatar.clone());
 
+    // The session is for the previous avatar.
+    self.stop_session();
+
     // Get the values for the new avatar.
     let skills = self.config.get_avatar_skills(&avatar).unwrap_or_default();
 
     self.level_info.skill_lvls = skills;
     self.level_info.adv_exp = 0;
+    self.level_info.prd_exp = 0;
+    self.exp_ts = None;
+    self.exp_samples = self.config.get_exp_samples(&avatar);
+    self.record_exp = false;
+    self.plans = self.config.get_skill_plans(&avatar).unwrap_or_default();
+    self.plan = None;
+    self.plan_name.clear();
+    let expanded = self.config.get_avatar_state(&avatar).expanded;
+    let groups = self.adventurer_skills.iter().chain(&self.producer_skills);
+    if self.expanded.load(expanded, groups.map(|group| group.name)) {
+      // Forget groups that no longer exist.
+      self.config.set_avatar_expanded(&avatar, self.expanded.names());
+    }
+    self.import = None;
     self.avatar = avatar;
   }
 
-  fn request_adv_exp(&mut self, ctx: &Context) {
+  fn request_exp(&mut self, ctx: &Context) {
     if self.avatar.is_empty() {
       return;
     }
 
     // Cancel any previous request.
-    if let Some(mut cancel) = self.channel.cancel_adv_exp.take() {
+    if let Some(mut cancel) = self.channel.cancel_exp.take() {
       cancel.cancel();
     }
 
     let cancel = Cancel::default();
-    self.channel.cancel_adv_exp = Some(cancel.clone());
+    self.channel.cancel_exp = Some(cancel.clone());
 
     // Show the busy cursor.
     self.state.set_busy(true);
@@ -386,10 +1145,10 @@ impl Experience {
     // Setup the future.
     let tx = self.channel.tx.clone();
     let ctx = ctx.clone();
-    let future = log_data::get_adv_exp(self.log_path.clone(), self.avatar.clone(), cancel);
+    let future = log_data::get_exp(self.log_paths.clone(), self.avatar.clone(), cancel);
     let future = async move {
-      let avatars = Message::AdvExp(future.await);
-      tx.unbounded_send(avatars).unwrap();
+      let exp = Message::Exp(future.await);
+      tx.unbounded_send(exp).unwrap();
       ctx.request_repaint();
     };
 
@@ -397,31 +1156,328 @@ impl Experience {
     self.threads.spawn_ok(future);
   }
 
-  fn get_adv_info(&self) -> Option<AdvInfo> {
-    let exp = self.level_info.adv_exp;
-    if exp > 0 {
-      let lvl = util::floor_search(exp, LEVEL_EXP).unwrap() as i32 + 1;
-      if lvl < 200 {
-        return Some(AdvInfo {
-          lvl,
-          exp: Some(LEVEL_EXP[lvl as usize] - exp),
-        });
-      } else {
-        return Some(AdvInfo { lvl, exp: None });
-      }
+  fn request_overview(&mut self, ctx: &Context) {
+    // Cancel any previous request.
+    if let Some(mut cancel) = self.channel.cancel_overview.take() {
+      cancel.cancel();
+    }
+
+    let cancel = Cancel::default();
+    self.channel.cancel_overview = Some(cancel.clone());
+
+    // Show the busy cursor.
+    self.state.set_busy(true);
+
+    // Setup the future.
+    let tx = self.channel.tx.clone();
+    let ctx = ctx.clone();
+    let log_paths = self.log_paths.clone();
+    let avatars = self.avatars.clone();
+    let threads = self.threads.clone();
+    let state = self.state.clone();
+    let future = log_data::get_avatars_exp(log_paths, avatars, cancel, Some(threads), state);
+    let future = async move {
+      let rows = Message::Overview(future.await);
+      tx.unbounded_send(rows).unwrap();
+      ctx.request_repaint();
+    };
+
+    // Execute the future on a pooled thread.
+    self.threads.spawn_ok(future);
+  }
+
+  /// Scan the chat log for experience gains since the previous scan.
+  fn request_session_exp(&mut self, ctx: &Context) {
+    let Some(session) = &self.session else { return };
+
+    // Cancel any previous request, the new one reads from the same offset.
+    if let Some(mut cancel) = self.channel.cancel_session.take() {
+      cancel.cancel();
     }
 
-    None
+    let cancel = Cancel::default();
+    self.channel.cancel_session = Some(cancel.clone());
+
+    // Setup the future.
+    let tx = self.channel.tx.clone();
+    let ctx = ctx.clone();
+    let future = log_data::get_session_exp(
+      self.log_paths.clone(),
+      self.avatar.clone(),
+      session.start,
+      session.path.clone(),
+      session.offset,
+      cancel.clone(),
+    );
+    let future = async move {
+      let scan = future.await;
+
+      // Gains from a canceled scan would be counted twice.
+      if !cancel.is_canceled() {
+        tx.unbounded_send(Message::SessionExp(scan)).unwrap();
+        ctx.request_repaint();
+      }
+    };
+
+    // Execute the future on a pooled thread.
+    self.threads.spawn_ok(future);
   }
 }
 
-struct AdvInfo {
+/// Level and experience needed for the next level.
+struct ExpInfo {
   lvl: i32,
   exp: Option<i64>,
 }
 
+fn get_exp_info(exp: i64) -> Option<ExpInfo> {
+  if exp > 0 {
+    let (lvl, exp) = util::get_level_info(exp);
+    return Some(ExpInfo { lvl, exp });
+  }
+
+  None
+}
+
+/// Show a level button followed by the experience needed for the next level. Returns true if the button was clicked.
+fn show_level(ui: &mut Ui, prefix: &str, info: Option<ExpInfo>, locale: &Locale) -> bool {
+  let (button_text, exp) = if let Some(info) = info {
+    (format!("{prefix} Lvl {}", info.lvl), info.exp)
+  } else {
+    (format!("{prefix} Lvl ?"), None)
+  };
+
+  let hover_text = "Type /xp in-game to update, or click to read the log again";
+  let clicked = ui.button(button_text).on_hover_text(hover_text).clicked();
+
+  if let Some(exp) = exp {
+    ui.spacing_mut().item_spacing.x *= 0.5;
+    ui.label("Next");
+
+    let text = exp.to_formatted_string(locale);
+    let response = Label::new(text).sense(Sense::click()).ui(ui);
+    if response.on_hover_text("Click to copy").clicked() {
+      util::set_clipboard_contents(format!("{}", exp));
+    }
+  }
+
+  clicked
+}
+
+/// Show the time of the `/xp` sample, including the date if it's not from today.
+fn show_exp_time(ui: &mut Ui, ts: i64) {
+  let Some(date_time) = util::timestamp_to_local(ts) else {
+    return;
+  };
+
+  let format = if date_time.date() == Local::now().date_naive() {
+    "as of %H:%M"
+  } else {
+    "as of %Y-%m-%d %H:%M"
+  };
+  ui.label(RichText::from(date_time.format(format).to_string()).weak())
+    .on_hover_text("When /xp was last used");
+}
+
+/// Show the change in experience since the previous refresh, the time left at that rate and a sparkline of the
+/// recent samples.
+fn show_exp_trend(ui: &mut Ui, trend: &ExpTrend, samples: &[(i64, i64)], locale: &Locale) {
+  let (text, color) = if trend.delta < 0 {
+    (trend.delta.to_formatted_string(locale), Color::Decrease.get())
+  } else {
+    (
+      format!("+{}", trend.delta.to_formatted_string(locale)),
+      Color::Increase.get(),
+    )
+  };
+  ui.label(RichText::from(text).color(color))
+    .on_hover_text("Change since the previous refresh");
+
+  if let Some(hours) = trend.hours_left {
+    ui.label(format!("≈{} h left", util::format_f64(hours, 1, locale)))
+      .on_hover_text("Time until the experience runs out at this rate");
+  }
+
+  // Sparkline of the recent samples.
+  let (rect, response) = ui.allocate_exact_size(vec2(SPARKLINE_WIDTH, ui.spacing().interact_size.y), Sense::hover());
+  let (first, last) = (samples[0].0, samples[samples.len() - 1].0);
+  let span = (last - first).max(1) as f32;
+  let range = (trend.max - trend.min).max(1) as f32;
+  let rect = rect.shrink(2.0);
+  let points = samples
+    .iter()
+    .map(|&(ts, exp)| {
+      let x = rect.left() + rect.width() * (ts - first) as f32 / span;
+      let y = rect.bottom() - rect.height() * (exp - trend.min) as f32 / range;
+      pos2(x, y)
+    })
+    .collect();
+  let stroke = Stroke::new(1.0, ui.visuals().text_color());
+  ui.painter().add(Shape::line(points, stroke));
+  response.on_hover_text(format!(
+    "{} samples\nMin: {}\nMax: {}",
+    samples.len(),
+    trend.min.to_formatted_string(locale),
+    trend.max.to_formatted_string(locale)
+  ));
+}
+
+/// Show the experience earned during the session, the rate and the estimated amount spent.
+fn show_session(ui: &mut Ui, session: &Session, now: i64, locale: &Locale) {
+  const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+  let secs = (now - session.start).max(0);
+  let earned = session.earned();
+  let per_hour = earned as f64 * 3600.0 / secs.max(1) as f64;
+  ui.horizontal(|ui| {
+    let x_spacing = ui.spacing().item_spacing.x;
+    let add = |ui: &mut Ui, label: &str, text: String| {
+      ui.spacing_mut().item_spacing.x = x_spacing * 0.5;
+      ui.label(RichText::from(label).color(LABEL_COLOR));
+      ui.spacing_mut().item_spacing.x = x_spacing;
+      ui.label(text)
+    };
+
+    add(ui, "Session", util::get_countdown_text("", secs as i32));
+    ui.separator();
+    add(ui, "Earned", earned.to_formatted_string(locale));
+    ui.separator();
+    add(ui, "Per Hour", (per_hour as i64).to_formatted_string(locale));
+    if let Some(spent) = session.spent() {
+      ui.separator();
+      add(ui, "Spent", format!("≈{}", spent.to_formatted_string(locale)))
+        .on_hover_text("Estimated from the change in /xp and the experience earned in between");
+    }
+  });
+}
+
+/// Get the current time as a log timestamp.
+fn get_log_now() -> i64 {
+  Utc::now().timestamp()
+}
+
+/// Adventurer experience earned since the session was started.
+struct Session {
+  /// Log timestamp of when the session was started.
+  start: i64,
+
+  /// Log file and offset to resume reading from.
+  path: Option<PathBuf>,
+  offset: u64,
+
+  /// Timestamp and amount of each gain.
+  gains: Vec<(i64, i64)>,
+
+  /// First and most recent `/xp` samples (timestamp and experience) during the session.
+  first_exp: Option<(i64, i64)>,
+  last_exp: Option<(i64, i64)>,
+}
+
+impl Session {
+  fn new(start: i64) -> Self {
+    Self {
+      start,
+      path: None,
+      offset: 0,
+      gains: Vec::new(),
+      first_exp: None,
+      last_exp: None,
+    }
+  }
+
+  fn earned(&self) -> i64 {
+    self.gains.iter().map(|&(_, exp)| exp).sum()
+  }
+
+  /// Estimate the experience spent into skills between the first and most recent `/xp`. Whatever was earned in between
+  /// and isn't in the pool was spent.
+  fn spent(&self) -> Option<i64> {
+    let (first_ts, first_exp) = self.first_exp?;
+    let (last_ts, last_exp) = self.last_exp?;
+    if last_ts <= first_ts {
+      return None;
+    }
+
+    let earned: i64 = self
+      .gains
+      .iter()
+      .filter(|&&(ts, _)| ts > first_ts && ts <= last_ts)
+      .map(|&(_, exp)| exp)
+      .sum();
+    Some((earned - (last_exp - first_exp)).max(0))
+  }
+}
+
+/// Change in experience between the two most recent samples.
+#[derive(Debug, PartialEq)]
+struct ExpTrend {
+  delta: i64,
+
+  /// Hours until the experience is used up, if it's draining.
+  hours_left: Option<f64>,
+
+  /// Range of all the samples.
+  min: i64,
+  max: i64,
+}
+
+/// Get the trend of the samples (timestamp and experience, oldest first). Requires at least two samples.
+fn get_exp_trend(samples: &[(i64, i64)]) -> Option<ExpTrend> {
+  let [.., (prev_ts, prev_exp), (ts, exp)] = *samples else {
+    return None;
+  };
+
+  let delta = exp - prev_exp;
+  let secs = ts - prev_ts;
+  let hours_left = (delta < 0 && secs > 0).then(|| {
+    let per_hour = -delta as f64 * 3600.0 / secs as f64;
+    exp.max(0) as f64 / per_hour
+  });
+
+  let values = samples.iter().map(|&(_, exp)| exp);
+  Some(ExpTrend {
+    delta,
+    hours_left,
+    min: values.clone().min()?,
+    max: values.max()?,
+  })
+}
+
+/// Add a sample, unless it's not newer than the last one, keeping only the most recent `MAX_EXP_SAMPLES`. Returns
+/// true if the sample was added.
+fn add_exp_sample(samples: &mut Vec<(i64, i64)>, sample: (i64, i64)) -> bool {
+  if samples.last().is_some_and(|last| last.0 >= sample.0) {
+    return false;
+  }
+
+  samples.push(sample);
+  if samples.len() > MAX_EXP_SAMPLES {
+    samples.drain(..samples.len() - MAX_EXP_SAMPLES);
+  }
+  true
+}
+
+/// Pending or failed import from a save-game.
+enum Import {
+  /// The save-game's avatar doesn't match.
+  Mismatch { name: String, levels: HashMap<u32, i32> },
+
+  /// Something was ignored while importing a skill plan.
+  Warning(String),
+
+  /// The save-game or skill plan couldn't be loaded.
+  Error(String),
+}
+
+/// Skill plan export file.
+#[derive(Serialize, Deserialize)]
+struct PlanFile {
+  name: String,
+  skills: BTreeMap<u32, (i32, i32)>,
+}
+
 struct LevelInfo {
   adv_exp: i64,
+  prd_exp: i64,
   skill_lvls: HashMap<u32, (i32, i32)>,
 }
 
@@ -429,33 +1485,270 @@ impl LevelInfo {
   fn new() -> Self {
     LevelInfo {
       adv_exp: 0,
+      prd_exp: 0,
       skill_lvls: HashMap::new(),
     }
   }
 }
 
+/// Experience needed to reach the target levels.
+#[derive(Clone, Copy)]
+struct ExpTotal {
+  needed: i64,
+  refund: i64,
+}
+
+impl ExpTotal {
+  fn to_string(self, locale: &Locale) -> String {
+    let text = self.needed.to_formatted_string(locale);
+    if self.refund != 0 {
+      // Show the un-training refund in parentheses, same as the individual skills.
+      return format!("{text} ({})", self.refund.to_formatted_string(locale));
+    }
+    text
+  }
+}
+
+/// How often the status area is repainted while progress is shown.
+const PROGRESS_REPAINT: Duration = Duration::from_millis(250);
+
+/// Number of `/xp` samples kept for each avatar.
+const MAX_EXP_SAMPLES: usize = 50;
+
+const SPARKLINE_WIDTH: f32 = 48.0;
+
 enum Message {
-  Avatars(Vec<String>),
-  AdvExp(Option<i64>),
+  Avatars((Vec<AvatarLogs>, Option<PathBuf>)),
+  Exp(AvatarExp),
+  Overview(Vec<(String, AvatarExp)>),
+  SessionExp(SessionExp),
+  LogChange(LogChange),
+  LogsAvailable,
 }
 
 struct Channel {
   tx: mpsc::UnboundedSender<Message>,
   rx: mpsc::UnboundedReceiver<Message>,
   cancel_avatars: Option<Cancel>,
-  cancel_adv_exp: Option<Cancel>,
+  cancel_exp: Option<Cancel>,
+  cancel_overview: Option<Cancel>,
+  cancel_session: Option<Cancel>,
+}
+
+/// Subscribe to the log watcher and forward changes as messages.
+fn watch_logs(ctx: &Context, channel: &Channel, watcher: &LogWatcher) -> LogSubscription {
+  let tx = channel.tx.clone();
+  let ctx = ctx.clone();
+  watcher.subscribe(move |event| {
+    let msg = match event {
+      LogEvent::Change(change) => Message::LogChange(change),
+      LogEvent::Available => Message::LogsAvailable,
+    };
+    tx.unbounded_send(msg).unwrap();
+    ctx.request_repaint();
+  })
+}
+
+/// Get the non-empty levels, sorted by skill id.
+fn get_plan_skills(levels: &HashMap<u32, (i32, i32)>) -> BTreeMap<u32, (i32, i32)> {
+  levels
+    .iter()
+    .filter(|(_, levels)| levels.0 > 0 || levels.1 > 0)
+    .map(|(id, levels)| (*id, *levels))
+    .collect()
 }
 
 fn get_skill_lvl_mut(levels: &mut HashMap<u32, (i32, i32)>, id: u32) -> &mut (i32, i32) {
   levels.entry(id).or_insert_with(|| (0, 0))
 }
 
-fn get_needed_exp(level: &(i32, i32), mul: f64) -> Option<i64> {
+/// Check if the target level (current, target) is below the current level.
+fn is_untraining(level: &(i32, i32)) -> bool {
+  level.1 < level.0
+}
+
+/// Sum the experience needed for each skill in the groups where the target level differs from the current level.
+fn get_total_exp(groups: &[SkillInfoGroup], levels: &HashMap<u32, (i32, i32)>, pooled: bool) -> ExpTotal {
+  let mut total = ExpTotal { needed: 0, refund: 0 };
+  for group in groups {
+    for skill in &group.skills {
+      let Some(level) = levels.get(&skill.id) else {
+        continue;
+      };
+
+      if level.0 == level.1 {
+        continue;
+      }
+
+      if let Some(exp) = get_needed_exp(level, skill, pooled) {
+        if exp < 0 {
+          // Half experience returned for un-training.
+          total.refund += exp.abs() / 2;
+        } else {
+          total.needed += exp;
+        }
+      }
+    }
+  }
+  total
+}
+
+/// Get the experience needed to go from the current level to the target level, at the pooled experience rate if
+/// `pooled` is true.
+fn get_needed_exp(level: &(i32, i32), skill: &SkillInfo, pooled: bool) -> Option<i64> {
   if level.0 > 0 || level.1 > 0 {
     let cur_lvl = level.0.max(1);
     let tgt_lvl = level.1.max(1);
     let val = SKILL_EXP[tgt_lvl as usize - 1] - SKILL_EXP[cur_lvl as usize - 1];
-    return Some((val as f64 * mul).ceil() as i64);
+    return Some((val as f64 * skill.get_mul(pooled)).ceil() as i64);
   }
   None
 }
+
+/// Hover text for a skill's experience, showing both rates if there's a pooled experience rate.
+fn get_exp_hover_text(level: &(i32, i32), skill: &SkillInfo, locale: &Locale) -> String {
+  if skill.pool_mul.is_none() {
+    return String::from("Click to copy");
+  }
+
+  let text = |pooled| {
+    let exp = get_needed_exp(level, skill, pooled).unwrap_or_default();
+    let exp = if exp < 0 { exp / 2 } else { exp };
+    exp.abs().to_formatted_string(locale)
+  };
+  format!("Crafting: {}\nPooled: {}\nClick to copy", text(false), text(true))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_untrain_refund() {
+    let skill = |id, mul| SkillInfo {
+      id,
+      mul,
+      ..Default::default()
+    };
+    let groups = [SkillInfoGroup {
+      name: "Air",
+      skills: vec![skill(1, 1.0), skill(2, 2.0), skill(3, 1.0)],
+    }];
+
+    // Only the skill with a target below its current level is refunded, at half the experience.
+    let levels = HashMap::from([(1, (80, 60)), (2, (20, 40)), (3, (50, 50))]);
+    let untraining: Vec<u32> = groups[0]
+      .skills
+      .iter()
+      .filter(|skill| is_untraining(&levels[&skill.id]))
+      .map(|skill| skill.id)
+      .collect();
+    assert_eq!(untraining, [1]);
+
+    let total = get_total_exp(&groups, &levels, false);
+    let exp = get_needed_exp(&levels[&1], &groups[0].skills[0], false).unwrap();
+    assert!(exp < 0);
+    assert_eq!(total.refund, -exp / 2);
+  }
+
+  #[test]
+  fn test_pooled_exp() {
+    let find = |category, name| {
+      let groups = skill_info::parse_skill_info_groups(category, SkillOverride::default());
+      let skill = groups
+        .into_iter()
+        .flat_map(|group| group.skills)
+        .find(|skill| skill.name == name);
+      skill.unwrap()
+    };
+
+    // Producer skills cost twice as much from the pooled experience.
+    let level = (20, 40);
+    for name in ["Water Plants", "Alchemy Mastery"] {
+      let skill = find(SkillCategory::Producer, name);
+      let crafting = get_needed_exp(&level, &skill, false).unwrap();
+      let pooled = get_needed_exp(&level, &skill, true).unwrap();
+      assert!(crafting > 0);
+      assert_eq!(pooled, crafting * 2, "{name}");
+    }
+
+    // Multipliers other than one are kept.
+    let skill = find(SkillCategory::Producer, "Alchemy Mastery");
+    assert_eq!((skill.get_mul(false), skill.get_mul(true)), (2.0, 4.0));
+
+    // Adventurer skills are always trained from the pooled experience, so there's only one rate.
+    let skill = find(SkillCategory::Adventurer, "Air Magic Specialization");
+    assert_eq!(skill.pool_mul, None);
+    assert_eq!(
+      get_needed_exp(&level, &skill, true),
+      get_needed_exp(&level, &skill, false)
+    );
+
+    // The default is the crafting rate, so totals don't change unless pooled is chosen.
+    let groups = [SkillInfoGroup {
+      name: "Alchemy",
+      skills: vec![find(SkillCategory::Producer, "Alchemy Mastery")],
+    }];
+    let levels = HashMap::from([(2079, level)]);
+    let crafting = get_total_exp(&groups, &levels, false).needed;
+    assert_eq!(crafting, get_needed_exp(&level, &groups[0].skills[0], false).unwrap());
+    assert_eq!(get_total_exp(&groups, &levels, true).needed, crafting * 2);
+  }
+
+  #[test]
+  fn test_session_spent() {
+    let mut session = Session::new(0);
+    session.gains = vec![(10, 100), (20, 200), (40, 400)];
+    assert_eq!(session.earned(), 700);
+    assert_eq!(session.spent(), None);
+
+    // Only one `/xp` sample.
+    session.first_exp = Some((15, 5000));
+    session.last_exp = Some((15, 5000));
+    assert_eq!(session.spent(), None);
+
+    // Earned 600 between the samples but the pool only grew by 100.
+    session.last_exp = Some((40, 5100));
+    assert_eq!(session.spent(), Some(500));
+
+    // Nothing spent.
+    session.last_exp = Some((40, 5600));
+    assert_eq!(session.spent(), Some(0));
+  }
+
+  #[test]
+  fn test_exp_trend() {
+    assert_eq!(get_exp_trend(&[]), None);
+    assert_eq!(get_exp_trend(&[(0, 1000)]), None);
+
+    // Draining 1,000 an hour with 5,000 left.
+    let samples = [(0, 8000), (3600, 6000), (7200, 5000)];
+    let trend = get_exp_trend(&samples).unwrap();
+    assert_eq!(trend.delta, -1000);
+    assert_eq!(trend.hours_left, Some(5.0));
+    assert_eq!((trend.min, trend.max), (5000, 8000));
+
+    // Gaining experience has no time left.
+    let trend = get_exp_trend(&[(0, 5000), (1800, 5500)]).unwrap();
+    assert_eq!(trend.delta, 500);
+    assert_eq!(trend.hours_left, None);
+  }
+
+  #[test]
+  fn test_add_exp_sample() {
+    let mut samples = Vec::new();
+    assert!(add_exp_sample(&mut samples, (10, 100)));
+
+    // Refreshing without a newer `/xp` isn't a new sample.
+    assert!(!add_exp_sample(&mut samples, (10, 100)));
+    assert!(!add_exp_sample(&mut samples, (5, 200)));
+    assert_eq!(samples, [(10, 100)]);
+
+    // Only the most recent are kept.
+    for ts in 11..11 + MAX_EXP_SAMPLES as i64 {
+      assert!(add_exp_sample(&mut samples, (ts, 100)));
+    }
+    assert_eq!(samples.len(), MAX_EXP_SAMPLES);
+    assert_eq!(samples[0].0, 11);
+  }
+}
@@ -1,15 +1,20 @@
 use crate::{
   config::Config,
   plant_dlg::PlantDlg,
-  plant_info::{CropTimer, Event},
-  util::{AppState, Cancel},
+  plant_info::{CropOrder, CropTimer, Event},
+  planting_dlg::PlantingDlg,
+  theme::Color,
+  util::{self, AppState, Cancel},
 };
+use chrono::{Local, NaiveDateTime};
 use eframe::{
-  egui::{Context, Label, ScrollArea, TextWrapMode, Ui, WidgetText},
+  egui::{Button, Context, Frame, Key, Label, RichText, ScrollArea, TextWrapMode, Ui, WidgetText, Window},
+  emath::Align2,
   epaint::Color32,
 };
-use notify_rust::Notification;
+use notify_rust::{Notification, NotificationHandle};
 use std::{
+  collections::{HashMap, HashSet},
   sync::{
     atomic::{AtomicBool, Ordering},
     Arc, Mutex,
@@ -21,8 +26,17 @@ use thread::JoinHandle;
 
 pub struct Farming {
   config: Config,
+  state: AppState,
   plant_dlg: PlantDlg,
+  planting_dlg: PlantingDlg,
+  edit_index: Option<usize>,
+  order: CropOrder,
+  group: bool,
   timers: Arc<Mutex<Vec<CropTimer>>>,
+  missed: Vec<(Event, String)>,
+  selecting: bool,
+  selected: HashSet<usize>,
+  bulk: Option<(Bulk, Vec<usize>)>,
   persist: Arc<AtomicBool>,
   cancel: Option<Cancel>,
   thread: Option<JoinHandle<()>>,
@@ -30,13 +44,19 @@ pub struct Farming {
 
 impl Farming {
   pub fn new(ctx: Context, config: Config, state: AppState) -> Self {
-    let plant_dlg = PlantDlg::new(config.clone(), state);
-    let timers = config.get_crop_timers().unwrap_or_default();
+    let plant_dlg = PlantDlg::new(config.clone(), state.clone());
+    let planting_dlg = PlantingDlg::new(state.clone());
+    let order = config.get_crop_order();
+    let group = config.get_group_crops();
+    let mut timers = config.get_crop_timers().unwrap_or_default();
+
+    // Catch up on events that became due while the app was closed.
+    let missed = get_missed_events(&mut timers, config.get_crops_checked());
     let timers = Arc::new(Mutex::new(timers));
-    let persist = Arc::new(AtomicBool::new(false));
+    let persist = Arc::new(AtomicBool::new(!missed.is_empty()));
     let cancel = Cancel::default();
     let thread = Some(thread::spawn({
-      let mut _notification = None;
+      let mut _notification = show_missed_notification(&missed);
       let timers = timers.clone();
       let persist = persist.clone();
       let cancel = cancel.clone();
@@ -51,15 +71,7 @@ impl Farming {
               Event::Harvest => "Harvest Plants",
             };
             if !summary.is_empty() {
-              let name = plant.seed_name();
-              let env = plant.environment();
-              let desc = plant.description();
-              let body = if desc.is_empty() {
-                format!("{name} | {env:?}")
-              } else {
-                format!("{name} | {env:?} | {desc}")
-              };
-
+              let body = get_timer_text(plant);
               match Notification::new().summary(summary).body(&body).show() {
                 Ok(handle) => {
                   // Discarding the handle closes the notification on Wayland, so keep it around until the next notification.
@@ -96,8 +108,17 @@ impl Farming {
 
     Self {
       config,
+      state,
       plant_dlg,
+      planting_dlg,
+      edit_index: None,
+      order,
+      group,
       timers,
+      missed,
+      selecting: false,
+      selected: HashSet::new(),
+      bulk: None,
       persist,
       cancel: Some(cancel),
       thread,
@@ -106,41 +127,162 @@ impl Farming {
 
   pub fn show(&mut self, ui: &mut Ui) {
     if !self.plant_dlg.show(ui.ctx()) {
-      if let Some(plant_info) = self.plant_dlg.take_result() {
-        self.timers.lock().unwrap().push(plant_info);
+      let edit_index = self.edit_index.take();
+      if let Some(mut plant_info) = self.plant_dlg.take_result() {
+        let mut lock = self.timers.lock().unwrap();
+        match edit_index.and_then(|index| lock.get_mut(index)) {
+          Some(timer) => {
+            // Replace the existing timer, keeping the event flags if the timing didn't change.
+            plant_info.keep_events(timer);
+            *timer = plant_info;
+          }
+          None => lock.push(plant_info),
+        }
         self.persist.store(true, Ordering::Relaxed);
       }
     }
 
+    // Take the timers as soon as they're added, so that each add is kept even if the dialog is closed afterwards.
+    self.planting_dlg.show(ui.ctx());
+    let timers = self.planting_dlg.take_result();
+    if !timers.is_empty() {
+      self.timers.lock().unwrap().extend(timers);
+      self.persist.store(true, Ordering::Relaxed);
+    }
+
+    self.show_bulk_confirm(ui.ctx());
+
     // Tool bar.
     ui.horizontal(|ui| {
       if ui.button("Add Crop Timer").clicked() {
+        self.edit_index = None;
         self.plant_dlg.open();
       }
+
+      if ui.button("Add from Notes").clicked() {
+        self.planting_dlg.open();
+      }
+
+      ui.separator();
+
+      let next = ui.selectable_value(&mut self.order, CropOrder::NextEvent, "By Next Event");
+      let planted = ui.selectable_value(&mut self.order, CropOrder::Planted, "By Planting");
+      if next.clicked() || planted.clicked() {
+        self.config.set_crop_order(self.order);
+      }
+
+      ui.separator();
+
+      if ui.checkbox(&mut self.group, "Group by Environment").changed() {
+        self.config.set_group_crops(self.group);
+      }
+
+      ui.separator();
+
+      // Bulk operations.
+      let (water, harvest) = {
+        let lock = self.timers.lock().unwrap();
+        let find = |event| -> Vec<usize> {
+          let timers = lock.iter().enumerate();
+          timers
+            .filter_map(|(index, timer)| (timer.current_event() == event).then_some(index))
+            .collect()
+        };
+        (find(Event::Water), find(Event::Harvest))
+      };
+
+      if ui
+        .add_enabled(!water.is_empty(), Button::new("Water All Due"))
+        .clicked()
+      {
+        self.bulk = Some((Bulk::Water, water));
+      }
+
+      if ui
+        .add_enabled(!harvest.is_empty(), Button::new("Harvest All Ready"))
+        .clicked()
+      {
+        self.bulk = Some((Bulk::Harvest, harvest));
+      }
+
+      ui.separator();
+
+      if ui.checkbox(&mut self.selecting, "Select").changed() {
+        self.selected.clear();
+      }
+
+      if self.selecting {
+        let text = format!("Discard Selected ({})", self.selected.len());
+        if ui.add_enabled(!self.selected.is_empty(), Button::new(text)).clicked() {
+          let mut selected: Vec<usize> = self.selected.iter().copied().collect();
+          selected.sort_unstable();
+          self.bulk = Some((Bulk::Discard, selected));
+        }
+      }
     });
 
     ui.separator();
 
+    if !self.missed.is_empty() {
+      self.show_missed(ui);
+      ui.separator();
+    }
+
     // Timer list.
     ScrollArea::vertical().id_salt("farming_scroll_area").show(ui, |ui| {
       let mut lock = self.timers.lock().unwrap();
-      let mut index = 0;
-      while index < lock.len() {
-        let mut delete = false;
+      let now = Local::now().naive_local();
+      let mut delete = None;
+      let mut environment = None;
+      let mut lot = None;
+
+      // Group by lot once any of the timers has a location.
+      let lot_counts = get_lot_counts(&lock);
+
+      // Sort a snapshot of the indices so that the stored order doesn't change.
+      for index in get_sorted_indices(&lock, self.order, self.group) {
         let plant = &mut lock[index];
         let event = plant.current_event();
         let item_spacing = ui.spacing().item_spacing;
         let mut events = plant.remaining_events();
 
+        if let Some(lot_counts) = &lot_counts {
+          let name = get_lot_name(plant);
+          if lot.as_deref() != Some(name) {
+            // Lot heading.
+            let text = format!("{name} ({})", lot_counts[name]);
+            ui.label(RichText::from(text).color(Color32::from_rgb(229, 187, 123)).strong());
+            lot = Some(name.to_owned());
+            environment = None;
+          }
+        }
+
+        if self.group && environment != Some(plant.environment()) {
+          // Environment heading.
+          environment = Some(plant.environment());
+          ui.label(RichText::from(format!("{:?}", plant.environment())).color(Color32::from_rgb(229, 187, 123)));
+        }
+
         // Use a single column in order to force the scroll area to fill the entire available width.
         ui.columns(1, |col| {
           col[0].horizontal(|ui| {
+            if self.selecting {
+              let mut selected = self.selected.contains(&index);
+              if ui.checkbox(&mut selected, "").changed() {
+                if selected {
+                  self.selected.insert(index);
+                } else {
+                  self.selected.remove(&index);
+                }
+              }
+            }
+
             // Seed name.
             let text = WidgetText::from(plant.seed_name());
             let text = match event {
-              Event::None => text.color(Color32::from_rgb(220, 220, 220)),
-              Event::Water => text.color(Color32::from_rgb(255, 255, 0)),
-              Event::Harvest => text.color(Color32::from_rgb(0, 255, 0)),
+              Event::None => text.color(Color::Growing.get()),
+              Event::Water => text.color(Color::Water.get()),
+              Event::Harvest => text.color(Color::Harvest.get()),
             };
             ui.spacing_mut().item_spacing.x = item_spacing.x * 0.5;
             ui.label(text);
@@ -151,14 +293,21 @@ impl Farming {
             ui.separator();
             ui.label(format!("{environment:?} {date_time}",));
 
+            // Plant bed.
+            if let Some(bed) = plant.location().and_then(|location| location.bed) {
+              ui.separator();
+              ui.label(format!("Bed {bed}"));
+            }
+
             if !events.is_empty() {
               ui.separator();
             }
 
-            // Next event.
+            // Next event, with a countdown.
             if let Some((event, date_time)) = events.pop() {
+              let countdown = util::get_countdown_text("", (date_time - now).num_seconds() as i32);
               let date_time = date_time.format("%Y-%m-%d %H:%M");
-              ui.label(format!("{event:?} {date_time}"));
+              ui.label(format!("{event:?} {date_time} ({countdown})"));
               if !events.is_empty() {
                 ui.separator();
               }
@@ -182,7 +331,7 @@ impl Farming {
             match event {
               Event::None => {
                 if ui.button("Discard").clicked() {
-                  delete = true;
+                  delete = Some(index);
                 }
               }
               Event::Water => {
@@ -193,11 +342,16 @@ impl Farming {
               }
               Event::Harvest => {
                 if ui.button("Harvest").clicked() {
-                  delete = true;
+                  delete = Some(index);
                 }
               }
             }
 
+            if ui.button("Edit").clicked() {
+              self.edit_index = Some(index);
+              self.plant_dlg.open_edit(plant);
+            }
+
             let widget = Label::new(plant.description()).wrap_mode(TextWrapMode::Extend);
             ui.spacing_mut().item_spacing.x = item_spacing.x;
             ui.add(widget);
@@ -206,18 +360,147 @@ impl Farming {
           col[0].visuals_mut().widgets.noninteractive.bg_stroke.color = Color32::from_gray(45);
           col[0].separator();
         });
+      }
 
-        if delete {
-          lock.remove(index);
-          self.persist.store(true, Ordering::Relaxed);
-        } else {
-          index += 1;
-        }
+      if let Some(index) = delete {
+        lock.remove(index);
+        self.persist.store(true, Ordering::Relaxed);
+
+        // The indices have changed.
+        self.selected.clear();
       }
 
       if self.persist.swap(false, Ordering::Relaxed) {
         // Persist the timers.
         self.config.set_crop_timers(&lock);
+        self.config.set_crops_checked(Local::now().naive_local());
+      }
+
+      // Keep the countdowns current.
+      if !lock.is_empty() {
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+      }
+    });
+  }
+
+  fn show_bulk_confirm(&mut self, ctx: &Context) {
+    let Some((bulk, indices)) = &self.bulk else { return };
+
+    // Keep the rest of the UI disabled while the confirmation is showing.
+    self.state.set_disabled(true);
+
+    let count = indices.len();
+    let plural = if count == 1 { "" } else { "s" };
+    let text = match bulk {
+      Bulk::Water => format!("Water {count} crop{plural}?"),
+      Bulk::Harvest => format!("Harvest {count} crop{plural}? The timer{plural} will be removed."),
+      Bulk::Discard => format!("Discard {count} selected timer{plural}?"),
+    };
+
+    let mut accept = None;
+    let mut open = true;
+    Window::new(RichText::from("⚠  Crop Timers").strong())
+      .open(&mut open)
+      .collapsible(false)
+      .resizable(false)
+      .current_pos([0.0, 24.0])
+      .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+      .show(ctx, |ui| {
+        ui.add_space(8.0);
+        ui.vertical_centered(|ui| {
+          ui.label(text);
+        });
+        ui.add_space(8.0);
+        ui.separator();
+        ui.horizontal(|ui| {
+          if ui.button("OK").clicked() {
+            accept = Some(true);
+          }
+          if ui.button("Cancel").clicked() {
+            accept = Some(false);
+          }
+        });
+      });
+
+    if ctx.input(|state| state.key_pressed(Key::Escape)) || !open {
+      accept = Some(false);
+    }
+
+    let Some(accept) = accept else { return };
+    if let Some((bulk, indices)) = self.bulk.take() {
+      if accept {
+        self.apply_bulk(bulk, &indices);
+      }
+    }
+    self.state.set_disabled(false);
+  }
+
+  /// Apply a bulk operation to the timers at the specified indices.
+  fn apply_bulk(&mut self, bulk: Bulk, indices: &[usize]) {
+    // Lock once for the whole operation so that the timer thread can't interleave.
+    let mut lock = self.timers.lock().unwrap();
+    match bulk {
+      Bulk::Water => {
+        for &index in indices {
+          if let Some(timer) = lock.get_mut(index) {
+            if timer.current_event() == Event::Water {
+              timer.reset_events();
+            }
+          }
+        }
+      }
+      Bulk::Harvest | Bulk::Discard => {
+        let indices: HashSet<usize> = indices.iter().copied().collect();
+        let mut index = 0;
+        lock.retain(|timer| {
+          let remove = indices.contains(&index) && (bulk == Bulk::Discard || timer.current_event() == Event::Harvest);
+          index += 1;
+          !remove
+        });
+
+        // The indices have changed.
+        self.selected.clear();
+      }
+    }
+    self.persist.store(true, Ordering::Relaxed);
+  }
+
+  fn show_missed(&mut self, ui: &mut Ui) {
+    const BANNER_COLOR: Color32 = Color32::from_rgb(60, 50, 20);
+    Frame::group(ui.style()).fill(BANNER_COLOR).show(ui, |ui| {
+      ui.set_min_width(ui.available_width());
+      ui.horizontal(|ui| {
+        ui.label(RichText::from("Events that became due while CotA was closed").strong());
+        if ui.button("Dismiss").clicked() {
+          self.missed.clear();
+        }
+      });
+      for (event, text) in &self.missed {
+        let color = match event {
+          Event::Harvest => Color::Harvest.get(),
+          _ => Color::Water.get(),
+        };
+        ui.label(RichText::from(format!("{event:?}: {text}")).color(color));
+      }
+    });
+  }
+
+  /// Get the soonest upcoming event of all the timers, along with when it's due and the plant text.
+  pub fn next_event(&self) -> Option<(Event, NaiveDateTime, String)> {
+    let timers = self.timers.lock().unwrap();
+    let (event, date_time, timer) = get_next_event(&timers)?;
+    Some((event, date_time, get_timer_text(timer)))
+  }
+
+  pub fn show_status(&mut self, ui: &mut Ui) {
+    let text = get_status_text(&self.timers.lock().unwrap(), Local::now().naive_local());
+    ui.centered_and_justified(|ui| {
+      if let Some(text) = text {
+        // Keep the countdown current.
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+        ui.label(text);
+      } else {
+        ui.label("No crop timers");
       }
     });
   }
@@ -232,5 +515,273 @@ impl Farming {
     if let Some(thread) = self.thread.take() {
       thread.join().unwrap();
     }
+
+    // Persist any events that were flagged while the page wasn't showing so that they aren't reported again.
+    let lock = self.timers.lock().unwrap();
+    if self.persist.swap(false, Ordering::Relaxed) {
+      self.config.set_crop_timers(&lock);
+    }
+    self.config.set_crops_checked(Local::now().naive_local());
+  }
+}
+
+/// Operations that apply to multiple timers.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bulk {
+  /// Water the crops that are due.
+  Water,
+
+  /// Harvest the crops that are ready.
+  Harvest,
+
+  /// Discard the selected timers.
+  Discard,
+}
+
+/// Flag the events that became due since the timers were last checked. Returns the event and plant text for each.
+fn get_missed_events(timers: &mut [CropTimer], last_checked: Option<NaiveDateTime>) -> Vec<(Event, String)> {
+  let mut missed = Vec::new();
+  for timer in timers {
+    if !timer.check() {
+      continue;
+    }
+
+    // Events that were due before the last check have already been reported.
+    let due = timer.event_date_time();
+    if last_checked.is_some_and(|last_checked| due.is_some_and(|due| due <= last_checked)) {
+      continue;
+    }
+
+    missed.push((timer.current_event(), get_timer_text(timer)));
+  }
+  missed
+}
+
+/// Get the soonest upcoming event of all the timers, along with when it's due and the timer.
+fn get_next_event(timers: &[CropTimer]) -> Option<(Event, NaiveDateTime, &CropTimer)> {
+  timers
+    .iter()
+    .filter_map(|timer| {
+      // The soonest event is last.
+      let (event, date_time) = timer.remaining_events().pop()?;
+      Some((event, date_time, timer))
+    })
+    .min_by_key(|&(_, date_time, _)| date_time)
+}
+
+/// Summarize the timers by state, followed by the time until the next event. Returns `None` if there are no timers.
+fn get_status_text(timers: &[CropTimer], now: NaiveDateTime) -> Option<String> {
+  if timers.is_empty() {
+    return None;
+  }
+
+  let count = |event| timers.iter().filter(|timer| timer.current_event() == event).count();
+  let (growing, water, harvest) = (count(Event::None), count(Event::Water), count(Event::Harvest));
+  let mut parts = Vec::new();
+  if growing > 0 {
+    parts.push(format!("{growing} growing"));
+  }
+
+  if water > 0 {
+    let needs = if water == 1 { "needs" } else { "need" };
+    parts.push(format!("{water} {needs} water"));
+  }
+
+  if harvest > 0 {
+    parts.push(format!("{harvest} ready to harvest"));
+  }
+
+  let mut text = parts.join(", ");
+  if let Some((event, date_time, _)) = get_next_event(timers) {
+    let secs = (date_time - now).num_seconds().clamp(0, i32::MAX as i64) as i32;
+    text.push_str(&util::get_countdown_text(&format!(" | {event:?} in "), secs));
+  }
+
+  Some(text)
+}
+
+/// Get the seed name, environment, location and description of a timer as one line of text.
+fn get_timer_text(timer: &CropTimer) -> String {
+  let mut text = format!("{} | {:?}", timer.seed_name(), timer.environment());
+  if let Some(location) = timer.location() {
+    text.push_str(&format!(" | {location}"));
+  }
+
+  let desc = timer.description();
+  if !desc.is_empty() {
+    text.push_str(&format!(" | {desc}"));
+  }
+  text
+}
+
+/// Get the name of the lot a timer is grouped under.
+fn get_lot_name(timer: &CropTimer) -> &str {
+  match timer.location() {
+    Some(location) => &location.lot,
+    None => "Unassigned",
+  }
+}
+
+/// Count the timers in each lot. Returns `None` if none of the timers have a location.
+fn get_lot_counts(timers: &[CropTimer]) -> Option<HashMap<String, usize>> {
+  if timers.iter().all(|timer| timer.location().is_none()) {
+    return None;
+  }
+
+  let mut counts = HashMap::new();
+  for timer in timers {
+    *counts.entry(get_lot_name(timer).to_owned()).or_default() += 1;
+  }
+  Some(counts)
+}
+
+/// Popup a single desktop notification summarizing the missed events.
+fn show_missed_notification(missed: &[(Event, String)]) -> Option<NotificationHandle> {
+  if missed.is_empty() {
+    return None;
+  }
+
+  let count = |event| missed.iter().filter(|(missed, _)| *missed == event).count();
+  let (water, harvest) = (count(Event::Water), count(Event::Harvest));
+  let summary = match (water, harvest) {
+    (0, _) => format!("Harvest {harvest} Plants"),
+    (_, 0) => format!("Water {water} Plants"),
+    _ => format!("Water {water} and Harvest {harvest} Plants"),
+  };
+  let body: Vec<String> = missed
+    .iter()
+    .map(|(event, text)| format!("{event:?}: {text}"))
+    .collect();
+  match Notification::new().summary(&summary).body(&body.join("\n")).show() {
+    Ok(handle) => Some(handle),
+    Err(err) => {
+      println!("{err:?}");
+      None
+    }
+  }
+}
+
+/// Get the timer indices in display order.
+fn get_sorted_indices(timers: &[CropTimer], order: CropOrder, group: bool) -> Vec<usize> {
+  // Pending events come first, then upcoming events and finally timers without any events.
+  let keys: Vec<_> = timers
+    .iter()
+    .map(|timer| match order {
+      CropOrder::NextEvent => {
+        if timer.current_event() != Event::None {
+          (0, timer.date_time())
+        } else if let Some((_, date_time)) = timer.remaining_events().last() {
+          (1, *date_time)
+        } else {
+          (2, timer.date_time())
+        }
+      }
+      CropOrder::Planted => (0, timer.date_time()),
+    })
+    .collect();
+
+  // Lots are in alphabetical order with the unassigned timers last.
+  let mut indices: Vec<usize> = (0..timers.len()).collect();
+  indices.sort_by_key(|&index| {
+    let timer = &timers[index];
+    let lot = timer.location().map(|location| location.lot.as_str());
+    let environment = if group { timer.environment() as i64 } else { 0 };
+    (lot.is_none(), lot, environment, keys[index])
+  });
+  indices
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::plant_info::{CropLocation, Environment, Seed};
+
+  fn new_timer(hours_ago: i64) -> CropTimer {
+    let date_time = Local::now().naive_local() - chrono::Duration::hours(hours_ago);
+    let name = String::from("Corn");
+    CropTimer::new(
+      String::new(),
+      date_time,
+      name,
+      Seed::Low,
+      Environment::Outside,
+      Some(3600),
+      Some(0),
+    )
+  }
+
+  #[test]
+  fn test_missed_events() {
+    let now = Local::now().naive_local();
+
+    // Harvest was due an hour ago, after the last check.
+    let mut timers = vec![new_timer(2)];
+    let missed = get_missed_events(&mut timers, Some(now - chrono::Duration::minutes(90)));
+    assert_eq!(missed.len(), 1);
+    assert_eq!(missed[0].0, Event::Harvest);
+    assert_eq!(missed[0].1, "Corn | Outside");
+
+    // Already flagged events aren't reported again.
+    assert!(get_missed_events(&mut timers, None).is_empty());
+
+    // Events that were due before the last check were already reported.
+    let mut timers = vec![new_timer(2)];
+    assert!(get_missed_events(&mut timers, Some(now - chrono::Duration::minutes(30))).is_empty());
+    assert_eq!(timers[0].current_event(), Event::Harvest);
+
+    // Without a last check, everything that's due is reported.
+    let mut timers = vec![new_timer(2), new_timer(0)];
+    assert_eq!(get_missed_events(&mut timers, None).len(), 1);
+  }
+
+  #[test]
+  fn test_status_text() {
+    let now = Local::now().naive_local();
+    assert_eq!(get_status_text(&[], now), None);
+
+    // One harvest is flagged and the other is due within the hour.
+    let mut timers = vec![new_timer(2), new_timer(0)];
+    get_missed_events(&mut timers, None);
+    let text = get_status_text(&timers, now).unwrap();
+    assert!(
+      text.starts_with("1 growing, 1 ready to harvest | Harvest in "),
+      "{text}"
+    );
+
+    // Nothing left to wait for.
+    let text = get_status_text(&timers[..1], now).unwrap();
+    assert_eq!(text, "1 ready to harvest");
+  }
+
+  #[test]
+  fn test_lots() {
+    // No grouping without locations.
+    let timers = vec![new_timer(0), new_timer(1)];
+    assert_eq!(get_lot_counts(&timers), None);
+
+    let located = |hours_ago, lot: &str, bed| {
+      let mut timer = new_timer(hours_ago);
+      timer.set_location(Some(CropLocation {
+        lot: lot.to_owned(),
+        bed,
+      }));
+      timer
+    };
+    let timers = vec![
+      new_timer(0),
+      located(0, "Town", Some(3)),
+      located(1, "Back Yard", None),
+      located(2, "Town", Some(1)),
+    ];
+    let counts = get_lot_counts(&timers).unwrap();
+    assert_eq!(counts.len(), 3);
+    assert_eq!((counts["Back Yard"], counts["Town"], counts["Unassigned"]), (1, 2, 1));
+
+    // Lots are alphabetical, unassigned is last and the oldest planting is first within a lot.
+    assert_eq!(get_sorted_indices(&timers, CropOrder::Planted, false), [2, 3, 1, 0]);
+
+    assert_eq!(get_timer_text(&timers[0]), "Corn | Outside");
+    assert_eq!(get_timer_text(&timers[1]), "Corn | Outside | Town, Bed 3");
+    assert_eq!(get_timer_text(&timers[2]), "Corn | Outside | Back Yard");
   }
 }
@@ -0,0 +1,145 @@
+use crate::{
+  game_folders::{self, GameFolder},
+  util::AppState,
+};
+use eframe::{
+  egui::{Button, Context, Grid, Key, RichText, Window},
+  emath::Align2,
+  epaint::Color32,
+};
+
+/// Dialog window for choosing a detected game folder.
+pub struct FoldersDlg {
+  state: AppState,
+  folders: Vec<GameFolder>,
+  selected: Option<usize>,
+  result: Option<GameFolder>,
+  visible: bool,
+}
+
+impl FoldersDlg {
+  pub fn new(state: AppState) -> Self {
+    Self {
+      state,
+      folders: Vec::new(),
+      selected: None,
+      result: None,
+      visible: false,
+    }
+  }
+
+  pub fn show(&mut self, ctx: &Context) -> bool {
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      let mut open = true;
+      Window::new(RichText::from("🔍  Game Folders").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          if self.folders.is_empty() {
+            ui.label("No game folders were found.");
+          } else {
+            ui.label("Choose the folder to read chat logs and save-games from.");
+            ui.add_space(ui.spacing().item_spacing.y);
+            Grid::new("game_folders_grid").striped(true).show(ui, |ui| {
+              const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+              ui.label(RichText::from("Source").color(HEADER_COLOR));
+              ui.label(RichText::from("Folder").color(HEADER_COLOR));
+              ui.label(RichText::from("Contains").color(HEADER_COLOR));
+              ui.end_row();
+
+              for (index, folder) in self.folders.iter().enumerate() {
+                ui.radio_value(&mut self.selected, Some(index), folder.source.name());
+                ui.label(folder.path.to_string_lossy());
+
+                let contains: Vec<&str> = [
+                  folder.log_path().map(|_| "Chat logs"),
+                  folder.save_game_path().map(|_| "Save-games"),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                ui.label(contains.join(", "));
+                ui.end_row();
+              }
+            });
+          }
+
+          ui.separator();
+          ui.horizontal(|ui| {
+            if ui.add_enabled(self.selected.is_some(), Button::new("Use")).clicked() {
+              self.accept();
+            }
+
+            if ui.button("Cancel").clicked() {
+              self.close();
+            }
+
+            if ui.button("Detect Again").clicked() {
+              self.detect();
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+    self.visible
+  }
+
+  pub fn open(&mut self) {
+    if !self.visible {
+      self.detect();
+      self.show_folders();
+    }
+  }
+
+  /// Open the dialog only if a game folder is found.
+  pub fn open_if_found(&mut self) {
+    if !self.visible {
+      self.detect();
+      if !self.folders.is_empty() {
+        self.show_folders();
+      }
+    }
+  }
+
+  pub fn take_result(&mut self) -> Option<GameFolder> {
+    self.result.take()
+  }
+
+  fn show_folders(&mut self) {
+    self.state.set_disabled(true);
+    self.result = None;
+    self.visible = true;
+  }
+
+  fn detect(&mut self) {
+    self.folders = game_folders::detect();
+    self.selected = (!self.folders.is_empty()).then_some(0);
+  }
+
+  fn accept(&mut self) {
+    if let Some(folder) = self.selected.and_then(|index| self.folders.get(index)) {
+      self.result = Some(folder.clone());
+    }
+    self.close();
+  }
+
+  fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
@@ -1,110 +1,211 @@
-use crate::{skill_info, util};
-use serde_json::Value;
-use skill_info::{SkillCategory, SkillInfo, SkillInfoGroup};
-use std::{borrow::Cow, fs::File, io::Write, ops::Range, path::PathBuf, sync::RwLock};
+use crate::{
+  ethos::{Virtue, VIRTUES},
+  skill_info, util,
+};
+use chrono::{Local, NaiveDateTime};
+use serde_json::{Map, Value};
+use skill_info::{SkillCategory, SkillInfo, SkillInfoGroup, SkillOverride};
+use std::{
+  borrow::Cow,
+  collections::{HashMap, HashSet, VecDeque},
+  fs::{self, File},
+  io::{self, Write},
+  ops::Range,
+  path::{Path, PathBuf},
+  sync::RwLock,
+  time::SystemTime,
+};
 use util::{LEVEL_EXP, LVL_RANGE, SKILL_EXP};
 
-// NOTE: UserKnowledge contains virtue.
-
 /// Structure to load and modify a SotA save-game file.
 pub struct GameData {
   // Save file path.
   path: RwLock<PathBuf>,
 
-  // Full file text.
+  // Modification time of the save file when it was loaded or last stored.
+  modified: RwLock<Option<SystemTime>>,
+
+  // Full file text, without a BOM and with LF line endings.
   text: String,
 
+  // BOM and line endings of the file.
+  format: TextFormat,
+
   // Avatar ID.
   avatar: String,
 
   // Avatar name.
   name: String,
 
-  // Backpack ID.
-  backpack: String,
-
-  // Parsed JSON sections.
-  character: Value,
-  inventory: Value,
-  gold: Value,
+  // Parsed JSON sections. Sections that failed to parse are `None` (or empty) and are never written back.
+  character: Option<Value>,
+  stores: Vec<ItemStore>,
+  gold: Option<Value>,
+  knowledge: Option<Knowledge>,
 
   // Save date.
-  date: Value,
+  date: Option<Value>,
+
+  // Sections that failed to load.
+  report: LoadReport,
 }
 
 impl GameData {
+  /// Load a save-game. Only the avatar ID and name are required, the other sections are parsed independently and any
+  /// failures are listed in the load report.
   pub fn load(path: PathBuf) -> Result<Self, Cow<'static, str>> {
-    match std::fs::read_to_string(&path) {
+    let source = path.clone();
+    Self::load_from(path, &source)
+  }
+
+  /// Load a backup of a save-game. The data is still stored to `path`, not to the backup.
+  pub fn load_backup(path: PathBuf, backup: &Path) -> Result<Self, Cow<'static, str>> {
+    Self::load_from(path, backup)
+  }
+
+  fn load_from(path: PathBuf, source: &Path) -> Result<Self, Cow<'static, str>> {
+    match fs::read_to_string(source) {
       Ok(text) => {
+        if !text.contains(COLLECTION_KEY) {
+          return Err(Cow::from(NOT_SAVE_GAME));
+        }
+
+        let (format, text) = TextFormat::detect(text);
+
         // Get the avatar ID.
         let avatar = get_avatar_id(&text)?;
 
         // Get the avatar name.
         let name = get_avatar_name(&text, &avatar)?;
 
-        // Get the backpack ID.
-        let backpack = get_backpack_id(&text, &avatar)?;
+        let mut report = LoadReport::default();
 
-        // Get the ItemStore JSON.
-        let inventory = get_json(&text, ITEM_STORE, &backpack)?;
+        // Get the ItemStore JSON for the backpack and any other containers.
+        let stores = report.check(Section::Inventory, get_item_stores(&text, &avatar));
 
         // Get the CharacterSheet JSON.
-        let character = get_json(&text, CHARACTER_SHEET, &avatar)?;
-
-        // Make sure adventurer experience is there.
-        if character.get(AE).and_then(|exp| exp.to_i64()).is_none() {
-          return Err(Cow::from("Unable to parse adventurer experience"));
-        }
+        let character = match get_json(&text, CHARACTER_SHEET, &avatar) {
+          Ok(character) => Some(character),
+          Err(err) => {
+            report.fail(Section::Levels, err.clone());
+            report.fail(Section::Skills, err.clone());
+            report.fail(Section::Decks, err);
+            None
+          }
+        };
 
-        // Make sure producer experience is there.
-        if character.get(PE).and_then(|exp| exp.to_i64()).is_none() {
-          return Err(Cow::from("Unable to parse producer experience"));
+        let mut date = None;
+        if let Some(character) = &character {
+          // Make sure adventurer and producer experience are there.
+          if character.get(AE).and_then(|exp| exp.to_i64()).is_none() {
+            report.fail(Section::Levels, Cow::from("Unable to parse adventurer experience"));
+          } else if character.get(PE).and_then(|exp| exp.to_i64()).is_none() {
+            report.fail(Section::Levels, Cow::from("Unable to parse producer experience"));
+          }
+
+          // Find a save date.
+          date = match character.get(SK2) {
+            Some(val) if val.is_object() => report.check(Section::Skills, find_date(val).map(Some)),
+            _ => {
+              report.fail(Section::Skills, Cow::from("Error reading skills"));
+              None
+            }
+          };
+
+          // Make sure the decks can be parsed.
+          report.check(Section::Decks, get_decks(character).map(|_| ()));
         }
 
-        // Find a save date.
-        let date = match character.get(SK2) {
-          Some(val) if val.is_object() => find_date(val)?,
-          _ => return Err(Cow::from("Error reading skills")),
-        };
-
         // Get the UserGold JSON.
-        let gold = get_json(&text, USER_GOLD, USER_ID)?;
+        let gold = report.check(Section::Gold, get_json(&text, USER_GOLD, USER_ID).map(Some));
+
+        // Get the UserKnowledge JSON, which contains virtue.
+        let knowledge = report.check(Section::Virtues, get_knowledge(&text, &avatar));
 
         Ok(GameData {
+          modified: RwLock::new(get_modified(&path)),
           path: RwLock::new(path),
           text,
+          format,
           avatar,
           name,
-          backpack,
           character,
-          inventory,
+          stores,
           gold,
+          knowledge,
           date,
+          report,
         })
       }
+      Err(err) if err.kind() == io::ErrorKind::InvalidData => Err(Cow::from(NOT_SAVE_GAME)),
       Err(err) => Err(Cow::from(format!("Unable to load file: {err}"))),
     }
   }
 
-  pub fn store(&self) -> Result<(), Cow<'static, str>> {
-    self.store_as(self.get_file_path())
+  /// Sections that failed to load.
+  pub fn report(&self) -> &LoadReport {
+    &self.report
   }
 
-  pub fn store_as(&self, path: PathBuf) -> Result<(), Cow<'static, str>> {
-    // Set CharacterSheet.
-    let text = set_json(&self.text, CHARACTER_SHEET, &self.avatar, &self.character)?;
+  /// Store the save-game, keeping up to `backups` backups of the previous file. Unless `overwrite` is set, this fails
+  /// if the file was modified (e.g. by the game client) since it was loaded or last stored.
+  pub fn store(&self, backups: usize, overwrite: bool) -> Result<(), Cow<'static, str>> {
+    if !overwrite && self.file_modified() {
+      return Err(Cow::from("The save-game was modified after it was loaded"));
+    }
+
+    self.store_as(self.get_file_path(), backups)
+  }
 
-    // Set ItemStore.
-    let text = set_json(&text, ITEM_STORE, &self.backpack, &self.inventory)?;
+  /// Check if the save file's modification time changed since it was loaded or last stored.
+  pub fn file_modified(&self) -> bool {
+    let Some(modified) = *self.modified.read().unwrap() else {
+      return false;
+    };
+
+    get_modified(&self.get_file_path()).is_some_and(|current| current != modified)
+  }
+
+  /// Store the save-game to `path`, keeping up to `backups` backups of the previous file. There's no check for the
+  /// file having been modified.
+  pub fn store_as(&self, path: PathBuf, backups: usize) -> Result<(), Cow<'static, str>> {
+    let mut text = self.text.clone();
+
+    // Set CharacterSheet, unless none of it could be edited.
+    if let Some(character) = &self.character {
+      let report = &self.report;
+      if report.is_loaded(Section::Levels) || report.is_loaded(Section::Skills) || report.is_loaded(Section::Decks) {
+        text = set_json(&text, CHARACTER_SHEET, &self.avatar, character)?;
+      }
+    }
+
+    // Set ItemStore for each container.
+    for store in &self.stores {
+      text = set_json(&text, ITEM_STORE, &store.id, &store.json)?;
+    }
 
     // Set UserGold.
-    let text = set_json(&text, USER_GOLD, USER_ID, &self.gold)?;
+    if let Some(gold) = &self.gold {
+      text = set_json(&text, USER_GOLD, USER_ID, gold)?;
+    }
+
+    // Set UserKnowledge.
+    if let Some(knowledge) = &self.knowledge {
+      text = set_json(&text, USER_KNOWLEDGE, &knowledge.id, &knowledge.json)?;
+    }
+
+    // Don't overwrite the file unless it was backed up.
+    if backups > 0 {
+      create_backup(&path, backups, Local::now().naive_local())?;
+    }
 
-    // Create the save-game file and store the data.
+    // Create the save-game file and store the data, the same way that it was loaded.
+    let text = self.format.apply(&text);
     match File::create(&path) {
       Ok(mut file) => match file.write_all(text.as_bytes()) {
         Ok(()) => {
-          // Change the path.
+          // Change the path and remember the new modification time.
+          *self.modified.write().unwrap() = get_modified(&path);
           *self.path.write().unwrap() = path;
           Ok(())
         }
@@ -118,34 +219,64 @@ impl GameData {
     &self.name
   }
 
-  pub fn get_gold(&self) -> Option<i32> {
-    Some(self.gold.get(G)?.to_i64()? as i32)
+  pub fn get_gold(&self) -> Option<i64> {
+    get_gold(self.gold.as_ref()?)
   }
 
-  pub fn set_gold(&mut self, gold: i32) {
-    self.gold[G] = gold.into();
+  pub fn set_gold(&mut self, gold: i64) {
+    if let Some(json) = &mut self.gold {
+      set_gold(json, gold);
+    }
   }
 
-  pub fn get_adv_lvl(&self) -> i32 {
-    let ae = self.character.get(AE).unwrap();
-    let exp = ae.to_i64().unwrap();
-    util::floor_search(exp, LEVEL_EXP).unwrap() as i32 + 1
+  pub fn get_adv_lvl(&self) -> Option<i32> {
+    self.get_lvl(AE)
   }
 
   pub fn set_adv_lvl(&mut self, lvl: i32) {
-    assert!(LVL_RANGE.contains(&lvl));
-    self.character[AE] = LEVEL_EXP[lvl as usize - 1].into();
+    self.set_lvl(AE, lvl);
   }
 
-  pub fn get_prd_lvl(&self) -> i32 {
-    let pe = self.character.get(PE).unwrap();
-    let exp = pe.to_i64().unwrap();
-    util::floor_search(exp, LEVEL_EXP).unwrap() as i32 + 1
+  pub fn get_prd_lvl(&self) -> Option<i32> {
+    self.get_lvl(PE)
   }
 
   pub fn set_prd_lvl(&mut self, lvl: i32) {
+    self.set_lvl(PE, lvl);
+  }
+
+  /// Adventurer experience as of when the save-game was loaded or last stored.
+  pub fn get_adv_exp(&self) -> Option<i64> {
+    self.get_exp(AE)
+  }
+
+  /// Producer experience as of when the save-game was loaded or last stored.
+  pub fn get_prd_exp(&self) -> Option<i64> {
+    self.get_exp(PE)
+  }
+
+  fn get_exp(&self, key: &str) -> Option<i64> {
+    if !self.report.is_loaded(Section::Levels) {
+      return None;
+    }
+
+    self.character.as_ref()?.get(key)?.to_i64()
+  }
+
+  fn get_lvl(&self, key: &str) -> Option<i32> {
+    let exp = self.get_exp(key)?;
+    Some(util::get_level_info(exp).0)
+  }
+
+  fn set_lvl(&mut self, key: &str, lvl: i32) {
     assert!(LVL_RANGE.contains(&lvl));
-    self.character[PE] = LEVEL_EXP[lvl as usize - 1].into();
+    if !self.report.is_loaded(Section::Levels) {
+      return;
+    }
+
+    if let Some(character) = &mut self.character {
+      character[key] = LEVEL_EXP[lvl as usize - 1].into();
+    }
   }
 
   pub fn get_file_path(&self) -> PathBuf {
@@ -157,9 +288,12 @@ impl GameData {
     path.file_name().unwrap().to_string_lossy().into()
   }
 
-  pub fn get_skills(&self, category: SkillCategory) -> Vec<SkillLvlGroup> {
-    let sk2 = self.character.get(SK2).unwrap();
-    let groups = skill_info::parse_skill_info_groups(category);
+  pub fn get_skills(&self, category: SkillCategory, skill_override: SkillOverride) -> Vec<SkillLvlGroup> {
+    let Some(sk2) = self.get_sk2() else {
+      return Vec::new();
+    };
+
+    let groups = skill_info::parse_skill_info_groups(category, skill_override);
     let mut skills = Vec::with_capacity(groups.len());
     for group in groups {
       skills.push(SkillLvlGroup::new(sk2, group));
@@ -168,52 +302,204 @@ impl GameData {
     skills
   }
 
-  pub fn set_skills(&mut self, skills: &Vec<SkillLvlGroup>) {
-    let sk2 = self.character.get_mut(SK2).unwrap();
-    for group in skills {
-      for skill in &group.skills {
-        set_skill_lvl(sk2, &self.date, skill);
-      }
+  pub fn set_skills(&mut self, skills: &[SkillLvlGroup]) {
+    if !self.report.is_loaded(Section::Skills) {
+      return;
+    }
+
+    let (Some(character), Some(date)) = (&mut self.character, &self.date) else {
+      return;
+    };
+
+    if let Some(sk2) = character.get_mut(SK2) {
+      set_skills(sk2, date, skills);
+    }
+  }
+
+  fn get_sk2(&self) -> Option<&Value> {
+    if !self.report.is_loaded(Section::Skills) {
+      return None;
+    }
+    self.character.as_ref()?.get(SK2)
+  }
+
+  /// Get the combat decks, if any.
+  pub fn get_decks(&self) -> Vec<Deck> {
+    if !self.report.is_loaded(Section::Decks) {
+      return Vec::new();
+    }
+
+    let Some(character) = &self.character else {
+      return Vec::new();
+    };
+
+    get_decks(character).unwrap_or_default()
+  }
+
+  pub fn set_decks(&mut self, decks: &[Deck]) {
+    if !self.report.is_loaded(Section::Decks) {
+      return;
+    }
+
+    if let Some(character) = &mut self.character {
+      set_decks(character, decks);
     }
   }
 
-  pub fn get_inventory_items(&self) -> Vec<Item> {
-    let inv = self.inventory.get(IN).unwrap();
-    let items_map = inv.as_object().unwrap();
-    let mut items = Vec::with_capacity(items_map.len());
-    for (key, val) in items_map {
-      if let Some(item) = Item::new(val, key) {
-        items.push(item);
+  pub fn get_virtues(&self) -> Vec<VirtueLvl> {
+    let Some(virtues) = self.knowledge.as_ref().and_then(|knowledge| knowledge.virtues()) else {
+      return Vec::new();
+    };
+
+    // Keep the same order as the virtue enum.
+    let mut result = Vec::new();
+    for virtue in VIRTUES.iter().filter(|virtue| **virtue != Virtue::Ethos) {
+      let name = format!("{virtue:?}");
+      for (key, val) in virtues {
+        if key.eq_ignore_ascii_case(&name) {
+          if let Some(value) = val.as_f64() {
+            result.push(VirtueLvl::new(name, key.clone(), value));
+          }
+          break;
+        }
       }
     }
 
-    items
+    result
   }
 
-  pub fn set_inventory_items(&mut self, items: &Vec<Item>) {
-    let inv = self.inventory.get_mut(IN).unwrap();
-    for item in items {
-      let val = inv.get_mut(&item.id).unwrap();
-      let val = val.get_mut(IN).unwrap();
-      val[QN] = item.cnt.into();
-      if let Some(dur) = &item.dur {
-        val[HP] = dur.minor.into();
-        val[PHP] = dur.major.into();
+  pub fn set_virtues(&mut self, virtues: &Vec<VirtueLvl>) {
+    let Some(map) = self.knowledge.as_mut().and_then(|knowledge| knowledge.virtues_mut()) else {
+      return;
+    };
+
+    for virtue in virtues {
+      if virtue.changed() {
+        if let Some(val) = map.get_mut(&virtue.key) {
+          set_virtue(val, virtue.value);
+        }
       }
     }
   }
+
+  pub fn get_inventory_items(&self) -> Vec<ItemGroup> {
+    let mut groups = Vec::with_capacity(self.stores.len());
+    for store in &self.stores {
+      let items_map = store.json.get(IN).unwrap().as_object().unwrap();
+      let mut items = Vec::with_capacity(items_map.len());
+      for (key, val) in items_map {
+        if let Some(item) = Item::new(val, key) {
+          items.push(item);
+        }
+      }
+
+      groups.push(ItemGroup {
+        name: store.name.clone(),
+        items_cmp: Vec::new(),
+        items,
+        id: store.id.clone(),
+      });
+    }
+
+    set_item_parents(&mut groups);
+    for group in &mut groups {
+      group.items_cmp.clone_from(&group.items);
+    }
+
+    groups
+  }
+
+  pub fn set_inventory_items(&mut self, groups: &Vec<ItemGroup>) {
+    for group in groups {
+      let Some(store) = self.stores.iter_mut().find(|store| store.id == group.id) else {
+        continue;
+      };
+
+      let inv = store.json.get_mut(IN).unwrap().as_object_mut().unwrap();
+      set_items(inv, &group.items);
+    }
+  }
+}
+
+/// Independently loaded part of a save-game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Section {
+  Levels,
+  Skills,
+  Inventory,
+  Gold,
+  Virtues,
+  Decks,
+}
+
+impl Section {
+  pub fn name(self) -> &'static str {
+    match self {
+      Section::Levels => "Levels",
+      Section::Skills => "Skills",
+      Section::Inventory => "Inventory",
+      Section::Gold => "Gold",
+      Section::Virtues => "Virtues",
+      Section::Decks => "Decks",
+    }
+  }
+}
+
+/// Which sections of a save-game loaded and why the others failed.
+#[derive(Default)]
+pub struct LoadReport {
+  failed: Vec<(Section, Cow<'static, str>)>,
+}
+
+impl LoadReport {
+  /// Check if every section loaded.
+  pub fn is_complete(&self) -> bool {
+    self.failed.is_empty()
+  }
+
+  pub fn is_loaded(&self, section: Section) -> bool {
+    self.error(section).is_none()
+  }
+
+  /// Get the reason that a section failed to load.
+  pub fn error(&self, section: Section) -> Option<&str> {
+    let (_, err) = self.failed.iter().find(|(failed, _)| *failed == section)?;
+    Some(err)
+  }
+
+  pub fn failed(&self) -> &[(Section, Cow<'static, str>)] {
+    &self.failed
+  }
+
+  fn fail(&mut self, section: Section, err: Cow<'static, str>) {
+    // Only the first reason is kept.
+    if self.is_loaded(section) {
+      self.failed.push((section, err));
+    }
+  }
+
+  /// Record the error if the section failed, otherwise return its value.
+  fn check<T: Default>(&mut self, section: Section, result: Result<T, Cow<'static, str>>) -> T {
+    result.unwrap_or_else(|err| {
+      self.fail(section, err);
+      T::default()
+    })
+  }
 }
 
+const USER_KNOWLEDGE: &str = "UserKnowledge";
 const USER_ID: &str = "000000000000000000000001";
 const CHARACTER_SHEET: &str = "CharacterSheet";
 const ITEM_STORE: &str = "ItemStore";
 const USER_GOLD: &str = "UserGold";
+const MAIN_BP: &str = "mainbp";
 const BAG: &str = "bag";
 const PHP: &str = "php";
 const SK2: &str = "sk2";
 const AE: &str = "ae";
 const AN: &str = "an";
 const DC: &str = "dc";
+const DK: &str = "dk";
 const FN: &str = "fn";
 const HP: &str = "hp";
 const IN: &str = "in";
@@ -221,34 +507,136 @@ const PE: &str = "pe";
 const QN: &str = "qn";
 const G: &str = "g";
 const M: &str = "m";
+const N: &str = "n";
+const S: &str = "s";
 const T: &str = "t";
 const X: &str = "x";
 
+/// Timestamp appended to backup file names.
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// Timestamp of backups from before milliseconds were added.
+const OLD_BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Every save-game has collections, so a file without any is something else.
+const COLLECTION_KEY: &str = "<collection";
+const NOT_SAVE_GAME: &str = "This doesn't look like a SotA save-game";
+const BOM: char = '\u{feff}';
+
+/// Byte order mark and line endings of a save file, so that they can be reproduced when storing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct TextFormat {
+  bom: bool,
+  crlf: bool,
+}
+
+impl TextFormat {
+  /// Detect the format and convert the text to LF line endings without a BOM. Mixed line endings are left as is.
+  fn detect(text: String) -> (Self, String) {
+    let (bom, text) = match text.strip_prefix(BOM) {
+      Some(stripped) => (true, stripped.to_owned()),
+      None => (false, text),
+    };
+
+    let crlf_count = text.matches("\r\n").count();
+    let crlf = crlf_count > 0 && crlf_count == text.matches('\n').count();
+    let text = if crlf { text.replace("\r\n", "\n") } else { text };
+    (Self { bom, crlf }, text)
+  }
+
+  /// Convert LF text back to this format.
+  fn apply(self, text: &str) -> Cow<'_, str> {
+    if !self.bom && !self.crlf {
+      return Cow::Borrowed(text);
+    }
+
+    let text = if self.crlf {
+      text.replace('\n', "\r\n")
+    } else {
+      text.to_owned()
+    };
+
+    if self.bom {
+      return Cow::Owned(format!("{BOM}{text}"));
+    }
+    Cow::Owned(text)
+  }
+}
+
 #[derive(Clone)]
 pub struct SkillLvl {
   pub info: SkillInfo,
   pub level: i32,
   comp: i32,
+  exp: Option<i64>,
+  exp_cmp: Option<i64>,
 }
 
 impl SkillLvl {
   fn new(sk2: &Value, info: SkillInfo) -> Self {
-    let level = get_skill_lvl(sk2, &info).unwrap_or(0);
+    let exp = get_skill_exp(sk2, &info);
+    let level = exp.and_then(|exp| get_exp_lvl(exp, info.mul)).unwrap_or(0);
     let comp = level;
 
-    Self { info, level, comp }
+    Self {
+      info,
+      level,
+      comp,
+      exp,
+      exp_cmp: exp,
+    }
   }
 
   fn accept(&mut self) {
+    self.exp = self.exp();
+    self.exp_cmp = self.exp;
     self.comp = self.level;
   }
 
   fn discard(&mut self) {
+    self.exp = self.exp_cmp;
     self.level = self.comp;
   }
 
   pub fn changed(&self) -> bool {
-    self.level != self.comp
+    self.level != self.comp || self.exp() != self.exp_cmp
+  }
+
+  /// Level as of when the save-game was loaded or last stored.
+  pub fn loaded_level(&self) -> i32 {
+    self.comp
+  }
+
+  /// Experience as of when the save-game was loaded or last stored.
+  pub fn loaded_exp(&self) -> Option<i64> {
+    self.exp_cmp
+  }
+
+  /// Get the skill's experience. The raw experience is kept unless the level was changed, in which case the
+  /// experience for the start of the level is used.
+  pub fn exp(&self) -> Option<i64> {
+    if self.level == 0 {
+      return None;
+    }
+
+    match self.exp {
+      Some(exp) if get_exp_lvl(exp, self.info.mul) == Some(self.level) => Some(exp),
+      _ => Some(get_lvl_exp(self.level, self.info.mul)),
+    }
+  }
+
+  /// Set the skill's experience, which also determines the level. It's limited to the skill's level cap.
+  pub fn set_exp(&mut self, exp: i64) {
+    let exp = exp.min(self.max_exp());
+    if let Some(level) = get_exp_lvl(exp, self.info.mul) {
+      self.level = level;
+      self.exp = Some(exp);
+    }
+  }
+
+  /// Maximum experience for this skill.
+  pub fn max_exp(&self) -> i64 {
+    get_lvl_exp(self.info.level_cap(), self.info.mul)
   }
 }
 
@@ -291,6 +679,270 @@ impl SkillLvlGroup {
   }
 }
 
+/// Parsed UserKnowledge record.
+struct Knowledge {
+  id: String,
+  json: Value,
+
+  // Key for the object that contains the virtues, or `None` if they're at the top level.
+  key: Option<String>,
+}
+
+impl Knowledge {
+  fn virtues(&self) -> Option<&Map<String, Value>> {
+    match &self.key {
+      Some(key) => self.json.get(key)?.as_object(),
+      None => self.json.as_object(),
+    }
+  }
+
+  fn virtues_mut(&mut self) -> Option<&mut Map<String, Value>> {
+    match &self.key {
+      Some(key) => self.json.get_mut(key)?.as_object_mut(),
+      None => self.json.as_object_mut(),
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct VirtueLvl {
+  pub name: String,
+  pub value: f64,
+  key: String,
+  comp: f64,
+}
+
+impl VirtueLvl {
+  fn new(name: String, key: String, value: f64) -> Self {
+    Self {
+      name,
+      value,
+      key,
+      comp: value,
+    }
+  }
+
+  pub fn accept(&mut self) {
+    self.comp = self.value;
+  }
+
+  pub fn discard(&mut self) {
+    self.value = self.comp;
+  }
+
+  pub fn changed(&self) -> bool {
+    self.value != self.comp
+  }
+
+  pub fn loaded_value(&self) -> f64 {
+    self.comp
+  }
+}
+
+/// Combat deck from the CharacterSheet. Each slot holds the ID of the skill (glyph) that's assigned to it.
+#[derive(Clone)]
+pub struct Deck {
+  pub name: String,
+  pub slots: Vec<Option<u32>>,
+  key: String,
+  comp: Vec<Option<u32>>,
+}
+
+impl Deck {
+  fn new(key: String, name: String, slots: Vec<Option<u32>>) -> Self {
+    Self {
+      name,
+      comp: slots.clone(),
+      slots,
+      key,
+    }
+  }
+
+  pub fn accept(&mut self) {
+    self.comp.clone_from(&self.slots);
+  }
+
+  pub fn discard(&mut self) {
+    self.slots.clone_from(&self.comp);
+  }
+
+  pub fn changed(&self) -> bool {
+    self.slots != self.comp
+  }
+
+  pub fn loaded_slots(&self) -> &[Option<u32>] {
+    &self.comp
+  }
+}
+
+/// Parsed ItemStore record.
+struct ItemStore {
+  name: String,
+  id: String,
+  json: Value,
+}
+
+/// An item and the items nested inside of it, if it's a container.
+pub struct ItemNode {
+  /// Index of the item's group.
+  pub group: usize,
+
+  /// Index of the item within its group.
+  pub index: usize,
+
+  pub children: Vec<ItemNode>,
+}
+
+impl ItemNode {
+  /// Get the number of items nested inside and the sum of their counts (stack sizes).
+  pub fn totals(&self, groups: &[ItemGroup]) -> (usize, u64) {
+    let mut totals = (0, 0);
+    for child in &self.children {
+      let (items, count) = child.totals(groups);
+      totals.0 += items + 1;
+      totals.1 += count + groups[child.group].items[child.index].cnt;
+    }
+    totals
+  }
+}
+
+/// Items from a single container.
+pub struct ItemGroup {
+  pub name: String,
+  pub items: Vec<Item>,
+  items_cmp: Vec<Item>,
+  id: String,
+}
+
+impl ItemGroup {
+  pub fn changed(&self) -> bool {
+    // Check for added or removed items.
+    if self.items.len() != self.items_cmp.len() {
+      return true;
+    }
+
+    for (item, item_cmp) in self.items.iter().zip(&self.items_cmp) {
+      if item.id != item_cmp.id || item.changed() {
+        return true;
+      }
+    }
+    false
+  }
+
+  pub fn accept(&mut self) {
+    for item in &mut self.items {
+      item.accept();
+    }
+    self.items_cmp.clone_from(&self.items);
+  }
+
+  pub fn discard(&mut self) {
+    self.items.clone_from(&self.items_cmp);
+  }
+
+  /// Items as of when the save-game was loaded or last stored.
+  pub fn loaded_items(&self) -> &[Item] {
+    &self.items_cmp
+  }
+
+  /// Add a new item using its asset name (e.g. "Items/Reagents/BlackPearl").
+  pub fn add_item(&mut self, asset: &str, id: String) -> Result<(), Cow<'static, str>> {
+    let asset = asset.trim();
+    let json = serde_json::json!({ IN: { AN: asset, QN: 1 } });
+    let Some(item) = Item::new(&json, &id) else {
+      return Err(Cow::from(format!("Invalid asset name: {asset}")));
+    };
+
+    self.items.push(item);
+    Ok(())
+  }
+
+  /// Add a copy of the item at `index`. Containers can't be duplicated.
+  pub fn duplicate_item(&mut self, index: usize, id: String) {
+    let Some(item) = self.items.get(index) else { return };
+    if item.is_container() {
+      return;
+    }
+
+    let mut item = item.clone();
+    item.id = id;
+    item.accept();
+    self.items.insert(index + 1, item);
+  }
+
+  /// Remove the item at `index`.
+  pub fn remove_item(&mut self, index: usize) {
+    if index < self.items.len() {
+      self.items.remove(index);
+    }
+  }
+
+  /// Check if any items are stored in this container.
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  /// Find the group for a container item.
+  pub fn find_bag<'a>(groups: &'a [ItemGroup], item: &Item) -> Option<&'a ItemGroup> {
+    let bag = item.bag.as_ref()?;
+    groups.iter().find(|group| group.id == *bag)
+  }
+
+  /// Check if a group is held by a container item. Bags that aren't held by anything are shown at the root.
+  pub fn is_nested(groups: &[ItemGroup], index: usize) -> bool {
+    let id = &groups[index].id;
+    let items = groups.iter().flat_map(|group| group.items.iter());
+    items.filter_map(|item| item.bag.as_ref()).any(|bag| bag == id)
+  }
+
+  /// Get the items of a group as a tree, with the contents of each container nested under it. Items that reference a
+  /// missing parent are placed at the root.
+  pub fn get_item_tree(groups: &[ItemGroup], index: usize) -> Vec<ItemNode> {
+    // Container item IDs, for checking that a parent exists.
+    let containers: HashSet<&str> = groups
+      .iter()
+      .flat_map(|group| group.items.iter())
+      .filter(|item| item.is_container())
+      .map(|item| item.id.as_str())
+      .collect();
+
+    let Some(group) = groups.get(index) else {
+      return Vec::new();
+    };
+
+    let mut visited = HashSet::new();
+    let roots = group
+      .items
+      .iter()
+      .enumerate()
+      .filter(|(_, item)| item.parent_id().is_none_or(|id| !containers.contains(id)));
+    roots
+      .map(|(item_index, _)| get_item_node(groups, index, item_index, &mut visited))
+      .collect()
+  }
+
+  /// Generate an item ID that isn't used by any of the groups. IDs are hexadecimal, so this uses the same width as the
+  /// existing IDs.
+  pub fn new_item_id(groups: &[ItemGroup]) -> String {
+    let ids = groups
+      .iter()
+      .flat_map(|group| group.items.iter().chain(&group.items_cmp))
+      .map(|item| item.id.as_str());
+
+    let mut width = USER_ID.len();
+    let mut max = 0;
+    for id in ids {
+      if let Ok(val) = u128::from_str_radix(id, 16) {
+        width = id.len();
+        max = max.max(val);
+      }
+    }
+
+    format!("{:0width$x}", max + 1)
+  }
+}
+
+/// Item durability. `minor` is the current durability (hp) and `major` is the primary durability (php).
 #[derive(PartialEq, Clone)]
 pub struct Durability {
   pub minor: f64,
@@ -304,35 +956,44 @@ impl Durability {
 
     Some(Durability { minor, major })
   }
+
+  /// Check that the current durability isn't greater than the primary durability.
+  pub fn is_valid(&self) -> bool {
+    (0.0..=self.major).contains(&self.minor)
+  }
 }
 
 #[derive(Clone)]
 pub struct Item {
   id: String,
   name: String,
+  json: Value,
   cnt_cmp: u64,
   cnt: u64,
   dur_cmp: Option<Durability>,
   dur: Option<Durability>,
-  bag: bool,
+  bag: Option<String>,
+  parent_id: Option<String>,
 }
 
 impl Item {
-  fn new(val: &Value, id: &str) -> Option<Self> {
-    let val = val.get(IN)?;
+  fn new(json: &Value, id: &str) -> Option<Self> {
+    let val = json.get(IN)?;
     let name = get_item_name(val)?;
     let cnt = val.get(QN).and_then(|v| v.as_u64())?;
     let dur = Durability::new(val);
-    let bag = val.get(BAG).is_some();
+    let bag = val.get(BAG).map(|bag| bag.as_str().unwrap_or_default().to_owned());
 
     Some(Item {
       id: id.into(),
       name,
+      json: json.clone(),
       cnt_cmp: cnt,
       cnt,
       dur_cmp: dur.clone(),
       dur,
       bag,
+      parent_id: None,
     })
   }
 
@@ -344,10 +1005,26 @@ impl Item {
     &self.name
   }
 
+  pub fn count(&self) -> u64 {
+    self.cnt
+  }
+
   pub fn count_mut(&mut self) -> &mut u64 {
     &mut self.cnt
   }
 
+  pub fn loaded_count(&self) -> u64 {
+    self.cnt_cmp
+  }
+
+  pub fn durability(&self) -> Option<&Durability> {
+    self.dur.as_ref()
+  }
+
+  pub fn loaded_durability(&self) -> Option<&Durability> {
+    self.dur_cmp.as_ref()
+  }
+
   pub fn durability_mut(&mut self) -> Option<&mut Durability> {
     if let Some(dur) = &mut self.dur {
       return Some(dur);
@@ -357,32 +1034,110 @@ impl Item {
   }
 
   pub fn is_container(&self) -> bool {
-    self.bag
+    self.bag.is_some()
+  }
+
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  /// ID of the container item that this item is inside of, if any.
+  pub fn parent_id(&self) -> Option<&str> {
+    self.parent_id.as_deref()
   }
 
   pub fn accept(&mut self) {
     self.cnt_cmp = self.cnt;
     self.dur_cmp.clone_from(&self.dur);
   }
+}
 
-  pub fn discard(&mut self) {
-    self.cnt = self.cnt_cmp;
-    self.dur.clone_from(&self.dur_cmp);
+/// Set the parent of each item. The items in a bag's store are inside the container item that references it.
+fn set_item_parents(groups: &mut [ItemGroup]) {
+  let parents: HashMap<String, String> = groups
+    .iter()
+    .flat_map(|group| group.items.iter())
+    .filter_map(|item| Some((item.bag.clone()?, item.id.clone())))
+    .collect();
+
+  for group in groups {
+    let parent_id = parents.get(&group.id);
+    for item in &mut group.items {
+      item.parent_id = parent_id.cloned();
+    }
+  }
+}
+
+/// Get the tree node for an item. `visited` keeps malformed saves with circular bag references from recursing forever.
+fn get_item_node<'a>(groups: &'a [ItemGroup], group: usize, index: usize, visited: &mut HashSet<&'a str>) -> ItemNode {
+  let item = &groups[group].items[index];
+  let mut children = Vec::new();
+  if item.is_container() && visited.insert(&item.id) {
+    for (group_index, child_group) in groups.iter().enumerate() {
+      for (item_index, child) in child_group.items.iter().enumerate() {
+        if child.parent_id() == Some(&item.id) {
+          children.push(get_item_node(groups, group_index, item_index, visited));
+        }
+      }
+    }
+  }
+
+  ItemNode { group, index, children }
+}
+
+/// Store the items for a container.
+fn set_items(inv: &mut Map<String, Value>, items: &[Item]) {
+  // Remove items that have been deleted.
+  inv.retain(|id, _| items.iter().any(|item| item.id == *id));
+
+  for item in items {
+    // Add the item if it's new.
+    let val = inv.entry(item.id.as_str()).or_insert_with(|| item.json.clone());
+    let val = val.get_mut(IN).unwrap();
+    val[QN] = item.cnt.into();
+    if let Some(dur) = &item.dur {
+      // Only rewrite durability values that changed so that the rest are left exactly as they were.
+      set_f64(val, HP, dur.minor);
+      set_f64(val, PHP, dur.major);
+    }
+  }
+}
+
+fn set_f64(val: &mut Value, key: &str, num: f64) {
+  if val.get(key).and_then(Value::as_f64) != Some(num) {
+    val[key] = num.into();
   }
 }
 
-fn get_skill_lvl(sk2: &Value, info: &SkillInfo) -> Option<i32> {
-  let exp = sk2.get(format!("{}", info.id))?.get(X)?;
-  let exp = (exp.to_i64()? as f64 / info.mul) as i64;
+fn get_skill_exp(sk2: &Value, info: &SkillInfo) -> Option<i64> {
+  sk2.get(format!("{}", info.id))?.get(X)?.to_i64()
+}
+
+/// Get the level for an amount of skill experience.
+fn get_exp_lvl(exp: i64, mul: f64) -> Option<i32> {
+  let exp = (exp as f64 / mul) as i64;
   Some(util::floor_search(exp, SKILL_EXP)? as i32 + 1)
 }
 
+/// Get the skill experience needed to reach a level.
+fn get_lvl_exp(level: i32, mul: f64) -> i64 {
+  (SKILL_EXP[level as usize - 1] as f64 * mul).ceil() as i64
+}
+
+/// Store the skills that were changed. Unchanged skills are left as-is.
+fn set_skills(sk2: &mut Value, date: &Value, skills: &[SkillLvlGroup]) {
+  for group in skills {
+    for skill in &group.skills {
+      if skill.changed() {
+        set_skill_lvl(sk2, date, skill);
+      }
+    }
+  }
+}
+
 fn set_skill_lvl(sk2: &mut Value, date: &Value, skill: &SkillLvl) {
-  assert!((0..=200).contains(&skill.level));
-  if skill.level == 0 {
-    remove_skill(sk2, skill.info.id)
-  } else {
-    let exp = (SKILL_EXP[skill.level as usize - 1] as f64 * skill.info.mul).ceil() as i64;
+  // Edits are limited to the level cap, but the cap can be lowered by the skill data override after a level was set.
+  if let Some(exp) = skill.exp().map(|exp| exp.min(skill.max_exp())) {
     let key = format!("{}", skill.info.id);
     if let Some(skill) = sk2.get_mut(&key) {
       // Set the skill's experience.
@@ -395,6 +1150,8 @@ fn set_skill_lvl(sk2: &mut Value, date: &Value, skill: &SkillLvl) {
         X: exp,
       });
     }
+  } else {
+    remove_skill(sk2, skill.info.id)
   }
 }
 
@@ -403,11 +1160,164 @@ fn remove_skill(sk2: &mut Value, id: u32) {
   skills.remove(&format!("{id}"));
 }
 
+/// Parse the combat decks from the CharacterSheet. Save-games without any decks have none.
+fn get_decks(character: &Value) -> Result<Vec<Deck>, Cow<'static, str>> {
+  let Some(decks) = character.get(DK) else {
+    return Ok(Vec::new());
+  };
+
+  let Some(decks) = decks.as_object() else {
+    return Err(Cow::from("Unable to parse the decks"));
+  };
+
+  let mut result = Vec::with_capacity(decks.len());
+  for (key, deck) in decks {
+    let name = match deck.get(N).and_then(Value::as_str) {
+      Some(name) => String::from(name),
+      None => format!("Deck {key}"),
+    };
+
+    let Some(slots) = deck.get(S).and_then(Value::as_array) else {
+      return Err(Cow::from(format!("Unable to parse the slots for '{name}'")));
+    };
+
+    // Empty slots are zero.
+    let mut ids = Vec::with_capacity(slots.len());
+    for slot in slots {
+      let Some(id) = slot.as_u64().and_then(|id| u32::try_from(id).ok()) else {
+        return Err(Cow::from(format!("Unable to parse the slots for '{name}'")));
+      };
+      ids.push((id != 0).then_some(id));
+    }
+
+    result.push(Deck::new(key.clone(), name, ids));
+  }
+
+  // Numbered decks are in numeric order.
+  result.sort_by(|a, b| (a.key.parse::<u32>().ok(), &a.key).cmp(&(b.key.parse::<u32>().ok(), &b.key)));
+  Ok(result)
+}
+
+/// Set a virtue value, keeping it an integer if it was one and the new value is whole.
+fn set_virtue(val: &mut Value, value: f64) {
+  if (val.is_i64() || val.is_u64()) && value.fract() == 0.0 && value.abs() <= i64::MAX as f64 {
+    *val = (value as i64).into();
+  } else {
+    *val = value.into();
+  }
+}
+
+/// Set the deck slots. Only slots that changed are rewritten.
+fn set_decks(character: &mut Value, decks: &[Deck]) {
+  let Some(map) = character.get_mut(DK).and_then(Value::as_object_mut) else {
+    return;
+  };
+
+  for deck in decks.iter().filter(|deck| deck.changed()) {
+    let Some(slots) = map
+      .get_mut(&deck.key)
+      .and_then(|deck| deck.get_mut(S))
+      .and_then(Value::as_array_mut)
+    else {
+      continue;
+    };
+
+    for (slot, (id, comp)) in slots.iter_mut().zip(deck.slots.iter().zip(&deck.comp)) {
+      if id != comp {
+        *slot = id.unwrap_or(0).into();
+      }
+    }
+  }
+}
+
+fn get_gold(gold: &Value) -> Option<i64> {
+  gold.get(G)?.to_i64()
+}
+
+fn set_gold(gold: &mut Value, amount: i64) {
+  gold[G] = amount.into();
+}
+
+/// Get a file's modification time.
+fn get_modified(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Get the backups for a save-game file, newest first.
+pub fn get_backups(path: &Path) -> Vec<(NaiveDateTime, PathBuf)> {
+  let Some(prefix) = get_backup_prefix(path) else {
+    return Vec::new();
+  };
+  let Some(Ok(entries)) = path.parent().map(fs::read_dir) else {
+    return Vec::new();
+  };
+
+  let mut backups = Vec::new();
+  for entry in entries.flatten() {
+    let file_name = entry.file_name();
+    let Some(time) = file_name.to_str().and_then(|name| name.strip_prefix(&prefix)) else {
+      continue;
+    };
+
+    let parse = |format| NaiveDateTime::parse_from_str(time, format);
+    if let Ok(time) = parse(BACKUP_TIME_FORMAT).or_else(|_| parse(OLD_BACKUP_TIME_FORMAT)) {
+      backups.push((time, entry.path()));
+    }
+  }
+
+  backups.sort_unstable_by_key(|backup| std::cmp::Reverse(backup.0));
+  backups
+}
+
+/// Copy an existing save-game file to a timestamped backup and remove all but the newest `keep` backups.
+fn create_backup(path: &Path, keep: usize, time: NaiveDateTime) -> Result<(), Cow<'static, str>> {
+  // Nothing to back up.
+  if !path.exists() {
+    return Ok(());
+  }
+
+  let Some(prefix) = get_backup_prefix(path) else {
+    return Err(Cow::from("Unable to back up the save-game: invalid file name"));
+  };
+
+  // Don't overwrite a backup that was made at the same time.
+  let mut time = time;
+  let mut backup = path.with_file_name(format!("{prefix}{}", time.format(BACKUP_TIME_FORMAT)));
+  while backup.exists() {
+    time += chrono::Duration::milliseconds(1);
+    backup = path.with_file_name(format!("{prefix}{}", time.format(BACKUP_TIME_FORMAT)));
+  }
+
+  if let Err(err) = fs::copy(path, &backup) {
+    return Err(Cow::from(format!(
+      "Unable to back up the save-game, it was not stored: {err}"
+    )));
+  }
+
+  // Remove the oldest backups.
+  for (_, old) in get_backups(path).into_iter().skip(keep) {
+    if let Err(err) = fs::remove_file(&old) {
+      println!("Unable to remove {old:?}: {err}");
+    }
+  }
+
+  Ok(())
+}
+
+fn get_backup_prefix(path: &Path) -> Option<String> {
+  let file_name = path.file_name()?.to_str()?;
+  Some(format!("{file_name}.bak-"))
+}
+
 fn get_item_name(val: &Value) -> Option<String> {
   let text = val.get(AN)?.as_str()?;
   let pos = text.rfind('/')?;
+  let name = &text[pos + 1..];
+  if name.is_empty() {
+    return None;
+  }
 
-  Some(text[pos + 1..].into())
+  Some(name.into())
 }
 
 trait ToI64 {
@@ -448,18 +1358,164 @@ fn get_avatar_name(text: &str, avatar: &str) -> Result<String, Cow<'static, str>
   Err(Cow::from("Unable to get the avatar name"))
 }
 
-fn get_backpack_id(text: &str, avatar: &str) -> Result<String, Cow<'static, str>> {
-  // Get the Character json.
-  let json = get_json(text, "Character", avatar)?;
-
+fn get_backpack_id(json: &Value) -> Result<String, Cow<'static, str>> {
   // Get the backpack ID.
-  if let Some(Value::String(id)) = json.get("mainbp") {
+  if let Some(Value::String(id)) = json.get(MAIN_BP) {
     return Ok(id.clone());
   }
 
   Err(Cow::from("Unable to find the avatar's backpack"))
 }
 
+/// Get the ItemStore records for the backpack, any other containers referenced by the avatar (bank etc.) and any
+/// bags within those.
+fn get_item_stores(text: &str, avatar: &str) -> Result<Vec<ItemStore>, Cow<'static, str>> {
+  // Get the Character json.
+  let json = get_json(text, "Character", avatar)?;
+
+  // The backpack is required.
+  let backpack = get_backpack_id(&json)?;
+  let ids = get_record_ids(text, ITEM_STORE);
+  let mut queue = VecDeque::from([(String::from("Backpack"), backpack)]);
+
+  // Other containers referenced by the character record.
+  if let Value::Object(obj) = &json {
+    for (key, val) in obj {
+      if key == MAIN_BP {
+        continue;
+      }
+
+      if let Value::String(id) = val {
+        if ids.contains(id.as_str()) {
+          let name = if key.to_lowercase().contains("bank") {
+            String::from("Bank")
+          } else {
+            key.clone()
+          };
+          queue.push_back((name, id.clone()));
+        }
+      }
+    }
+  }
+
+  let mut visited = HashSet::new();
+  let mut stores = Vec::new();
+  while let Some((name, id)) = queue.pop_front() {
+    if !visited.insert(id.clone()) {
+      continue;
+    }
+
+    let json = match get_json(text, ITEM_STORE, &id) {
+      Ok(json) if json.get(IN).is_some_and(|inv| inv.is_object()) => json,
+      Ok(_) if stores.is_empty() => return Err(Cow::from("Unable to parse the backpack items")),
+      Err(err) if stores.is_empty() => return Err(err),
+      _ => continue,
+    };
+
+    // Look for bags that have their own ItemStore record.
+    if let Some(Value::Object(items)) = json.get(IN) {
+      for val in items.values() {
+        let Some(val) = val.get(IN) else { continue };
+        let Some(Value::String(bag)) = val.get(BAG) else {
+          continue;
+        };
+
+        if ids.contains(bag.as_str()) {
+          let name = get_item_name(val).unwrap_or_else(|| bag.clone());
+          queue.push_back((name, bag.clone()));
+        }
+      }
+    }
+
+    stores.push(ItemStore { name, id, json });
+  }
+
+  Ok(stores)
+}
+
+/// Get the UserKnowledge record that contains the virtues. It's not an error for there to be no virtues, but it is if a
+/// record can't be parsed.
+fn get_knowledge(text: &str, avatar: &str) -> Result<Option<Knowledge>, Cow<'static, str>> {
+  // Check if the object contains any of the virtues.
+  let has_virtues = |obj: &Map<String, Value>| {
+    VIRTUES.iter().filter(|virtue| **virtue != Virtue::Ethos).any(|virtue| {
+      let name = format!("{virtue:?}");
+      obj
+        .iter()
+        .any(|(key, val)| key.eq_ignore_ascii_case(&name) && val.is_number())
+    })
+  };
+
+  let mut error = None;
+  for id in [avatar, USER_ID] {
+    if get_json_range(text, USER_KNOWLEDGE, id).is_none() {
+      continue;
+    }
+
+    let json = match get_json(text, USER_KNOWLEDGE, id) {
+      Ok(json) => json,
+      Err(err) => {
+        error.get_or_insert(err);
+        continue;
+      }
+    };
+
+    let Some(obj) = json.as_object() else {
+      continue;
+    };
+
+    if has_virtues(obj) {
+      return Ok(Some(Knowledge {
+        id: id.to_owned(),
+        json,
+        key: None,
+      }));
+    }
+
+    // Look one level down.
+    let key = obj.iter().find_map(|(key, val)| {
+      let obj = val.as_object()?;
+      has_virtues(obj).then(|| key.clone())
+    });
+
+    if key.is_some() {
+      return Ok(Some(Knowledge {
+        id: id.to_owned(),
+        json,
+        key,
+      }));
+    }
+  }
+
+  match error {
+    Some(err) => Err(err),
+    None => Ok(None),
+  }
+}
+
+/// Get the IDs of all the records in a collection.
+fn get_record_ids<'a>(text: &'a str, collection: &str) -> HashSet<&'a str> {
+  const RECORD_START: &str = r#"<record Id=""#;
+  let mut ids = HashSet::new();
+  let find = collection_tag(collection);
+  let Some(start) = text.find(&find) else {
+    return ids;
+  };
+
+  // Limit the search to the collection.
+  let text = &text[start + find.len()..];
+  let end = text.find(collection_end()).unwrap_or(text.len());
+  let mut text = &text[..end];
+  while let Some(pos) = text.find(RECORD_START) {
+    text = &text[pos + RECORD_START.len()..];
+    let Some(pos) = text.find('"') else { break };
+    ids.insert(&text[..pos]);
+    text = &text[pos..];
+  }
+
+  ids
+}
+
 fn collection_tag(collection: &str) -> String {
   format!(r#"<collection name="{collection}">"#)
 }
@@ -472,6 +1528,10 @@ const fn record_end() -> &'static str {
   "</record>"
 }
 
+const fn collection_end() -> &'static str {
+  "</collection>"
+}
+
 fn get_json_range(text: &str, collection: &str, id: &str) -> Option<Range<usize>> {
   // Find the collection tag.
   let find = collection_tag(collection);
@@ -505,8 +1565,13 @@ fn get_json(text: &str, collection: &str, id: &str) -> Result<Value, Cow<'static
   Err(Cow::from(err))
 }
 
+/// Replace the JSON for a record. The original JSON text is kept if the value didn't change.
 fn set_json(text: &str, collection: &str, id: &str, val: &Value) -> Result<String, Cow<'static, str>> {
   if let Some(range) = get_json_range(text, collection, id) {
+    if serde_json::from_str::<Value>(&text[range.clone()]).is_ok_and(|current| current == *val) {
+      return Ok(text.to_owned());
+    }
+
     // Convert the value to JSON text.
     let json = val.to_string();
 
@@ -534,3 +1599,511 @@ fn find_date(val: &Value) -> Result<Value, Cow<'static, str>> {
 
   Err(Cow::from("Unable to find a save date"))
 }
+
+/// Avatar ID of the test save-games.
+#[cfg(test)]
+pub const TEST_AVATAR: &str = "00000000000000000000000a";
+
+/// User ID of the test save-games.
+#[cfg(test)]
+pub const TEST_USER: &str = USER_ID;
+
+/// Records of a test save-game for Mira: the User and CharacterName records, followed by `records` as
+/// (collection, ID, JSON).
+#[cfg(test)]
+pub fn test_save_game_records(records: &[(&str, &str, &str)]) -> Vec<String> {
+  let record = |collection: &str, id: &str, json: &str| {
+    format!(r#"<collection name="{collection}"><record Id="{id}">{json}</record></collection>"#)
+  };
+  let user = format!(r#"{{"dc":"{TEST_AVATAR}"}}"#);
+  let mut result = vec![
+    record("User", USER_ID, &user),
+    record("CharacterName", TEST_AVATAR, r#"{"fn":"Mira"}"#),
+  ];
+  result.extend(
+    records
+      .iter()
+      .map(|&(collection, id, json)| record(collection, id, json)),
+  );
+  result
+}
+
+/// Text of a test save-game for Mira, see [`test_save_game_records`].
+#[cfg(test)]
+pub fn test_save_game(records: &[(&str, &str, &str)]) -> String {
+  test_save_game_records(records).concat()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::TempDir;
+
+  #[test]
+  fn test_gold_round_trip() {
+    // More than will fit in an i32.
+    const GOLD: i64 = 5_000_000_123;
+    let text = format!(r#"<collection name="{USER_GOLD}"><record Id="{USER_ID}">{{"g":{GOLD}}}</record></collection>"#);
+
+    let mut gold = get_json(&text, USER_GOLD, USER_ID).unwrap();
+    assert_eq!(get_gold(&gold), Some(GOLD));
+
+    // Store a larger value and read it back.
+    set_gold(&mut gold, GOLD * 3);
+    let text = set_json(&text, USER_GOLD, USER_ID, &gold).unwrap();
+    let gold = get_json(&text, USER_GOLD, USER_ID).unwrap();
+    assert_eq!(get_gold(&gold), Some(GOLD * 3));
+  }
+
+  #[test]
+  fn test_virtue_round_trip() {
+    // Integers stay integers.
+    let mut val = serde_json::json!(25);
+    set_virtue(&mut val, 40.0);
+    assert_eq!(val.to_string(), "40");
+    set_virtue(&mut val, -12.0);
+    assert_eq!(val.to_string(), "-12");
+
+    // Unless the new value isn't whole.
+    set_virtue(&mut val, 12.5);
+    assert_eq!(val.to_string(), "12.5");
+
+    // Floats stay floats.
+    let mut val = serde_json::json!(25.5);
+    set_virtue(&mut val, 40.0);
+    assert_eq!(val.to_string(), "40.0");
+  }
+
+  #[test]
+  fn test_skill_exp_round_trip() {
+    let group = skill_info::parse_skill_info_groups(SkillCategory::Adventurer, SkillOverride::default()).remove(0);
+    let info = group.skills[0].clone();
+    let partial = get_lvl_exp(80, info.mul) + 7;
+    let mut sk2 = serde_json::json!({
+      format!("{}", info.id): { M: 0, T: "2024-01-02", X: partial },
+    });
+    let date = Value::from("2024-01-02");
+    let text = sk2.to_string();
+
+    // Unchanged skills aren't rewritten.
+    let mut groups = vec![SkillLvlGroup::new(&sk2, group)];
+    assert_eq!(groups[0].skills[0].level, 80);
+    assert_eq!(groups[0].skills[0].exp(), Some(partial));
+    set_skills(&mut sk2, &date, &groups);
+    assert_eq!(sk2.to_string(), text);
+
+    // Changing the level uses the experience for that level.
+    groups[0].skills[0].level = 81;
+    set_skills(&mut sk2, &date, &groups);
+    assert_eq!(get_skill_exp(&sk2, &info), Some(get_lvl_exp(81, info.mul)));
+
+    // Changing the experience keeps the exact value and derives the level.
+    groups[0].accept();
+    groups[0].skills[0].set_exp(partial);
+    assert_eq!(groups[0].skills[0].level, 80);
+    set_skills(&mut sk2, &date, &groups);
+    assert_eq!(sk2.to_string(), text);
+  }
+
+  #[test]
+  fn test_skill_level_cap() {
+    let mut group = skill_info::parse_skill_info_groups(SkillCategory::Adventurer, SkillOverride::default()).remove(0);
+    group.skills[0].max_lvl = Some(40);
+    let sk2 = serde_json::json!({});
+    let mut group = SkillLvlGroup::new(&sk2, group);
+    let skill = &mut group.skills[0];
+
+    // Experience past the cap is limited to it.
+    assert_eq!(skill.max_exp(), get_lvl_exp(40, skill.info.mul));
+    skill.set_exp(get_lvl_exp(120, skill.info.mul));
+    assert_eq!(skill.level, 40);
+
+    // So is a level that was set past it.
+    let info = skill.info.clone();
+    skill.level = 120;
+    let mut sk2 = serde_json::json!({});
+    set_skills(&mut sk2, &Value::from("2024-01-02"), &[group]);
+    assert_eq!(get_skill_exp(&sk2, &info), Some(get_lvl_exp(40, info.mul)));
+  }
+
+  #[test]
+  fn test_deck_round_trip() {
+    let mut character = serde_json::json!({
+      AE: 0,
+      DK: {
+        "10": { N: "Archery", S: [101, 0, 102] },
+        "2": { N: "Fire", S: [201, 202] },
+      },
+    });
+    let text = character.to_string();
+
+    let mut decks = get_decks(&character).unwrap();
+    let names: Vec<&str> = decks.iter().map(|deck| deck.name.as_str()).collect();
+    assert_eq!(names, ["Fire", "Archery"]);
+    assert_eq!(decks[1].slots, [Some(101), None, Some(102)]);
+
+    // Unchanged decks aren't rewritten.
+    set_decks(&mut character, &decks);
+    assert_eq!(character.to_string(), text);
+
+    // Only the changed slot is rewritten.
+    decks[1].slots[1] = Some(103);
+    decks[1].slots[2] = None;
+    assert!(decks[1].changed());
+    set_decks(&mut character, &decks);
+    assert_eq!(character[DK]["10"][S], serde_json::json!([101, 103, 0]));
+    assert_eq!(character[DK]["2"][S], serde_json::json!([201, 202]));
+
+    decks[1].discard();
+    assert!(!decks[1].changed());
+
+    // Save-games without decks are fine.
+    assert!(get_decks(&serde_json::json!({ AE: 0 })).unwrap().is_empty());
+
+    // Malformed slots fail.
+    assert!(get_decks(&serde_json::json!({ DK: { "1": { S: ["x"] } } })).is_err());
+  }
+
+  #[test]
+  fn test_store_keeps_decks() {
+    // Deck data is stored exactly as it was loaded, including anything that isn't parsed.
+    let decks = r#"{"2":{"n":"Fire","s":[201,202],"x":{"k":[1,2]}},"10":{"s":[101,0,102],"n":"Archery"}}"#;
+    let sheet = format!(r#"{{"pe":0,"ae":0,"dk":{decks}}}"#);
+    let text = test_save_game(&[(CHARACTER_SHEET, TEST_AVATAR, &sheet)]);
+
+    let dir = TempDir::new("decks");
+    let path = dir.join("decks.sota");
+    std::fs::write(&path, &text).unwrap();
+
+    let mut data = GameData::load(path.clone()).unwrap();
+    assert!(data.report().is_loaded(Section::Decks));
+    assert_eq!(data.get_decks().len(), 2);
+
+    // An unmodified store is identical.
+    let copy = dir.join("decks_copy.sota");
+    data.store_as(copy.clone(), 0).unwrap();
+    assert_eq!(std::fs::read_to_string(&copy).unwrap(), text);
+
+    // Other edits to the CharacterSheet keep the deck data.
+    data.set_adv_lvl(2);
+    data.store_as(copy.clone(), 0).unwrap();
+    let stored = GameData::load(copy).unwrap();
+    let character = stored.character.as_ref().unwrap();
+    assert_eq!(character[DK], serde_json::from_str::<Value>(decks).unwrap());
+    let slots: Vec<_> = stored.get_decks().into_iter().map(|deck| deck.slots).collect();
+    assert_eq!(slots, [vec![Some(201), Some(202)], vec![Some(101), None, Some(102)]]);
+  }
+
+  #[test]
+  fn test_partial_load() {
+    const BACKPACK: &str = "00000000000000000000000b";
+    let broken_gold = r#"{"g":}"#;
+    let character = format!(r#"{{"mainbp":"{BACKPACK}"}}"#);
+    let text = test_save_game(&[
+      ("Character", TEST_AVATAR, &character),
+      (ITEM_STORE, BACKPACK, r#"{"in":{}}"#),
+      (CHARACTER_SHEET, TEST_AVATAR, r#"{"ae":0,"pe":0,"sk2":{}}"#),
+      (USER_GOLD, USER_ID, broken_gold),
+    ]);
+
+    let dir = TempDir::new("save");
+    let path = dir.join("partial.sota");
+    std::fs::write(&path, &text).unwrap();
+
+    let mut data = GameData::load(path.clone()).unwrap();
+    let report = data.report();
+    assert!(!report.is_complete());
+    assert!(report.is_loaded(Section::Levels));
+    assert!(report.is_loaded(Section::Inventory));
+    assert!(report.is_loaded(Section::Virtues));
+    assert!(report.error(Section::Gold).is_some());
+
+    // There's no save date in the skills.
+    assert!(report.error(Section::Skills).is_some());
+    assert!(data
+      .get_skills(SkillCategory::Adventurer, SkillOverride::default())
+      .is_empty());
+    assert_eq!(data.get_gold(), None);
+
+    // Only the sections that loaded are rewritten.
+    data.set_adv_lvl(2);
+    data.set_gold(100);
+    data.store(1, false).unwrap();
+
+    // The original file was backed up first.
+    let backups = get_backups(&path);
+    assert_eq!(backups.len(), 1);
+    assert_eq!(std::fs::read_to_string(&backups[0].1).unwrap(), text);
+
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(stored.contains(broken_gold));
+    assert!(stored.contains(&format!(r#""ae":{}"#, LEVEL_EXP[1])));
+  }
+
+  #[test]
+  fn test_store_keeps_format() {
+    // Keys that aren't sorted and whitespace are kept for unchanged records.
+    let records = test_save_game_records(&[
+      ("Character", TEST_AVATAR, r#"{"y": 2, "x": 1}"#),
+      (USER_GOLD, USER_ID, r#"{"x":1,"g":10}"#),
+    ]);
+    let lines: Vec<_> = [r#"<?xml version="1.0" encoding="utf-8"?>"#, "<save>"]
+      .into_iter()
+      .chain(records.iter().map(String::as_str))
+      .chain(["</save>", ""])
+      .collect();
+
+    let dir = TempDir::new("format");
+    for (name, newline, bom) in [("lf", "\n", ""), ("crlf", "\r\n", ""), ("bom", "\r\n", "\u{feff}")] {
+      let text = format!("{bom}{}", lines.join(newline));
+      let path = dir.join(format!("{name}.sota"));
+      std::fs::write(&path, &text).unwrap();
+
+      // An unmodified store is identical.
+      let mut data = GameData::load(path.clone()).unwrap();
+      let copy = dir.join(format!("{name}_copy.sota"));
+      data.store_as(copy.clone(), 0).unwrap();
+      assert_eq!(std::fs::read(&copy).unwrap(), text.as_bytes(), "{name}");
+
+      // A modified store keeps the BOM and line endings.
+      data.set_gold(20);
+      data.store_as(copy.clone(), 0).unwrap();
+      let stored = std::fs::read_to_string(&copy).unwrap();
+      assert_eq!(stored.starts_with('\u{feff}'), !bom.is_empty(), "{name}");
+      assert_eq!(stored.matches(newline).count(), lines.len() - 1, "{name}");
+      assert_eq!(stored.matches('\n').count(), lines.len() - 1, "{name}");
+      assert!(stored.contains(r#"{"g":20,"x":1}"#), "{name}");
+    }
+  }
+
+  #[test]
+  fn test_not_save_game() {
+    let dir = TempDir::new("not_save");
+    let path = dir.join("notes.sota");
+    std::fs::write(&path, "Remember to water the crops\n").unwrap();
+    assert_eq!(GameData::load(path).err(), Some(Cow::from(NOT_SAVE_GAME)));
+
+    let path = dir.join("image.sota");
+    std::fs::write(&path, [0x89, b'P', b'N', b'G', 0xff, 0xfe]).unwrap();
+    assert_eq!(GameData::load(path).err(), Some(Cow::from(NOT_SAVE_GAME)));
+  }
+
+  #[test]
+  fn test_modified_after_load() {
+    let text = test_save_game(&[(USER_GOLD, USER_ID, r#"{"g":10}"#)]);
+
+    let dir = TempDir::new("modified");
+    let path = dir.join("modified.sota");
+    std::fs::write(&path, &text).unwrap();
+
+    let mut data = GameData::load(path.clone()).unwrap();
+    assert!(!data.file_modified());
+
+    // Simulate the game client saving after the file was loaded.
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file
+      .set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+      .unwrap();
+    drop(file);
+    assert!(data.file_modified());
+
+    data.set_gold(20);
+    assert!(data.store(0, false).is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+
+    // Saving somewhere else isn't affected.
+    let other = dir.join("other.sota");
+    data.store_as(other.clone(), 0).unwrap();
+    assert!(std::fs::read_to_string(&other).unwrap().contains(r#""g":20"#));
+    assert!(!data.file_modified());
+
+    // Overwriting remembers the new modification time.
+    let file = std::fs::File::options().write(true).open(&other).unwrap();
+    file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+    drop(file);
+    assert!(data.file_modified());
+    data.store(0, true).unwrap();
+    assert!(!data.file_modified());
+  }
+
+  #[test]
+  fn test_item_structure_changes() {
+    let json = serde_json::json!({ IN: { AN: "Items/Reagents/BlackPearl", QN: 5 } });
+    let items = vec![Item::new(&json, "00000000000000000000000a").unwrap()];
+    let mut groups = vec![ItemGroup {
+      name: String::from("Backpack"),
+      items_cmp: items.clone(),
+      items,
+      id: String::from("1"),
+    }];
+
+    // New IDs keep the same width.
+    let id = ItemGroup::new_item_id(&groups);
+    assert_eq!(id, "00000000000000000000000b");
+
+    groups[0].duplicate_item(0, id);
+    assert!(groups[0].changed());
+    assert_eq!(groups[0].items[1].name(), "BlackPearl");
+
+    groups[0].discard();
+    assert!(!groups[0].changed());
+    assert_eq!(groups[0].items.len(), 1);
+
+    groups[0].remove_item(0);
+    assert!(groups[0].changed());
+    groups[0].accept();
+    assert!(!groups[0].changed());
+
+    // The asset name needs a path.
+    assert!(groups[0].add_item("BlackPearl", String::from("c")).is_err());
+    assert!(groups[0].add_item("Items/Reagents/", String::from("c")).is_err());
+    assert!(groups[0]
+      .add_item("Items/Reagents/BlackPearl", String::from("c"))
+      .is_ok());
+    assert!(groups[0].changed());
+  }
+
+  #[test]
+  fn test_item_tree() {
+    let item = |asset: &str, id: &str, count: u64, bag: Option<&str>| {
+      let mut json = serde_json::json!({ IN: { AN: asset, QN: count } });
+      if let Some(bag) = bag {
+        json[IN][BAG] = bag.into();
+      }
+      Item::new(&json, id).unwrap()
+    };
+    let group = |id: &str, items: Vec<Item>| ItemGroup {
+      name: String::from(id),
+      items_cmp: items.clone(),
+      items,
+      id: String::from(id),
+    };
+
+    // Backpack > Sack > Pouch, plus a bag that nothing is holding.
+    let mut groups = vec![
+      group(
+        "backpack",
+        vec![
+          item("Items/Reagents/BlackPearl", "1", 5, None),
+          item("Items/Containers/Sack", "2", 1, Some("sack")),
+        ],
+      ),
+      group(
+        "sack",
+        vec![
+          item("Items/Reagents/Ash", "3", 10, None),
+          item("Items/Containers/Pouch", "4", 1, Some("pouch")),
+        ],
+      ),
+      group("pouch", vec![item("Items/Reagents/Garlic", "5", 3, None)]),
+      group("orphan", vec![item("Items/Reagents/Mandrake", "6", 2, None)]),
+    ];
+    set_item_parents(&mut groups);
+    assert_eq!(groups[1].items[0].parent_id(), Some("2"));
+    assert_eq!(groups[2].items[0].parent_id(), Some("4"));
+    assert_eq!(groups[0].items[0].parent_id(), None);
+
+    let nested: Vec<bool> = (0..groups.len())
+      .map(|index| ItemGroup::is_nested(&groups, index))
+      .collect();
+    assert_eq!(nested, [false, true, true, false]);
+
+    let tree = ItemGroup::get_item_tree(&groups, 0);
+    assert_eq!(tree.len(), 2);
+    assert!(tree[0].children.is_empty());
+    let sack = &tree[1];
+    assert_eq!((sack.group, sack.index), (0, 1));
+    assert_eq!(sack.children.len(), 2);
+    assert_eq!((sack.children[1].group, sack.children[1].index), (1, 1));
+    assert_eq!(sack.children[1].children.len(), 1);
+
+    // Aggregates include the nested containers.
+    assert_eq!(sack.totals(&groups), (3, 14));
+
+    // Items whose parent is missing are at the root.
+    groups[3].items[0].parent_id = Some(String::from("missing"));
+    let tree = ItemGroup::get_item_tree(&groups, 3);
+    assert_eq!(tree.len(), 1);
+  }
+
+  #[test]
+  fn test_durability_round_trip() {
+    const ID: &str = "00000000000000000000000a";
+    let mut inv = serde_json::json!({
+      ID: { IN: { AN: "Items/Weapons/Sword", QN: 1, HP: 4500, PHP: 5000 } },
+    });
+    let text = inv.to_string();
+    let map = inv.as_object_mut().unwrap();
+    let mut items = vec![Item::new(&map[ID], ID).unwrap()];
+    assert!(items[0].durability_mut().unwrap().is_valid());
+
+    // Unchanged durability isn't rewritten.
+    set_items(map, &items);
+    assert_eq!(inv.to_string(), text);
+
+    // Edited values are written back exactly.
+    let map = inv.as_object_mut().unwrap();
+    let dur = items[0].durability_mut().unwrap();
+    dur.minor = 4999.75;
+    dur.major = 4999.875;
+    assert!(items[0].changed());
+    set_items(map, &items);
+
+    let item = Item::new(&map[ID], ID).unwrap();
+    assert!(item.dur == items[0].dur);
+    assert_eq!(map[ID][IN][HP].as_f64(), Some(4999.75));
+    assert_eq!(map[ID][IN][PHP].as_f64(), Some(4999.875));
+
+    // Current durability can't exceed the primary durability.
+    let dur = items[0].durability_mut().unwrap();
+    dur.minor = 5000.0;
+    assert!(!dur.is_valid());
+  }
+
+  #[test]
+  fn test_backups() {
+    let dir = TempDir::new("backup");
+    let path = dir.join("Mira.sota");
+
+    // Nothing to back up yet.
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let time = |sec| date.and_hms_opt(15, 30, sec).unwrap();
+    create_backup(&path, 2, time(0)).unwrap();
+    assert!(get_backups(&path).is_empty());
+
+    // Only the newest backups are kept.
+    for sec in 0..3 {
+      std::fs::write(&path, format!("{sec}")).unwrap();
+      create_backup(&path, 2, time(sec)).unwrap();
+    }
+
+    // Unrelated files are ignored.
+    std::fs::write(dir.join("Mira.sota.bak-invalid"), "").unwrap();
+    std::fs::write(dir.join("Dane.sota.bak-20240101-153000.000"), "").unwrap();
+
+    let backups = get_backups(&path);
+    let times: Vec<_> = backups.iter().map(|(time, _)| *time).collect();
+    assert_eq!(times, [time(2), time(1)]);
+    assert_eq!(backups[0].1, dir.join("Mira.sota.bak-20240101-153002.000"));
+    assert_eq!(std::fs::read_to_string(&backups[1].1).unwrap(), "1");
+
+    // Storing twice at the same time keeps both backups.
+    std::fs::write(&path, "3").unwrap();
+    create_backup(&path, 3, time(2)).unwrap();
+    let backups = get_backups(&path);
+    assert_eq!(backups.len(), 3);
+    assert_eq!(backups[0].1, dir.join("Mira.sota.bak-20240101-153002.001"));
+    assert_eq!(std::fs::read_to_string(&backups[0].1).unwrap(), "3");
+    assert_eq!(std::fs::read_to_string(&backups[1].1).unwrap(), "2");
+
+    // Backups from before milliseconds were added are still found.
+    std::fs::write(dir.join("Mira.sota.bak-20240101-152959"), "").unwrap();
+    let backups = get_backups(&path);
+    assert_eq!(backups.last().unwrap().0, date.and_hms_opt(15, 29, 59).unwrap());
+
+    // A backup that can't be written is an error.
+    let folder = dir.join("Folder.sota");
+    std::fs::create_dir_all(&folder).unwrap();
+    assert!(create_backup(&folder, 2, time(10)).is_err());
+  }
+}
@@ -0,0 +1,317 @@
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+};
+
+/// Where a game folder was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+  /// The game's standard settings location.
+  Standard,
+
+  /// A Proton prefix in a Steam library.
+  Proton,
+
+  /// A Wine prefix.
+  Wine,
+}
+
+impl Source {
+  pub fn name(self) -> &'static str {
+    match self {
+      Source::Standard => "Standard",
+      Source::Proton => "Steam (Proton)",
+      Source::Wine => "Wine",
+    }
+  }
+}
+
+/// A SotA data folder containing chat logs and/or save-games.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameFolder {
+  pub source: Source,
+  pub path: PathBuf,
+  logs: bool,
+  saves: bool,
+}
+
+impl GameFolder {
+  /// Check a folder for chat logs and save-games.
+  fn probe(source: Source, path: PathBuf) -> Option<Self> {
+    let logs = path.join(LOGS_FOLDER).is_dir();
+    let saves = path.join(SAVES_FOLDER).is_dir();
+    (logs || saves).then_some(Self {
+      source,
+      path,
+      logs,
+      saves,
+    })
+  }
+
+  pub fn log_path(&self) -> Option<PathBuf> {
+    self.logs.then(|| self.path.join(LOGS_FOLDER))
+  }
+
+  pub fn save_game_path(&self) -> Option<PathBuf> {
+    self.saves.then(|| self.path.join(SAVES_FOLDER))
+  }
+}
+
+/// Probe the common install locations for SotA data folders.
+pub fn detect() -> Vec<GameFolder> {
+  let home = dirs::home_dir();
+  let steam_roots = home.as_deref().map(get_steam_roots).unwrap_or_default();
+  detect_in(dirs::config_dir().as_deref(), home.as_deref(), &steam_roots)
+}
+
+/// Probe for SotA data folders using the specified settings folder, home folder and Steam installs.
+fn detect_in(config_dir: Option<&Path>, home: Option<&Path>, steam_roots: &[PathBuf]) -> Vec<GameFolder> {
+  let mut candidates = Vec::new();
+
+  // Standard location used by the native clients and by the Windows client (including Steam).
+  if let Some(config_dir) = config_dir {
+    candidates.push((Source::Standard, config_dir.join(SOTA_FOLDER)));
+  }
+
+  // Proton prefixes in each Steam library.
+  for library in get_steam_libraries(steam_roots) {
+    let Ok(entries) = fs::read_dir(library.join("steamapps").join("compatdata")) else {
+      continue;
+    };
+
+    let mut prefixes: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    prefixes.sort();
+    for prefix in prefixes {
+      let users = prefix.join("pfx").join("drive_c").join("users").join("steamuser");
+      candidates.push((Source::Proton, users.join(ROAMING_FOLDER).join(SOTA_FOLDER)));
+    }
+  }
+
+  // The default Wine prefix, used by the standalone installer.
+  if let Some(home) = home {
+    if let Ok(entries) = fs::read_dir(home.join(".wine").join("drive_c").join("users")) {
+      let mut users: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+      users.sort();
+      for user in users {
+        candidates.push((Source::Wine, user.join(ROAMING_FOLDER).join(SOTA_FOLDER)));
+        candidates.push((Source::Wine, user.join("Application Data").join(SOTA_FOLDER)));
+      }
+    }
+  }
+
+  // Skip folders that were already found through a different path (e.g. symbolic links).
+  let mut found = HashSet::new();
+  candidates
+    .into_iter()
+    .filter_map(|(source, path)| GameFolder::probe(source, path))
+    .filter(|folder| found.insert(fs::canonicalize(&folder.path).unwrap_or_else(|_| folder.path.clone())))
+    .collect()
+}
+
+/// Get the Steam library folders, including the Steam installs themselves.
+fn get_steam_libraries(steam_roots: &[PathBuf]) -> Vec<PathBuf> {
+  let mut libraries = Vec::new();
+  let mut found = HashSet::new();
+  for root in steam_roots {
+    let mut paths = vec![root.clone()];
+    for file in [
+      root.join("steamapps").join(LIBRARY_FOLDERS_FILE),
+      root.join("config").join(LIBRARY_FOLDERS_FILE),
+    ] {
+      if let Ok(text) = fs::read_to_string(file) {
+        paths.extend(parse_library_folders(&text));
+      }
+    }
+
+    for path in paths {
+      if path.is_dir() && found.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+        libraries.push(path);
+      }
+    }
+  }
+  libraries
+}
+
+/// Parse the library paths from the text of a Steam `libraryfolders.vdf` file.
+fn parse_library_folders(text: &str) -> Vec<PathBuf> {
+  let mut paths = Vec::new();
+  let mut depth = 0;
+  for line in text.lines() {
+    match line.trim() {
+      "{" => depth += 1,
+      "}" => depth -= 1,
+      line => {
+        let tokens = get_quoted(line);
+        let [key, value] = tokens.as_slice() else {
+          continue;
+        };
+
+        // Current files have a "path" entry in each library section. Older files map numbers directly to paths.
+        let numbered = depth == 1 && key.chars().all(|ch| ch.is_ascii_digit());
+        if (depth == 2 && key == "path") || numbered {
+          paths.push(PathBuf::from(value));
+        }
+      }
+    }
+  }
+  paths
+}
+
+/// Get the quoted strings from a line of VDF text.
+fn get_quoted(line: &str) -> Vec<String> {
+  let mut tokens = Vec::new();
+  let mut chars = line.chars();
+  while let Some(ch) = chars.next() {
+    if ch != '"' {
+      continue;
+    }
+
+    let mut token = String::new();
+    while let Some(ch) = chars.next() {
+      match ch {
+        '"' => break,
+        '\\' => token.extend(chars.next()),
+        _ => token.push(ch),
+      }
+    }
+    tokens.push(token);
+  }
+  tokens
+}
+
+/// Get the folders where Steam is commonly installed.
+#[cfg(target_os = "linux")]
+fn get_steam_roots(home: &Path) -> Vec<PathBuf> {
+  vec![
+    home.join(".steam").join("steam"),
+    home.join(".local").join("share").join("Steam"),
+    home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+  ]
+}
+
+/// Get the folders where Steam is commonly installed.
+#[cfg(target_os = "macos")]
+fn get_steam_roots(home: &Path) -> Vec<PathBuf> {
+  vec![home.join("Library").join("Application Support").join("Steam")]
+}
+
+/// Get the folders where Steam is commonly installed.
+#[cfg(target_os = "windows")]
+fn get_steam_roots(_home: &Path) -> Vec<PathBuf> {
+  vec![
+    PathBuf::from(r"C:\Program Files (x86)\Steam"),
+    PathBuf::from(r"C:\Program Files\Steam"),
+  ]
+}
+
+/// Get the folders where Steam is commonly installed.
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn get_steam_roots(_home: &Path) -> Vec<PathBuf> {
+  Vec::new()
+}
+
+/// SotA data folder, relative to the settings folder.
+const SOTA_FOLDER: &str = "Portalarium/Shroud of the Avatar";
+
+/// Windows settings folder, relative to a user's folder.
+const ROAMING_FOLDER: &str = "AppData/Roaming";
+const LOGS_FOLDER: &str = "ChatLogs";
+const SAVES_FOLDER: &str = "SavedGames";
+const LIBRARY_FOLDERS_FILE: &str = "libraryfolders.vdf";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::TempDir;
+
+  fn temp_dir(name: &str) -> TempDir {
+    TempDir::new(&format!("folders_{name}"))
+  }
+
+  #[test]
+  fn test_parse_library_folders() {
+    let text = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/avatar/.local/share/Steam"
+		"label"		""
+		"apps"
+		{
+			"326160"		"12345"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+	}
+}"#;
+    let paths = parse_library_folders(text);
+    assert_eq!(
+      paths,
+      [
+        PathBuf::from("/home/avatar/.local/share/Steam"),
+        PathBuf::from(r"D:\SteamLibrary"),
+      ]
+    );
+
+    // Older format.
+    let text = "\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\"1\"\n\t\"1\"\t\"/mnt/games/steam\"\n}";
+    assert_eq!(parse_library_folders(text), [PathBuf::from("/mnt/games/steam")]);
+  }
+
+  #[test]
+  fn test_detect() {
+    let root = temp_dir("detect");
+
+    // Standard location with only chat logs.
+    let config_dir = root.join("config");
+    fs::create_dir_all(config_dir.join(SOTA_FOLDER).join(LOGS_FOLDER)).unwrap();
+
+    // Steam install pointing to a second library with a Proton prefix.
+    let steam = root.join("steam");
+    let library = root.join("library");
+    let vdf = format!(
+      "\"libraryfolders\"\n{{\n\t\"1\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}",
+      library.display()
+    );
+    fs::create_dir_all(steam.join("steamapps")).unwrap();
+    fs::write(steam.join("steamapps").join(LIBRARY_FOLDERS_FILE), vdf).unwrap();
+    let proton = library.join("steamapps/compatdata/326160/pfx/drive_c/users/steamuser");
+    let proton = proton.join(ROAMING_FOLDER).join(SOTA_FOLDER);
+    fs::create_dir_all(proton.join(LOGS_FOLDER)).unwrap();
+    fs::create_dir_all(proton.join(SAVES_FOLDER)).unwrap();
+
+    // Empty prefixes aren't reported.
+    fs::create_dir_all(library.join("steamapps/compatdata/999/pfx/drive_c/users/steamuser")).unwrap();
+
+    // Wine prefix in the home folder.
+    let home = root.join("home");
+    let wine = home
+      .join(".wine/drive_c/users/avatar")
+      .join(ROAMING_FOLDER)
+      .join(SOTA_FOLDER);
+    fs::create_dir_all(wine.join(SAVES_FOLDER)).unwrap();
+
+    // The same Steam install listed twice is only probed once.
+    let folders = detect_in(Some(&config_dir), Some(&home), &[steam.clone(), steam]);
+    let sources: Vec<Source> = folders.iter().map(|folder| folder.source).collect();
+    assert_eq!(sources, [Source::Standard, Source::Proton, Source::Wine]);
+
+    assert_eq!(
+      folders[0].log_path(),
+      Some(config_dir.join(SOTA_FOLDER).join(LOGS_FOLDER))
+    );
+    assert_eq!(folders[0].save_game_path(), None);
+    assert_eq!(folders[1].log_path(), Some(proton.join(LOGS_FOLDER)));
+    assert_eq!(folders[1].save_game_path(), Some(proton.join(SAVES_FOLDER)));
+    assert_eq!(folders[2].log_path(), None);
+    assert_eq!(folders[2].save_game_path(), Some(wine.join(SAVES_FOLDER)));
+
+    // Nothing to find.
+    let empty = root.join("empty");
+    assert!(detect_in(Some(&empty), Some(&empty), &[empty.join("steam")]).is_empty());
+  }
+}
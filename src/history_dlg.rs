@@ -0,0 +1,192 @@
+use crate::{
+  theme::Color,
+  util::{self, AppState, Cancel},
+};
+use eframe::{
+  egui::{Button, Context, Key, RichText, Spinner, Window},
+  emath::Align2,
+};
+use egui_plot::{Line, Plot, PlotPoints, Points};
+use num_format::Locale;
+use std::{fs, mem, path::PathBuf};
+
+/// Dialog window that plots a stat over time.
+pub struct HistoryDlg {
+  state: AppState,
+  locale: Locale,
+  avatar: String,
+  stat: String,
+  history: Vec<(i64, f64)>,
+  loading: bool,
+  cancel: Option<Cancel>,
+  csv_request: bool,
+  error: Option<String>,
+  visible: bool,
+}
+
+impl HistoryDlg {
+  pub fn new(state: AppState, locale: Locale) -> Self {
+    Self {
+      state,
+      locale,
+      avatar: String::new(),
+      stat: String::new(),
+      history: Vec::new(),
+      loading: false,
+      cancel: None,
+      csv_request: false,
+      error: None,
+      visible: false,
+    }
+  }
+
+  pub fn show(&mut self, ctx: &Context) {
+    if self.visible {
+      // The CSV file dialog enables the UI when it closes, so keep it disabled while this dialog is showing.
+      self.state.set_disabled(true);
+      self.handle_hotkeys(ctx);
+
+      let mut open = true;
+      let title = format!("📈  {} History", self.stat);
+      Window::new(RichText::from(title).strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          if self.loading {
+            ui.horizontal(|ui| {
+              ui.add(Spinner::new());
+              ui.label("Scanning logs...");
+            });
+          } else if self.history.is_empty() {
+            ui.label(format!("No snapshots with {} were found.", self.stat));
+          } else {
+            let points: Vec<[f64; 2]> = self.history.iter().map(|&(ts, value)| [ts as f64, value]).collect();
+            let locale = self.locale;
+            Plot::new("stat_history_plot")
+              .width(PLOT_WIDTH)
+              .height(PLOT_HEIGHT)
+              .x_axis_formatter(|mark, _| date_text(mark.value, "%Y-%m-%d"))
+              .label_formatter(move |_, point| {
+                let date = date_text(point.x, "%Y-%m-%d %H:%M:%S");
+                format!("{date}\n{}", util::format_f64(point.y, 6, &locale))
+              })
+              .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::from(points.clone())));
+                plot_ui.points(Points::new(PlotPoints::from(points)).radius(3.0));
+              });
+            ui.label(format!("{} snapshots", self.history.len()));
+          }
+
+          if let Some(error) = &self.error {
+            ui.label(RichText::from(error).color(Color::Error.get()));
+          }
+
+          ui.separator();
+          ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+              self.close();
+            }
+
+            let enabled = !self.history.is_empty();
+            if ui.add_enabled(enabled, Button::new("Copy as CSV")).clicked() {
+              util::set_clipboard_contents(self.get_csv());
+            }
+
+            if ui.add_enabled(enabled, Button::new("Save as CSV...")).clicked() {
+              self.error = None;
+              self.csv_request = true;
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+  }
+
+  /// Open the dialog while the history is being scanned. The scan is canceled if the dialog is closed first.
+  pub fn open(&mut self, avatar: &str, stat: &str, cancel: Cancel) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      self.visible = true;
+    }
+
+    avatar.clone_into(&mut self.avatar);
+    stat.clone_into(&mut self.stat);
+    self.history.clear();
+    self.error = None;
+    self.loading = true;
+    self.cancel = Some(cancel);
+  }
+
+  pub fn set_history(&mut self, history: Vec<(i64, f64)>) {
+    self.history = history;
+    self.loading = false;
+    self.cancel = None;
+  }
+
+  pub fn close(&mut self) {
+    if self.visible {
+      if let Some(mut cancel) = self.cancel.take() {
+        // Nothing will be received for the scan, so stop showing the busy cursor too.
+        cancel.cancel();
+        self.loading = false;
+        self.state.set_busy(false);
+      }
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  /// Check if the user requested saving the history as CSV.
+  pub fn csv_request(&mut self) -> bool {
+    mem::take(&mut self.csv_request)
+  }
+
+  /// Default file name for saving the history as CSV.
+  pub fn csv_file_name(&self) -> String {
+    format!("{}_{}.csv", self.avatar, self.stat)
+  }
+
+  /// Save the history to a CSV file.
+  pub fn save_csv(&mut self, path: PathBuf) {
+    // Use the "csv" extension if there isn't one.
+    let path = if path.extension().is_none() {
+      path.with_extension("csv")
+    } else {
+      path
+    };
+
+    if let Err(err) = fs::write(&path, self.get_csv()) {
+      self.error = Some(format!("Unable to save to {path:?}: {err}"));
+    }
+  }
+
+  fn get_csv(&self) -> String {
+    let mut csv = String::from("Date,Value\n");
+    for &(ts, value) in &self.history {
+      csv.push_str(&format!("{},{value}\n", util::timestamp_to_string(Some(ts))));
+    }
+    csv
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
+
+/// Format a plot's X value (a stats timestamp) as a date.
+fn date_text(value: f64, format: &str) -> String {
+  match util::timestamp_to_local(value as i64) {
+    Some(date) => date.format(format).to_string(),
+    None => String::new(),
+  }
+}
+
+const PLOT_WIDTH: f32 = 560.0;
+const PLOT_HEIGHT: f32 = 300.0;
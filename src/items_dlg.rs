@@ -1,23 +1,86 @@
-use crate::{game_data::Item, util};
+use crate::{
+  game_data::{Item, ItemGroup, ItemNode},
+  theme::Color,
+  util,
+};
 use eframe::{
-  egui::{Context, DragValue, Key, Layout, RichText, Window},
+  egui::{Button, ComboBox, Context, DragValue, Key, Label, Layout, RichText, Sense, TextEdit, Ui, Window},
   emath::{Align, Align2},
   epaint::Color32,
 };
 use egui_extras::{Column, TableBuilder};
-use util::AppState;
+use std::{
+  borrow::Cow,
+  cmp::Ordering,
+  collections::{HashMap, HashSet},
+};
+use util::{AppState, Search};
 
 pub struct ItemsDlg {
   state: AppState,
+  group: usize,
+  filter: String,
+  asset: String,
+  error: Option<Cow<'static, str>>,
+  expanded: HashSet<String>,
+  sort: Sort,
+
+  /// Sorted position of each item by ID. Edits don't move the items until they're sorted again.
+  ranks: Option<HashMap<String, usize>>,
   visible: bool,
 }
 
+/// Column that the items are sorted by.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+  Name,
+  Count,
+  Durability,
+}
+
+#[derive(Clone, Copy)]
+struct Sort {
+  column: SortColumn,
+  ascending: bool,
+}
+
+/// Structural change requested from an item row, by group and item index.
+enum Action {
+  Duplicate(usize, usize),
+  Delete(usize, usize),
+}
+
+/// Row in the item tree.
+struct Row {
+  group: usize,
+  index: usize,
+  depth: usize,
+
+  /// Number of items inside and the sum of their counts, for containers.
+  totals: Option<(usize, u64)>,
+  expanded: bool,
+  deletable: bool,
+}
+
 impl ItemsDlg {
   pub fn new(state: AppState) -> Self {
-    Self { state, visible: false }
+    Self {
+      state,
+      group: 0,
+      filter: String::new(),
+      asset: String::new(),
+      error: None,
+      expanded: HashSet::new(),
+      sort: Sort {
+        column: SortColumn::Name,
+        ascending: true,
+      },
+      ranks: None,
+      visible: false,
+    }
   }
 
-  pub fn show(&mut self, items: &mut Vec<Item>, ctx: &Context) -> bool {
+  pub fn show(&mut self, groups: &mut [ItemGroup], ctx: &Context) -> bool {
     let mut modified = false;
     if self.visible {
       self.handle_hotkeys(ctx);
@@ -33,6 +96,87 @@ impl ItemsDlg {
         .anchor(Align2::CENTER_TOP, [0.0, 0.0])
         .default_size(available.size())
         .show(ctx, |ui| {
+          // Container selection. Bags held by another container are shown within that container.
+          let roots: Vec<usize> = (0..groups.len())
+            .filter(|&index| !ItemGroup::is_nested(groups, index))
+            .collect();
+          if !roots.contains(&self.group) {
+            self.group = roots.first().copied().unwrap_or_default();
+          }
+
+          ui.horizontal(|ui| {
+            const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+            ui.label(RichText::from("Container").color(LABEL_COLOR));
+            let text = groups
+              .get(self.group)
+              .map(|group| group.name.as_str())
+              .unwrap_or_default();
+            ComboBox::from_id_salt("container_combo")
+              .selected_text(text)
+              .width(ui.available_width())
+              .show_ui(ui, |ui| {
+                for &index in &roots {
+                  let selected = index == self.group;
+                  if ui.selectable_label(selected, &groups[index].name).clicked() && !selected {
+                    self.group = index;
+                  }
+                }
+              });
+          });
+
+          // Item filter.
+          ui.horizontal(|ui| {
+            const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+            ui.label(RichText::from("Filter").color(LABEL_COLOR));
+            let widget = TextEdit::singleline(&mut self.filter).hint_text("item name");
+            ui.add_sized([ui.available_width(), ui.spacing().interact_size.y], widget);
+          });
+
+          // Add an item.
+          ui.horizontal(|ui| {
+            const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+            ui.label(RichText::from("Asset").color(LABEL_COLOR));
+            let enabled = self.group < groups.len() && !self.asset.trim().is_empty();
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+              if ui.add_enabled(enabled, Button::new("Add")).clicked() {
+                let id = ItemGroup::new_item_id(groups);
+                let group = &mut groups[self.group];
+                match group.add_item(&self.asset, id) {
+                  Ok(()) => {
+                    self.asset.clear();
+                    self.error = None;
+                    self.ranks = None;
+                    modified = true;
+                  }
+                  Err(err) => self.error = Some(err),
+                }
+              }
+
+              let widget =
+                TextEdit::singleline(&mut self.asset).hint_text("asset name (e.g. Items/Reagents/BlackPearl)");
+              ui.add_sized([ui.available_width(), ui.spacing().interact_size.y], widget);
+            });
+          });
+
+          if let Some(error) = &self.error {
+            ui.label(RichText::from(error.as_ref()).color(Color::Error.get()));
+          }
+
+          ui.separator();
+
+          let filter = util::get_name_filter(&self.filter);
+          let sort = self.sort;
+          let ranks = self.ranks.get_or_insert_with(|| get_ranks(groups, sort));
+          let mut tree = ItemGroup::get_item_tree(groups, self.group);
+          sort_nodes(&mut tree, groups, ranks);
+
+          let mut rows = Vec::new();
+          add_rows(&tree, groups, filter.as_ref(), &self.expanded, 0, &mut rows);
+
+          let mut action = None;
+          let mut toggle = None;
+          let mut clicked = None;
+
           // This scope is here to constrain the set_max_height call.
           ui.scope(|ui| {
             ui.set_max_height(available.height() * 0.8);
@@ -42,28 +186,64 @@ impl ItemsDlg {
             TableBuilder::new(ui)
               .cell_layout(Layout::left_to_right(Align::Center))
               .striped(true)
-              .column(Column::exact(available_width * 0.75 - spacing[0]))
-              .column(Column::exact(available_width * 0.125 - spacing[0]))
+              .column(Column::exact(available_width * 0.4 - spacing[0]))
+              .column(Column::exact(available_width * 0.12 - spacing[0]))
+              .column(Column::exact(available_width * 0.12 - spacing[0]))
+              .column(Column::exact(available_width * 0.12 - spacing[0]))
+              .column(Column::exact(available_width * 0.12 - spacing[0]))
               .column(Column::remainder())
               .header(row_size, |mut header| {
                 const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
                 header.col(|ui| {
-                  ui.label(RichText::from("Item Name").color(HEADER_COLOR));
+                  if show_sort_header(ui, "Item Name", SortColumn::Name, sort) {
+                    clicked = Some(SortColumn::Name);
+                  }
+                });
+                header.col(|ui| {
+                  if show_sort_header(ui, "Count", SortColumn::Count, sort) {
+                    clicked = Some(SortColumn::Count);
+                  }
                 });
                 header.col(|ui| {
-                  ui.label(RichText::from("Count").color(HEADER_COLOR));
+                  if show_sort_header(ui, "Durability", SortColumn::Durability, sort) {
+                    clicked = Some(SortColumn::Durability);
+                  }
                 });
+                header.col(|ui| {
+                  ui.label(RichText::from("Primary").color(HEADER_COLOR))
+                    .on_hover_text("Primary (maximum) durability");
+                });
+                header.col(|_| {});
                 header.col(|_| {});
               })
               .body(|mut body| {
                 const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
-                for item in items {
+                let indent = body.ui_mut().spacing().indent;
+                for tree_row in &rows {
+                  let item = &mut groups[tree_row.group].items[tree_row.index];
                   body.row(row_size, |mut row| {
                     row.col(|ui| {
+                      ui.add_space(tree_row.depth as f32 * indent);
+                      if item.is_container() {
+                        // Containers are always expanded while filtering.
+                        let text = if tree_row.expanded { "⏷" } else { "⏵" };
+                        let button = Button::new(text).frame(false);
+                        let enabled = filter.is_none();
+                        if ui.add_enabled(enabled, button).clicked() {
+                          toggle = Some(item.id().to_owned());
+                        }
+                      } else {
+                        ui.add_space(indent);
+                      }
                       ui.label(RichText::from(item.name()).color(NAME_COLOR));
                     });
                     row.col(|ui| {
-                      if !item.is_container() {
+                      if let Some((items, count)) = tree_row.totals {
+                        // Containers show what they hold.
+                        let text = format!("Σ {count}");
+                        ui.label(text)
+                          .on_hover_text(format!("{items} items inside, with a total count of {count}"));
+                      } else {
                         // It's safe to adjust the count (stack size) for all items (except containers) even for
                         // those that are equipped or have durability.
                         let count = item.count_mut();
@@ -75,6 +255,38 @@ impl ItemsDlg {
                         }
                       }
                     });
+                    row.col(|ui| {
+                      if let Some(dur) = item.durability_mut() {
+                        // Keep the current durability from exceeding the primary durability, but don't clamp values
+                        // that are already outside of the range.
+                        let range = 0.0..=dur.major.max(0.0);
+                        let widget = DragValue::new(&mut dur.minor)
+                          .speed(1.0)
+                          .range(range)
+                          .clamp_existing_to_range(false);
+                        if ui.add(widget).changed() {
+                          modified = true;
+                        }
+
+                        if !dur.is_valid() {
+                          let text = "Current durability is greater than the primary durability";
+                          ui.label(RichText::from("⚠").color(Color::Error.get()))
+                            .on_hover_text(text);
+                        }
+                      }
+                    });
+                    row.col(|ui| {
+                      if let Some(dur) = item.durability_mut() {
+                        let range = dur.minor.max(0.0)..=MAX_DURABILITY.max(dur.minor);
+                        let widget = DragValue::new(&mut dur.major)
+                          .speed(1.0)
+                          .range(range)
+                          .clamp_existing_to_range(false);
+                        if ui.add(widget).changed() {
+                          modified = true;
+                        }
+                      }
+                    });
                     row.col(|ui| {
                       if let Some(dur) = item.durability_mut() {
                         if dur.minor == dur.major {
@@ -84,17 +296,66 @@ impl ItemsDlg {
                         if ui.button("Repair").clicked() {
                           // The actual maximum durability is unknown here, so just set the durability to a high
                           // value, it will be adjusted in-game to the actual maximum when the item takes damage.
-                          dur.minor = 5000.0;
-                          dur.major = 5000.0;
+                          dur.minor = REPAIR_DURABILITY;
+                          dur.major = REPAIR_DURABILITY;
                           modified = true;
                         }
                       }
                     });
+                    row.col(|ui| {
+                      let enabled = !item.is_container();
+                      let response = ui.add_enabled(enabled, Button::new("⧉"));
+                      if response.on_hover_text("Duplicate").clicked() {
+                        action = Some(Action::Duplicate(tree_row.group, tree_row.index));
+                      }
+
+                      let response = ui.add_enabled(tree_row.deletable, Button::new("🗑"));
+                      let response = response
+                        .on_hover_text("Delete")
+                        .on_disabled_hover_text("Containers must be empty before they can be deleted");
+                      if response.clicked() {
+                        action = Some(Action::Delete(tree_row.group, tree_row.index));
+                      }
+                    });
                   });
                 }
               });
           });
 
+          if let Some(column) = clicked {
+            // Clicking the sorted column reverses the direction.
+            self.sort = if column == self.sort.column {
+              Sort {
+                column,
+                ascending: !self.sort.ascending,
+              }
+            } else {
+              Sort {
+                column,
+                ascending: true,
+              }
+            };
+            self.ranks = None;
+          }
+
+          if let Some(id) = toggle {
+            if !self.expanded.remove(&id) {
+              self.expanded.insert(id);
+            }
+          }
+
+          if let Some(action) = action {
+            match action {
+              Action::Duplicate(group, index) => {
+                let id = ItemGroup::new_item_id(groups);
+                groups[group].duplicate_item(index, id);
+              }
+              Action::Delete(group, index) => groups[group].remove_item(index),
+            }
+            self.ranks = None;
+            modified = true;
+          }
+
           ui.add_space(ui.spacing().item_spacing.y);
           ui.separator();
           ui.horizontal(|ui| {
@@ -113,6 +374,9 @@ impl ItemsDlg {
   pub fn open(&mut self) {
     if !self.visible {
       self.state.set_disabled(true);
+      self.group = 0;
+      self.error = None;
+      self.ranks = None;
       self.visible = true;
     }
   }
@@ -130,3 +394,111 @@ impl ItemsDlg {
     }
   }
 }
+
+/// Show a column header that can be clicked to sort by the column. Returns true if it was clicked.
+fn show_sort_header(ui: &mut Ui, text: &str, column: SortColumn, sort: Sort) -> bool {
+  const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+  let text = match (column == sort.column, sort.ascending) {
+    (true, true) => format!("{text} ⏶"),
+    (true, false) => format!("{text} ⏷"),
+    (false, _) => text.to_owned(),
+  };
+
+  let label = Label::new(RichText::from(text).color(HEADER_COLOR)).sense(Sense::click());
+  let hover_text = match column {
+    SortColumn::Durability => "Current durability, click to sort",
+    _ => "Click to sort",
+  };
+  ui.add(label).on_hover_text(hover_text).clicked()
+}
+
+/// Get the sorted position of every item. Containers are sorted by the total count of the items inside. Ties are
+/// sorted by name and then by ID so that the order doesn't change between loads.
+fn get_ranks(groups: &[ItemGroup], sort: Sort) -> HashMap<String, usize> {
+  fn add_counts(nodes: &[ItemNode], groups: &[ItemGroup], counts: &mut HashMap<String, u64>) {
+    for node in nodes {
+      let item = &groups[node.group].items[node.index];
+      if item.is_container() {
+        counts.insert(item.id().to_owned(), node.totals(groups).1);
+      }
+      add_counts(&node.children, groups, counts);
+    }
+  }
+
+  let mut counts = HashMap::new();
+  for index in 0..groups.len() {
+    add_counts(&ItemGroup::get_item_tree(groups, index), groups, &mut counts);
+  }
+
+  let count = |item: &Item| counts.get(item.id()).copied().unwrap_or(item.count());
+  let durability = |item: &Item| item.durability().map(|dur| dur.minor);
+  let mut items: Vec<&Item> = groups.iter().flat_map(|group| group.items.iter()).collect();
+  items.sort_by(|a, b| {
+    let ordering = match sort.column {
+      SortColumn::Name => Ordering::Equal,
+      SortColumn::Count => count(a).cmp(&count(b)),
+      SortColumn::Durability => durability(a).partial_cmp(&durability(b)).unwrap_or(Ordering::Equal),
+    };
+    let ordering = ordering.then_with(|| a.name().cmp(b.name()));
+    let ordering = if sort.ascending { ordering } else { ordering.reverse() };
+    ordering.then_with(|| a.id().cmp(b.id()))
+  });
+
+  let ranks = items.into_iter().enumerate();
+  ranks.map(|(rank, item)| (item.id().to_owned(), rank)).collect()
+}
+
+/// Sort the nodes at each level of the tree. Items without a rank (e.g. ones that were just added) go last.
+fn sort_nodes(nodes: &mut [ItemNode], groups: &[ItemGroup], ranks: &HashMap<String, usize>) {
+  let rank = |node: &ItemNode| {
+    let item = &groups[node.group].items[node.index];
+    ranks.get(item.id()).copied().unwrap_or(usize::MAX)
+  };
+
+  nodes.sort_by_key(rank);
+  for node in nodes {
+    sort_nodes(&mut node.children, groups, ranks);
+  }
+}
+
+/// Flatten the item tree into rows. Collapsed containers hide their contents, except while filtering, where containers
+/// are shown if anything inside of them matches.
+fn add_rows(
+  nodes: &[ItemNode],
+  groups: &[ItemGroup],
+  filter: Option<&Search>,
+  expanded: &HashSet<String>,
+  depth: usize,
+  rows: &mut Vec<Row>,
+) {
+  for node in nodes {
+    let item = &groups[node.group].items[node.index];
+    let is_expanded = filter.is_some() || expanded.contains(item.id());
+    let mut children = Vec::new();
+    if is_expanded {
+      add_rows(&node.children, groups, filter, expanded, depth + 1, &mut children);
+    }
+
+    let matched = filter.is_none_or(|filter| filter.find_in(item.name()).is_some());
+    if !matched && children.is_empty() {
+      continue;
+    }
+
+    rows.push(Row {
+      group: node.group,
+      index: node.index,
+      depth,
+      totals: item.is_container().then(|| node.totals(groups)),
+      expanded: is_expanded,
+      // Containers can only be deleted if they're empty.
+      deletable: ItemGroup::find_bag(groups, item).is_none_or(|bag| bag.is_empty()),
+    });
+    rows.extend(children);
+  }
+}
+
+/// Durability used by the "Repair" button.
+const REPAIR_DURABILITY: f64 = 5000.0;
+
+/// Maximum primary durability allowed when editing.
+const MAX_DURABILITY: f64 = 100000.0;
@@ -0,0 +1,301 @@
+use eframe::egui::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Actions that can be triggered with a hotkey.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+  StoreSaveGame,
+  SearchLogs,
+  TallyDps,
+  ReloadStats,
+  Quit,
+  Undo,
+  Redo,
+  EffectiveResists,
+  FilterStats,
+  ClearStatsFilter,
+  NextPage,
+  PreviousPage,
+  ChronometerPage,
+  ExperiencePage,
+  FarmingPage,
+  OfflinePage,
+  StatsPage,
+}
+
+impl Action {
+  /// All the actions, in the order that they're listed.
+  pub const ALL: [Action; 17] = [
+    Action::StoreSaveGame,
+    Action::SearchLogs,
+    Action::TallyDps,
+    Action::ReloadStats,
+    Action::Quit,
+    Action::Undo,
+    Action::Redo,
+    Action::EffectiveResists,
+    Action::FilterStats,
+    Action::ClearStatsFilter,
+    Action::NextPage,
+    Action::PreviousPage,
+    Action::ChronometerPage,
+    Action::ExperiencePage,
+    Action::FarmingPage,
+    Action::OfflinePage,
+    Action::StatsPage,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Action::StoreSaveGame => "Store Save-game",
+      Action::SearchLogs => "Search Logs",
+      Action::TallyDps => "Tally DPS",
+      Action::ReloadStats => "Reload Stats",
+      Action::Quit => "Quit",
+      Action::Undo => "Undo",
+      Action::Redo => "Redo",
+      Action::EffectiveResists => "Effective Resists",
+      Action::FilterStats => "Filter Stats",
+      Action::ClearStatsFilter => "Show All Stats",
+      Action::NextPage => "Next Page",
+      Action::PreviousPage => "Previous Page",
+      Action::ChronometerPage => "Chronometer Page",
+      Action::ExperiencePage => "Experience Page",
+      Action::FarmingPage => "Farming Page",
+      Action::OfflinePage => "Offline Page",
+      Action::StatsPage => "Stats Page",
+    }
+  }
+
+  fn default_hotkey(self) -> Hotkey {
+    match self {
+      Action::StoreSaveGame => Hotkey::command(Key::S),
+      Action::SearchLogs => Hotkey::command(Key::L),
+      Action::TallyDps => Hotkey::command(Key::D),
+      Action::ReloadStats => Hotkey::new(Key::F5, Modifiers::NONE),
+      Action::Quit => Hotkey::command(Key::Q),
+      Action::Undo => Hotkey::command(Key::Z),
+      Action::Redo => Hotkey::new(Key::Z, Modifiers::COMMAND | Modifiers::SHIFT),
+      Action::EffectiveResists => Hotkey::command(Key::R),
+      Action::FilterStats => Hotkey::command(Key::F),
+      Action::ClearStatsFilter => Hotkey::new(Key::Escape, Modifiers::NONE),
+      Action::NextPage => Hotkey::command(Key::Tab),
+      Action::PreviousPage => Hotkey::new(Key::Tab, Modifiers::COMMAND | Modifiers::SHIFT),
+      Action::ChronometerPage => Hotkey::command(Key::Num1),
+      Action::ExperiencePage => Hotkey::command(Key::Num2),
+      Action::FarmingPage => Hotkey::command(Key::Num3),
+      Action::OfflinePage => Hotkey::command(Key::Num4),
+      Action::StatsPage => Hotkey::command(Key::Num5),
+    }
+  }
+}
+
+/// Key and modifiers that trigger an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "HotkeyDef", into = "HotkeyDef")]
+pub struct Hotkey {
+  pub key: Key,
+  pub command: bool,
+  pub alt: bool,
+  pub shift: bool,
+}
+
+impl Hotkey {
+  pub fn new(key: Key, modifiers: Modifiers) -> Self {
+    Self {
+      key,
+      command: modifiers.command,
+      alt: modifiers.alt,
+      shift: modifiers.shift,
+    }
+  }
+
+  fn command(key: Key) -> Self {
+    Self::new(key, Modifiers::COMMAND)
+  }
+
+  /// Check if a key press triggers this hotkey.
+  pub fn matches(&self, key: Key, modifiers: &Modifiers) -> bool {
+    key == self.key && modifiers.command == self.command && modifiers.alt == self.alt && modifiers.shift == self.shift
+  }
+
+  /// Text shown as the menu shortcut.
+  pub fn text(&self) -> String {
+    let mut text = String::new();
+    if self.command {
+      text.push_str(COMMAND_TEXT);
+    }
+    if self.alt {
+      text.push_str("Alt + ");
+    }
+    if self.shift {
+      text.push_str("Shift + ");
+    }
+    text.push_str(self.key.name());
+    text
+  }
+}
+
+/// Serialized form of `Hotkey`.
+#[derive(Serialize, Deserialize)]
+struct HotkeyDef {
+  key: String,
+  command: bool,
+  alt: bool,
+  shift: bool,
+}
+
+impl TryFrom<HotkeyDef> for Hotkey {
+  type Error = String;
+
+  fn try_from(def: HotkeyDef) -> Result<Self, Self::Error> {
+    let Some(key) = Key::from_name(&def.key) else {
+      return Err(format!("Unknown key: {}", def.key));
+    };
+
+    Ok(Self {
+      key,
+      command: def.command,
+      alt: def.alt,
+      shift: def.shift,
+    })
+  }
+}
+
+impl From<Hotkey> for HotkeyDef {
+  fn from(hotkey: Hotkey) -> Self {
+    Self {
+      key: hotkey.key.name().to_owned(),
+      command: hotkey.command,
+      alt: hotkey.alt,
+      shift: hotkey.shift,
+    }
+  }
+}
+
+/// Hotkey bindings. Actions that are unbound have no hotkey.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+  bindings: BTreeMap<Action, Option<Hotkey>>,
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    let bindings = Action::ALL
+      .into_iter()
+      .map(|action| (action, Some(action.default_hotkey())))
+      .collect();
+    Self { bindings }
+  }
+}
+
+impl Keymap {
+  /// Create a keymap from stored bindings. Actions that aren't in `bindings` use their default hotkey.
+  pub fn with_bindings(bindings: BTreeMap<Action, Option<Hotkey>>) -> Self {
+    let mut keymap = Self::default();
+    keymap.bindings.extend(bindings);
+    keymap
+  }
+
+  pub fn bindings(&self) -> &BTreeMap<Action, Option<Hotkey>> {
+    &self.bindings
+  }
+
+  pub fn get(&self, action: Action) -> Option<Hotkey> {
+    self.bindings.get(&action).copied().flatten()
+  }
+
+  /// Bind a hotkey to an action. Any other action using the same hotkey is unbound.
+  pub fn set(&mut self, action: Action, hotkey: Option<Hotkey>) {
+    if let Some(hotkey) = hotkey {
+      for binding in self.bindings.values_mut() {
+        if *binding == Some(hotkey) {
+          *binding = None;
+        }
+      }
+    }
+    self.bindings.insert(action, hotkey);
+  }
+
+  /// Menu shortcut text for an action.
+  pub fn text(&self, action: Action) -> Option<String> {
+    self.get(action).map(|hotkey| hotkey.text())
+  }
+
+  /// Find the action triggered by a key press.
+  pub fn find(&self, key: Key, modifiers: &Modifiers) -> Option<Action> {
+    let mut bindings = self.bindings.iter();
+    bindings.find_map(|(action, hotkey)| hotkey.filter(|hotkey| hotkey.matches(key, modifiers)).map(|_| *action))
+  }
+}
+
+#[cfg(target_os = "macos")]
+const COMMAND_TEXT: &str = "⌘ + ";
+
+#[cfg(not(target_os = "macos"))]
+const COMMAND_TEXT: &str = "Ctrl + ";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_keymap() {
+    let mut keymap = Keymap::default();
+    assert_eq!(keymap.find(Key::Q, &Modifiers::COMMAND), Some(Action::Quit));
+    assert_eq!(keymap.find(Key::Z, &Modifiers::COMMAND), Some(Action::Undo));
+    assert_eq!(
+      keymap.find(Key::Z, &(Modifiers::COMMAND | Modifiers::SHIFT)),
+      Some(Action::Redo)
+    );
+    assert_eq!(keymap.find(Key::Q, &Modifiers::NONE), None);
+    assert_eq!(
+      keymap.find(Key::Tab, &(Modifiers::COMMAND | Modifiers::SHIFT)),
+      Some(Action::PreviousPage)
+    );
+    assert_eq!(keymap.find(Key::Num3, &Modifiers::COMMAND), Some(Action::FarmingPage));
+    assert_eq!(
+      keymap.find(Key::Escape, &Modifiers::NONE),
+      Some(Action::ClearStatsFilter)
+    );
+
+    // Every action has its own default hotkey.
+    let hotkeys: std::collections::HashSet<_> = Action::ALL.iter().map(|action| keymap.text(*action)).collect();
+    assert_eq!(hotkeys.len(), Action::ALL.len());
+
+    // Unbound actions have no hotkey.
+    keymap.set(Action::Quit, None);
+    assert_eq!(keymap.find(Key::Q, &Modifiers::COMMAND), None);
+    assert_eq!(keymap.text(Action::Quit), None);
+
+    // Taking another action's hotkey unbinds it.
+    keymap.set(Action::Quit, Some(Hotkey::command(Key::S)));
+    assert_eq!(keymap.find(Key::S, &Modifiers::COMMAND), Some(Action::Quit));
+    assert_eq!(keymap.get(Action::StoreSaveGame), None);
+    assert_eq!(keymap.text(Action::Quit), Some(format!("{COMMAND_TEXT}S")));
+  }
+
+  #[test]
+  fn test_keymap_round_trip() {
+    let mut keymap = Keymap::default();
+    keymap.set(Action::Quit, None);
+    keymap.set(Action::ReloadStats, Some(Hotkey::new(Key::F6, Modifiers::ALT)));
+
+    let text = ron::to_string(keymap.bindings()).unwrap();
+    let mut bindings: BTreeMap<Action, Option<Hotkey>> = ron::from_str(&text).unwrap();
+    assert_eq!(Keymap::with_bindings(bindings.clone()), keymap);
+
+    // Missing actions use the default.
+    bindings.remove(&Action::FilterStats);
+    let loaded = Keymap::with_bindings(bindings);
+    assert_eq!(
+      loaded.get(Action::FilterStats),
+      Some(Action::FilterStats.default_hotkey())
+    );
+
+    // Unknown keys fail to parse.
+    let text = text.replace("\"F6\"", "\"Bogus\"");
+    assert!(ron::from_str::<BTreeMap<Action, Option<Hotkey>>>(&text).is_err());
+  }
+}
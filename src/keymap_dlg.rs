@@ -0,0 +1,156 @@
+use crate::{
+  keymap::{Action, Hotkey, Keymap},
+  util::{self, AppState},
+};
+use eframe::{
+  egui::{Button, Context, Event, Key, Layout, RichText, Window},
+  emath::{Align, Align2},
+  epaint::Color32,
+};
+use egui_extras::{Column, TableBuilder};
+
+pub struct KeymapDlg {
+  state: AppState,
+  capture: Option<Action>,
+  visible: bool,
+}
+
+impl KeymapDlg {
+  pub fn new(state: AppState) -> Self {
+    Self {
+      state,
+      capture: None,
+      visible: false,
+    }
+  }
+
+  pub fn show(&mut self, keymap: &mut Keymap, ctx: &Context) -> bool {
+    let mut modified = false;
+    if self.visible {
+      if let Some(action) = self.capture {
+        // Wait for the new hotkey.
+        if let Some((key, modifiers)) = ctx.input(|state| {
+          state.events.iter().find_map(|event| match event {
+            Event::Key {
+              key,
+              pressed: true,
+              repeat: false,
+              modifiers,
+              ..
+            } => Some((*key, *modifiers)),
+            _ => None,
+          })
+        }) {
+          // Escape cancels.
+          if key != Key::Escape {
+            keymap.set(action, Some(Hotkey::new(key, modifiers)));
+            modified = true;
+          }
+          self.capture = None;
+        }
+      } else {
+        self.handle_hotkeys(ctx);
+      }
+
+      let mut open = true;
+      Window::new(RichText::from("⌨  Hotkeys").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          let spacing = ui.spacing().item_spacing;
+          let row_size = util::button_size(ui) + spacing[1] * 2.0;
+          TableBuilder::new(ui)
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .striped(true)
+            .column(Column::exact(140.0))
+            .column(Column::exact(140.0))
+            .column(Column::exact(50.0))
+            .header(row_size, |mut header| {
+              const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+              header.col(|ui| {
+                ui.label(RichText::from("Action").color(HEADER_COLOR));
+              });
+              header.col(|ui| {
+                ui.label(RichText::from("Hotkey").color(HEADER_COLOR));
+              });
+              header.col(|_| {});
+            })
+            .body(|mut body| {
+              const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+              for action in Action::ALL {
+                body.row(row_size, |mut row| {
+                  row.col(|ui| {
+                    ui.label(RichText::from(action.name()).color(NAME_COLOR));
+                  });
+                  row.col(|ui| {
+                    let text = if self.capture == Some(action) {
+                      String::from("Press a key...")
+                    } else {
+                      keymap.text(action).unwrap_or_else(|| String::from("None"))
+                    };
+                    let response = ui.add(Button::new(text).selected(self.capture == Some(action)));
+                    if response.on_hover_text("Click to set a new hotkey").clicked() {
+                      self.capture = Some(action);
+                    }
+                  });
+                  row.col(|ui| {
+                    let enabled = keymap.get(action).is_some();
+                    let response = ui.add_enabled(enabled, Button::new("🗑"));
+                    if response.on_hover_text("Remove the hotkey").clicked() {
+                      keymap.set(action, None);
+                      self.capture = None;
+                      modified = true;
+                    }
+                  });
+                });
+              }
+            });
+
+          ui.add_space(ui.spacing().item_spacing.y);
+          ui.label("Assigning a hotkey that's already in use removes it from the other action.");
+          ui.separator();
+          ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+              self.close();
+            }
+
+            let enabled = *keymap != Keymap::default();
+            if ui.add_enabled(enabled, Button::new("Reset")).clicked() {
+              *keymap = Keymap::default();
+              self.capture = None;
+              modified = true;
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+    modified
+  }
+
+  pub fn open(&mut self) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      self.capture = None;
+      self.visible = true;
+    }
+  }
+
+  pub fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.capture = None;
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
@@ -1,14 +1,25 @@
-use crate::util;
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use crate::{
+  stats_cache::{FileStamp, StatsCache},
+  util,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use flate2::read::GzDecoder;
 use futures::{channel::mpsc, executor::ThreadPool, future, StreamExt};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
-  collections::HashSet,
-  fs,
+  collections::{hash_map::Entry, HashMap, HashSet},
+  ffi::OsStr,
+  fs::{self, File},
+  future::Future,
+  io::{self, Read, Seek, SeekFrom},
+  iter::Peekable,
   path::{Path, PathBuf},
   str::SplitWhitespace,
+  time::SystemTime,
 };
-use util::{Cancel, Search};
+use util::{AppState, Cancel, ProgressTracker, Search};
+use zip::ZipArchive;
 
 /// Get the date portion of a log entry.
 pub fn get_log_date(line: &str) -> Option<&str> {
@@ -16,7 +27,11 @@ pub fn get_log_date(line: &str) -> Option<&str> {
     return None;
   }
 
+  // A partially written date/time isn't closed. The bracket must also close this date/time rather than a later one.
   let pos = line.find(']')?;
+  if line[1..pos].contains('[') {
+    return None;
+  }
   Some(&line[0..=pos])
 }
 
@@ -37,13 +52,16 @@ pub fn get_log_text(line: &str) -> &str {
 }
 
 pub struct StatsIter<'a> {
-  iter: SplitWhitespace<'a>,
+  iter: Peekable<SplitWhitespace<'a>>,
+  decimal: Option<char>,
 }
 
 impl StatsIter<'_> {
-  fn new(text: &str) -> StatsIter {
+  fn new(text: &str) -> StatsIter<'_> {
     StatsIter {
-      iter: text.split_whitespace(),
+      iter: text.split_whitespace().peekable(),
+      // Lone separators (e.g. "1,234") are ambiguous, so use the other values to tell which one is the decimal.
+      decimal: util::find_decimal_separator(text.split_whitespace()),
     }
   }
 }
@@ -54,11 +72,32 @@ impl<'a> Iterator for StatsIter<'a> {
   fn next(&mut self) -> Option<Self::Item> {
     // We're expecting "name: value" pairs.
     let name = self.iter.next()?.strip_suffix(':')?;
-    let value = util::replace_decimal(self.iter.next()?).parse().ok()?;
+    let mut value = String::from(self.iter.next()?);
+
+    // Digits grouped with spaces (including non-breaking spaces) are split into separate words.
+    while let Some(group) = self.iter.next_if(|word| is_digit_group(&value, word)) {
+      value.push(' ');
+      value.push_str(group);
+    }
+
+    let value = util::parse_decimal(&value, self.decimal)?;
     Some((name, value))
   }
 }
 
+/// Check if a word continues a number whose digits are grouped with spaces (e.g. "234,5" after "1").
+fn is_digit_group(number: &str, word: &str) -> bool {
+  let whole = number.trim_start_matches('-');
+  if whole.is_empty() || !whole.chars().all(|ch| ch.is_ascii_digit() || ch == ' ') {
+    return false;
+  }
+
+  let digits = word.chars().take_while(|ch| ch.is_ascii_digit()).count();
+  let rest = &word[digits..];
+  digits == 3
+    && (rest.is_empty() || rest.starts_with([',', '.']) && util::parse_decimal(rest, rest.chars().next()).is_some())
+}
+
 #[derive(Default)]
 pub struct StatsData {
   text: String,
@@ -81,66 +120,120 @@ impl StatsData {
 const FILENAME_START: &str = "SotAChatLog";
 const STATS_KEY: &str = " AdventurerLevel: ";
 const ADV_EXP_KEY: &str = " Adventurer Experience: ";
+const PRD_EXP_KEY: &str = " Producer Experience: ";
+const EXP_GAIN_KEYS: [&str; 2] = ["You gained ", "You gain "];
 const LOG_SEARCH_LIMIT: usize = 256 * 1024;
+const WITH_KEY: &str = " with ";
+
+/// Name used for attacks that don't specify an attack name.
+const AUTO_ATTACK: &str = "(auto-attack)";
+
+/// Name for a pet that's shown as "<Avatar>" without a name.
+const UNNAMED_PET: &str = "(pet)";
 
-/// Get a vector of avatar names from the log file names.
-pub async fn get_avatars(log_path: PathBuf, cancel: Cancel) -> Vec<String> {
-  let filenames = get_log_filenames(&log_path, None, None);
-  let mut name_set = HashSet::new();
+/// Separates the avatar name from the rest of the line in search results for all avatars.
+pub const AVATAR_PREFIX_END: &str = ": ";
 
-  for filename in &filenames {
+/// Get a vector of avatar names from the log file names, along with the path of the first log folder that can't be
+/// read, which is distinct from a folder that doesn't have any chat logs. Folders that can't be read are skipped.
+pub async fn get_avatars(log_paths: Vec<PathBuf>, cancel: Cancel) -> (Vec<AvatarLogs>, Option<PathBuf>) {
+  let inaccessible = find_inaccessible_log_path(&log_paths).cloned();
+  let paths = get_log_filenames(&log_paths, None, None);
+  let mut modified_map: HashMap<&str, Option<SystemTime>> = HashMap::new();
+
+  for path in &paths {
     if cancel.is_canceled() {
-      return Vec::new();
+      return (Vec::new(), inaccessible);
     }
 
-    let filename = &filename[FILENAME_START.len() + 1..];
-    if let Some(pos) = filename.rfind('_') {
-      name_set.insert(&filename[..pos]);
+    if let Some(name) = get_log_file_avatar(get_file_name(path)) {
+      // Keep the newest modification time. Files that can't be read (e.g. removed since they were listed) don't count.
+      let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+      let entry = modified_map.entry(name).or_default();
+      *entry = (*entry).max(modified);
     }
   }
 
-  let mut avatars = Vec::with_capacity(name_set.len());
-  for name in name_set {
+  let mut avatars = Vec::with_capacity(modified_map.len());
+  for (name, modified) in modified_map {
     if cancel.is_canceled() {
-      return Vec::new();
+      return (Vec::new(), inaccessible);
     }
 
-    avatars.push(String::from(name));
+    avatars.push(AvatarLogs {
+      name: String::from(name),
+      modified,
+    });
   }
 
   if cancel.is_canceled() {
-    return Vec::new();
+    return (Vec::new(), inaccessible);
   }
 
   // Sort the avatars.
-  avatars.sort_unstable();
-  avatars
+  avatars.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+  (avatars, inaccessible)
+}
+
+/// Avatar that has logs.
+#[derive(Debug, PartialEq)]
+pub struct AvatarLogs {
+  pub name: String,
+
+  /// Modification time of the avatar's newest log file.
+  pub modified: Option<SystemTime>,
+}
+
+/// Find the avatar whose logs were modified most recently. Ties go to the avatar that's first in the list.
+pub fn find_active_avatar(avatars: &[AvatarLogs]) -> Option<&str> {
+  let mut active: Option<&AvatarLogs> = None;
+  for avatar in avatars {
+    if avatar.modified.is_some() && active.is_none_or(|active| avatar.modified > active.modified) {
+      active = Some(avatar);
+    }
+  }
+  active.map(|avatar| avatar.name.as_str())
 }
 
-/// Get a vector of timestamps where `/stats` was used for the specified avatar.
+/// Get a vector of timestamps where `/stats` was used for the specified avatar. Files that are unchanged since they
+/// were added to `cache` aren't parsed again.
 pub async fn get_stats_timestamps(
-  log_path: PathBuf,
+  log_paths: Vec<PathBuf>,
   avatar: String,
   cancel: Cancel,
   threads: Option<ThreadPool>,
+  cache: Option<StatsCache>,
+  state: AppState,
 ) -> Vec<i64> {
+  let paths = get_log_filenames(&log_paths, Some(&avatar), None);
+  let progress = state.begin_progress("Scanning logs", paths.len());
+
   // Collect the futures, one for each matching log file.
   let futures = {
-    let filenames = get_log_filenames(&log_path, Some(&avatar), None);
-    let mut futures = Vec::with_capacity(filenames.len());
+    let mut futures = Vec::with_capacity(paths.len());
 
-    for filename in filenames {
+    for path in paths {
       if cancel.is_canceled() {
         return Vec::new();
       }
 
-      let path = log_path.join(filename.as_str());
       let cancel = cancel.clone();
+      let mut cache = cache.clone();
       futures.push(async move {
         let Some(date) = get_log_file_date(&path) else {
           return Vec::new();
         };
-        let text = ok!(fs::read_to_string(&path), Vec::new());
+
+        // Get the stamp before reading so that a file that grows while it's being read is parsed again next time.
+        let stamp = FileStamp::new(&path);
+        if let (Some(cache), Some(stamp)) = (&cache, &stamp) {
+          if let Some(timestamps) = cache.get(&path, stamp) {
+            return timestamps;
+          }
+        }
+
+        let text = ok!(read_log_text(&path), Vec::new());
+        let (text, _) = split_partial_line(&text);
         let mut timestamps = Vec::new();
 
         for line in text.lines() {
@@ -153,6 +246,10 @@ pub async fn get_stats_timestamps(
           }
         }
 
+        if let (Some(cache), Some(stamp)) = (&mut cache, stamp) {
+          cache.set(&path, stamp, timestamps.clone());
+        }
+
         timestamps
       });
     }
@@ -160,7 +257,101 @@ pub async fn get_stats_timestamps(
     futures
   };
 
-  let results = if let Some(threads) = threads {
+  let results = run_all(futures, threads, &progress).await;
+  if let Some(cache) = &cache {
+    cache.persist();
+  }
+
+  if cancel.is_canceled() {
+    return Vec::new();
+  }
+
+  // Flatten the results.
+  let mut timestamps: Vec<i64> = results.into_iter().flat_map(|v| v.into_iter()).collect();
+
+  // Sort the timestamps so that the most recent is first.
+  timestamps.sort_unstable_by(|a, b| b.cmp(a));
+  timestamps
+}
+
+/// Get the value of a stat from each of the avatar's `/stats` snapshots, oldest first. Snapshots that don't have the
+/// stat are skipped.
+pub async fn get_stat_history(
+  log_paths: Vec<PathBuf>,
+  avatar: String,
+  name: String,
+  cancel: Cancel,
+  threads: Option<ThreadPool>,
+  state: AppState,
+) -> Vec<(i64, f64)> {
+  let paths = get_log_filenames(&log_paths, Some(&avatar), None);
+  let progress = state.begin_progress("Scanning logs", paths.len());
+  let mut futures = Vec::with_capacity(paths.len());
+  for path in paths {
+    if cancel.is_canceled() {
+      return Vec::new();
+    }
+
+    let cancel = cancel.clone();
+    let name = name.clone();
+    futures.push(async move {
+      let Some(date) = get_log_file_date(&path) else {
+        return Vec::new();
+      };
+      let text = ok!(read_log_text(&path), Vec::new());
+      let (text, _) = split_partial_line(&text);
+      let find = |text| {
+        StatsIter::new(text)
+          .find(|(stat, _)| *stat == name)
+          .map(|(_, value)| value)
+      };
+
+      // Stats can continue on lines that don't start with a square bracket.
+      let mut history = Vec::new();
+      let mut current: Option<(i64, Option<f64>)> = None;
+      for line in text.lines() {
+        if cancel.is_canceled() {
+          return Vec::new();
+        }
+
+        if line.starts_with('[') {
+          if let Some((ts, Some(value))) = current.take() {
+            history.push((ts, value));
+          }
+
+          if let Some(ts) = get_stats_timestamp(line, date) {
+            current = Some((ts, find(get_log_text(line))));
+          }
+        } else if let Some((_, value @ None)) = &mut current {
+          *value = find(line);
+        }
+      }
+
+      if let Some((ts, Some(value))) = current {
+        history.push((ts, value));
+      }
+
+      history
+    });
+  }
+
+  let results = run_all(futures, threads, &progress).await;
+  if cancel.is_canceled() {
+    return Vec::new();
+  }
+
+  let mut history: Vec<(i64, f64)> = results.into_iter().flatten().collect();
+  history.sort_unstable_by_key(|(ts, _)| *ts);
+  history
+}
+
+/// Run the futures, each on a pooled thread if `threads` is specified, stepping the progress as each one finishes.
+async fn run_all<T, F>(futures: Vec<F>, threads: Option<ThreadPool>, progress: &ProgressTracker) -> Vec<T>
+where
+  T: Send + 'static,
+  F: Future<Output = T> + Send + 'static,
+{
+  if let Some(threads) = threads {
     // Process each future on a pooled thread.
     let (tx, rx) = mpsc::unbounded();
     for future in futures {
@@ -171,34 +362,29 @@ pub async fn get_stats_timestamps(
       });
     }
     drop(tx);
-    rx.collect().await
+    rx.inspect(|_| progress.step()).collect().await
   } else {
     // Collect the results directly.
+    let futures = futures.into_iter().map(|future| async move {
+      let result = future.await;
+      progress.step();
+      result
+    });
     future::join_all(futures).await
-  };
-
-  if cancel.is_canceled() {
-    return Vec::new();
   }
-
-  // Flatten the results.
-  let mut timestamps: Vec<i64> = results.into_iter().flat_map(|v| v.into_iter()).collect();
-
-  // Sort the timestamps so that the most recent is first.
-  timestamps.sort_unstable_by(|a, b| b.cmp(a));
-  timestamps
 }
 
 /// Get the stats for the specified avatar and timestamp.
-pub async fn get_stats(log_path: PathBuf, avatar: String, ts: i64, cancel: Cancel) -> StatsData {
+pub async fn get_stats(log_paths: Vec<PathBuf>, avatar: String, ts: i64, cancel: Cancel) -> StatsData {
   if !avatar.is_empty() {
-    let filenames = get_log_filenames(&log_path, Some(&avatar), Some(ts));
+    let paths = get_log_filenames(&log_paths, Some(&avatar), Some(ts));
 
     // There will actually only be one file with the specific avatar name and date.
-    for filename in filenames {
-      let path = log_path.join(filename.as_str());
+    for path in paths {
       if let Some(date) = get_log_file_date(&path) {
-        if let Ok(text) = fs::read_to_string(path) {
+        if let Ok(text) = read_log_text(&path) {
+          let (text, partial) = split_partial_line(&text);
+
           // Find the line with the specific date/time.
           for line in text.lines() {
             if cancel.is_canceled() {
@@ -206,14 +392,21 @@ pub async fn get_stats(log_path: PathBuf, avatar: String, ts: i64, cancel: Cance
             }
 
             if let Some(mut stats) = get_stats_text(line, ts, date) {
-              // Include subsequent lines that do not start with a square bracket.
-              let pos = util::offset(&text, stats).unwrap();
+              // Include subsequent lines that do not start with a square bracket. The stats are still being written if
+              // they run into a partially written line that continues them.
+              let mut truncated = !partial.is_empty() && !partial.starts_with('[');
+              let pos = util::offset(text, stats).unwrap();
               let sub = &text[pos + stats.len()..];
               for line in sub.lines() {
                 if line.starts_with('[') {
+                  truncated = false;
                   break;
                 }
-                stats = &text[pos..util::offset(&text, line).unwrap()];
+                stats = &text[pos..util::offset(text, line).unwrap()];
+              }
+
+              if truncated {
+                return StatsData::default();
               }
 
               return StatsData::new(stats.into());
@@ -227,57 +420,123 @@ pub async fn get_stats(log_path: PathBuf, avatar: String, ts: i64, cancel: Cance
   StatsData::default()
 }
 
-/// Get the latest adventurer experience from `/xp`.
-pub async fn get_adv_exp(log_path: PathBuf, avatar: String, cancel: Cancel) -> Option<i64> {
+/// Adventurer and producer experience from `/xp`.
+#[derive(Clone, Copy, Default)]
+pub struct AvatarExp {
+  pub adv: Option<i64>,
+  pub prd: Option<i64>,
+
+  /// Timestamp of the most recent `/xp` entry.
+  pub ts: Option<i64>,
+}
+
+/// Get the latest adventurer and producer experience from `/xp`.
+pub async fn get_exp(log_paths: Vec<PathBuf>, avatar: String, cancel: Cancel) -> AvatarExp {
   // Work on files from newest to oldest.
-  let filenames = {
-    let mut filenames = get_log_filenames(&log_path, Some(&avatar), None);
-    filenames.sort_unstable_by(|a, b| b.cmp(a));
-    filenames
+  let paths = {
+    let mut paths = get_log_filenames(&log_paths, Some(&avatar), None);
+    paths.sort_unstable_by(|a, b| get_file_name(b).cmp(get_file_name(a)));
+    paths
   };
 
-  for filename in filenames {
+  let mut exp = AvatarExp::default();
+  for path in paths {
     if cancel.is_canceled() {
       break;
     }
 
-    let path = log_path.join(filename);
-    if let Ok(text) = fs::read_to_string(path) {
+    if let Ok(text) = read_log_text(&path) {
       if text.is_empty() {
         continue;
       }
 
       // Search from the latest entry.
+      let (text, _) = split_partial_line(&text);
       for line in text.lines().rev() {
-        let exp = get_adv_xp(line);
-        if exp.is_some() {
+        if exp.adv.is_none() {
+          exp.adv = get_xp(line, ADV_EXP_KEY);
+        }
+        if exp.prd.is_none() {
+          exp.prd = get_xp(line, PRD_EXP_KEY);
+        }
+        if exp.ts.is_none() && (exp.adv.is_some() || exp.prd.is_some()) {
+          exp.ts = get_log_file_date(&path).and_then(|date| get_log_timestamp(line, date));
+        }
+        if exp.adv.is_some() && exp.prd.is_some() {
           return exp;
         }
       }
     }
   }
 
-  None
+  exp
 }
 
-/// Find log entries matching the provided search term.
-pub async fn find_log_entries(log_path: PathBuf, avatar: String, search: Search, cancel: Cancel) -> String {
+/// Get the latest adventurer and producer experience for each of the avatars, sorted by name. Each avatar is searched
+/// on a pooled thread if `threads` is specified.
+pub async fn get_avatars_exp(
+  log_paths: Vec<PathBuf>,
+  avatars: Vec<String>,
+  cancel: Cancel,
+  threads: Option<ThreadPool>,
+  state: AppState,
+) -> Vec<(String, AvatarExp)> {
+  let progress = state.begin_progress("Scanning logs", avatars.len());
+  let futures = avatars
+    .into_iter()
+    .map(|avatar| {
+      let future = get_exp(log_paths.clone(), avatar.clone(), cancel.clone());
+      async move { (avatar, future.await) }
+    })
+    .collect();
+
+  let mut results = run_all(futures, threads, &progress).await;
+  if cancel.is_canceled() {
+    return Vec::new();
+  }
+
+  results.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+  results
+}
+
+/// Find log entries matching the search term. If `avatar` is `None` then the logs for all avatars are searched and
+/// each line is prefixed with the avatar's name. The matching lines are passed to `send` as each file is processed,
+/// starting with the newest file.
+pub async fn find_log_entries<F: FnMut(String)>(
+  log_paths: Vec<PathBuf>,
+  avatar: Option<String>,
+  search: Search,
+  range: DateRange,
+  cancel: Cancel,
+  state: AppState,
+  mut send: F,
+) {
   // Work on files from newest to oldest.
-  let filenames = {
-    let mut filenames = get_log_filenames(&log_path, Some(&avatar), None);
-    filenames.sort_unstable_by(|a, b| b.cmp(a));
-    filenames
-  };
+  let paths = get_sorted_log_filenames(&log_paths, avatar.as_deref(), range);
+  let progress = state.begin_progress("Searching logs", paths.len());
 
-  let mut results = Vec::new();
   let mut total_size: usize = 0;
-  for filename in filenames {
+  for (done, path) in paths.into_iter().enumerate() {
     if cancel.is_canceled() {
-      return String::new();
+      return;
     }
 
-    let path = log_path.join(filename);
-    if let Ok(text) = fs::read_to_string(path) {
+    progress.set_done(done);
+
+    // Prefix the lines with the avatar name if searching all avatars.
+    let prefix = match avatar {
+      Some(_) => String::new(),
+      None => match get_log_file_avatar(get_file_name(&path)) {
+        Some(name) => format!("{name}{AVATAR_PREFIX_END}"),
+        None => continue,
+      },
+    };
+
+    let Some(file_date) = get_log_file_date(&path) else {
+      continue;
+    };
+
+    if let Ok(text) = read_log_text(&path) {
       if text.is_empty() || !verify_log_text(&text) {
         continue;
       }
@@ -288,13 +547,22 @@ pub async fn find_log_entries(log_path: PathBuf, avatar: String, search: Search,
       // Iterate through the lines in reverse order (newest to oldest).
       for line in text.lines().rev() {
         if cancel.is_canceled() {
-          return String::new();
+          return;
         }
 
         if search.find_in(line).is_none() {
           continue;
         }
 
+        // Skip entries that are outside of the date range.
+        if !range.is_empty() {
+          if let Some(ts) = get_log_timestamp(line, file_date) {
+            if !range.contains_timestamp(ts) {
+              continue;
+            }
+          }
+        }
+
         // Filter out superfluous chat timestamp.
         let (date, text) = if let Some(date) = get_log_date(line) {
           (date, get_log_text(line))
@@ -304,6 +572,7 @@ pub async fn find_log_entries(log_path: PathBuf, avatar: String, search: Search,
 
         let size = date.len() + text.len();
         if size > 0 {
+          let size = size + prefix.len();
           // Account for a newline.
           let size = size + 1;
           alloc_size += size;
@@ -316,49 +585,116 @@ pub async fn find_log_entries(log_path: PathBuf, avatar: String, search: Search,
         }
       }
 
+      if lines.is_empty() {
+        continue;
+      }
+
       // Push all the matching lines to a new string.
       let mut concatenated = String::with_capacity(alloc_size);
       for (date, text) in lines {
         if cancel.is_canceled() {
-          return String::new();
+          return;
         }
 
+        concatenated.push_str(&prefix);
         concatenated.push_str(date);
         concatenated.push_str(text);
         concatenated.push('\n');
       }
-      results.push(concatenated);
+      send(concatenated);
     }
 
+    // Stop searching once the limit is reached.
     if total_size >= LOG_SEARCH_LIMIT {
       break;
     }
   }
+}
 
-  // Concatenate the results.
-  let mut text = String::with_capacity(total_size);
-  for result in results {
-    if cancel.is_canceled() {
-      return String::new();
+/// Optional date range for limiting log searches. The dates are inclusive.
+#[derive(Clone, Copy, Default)]
+pub struct DateRange {
+  pub begin: Option<NaiveDate>,
+  pub end: Option<NaiveDate>,
+}
+
+impl DateRange {
+  pub fn is_empty(&self) -> bool {
+    self.begin.is_none() && self.end.is_none()
+  }
+
+  pub fn contains(&self, date: NaiveDate) -> bool {
+    if let Some(begin) = self.begin {
+      if date < begin {
+        return false;
+      }
+    }
+
+    if let Some(end) = self.end {
+      if date > end {
+        return false;
+      }
     }
 
-    text.push_str(&result);
+    true
   }
 
-  text
+  fn contains_timestamp(&self, ts: i64) -> bool {
+    match util::timestamp_to_local(ts) {
+      Some(date_time) => self.contains(date_time.date()),
+      None => false,
+    }
+  }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Span {
   pub begin: NaiveDateTime,
   pub end: NaiveDateTime,
 }
 
+/// Damage dealt by the avatar with a single attack, or by a single pet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackTally {
+  pub name: String,
+  pub damage: u64,
+  pub hits: u64,
+  pub max_hit: u64,
+}
+
+impl AttackTally {
+  fn new(name: &str) -> Self {
+    Self {
+      name: name.to_owned(),
+      damage: 0,
+      hits: 0,
+      max_hit: 0,
+    }
+  }
+
+  fn add_hit(&mut self, damage: u64) {
+    self.damage += damage;
+    self.hits += 1;
+    self.max_hit = self.max_hit.max(damage);
+  }
+}
+
 pub struct DPSTally {
   pub span: Span,
   pub avatar: u64,
   pub pet: u64,
   pub secs: u64,
+  pub avatar_taken: u64,
+  pub pet_taken: u64,
+  pub healing_done: u64,
+  pub healing_received: u64,
+  pub combat_secs: u64,
+
+  /// The avatar's damage broken down by attack, sorted by damage (highest first).
+  pub attacks: Vec<AttackTally>,
+
+  /// The pet damage broken down by pet name, sorted by damage (highest first).
+  pub pets: Vec<AttackTally>,
 }
 
 impl DPSTally {
@@ -368,27 +704,69 @@ impl DPSTally {
       avatar: 0,
       pet: 0,
       secs: 0,
+      avatar_taken: 0,
+      pet_taken: 0,
+      healing_done: 0,
+      healing_received: 0,
+      combat_secs: 0,
+      attacks: Vec::new(),
+      pets: Vec::new(),
     }
   }
 }
 
-pub async fn tally_dps(log_path: PathBuf, avatar: String, span: Span, cancel: Cancel) -> DPSTally {
-  let filenames = {
+/// Hits by an attacker that might be one of the avatar's pets.
+struct PetCandidate {
+  tally: AttackTally,
+  targets: HashSet<String>,
+  first_ts: i64,
+  last_ts: i64,
+}
+
+impl PetCandidate {
+  fn new(name: &str, ts: i64) -> Self {
+    Self {
+      tally: AttackTally::new(name),
+      targets: HashSet::new(),
+      first_ts: ts,
+      last_ts: ts,
+    }
+  }
+
+  fn add_hit(&mut self, target: &str, damage: u64, ts: i64) {
+    self.tally.add_hit(damage);
+    self.last_ts = ts;
+    if !self.targets.contains(target) {
+      self.targets.insert(target.to_owned());
+    }
+  }
+}
+
+pub async fn tally_dps(
+  log_paths: Vec<PathBuf>,
+  avatar: String,
+  span: Span,
+  cancel: Cancel,
+  state: AppState,
+) -> DPSTally {
+  let paths = {
     let begin = span.begin.date();
     let end = span.end.date();
 
-    // Filter the filenames to the date range.
-    let filenames: Vec<String> = get_log_filenames(&log_path, Some(&avatar), None)
+    // Filter the files to the date range.
+    let mut paths: Vec<PathBuf> = get_log_filenames(&log_paths, Some(&avatar), None)
       .into_iter()
-      .filter(|filename| {
-        let path = Path::new(filename);
+      .filter(|path| {
         if let Some(date) = get_log_file_date(path) {
           return date >= begin && date <= end;
         }
         false
       })
       .collect();
-    filenames
+
+    // Oldest first, so that a fight that crosses midnight begins in the first file.
+    paths.sort_by_key(|path| get_log_file_date(path));
+    paths
   };
 
   let mut dps_tally = DPSTally::new(span.clone());
@@ -396,15 +774,27 @@ pub async fn tally_dps(log_path: PathBuf, avatar: String, span: Span, cancel: Ca
     return dps_tally;
   }
 
-  // Use regular expressions for the searches.
-  let avatar_search = format!("^ {avatar} attacks .+ and hits, dealing [0-9]+");
+  // Use regular expressions for the searches. The avatar's name is escaped in case it contains special characters.
+  let name = regex::escape(&avatar);
+  let avatar_search = format!("^ {name} attacks .+ and hits, dealing [0-9]+ ");
   let avatar_search = ok!(Regex::new(&avatar_search), dps_tally);
-  let pet_search = format!("<{avatar}> attacks .+ and hits, dealing [0-9]+");
-  let pet_search = ok!(Regex::new(&pet_search), dps_tally);
+  let pet_suffix = format!("<{avatar}>");
+  let avatar_taken_search = format!("^ .+ attacks {name} and hits, dealing [0-9]+ ");
+  let avatar_taken_search = ok!(Regex::new(&avatar_taken_search), dps_tally);
+  let pet_taken_search = format!("^ .+ attacks .+<{name}> and hits, dealing [0-9]+ ");
+  let pet_taken_search = ok!(Regex::new(&pet_taken_search), dps_tally);
+  let healing_done_search = format!("^ {name} heals .+ for [0-9]+ ");
+  let healing_done_search = ok!(Regex::new(&healing_done_search), dps_tally);
+  let healing_received_search = format!(" heals {name} for [0-9]+ ");
+  let healing_received_search = ok!(Regex::new(&healing_received_search), dps_tally);
 
   // Range for checking log entry date/time.
-  let begin_ts = span.begin.and_utc().timestamp();
-  let end_ts = span.end.and_utc().timestamp();
+  let (Some(begin_ts), Some(end_ts)) = (
+    util::local_to_timestamp(span.begin, None),
+    util::local_to_timestamp(span.end, None),
+  ) else {
+    return dps_tally;
+  };
   let range = if end_ts >= begin_ts {
     begin_ts..=end_ts
   } else {
@@ -415,76 +805,495 @@ pub async fn tally_dps(log_path: PathBuf, avatar: String, span: Span, cancel: Ca
   let mut dmg_start_ts = None;
   let mut dmg_end_ts = None;
 
-  for filename in filenames {
+  // Start and end timestamps for any combat activity (damage dealt, damage taken or healing).
+  let mut combat_start_ts = None;
+  let mut combat_end_ts = None;
+
+  // The avatar's damage by attack name.
+  let mut attacks: HashMap<String, AttackTally> = HashMap::new();
+
+  // Pets are either shown as "Name<Avatar>", or by name only. Attackers that are shown by name only are candidates
+  // until the end, when they're checked against what the avatar and their bracketed pets were fighting.
+  let mut pets: HashMap<String, AttackTally> = HashMap::new();
+  let mut candidates: HashMap<String, PetCandidate> = HashMap::new();
+  let mut targets = HashSet::new();
+  let mut hostiles = HashSet::new();
+  let mut attackers: HashMap<String, HashSet<String>> = HashMap::new();
+  let mut taken: HashMap<String, u64> = HashMap::new();
+
+  let progress = state.begin_progress("Tallying DPS", paths.len());
+  for path in paths {
     if cancel.is_canceled() {
       return DPSTally::new(span.clone());
     }
 
     // Read the log file.
-    let path = log_path.join(filename);
     let file_date = get_log_file_date(&path).unwrap();
-    if let Ok(text) = fs::read_to_string(path) {
-      // Search for attack lines.
+    if let Ok(text) = read_log_text(&path) {
+      let (text, _) = split_partial_line(&text);
+
+      // Search for attack and heal lines, keeping track of the previous entry's time so that the repeated hour is
+      // handled when the clocks go back.
+      let mut last_ts = None;
       for line in text.lines() {
-        let Some(ts) = get_log_timestamp(line, file_date) else {
+        let Some(ts) = get_log_timestamp_after(line, file_date, last_ts) else {
           continue;
         };
+        last_ts = Some(ts);
 
         if !range.contains(&ts) {
           continue;
         }
 
         let line = get_log_text(line);
-        if let Some(found) = avatar_search.find(line) {
-          // The search term ends just past the damage value.
-          if let Some(digits) = line[found.range()].split_whitespace().next_back() {
-            if let Ok(value) = digits.parse::<u64>() {
-              if dmg_start_ts.is_none() {
-                dmg_start_ts = Some(ts);
-              }
-              dmg_end_ts = Some(ts);
-              dps_tally.avatar += value;
-            }
+        let attack = parse_attack(line);
+        if let Some((attacker, target, _)) = attack {
+          let ours = |name: &str| name == avatar || name.ends_with(&pet_suffix);
+          if ours(attacker) && !targets.contains(target) {
+            targets.insert(target.to_owned());
           }
-        } else if let Some(found) = pet_search.find(line) {
-          if let Some(digits) = line[found.range()].split_whitespace().next_back() {
-            if let Ok(value) = digits.parse::<u64>() {
-              if dmg_start_ts.is_none() {
-                dmg_start_ts = Some(ts);
-              }
-              dmg_end_ts = Some(ts);
-              dps_tally.pet += value;
+          if ours(target) && !hostiles.contains(attacker) {
+            hostiles.insert(attacker.to_owned());
+          }
+          if !ours(target) && !target.contains('<') {
+            let attackers = attackers.entry(target.to_owned()).or_default();
+            if !attackers.contains(attacker) {
+              attackers.insert(attacker.to_owned());
             }
           }
         }
+
+        let mut combat = true;
+        if let Some(value) = find_value(&avatar_search, line) {
+          if dmg_start_ts.is_none() {
+            dmg_start_ts = Some(ts);
+          }
+          dmg_end_ts = Some(ts);
+          dps_tally.avatar += value;
+
+          // Attacks without a name are auto-attacks.
+          let name = get_attack_name(line).unwrap_or(AUTO_ATTACK);
+          let attack = attacks
+            .entry(name.to_owned())
+            .or_insert_with_key(|name| AttackTally::new(name));
+          attack.add_hit(value);
+        } else if let Some((name, value)) = attack.and_then(|(attacker, _, value)| {
+          let name = attacker.strip_suffix(&pet_suffix)?;
+          Some((name, value?))
+        }) {
+          if dmg_start_ts.is_none() {
+            dmg_start_ts = Some(ts);
+          }
+          dmg_end_ts = Some(ts);
+          dps_tally.pet += value;
+
+          let name = if name.is_empty() { UNNAMED_PET } else { name };
+          let pet = pets
+            .entry(name.to_owned())
+            .or_insert_with_key(|name| AttackTally::new(name));
+          pet.add_hit(value);
+        } else if let Some(value) = find_value(&avatar_taken_search, line) {
+          dps_tally.avatar_taken += value;
+        } else if let Some(value) = find_value(&pet_taken_search, line) {
+          dps_tally.pet_taken += value;
+        } else if let Some((attacker, target, Some(value))) = attack {
+          // Damage by or to an attacker that might be a pet. Bracketed attackers are other avatars' pets.
+          if attacker.contains('<') {
+            continue;
+          }
+
+          let candidate = candidates
+            .entry(attacker.to_owned())
+            .or_insert_with(|| PetCandidate::new(attacker, ts));
+          candidate.add_hit(target, value, ts);
+          *taken.entry(target.to_owned()).or_default() += value;
+          combat = false;
+        } else {
+          combat = false;
+
+          // A self-heal counts as both healing done and healing received.
+          if let Some(value) = find_value(&healing_done_search, line) {
+            dps_tally.healing_done += value;
+            combat = true;
+          }
+          if let Some(value) = find_value(&healing_received_search, line) {
+            dps_tally.healing_received += value;
+            combat = true;
+          }
+        }
+
+        if combat {
+          if combat_start_ts.is_none() {
+            combat_start_ts = Some(ts);
+          }
+          combat_end_ts = Some(ts);
+        }
+      }
+    }
+
+    progress.step();
+  }
+
+  // Attackers shown by name only are pets if they weren't fighting the avatar and are either also shown as bracketed
+  // pets, or were fighting the avatar's targets while being attacked by the avatar's hostiles. Fighting the same
+  // targets isn't enough by itself, since other players do that too.
+  for (name, candidate) in candidates {
+    let attacked = attackers
+      .get(&name)
+      .is_some_and(|attackers| !attackers.is_disjoint(&hostiles));
+    let fighting = pets.contains_key(&name) || (attacked && !candidate.targets.is_disjoint(&targets));
+    if !fighting || targets.contains(&name) || hostiles.contains(&name) {
+      continue;
+    }
+
+    let tally = candidate.tally;
+    dps_tally.pet += tally.damage;
+    dps_tally.pet_taken += taken.get(&name).copied().unwrap_or_default();
+    let (first, last) = (candidate.first_ts, candidate.last_ts);
+    dmg_start_ts = Some(dmg_start_ts.map_or(first, |ts| ts.min(first)));
+    dmg_end_ts = Some(dmg_end_ts.map_or(last, |ts| ts.max(last)));
+    combat_start_ts = Some(combat_start_ts.map_or(first, |ts| ts.min(first)));
+    combat_end_ts = Some(combat_end_ts.map_or(last, |ts| ts.max(last)));
+
+    match pets.get_mut(&name) {
+      Some(pet) => {
+        pet.damage += tally.damage;
+        pet.hits += tally.hits;
+        pet.max_hit = pet.max_hit.max(tally.max_hit);
+      }
+      None => {
+        pets.insert(name, tally);
       }
     }
   }
 
   if let Some(start_ts) = dmg_start_ts {
-    if let Some(begin) = DateTime::from_timestamp(start_ts, 0) {
+    if let Some(begin) = util::timestamp_to_local(start_ts) {
       // Update the begin data/time.
-      dps_tally.span.begin = begin.naive_utc();
+      dps_tally.span.begin = begin;
     }
     if let Some(end_ts) = dmg_end_ts {
-      if let Some(end) = DateTime::from_timestamp(end_ts, 0) {
+      if let Some(end) = util::timestamp_to_local(end_ts) {
         // Update the end data/time.
-        dps_tally.span.end = end.naive_utc();
+        dps_tally.span.end = end;
       }
       dps_tally.secs = 0.max(end_ts - start_ts) as u64;
     }
   }
 
+  if let (Some(start_ts), Some(end_ts)) = (combat_start_ts, combat_end_ts) {
+    dps_tally.combat_secs = 0.max(end_ts - start_ts) as u64;
+  }
+
+  // Sort the attacks by damage, highest first.
+  let mut attacks: Vec<AttackTally> = attacks.into_values().collect();
+  attacks.sort_unstable_by(|a, b| b.damage.cmp(&a.damage).then_with(|| a.name.cmp(&b.name)));
+  dps_tally.attacks = attacks;
+
+  let mut pets: Vec<AttackTally> = pets.into_values().collect();
+  pets.sort_unstable_by(|a, b| b.damage.cmp(&a.damage).then_with(|| a.name.cmp(&b.name)));
+  dps_tally.pets = pets;
+
   dps_tally.secs += 1;
+  dps_tally.combat_secs += 1;
   dps_tally
 }
 
-fn get_log_filenames(log_path: &Path, avatar: Option<&str>, ts: Option<i64>) -> Vec<String> {
-  let mut filenames = Vec::new();
-  let entries = ok!(log_path.read_dir(), filenames);
+/// Find the span of the most recent fight in the avatar's newest log file. A fight ends when there's a gap of more than
+/// `gap` seconds between attack lines.
+pub async fn find_last_fight_span(log_paths: Vec<PathBuf>, avatar: String, gap: i64, cancel: Cancel) -> Option<Span> {
+  let paths = get_sorted_log_filenames(&log_paths, Some(&avatar), DateRange::default());
+  let path = paths.first()?;
+  if cancel.is_canceled() {
+    return None;
+  }
+
+  let file_date = get_log_file_date(path)?;
+  let text = ok!(read_log_text(path), None);
+  find_last_fight(&text, &avatar, file_date, gap)
+}
+
+/// Find the span of the last contiguous block of attack lines involving the avatar or their pet.
+pub fn find_last_fight(text: &str, avatar: &str, file_date: NaiveDate, gap: i64) -> Option<Span> {
+  let avatar = regex::escape(avatar);
+  let search = format!("^ {avatar} attacks |<{avatar}> attacks |^ .+ attacks {avatar} |^ .+ attacks .+<{avatar}> ");
+  let search = ok!(Regex::new(&search), None);
+
+  // Iterate through the lines in reverse order (newest to oldest).
+  let (text, _) = split_partial_line(text);
+  let mut range: Option<(i64, i64)> = None;
+  for line in text.lines().rev() {
+    let Some(ts) = get_log_timestamp(line, file_date) else {
+      continue;
+    };
+
+    if !search.is_match(get_log_text(line)) {
+      continue;
+    }
+
+    match &mut range {
+      Some((begin, _)) => {
+        if *begin - ts > gap {
+          break;
+        }
+        *begin = ts;
+      }
+      None => range = Some((ts, ts)),
+    }
+  }
+
+  let (begin, end) = range?;
+  let begin = util::timestamp_to_local(begin)?;
+  let end = util::timestamp_to_local(end)?;
+  Some(Span { begin, end })
+}
+
+/// Adventurer experience gains read from the end of the newest chat log.
+#[derive(Debug, Default, PartialEq)]
+pub struct SessionExp {
+  /// Log file that was read.
+  pub path: Option<PathBuf>,
+
+  /// How far into the file was read.
+  pub offset: u64,
+
+  /// Timestamp and amount of each gain.
+  pub gains: Vec<(i64, i64)>,
+}
+
+/// Get the adventurer experience gains after `since` from the avatar's newest chat log. Reading resumes at `offset`
+/// if `path` is still the newest log, otherwise the rest of `path` is read before starting on the newest one.
+pub async fn get_session_exp(
+  log_paths: Vec<PathBuf>,
+  avatar: String,
+  since: i64,
+  path: Option<PathBuf>,
+  offset: u64,
+  cancel: Cancel,
+) -> SessionExp {
+  let newest = get_sorted_log_filenames(&log_paths, Some(&avatar), DateRange::default())
+    .into_iter()
+    .find(|path| !is_log_archive(path));
+
+  let mut session = SessionExp {
+    path: path.clone(),
+    offset,
+    gains: Vec::new(),
+  };
+
+  let Some(newest) = newest else { return session };
+  let mut reads = Vec::with_capacity(2);
+  match path {
+    Some(path) if path == newest => reads.push((newest, offset)),
+    Some(path) => {
+      // The log rolled over to a new day.
+      reads.push((path, offset));
+      reads.push((newest, 0));
+    }
+    None => reads.push((newest, 0)),
+  }
+
+  for (path, offset) in reads {
+    if cancel.is_canceled() {
+      break;
+    }
+
+    let Some(file_date) = get_log_file_date(&path) else {
+      continue;
+    };
+
+    let Ok((text, offset)) = read_log_lines(&path, offset) else {
+      continue;
+    };
+
+    for line in text.lines() {
+      let Some(exp) = get_exp_gain(line) else { continue };
+      if let Some(ts) = get_log_timestamp(line, file_date).filter(|&ts| ts >= since) {
+        session.gains.push((ts, exp));
+      }
+    }
+
+    session.path = Some(path);
+    session.offset = offset;
+  }
+
+  session
+}
+
+/// Read the complete lines of a log file starting at `offset`. Returns the text and the offset just past it. If the
+/// file is shorter than `offset` (e.g. it was replaced) then it's read from the beginning.
+fn read_log_lines(path: &Path, offset: u64) -> io::Result<(String, u64)> {
+  let mut file = File::open(path)?;
+  let offset = if file.metadata()?.len() < offset { 0 } else { offset };
+  file.seek(SeekFrom::Start(offset))?;
+
+  let mut bytes = Vec::new();
+  file.read_to_end(&mut bytes)?;
+
+  // Leave a partially written line for next time.
+  let len = bytes.iter().rposition(|&byte| byte == b'\n').map_or(0, |pos| pos + 1);
+  bytes.truncate(len);
+  Ok((String::from_utf8_lossy(&bytes).into_owned(), offset + len as u64))
+}
+
+/// Get the amount from an adventurer experience gain entry (e.g. "You gained 1,234 experience.").
+fn get_exp_gain(line: &str) -> Option<i64> {
+  get_log_date(line)?;
+  let text = get_log_text(line).trim_start();
+  let text = EXP_GAIN_KEYS.iter().find_map(|key| text.strip_prefix(key))?;
+  let (value, text) = text.split_once(' ')?;
 
-  // The name text is either a specific avatar or, if not specified, a regex wildcard.
-  let name = avatar.unwrap_or(".+");
+  // Producer experience is a separate pool.
+  let text = text.strip_prefix("Adventurer ").unwrap_or(text);
+  if !text.starts_with("experience") && !text.starts_with("Experience") {
+    return None;
+  }
+
+  util::parse_grouped_int(value)
+}
+
+/// Get the attack name from an attack line (e.g. "Mira attacks Wolf with Fire Arrow and hits, dealing 10").
+fn get_attack_name(line: &str) -> Option<&str> {
+  let end = line.find(" and hits, dealing ")?;
+  let text = &line[..end];
+  let pos = text.rfind(WITH_KEY)?;
+  let name = text[pos + WITH_KEY.len()..].trim();
+  if name.is_empty() {
+    return None;
+  }
+  Some(name)
+}
+
+/// Parse an attack line into the attacker, target and damage (`None` for a miss).
+fn parse_attack(line: &str) -> Option<(&str, &str, Option<u64>)> {
+  let (attacker, text) = line.trim_start().split_once(" attacks ")?;
+  let (text, damage) = match text.split_once(" and hits, dealing ") {
+    // The damage is followed by the rest of the line unless it was only partially written.
+    Some((text, damage)) => (text, Some(damage.split_once(' ')?.0.parse().ok()?)),
+    None => (text.split_once(" and ")?.0, None),
+  };
+
+  // The target is followed by the attack name, if there is one.
+  let target = match text.rfind(WITH_KEY) {
+    Some(pos) => &text[..pos],
+    None => text,
+  };
+  Some((attacker.trim(), target.trim(), damage))
+}
+
+/// Find the search term in the line and parse the value at the end of the match.
+fn find_value(search: &Regex, line: &str) -> Option<u64> {
+  let found = search.find(line)?;
+
+  // The search term ends with the space just past the value.
+  let digits = line[found.range()].split_whitespace().next_back()?;
+  digits.parse().ok()
+}
+
+/// Check if the text contains a `/stats` entry.
+pub fn has_stats(text: &str) -> bool {
+  text.contains(STATS_KEY)
+}
+
+/// Check if the text contains an adventurer or producer experience entry from `/xp`.
+pub fn has_exp(text: &str) -> bool {
+  text.contains(ADV_EXP_KEY) || text.contains(PRD_EXP_KEY)
+}
+
+/// Get the avatar name from a log filename.
+pub fn get_log_file_avatar(filename: &str) -> Option<&str> {
+  let name = filename.strip_prefix(FILENAME_START)?.strip_prefix('_')?;
+  let pos = name.rfind('_')?;
+  Some(&name[..pos])
+}
+
+/// Get the paths of the log files for the avatar (or all avatars if `None`) and date (or all dates if `None`) from all
+/// the log folders. If the same log is in more than one folder then only the larger file is included, and a plain log is
+/// preferred over an archived one.
+pub fn get_log_filenames(log_paths: &[PathBuf], avatar: Option<&str>, ts: Option<i64>) -> Vec<PathBuf> {
+  let mut paths = Vec::new();
+  let regex = ok!(get_log_filename_regex(avatar, ts), paths);
+
+  // Index into `paths` by filename.
+  let mut indices = HashMap::new();
+  for log_path in log_paths {
+    let Ok(entries) = log_path.read_dir() else {
+      continue;
+    };
+
+    for entry in entries.flatten() {
+      let Ok(filename) = entry.file_name().into_string() else {
+        continue;
+      };
+
+      if !regex.is_match(&filename) {
+        continue;
+      }
+
+      match indices.entry(get_log_stem(&filename).to_owned()) {
+        Entry::Occupied(entry_index) => {
+          let path: &mut PathBuf = &mut paths[*entry_index.get()];
+          let new_path = entry.path();
+          let replace = match (is_log_archive(path), is_log_archive(&new_path)) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => get_file_len(&new_path) > get_file_len(path),
+          };
+          if replace {
+            *path = new_path;
+          }
+        }
+        Entry::Vacant(entry_index) => {
+          entry_index.insert(paths.len());
+          paths.push(entry.path());
+        }
+      }
+    }
+  }
+
+  paths
+}
+
+/// Get the path of the avatar's log file for the date of `ts`.
+pub fn get_log_file(log_paths: &[PathBuf], avatar: &str, ts: i64) -> Option<PathBuf> {
+  get_log_filenames(log_paths, Some(avatar), Some(ts)).into_iter().next()
+}
+
+/// Find the first log folder that is missing or can't be read (e.g. on a disconnected drive).
+pub fn find_inaccessible_log_path(log_paths: &[PathBuf]) -> Option<&PathBuf> {
+  log_paths.iter().find(|path| path.read_dir().is_err())
+}
+
+/// Get the filename portion of a path as a `&str`.
+pub fn get_file_name(path: &Path) -> &str {
+  path.file_name().and_then(OsStr::to_str).unwrap_or_default()
+}
+
+/// Check if a log file is compressed (`.gz` or `.zip`).
+pub fn is_log_archive(path: &Path) -> bool {
+  get_log_archive(path).is_some()
+}
+
+/// Get the log filename without the `.txt` and archive extensions.
+fn get_log_stem(filename: &str) -> &str {
+  let name = filename.strip_suffix(".gz");
+  let name = name.or_else(|| filename.strip_suffix(".zip")).unwrap_or(filename);
+  name.strip_suffix(".txt").unwrap_or(name)
+}
+
+fn get_file_len(path: &Path) -> u64 {
+  path.metadata().map(|metadata| metadata.len()).unwrap_or_default()
+}
+
+/// Get a regex that matches the log filenames for the avatar (or all avatars if `None`) and date (or all dates if
+/// `None`).
+fn get_log_filename_regex(avatar: Option<&str>, ts: Option<i64>) -> Result<Regex, regex::Error> {
+  // The name text is either a specific avatar (escaped so that it's matched exactly) or, if not specified, a regex
+  // wildcard.
+  let name = match avatar {
+    Some(avatar) => regex::escape(avatar),
+    None => String::from(".+"),
+  };
 
   // The date text is either a specific date or, if not specified, regex to match the date.
   let date = if let Some(ts) = ts {
@@ -493,17 +1302,94 @@ fn get_log_filenames(log_path: &Path, avatar: Option<&str>, ts: Option<i64>) ->
     String::from(r"\d{4}-\d{2}-\d{2}")
   };
 
-  let regex = ok!(Regex::new(&format!("^{FILENAME_START}_{name}_{date}.txt$")), filenames);
+  // Archived logs can be compressed with gzip or zip.
+  Regex::new(&format!(
+    r"^{FILENAME_START}_(?:{name})_{date}\.(?:txt(?:\.gz|\.zip)?|zip)$"
+  ))
+}
 
-  for entry in entries.flatten() {
-    if let Ok(filename) = entry.file_name().into_string() {
-      if regex.is_match(&filename) {
-        filenames.push(filename);
+/// Get the log file paths for the avatar (or all avatars if `None`) sorted from newest to oldest by file date.
+/// Only files with dates inside the range are included.
+fn get_sorted_log_filenames(log_paths: &[PathBuf], avatar: Option<&str>, range: DateRange) -> Vec<PathBuf> {
+  let mut paths = get_log_filenames(log_paths, avatar, None);
+  if !range.is_empty() {
+    paths.retain(|path| match get_log_file_date(path) {
+      Some(date) => range.contains(date),
+      None => false,
+    });
+  }
+
+  paths.sort_unstable_by(|a, b| {
+    let a = get_log_stem(get_file_name(a));
+    let b = get_log_stem(get_file_name(b));
+    let a_date = a.rsplit_once('_').map(|(_, date)| date);
+    let b_date = b.rsplit_once('_').map(|(_, date)| date);
+    b_date.cmp(&a_date).then_with(|| b.cmp(a))
+  });
+  paths
+}
+
+/// Split the text into its complete lines and the partially written line at the end, if any. The game may still be
+/// writing to the newest log.
+fn split_partial_line(text: &str) -> (&str, &str) {
+  match text.rfind('\n') {
+    Some(pos) => text.split_at(pos + 1),
+    None => ("", text),
+  }
+}
+
+/// Read a chat log as text. Older logs can contain bytes that aren't valid UTF-8 (e.g. Windows-1252 player names), so
+/// invalid sequences are replaced rather than rejecting the whole file.
+fn read_log_text(path: &Path) -> io::Result<String> {
+  let bytes = match get_log_archive(path) {
+    Some(archive) => match read_log_archive(path, archive) {
+      Ok(bytes) => bytes,
+      Err(err) => {
+        // Skip corrupted archives.
+        println!("Unable to read {path:?}: {err:?}");
+        return Err(err);
       }
-    }
+    },
+    None => fs::read(path)?,
+  };
+  Ok(match String::from_utf8(bytes) {
+    Ok(text) => text,
+    Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+  })
+}
+
+#[derive(Clone, Copy)]
+enum Archive {
+  Gzip,
+  Zip,
+}
+
+fn get_log_archive(path: &Path) -> Option<Archive> {
+  match path.extension()?.to_str()? {
+    "gz" => Some(Archive::Gzip),
+    "zip" => Some(Archive::Zip),
+    _ => None,
   }
+}
 
-  filenames
+/// Decompress an archived chat log. Zip archives must contain a single log.
+fn read_log_archive(path: &Path, archive: Archive) -> io::Result<Vec<u8>> {
+  let file = File::open(path)?;
+  let mut bytes = Vec::new();
+  match archive {
+    Archive::Gzip => {
+      GzDecoder::new(file).read_to_end(&mut bytes)?;
+    }
+    Archive::Zip => {
+      let mut zip = ZipArchive::new(file).map_err(io::Error::other)?;
+      if zip.len() != 1 {
+        let msg = format!("expected a single log, found {} files", zip.len());
+        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+      }
+      zip.by_index(0).map_err(io::Error::other)?.read_to_end(&mut bytes)?;
+    }
+  }
+  Ok(bytes)
 }
 
 /// Make sure the text contains at least one date/time.
@@ -518,7 +1404,8 @@ fn verify_log_text(text: &str) -> bool {
 
 /// Convert a SotA log date & time into a timestamp. Since the dates are localized, we don't know
 /// if day or month come first, so we use the date from the filename, which is always YYYY-MM-DD.
-fn log_date_to_timestamp(text: &str, date: NaiveDate) -> Option<i64> {
+/// Log times are local, see `util::local_to_timestamp` for the meaning of `after`.
+fn log_date_to_timestamp(text: &str, date: NaiveDate, after: Option<i64>) -> Option<i64> {
   let mut iter = text.split_whitespace();
   let _date = iter.next()?;
   let time = iter.next()?;
@@ -548,16 +1435,13 @@ fn log_date_to_timestamp(text: &str, date: NaiveDate) -> Option<i64> {
   let minute = iter.next()?.parse().ok()?;
   let second = iter.next()?.parse().ok()?;
 
-  Some(
-    NaiveDateTime::new(date, NaiveTime::from_hms_opt(hour, minute, second)?)
-      .and_utc()
-      .timestamp(),
-  )
+  let date_time = NaiveDateTime::new(date, NaiveTime::from_hms_opt(hour, minute, second)?);
+  util::local_to_timestamp(date_time, after)
 }
 
 /// Convert a timestamp into a log filename date string.
 fn timestamp_to_file_date(ts: i64) -> String {
-  let Some(dt) = DateTime::from_timestamp(ts, 0) else {
+  let Some(dt) = util::timestamp_to_local(ts) else {
     return String::default();
   };
   dt.format("%Y-%m-%d").to_string()
@@ -565,7 +1449,7 @@ fn timestamp_to_file_date(ts: i64) -> String {
 
 /// Get a NaiveDate from a log filename.
 fn get_log_file_date(path: &Path) -> Option<NaiveDate> {
-  let filename = path.file_stem()?.to_str()?;
+  let filename = get_log_stem(path.file_name()?.to_str()?);
   let pos = filename.rfind('_')?;
   let text = &filename[pos + 1..];
   NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
@@ -573,15 +1457,20 @@ fn get_log_file_date(path: &Path) -> Option<NaiveDate> {
 
 /// Get the log entry date/time as a timestamp.
 fn get_log_timestamp(line: &str, file_date: NaiveDate) -> Option<i64> {
+  get_log_timestamp_after(line, file_date, None)
+}
+
+/// Get the log entry date/time as a timestamp, given the timestamp of the previous entry.
+fn get_log_timestamp_after(line: &str, file_date: NaiveDate, after: Option<i64>) -> Option<i64> {
   let date = get_log_date(line)?;
-  log_date_to_timestamp(&date[1..date.len() - 1], file_date)
+  log_date_to_timestamp(&date[1..date.len() - 1], file_date, after)
 }
 
 /// Get the log entry date/time as a timestamp if it's a `/stats` entry.
 fn get_stats_timestamp(line: &str, file_date: NaiveDate) -> Option<i64> {
   let date = get_log_date(line)?;
   if line[date.len()..].contains(STATS_KEY) {
-    return log_date_to_timestamp(&date[1..date.len() - 1], file_date);
+    return log_date_to_timestamp(&date[1..date.len() - 1], file_date, None);
   }
 
   None
@@ -597,12 +1486,912 @@ fn get_stats_text(line: &str, ts: i64, file_date: NaiveDate) -> Option<&str> {
   None
 }
 
-fn get_adv_xp(line: &str) -> Option<i64> {
+fn get_xp(line: &str, key: &str) -> Option<i64> {
   let text = get_log_text(line);
-  if let Some(text) = text.strip_prefix(ADV_EXP_KEY) {
-    let text = util::remove_separators(text);
-    return text.parse().ok();
+  if let Some(text) = text.strip_prefix(key) {
+    return util::parse_grouped_int(text);
   }
 
   None
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use util::TempDir;
+
+  #[test]
+  fn test_log_filename_regex_prefix() {
+    let regex = get_log_filename_regex(Some("Mira"), None).unwrap();
+    assert!(regex.is_match("SotAChatLog_Mira_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_Mira Dane_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_Mira_Dane_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_Miranda_2024-01-02.txt"));
+
+    let regex = get_log_filename_regex(Some("Mira Dane"), None).unwrap();
+    assert!(regex.is_match("SotAChatLog_Mira Dane_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_Mira_2024-01-02.txt"));
+
+    // The extension must match exactly.
+    assert!(!regex.is_match("SotAChatLog_Mira Dane_2024-01-02_txt"));
+  }
+
+  #[test]
+  fn test_log_filename_regex_special() {
+    let regex = get_log_filename_regex(Some("Sir+Knight"), None).unwrap();
+    assert!(regex.is_match("SotAChatLog_Sir+Knight_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_SirrKnight_2024-01-02.txt"));
+
+    let regex = get_log_filename_regex(Some("D'Artagnan (Jr.)"), None).unwrap();
+    assert!(regex.is_match("SotAChatLog_D'Artagnan (Jr.)_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_D'Artagnan Jr._2024-01-02.txt"));
+
+    let regex = get_log_filename_regex(Some("a.b|c*"), None).unwrap();
+    assert!(regex.is_match("SotAChatLog_a.b|c*_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_axb_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_c_2024-01-02.txt"));
+  }
+
+  #[test]
+  fn test_find_last_fight() {
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+      "[1/2/2024 10:00:20 AM] Wolf attacks Mira and misses.\n",
+      "[1/2/2024 10:05:00 AM] Mira attacks Bear and hits, dealing 12 points of damage.\n",
+      "[1/2/2024 10:05:10 AM] Bear attacks Fluffy<Mira> and hits, dealing 4 points of damage.\n",
+      "[1/2/2024 10:05:25 AM] Fluffy<Mira> attacks Bear and hits, dealing 8 points of damage.\n",
+      "[1/2/2024 10:05:40 AM] Mira says that was close\n",
+      "[1/2/2024 10:06:00 AM] Dane attacks Bear and hits, dealing 9 points of damage.\n",
+    );
+    let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let time = |h, m, s| NaiveDateTime::new(date, NaiveTime::from_hms_opt(h, m, s).unwrap());
+
+    let span = find_last_fight(text, "Mira", date, 30).unwrap();
+    assert_eq!(span.begin, time(10, 5, 0));
+    assert_eq!(span.end, time(10, 5, 25));
+
+    // A large enough gap joins both fights.
+    let span = find_last_fight(text, "Mira", date, 600).unwrap();
+    assert_eq!(span.begin, time(10, 0, 0));
+    assert_eq!(span.end, time(10, 5, 25));
+
+    // A small gap splits the pet's attack from the rest of the fight.
+    let span = find_last_fight(text, "Mira", date, 10).unwrap();
+    assert_eq!(span.begin, time(10, 5, 25));
+    assert_eq!(span.end, time(10, 5, 25));
+
+    assert!(find_last_fight(text, "Nobody", date, 30).is_none());
+  }
+
+  #[test]
+  fn test_tally_attacks() {
+    let log_path = TempDir::new("dps");
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Mira attacks Wolf with Fire Arrow and hits, dealing 40 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Mira attacks Wolf with Fire Arrow and hits, dealing 25 points of damage.\n",
+      "[1/2/2024 10:00:05 AM] Fluffy<Mira> attacks Wolf with Bite and hits, dealing 8 points of damage.\n",
+      "[1/2/2024 10:00:06 AM] Mira attacks Wolf with Fire Arrow and misses.\n",
+      "[1/2/2024 10:00:07 AM] Wolf attacks Mira and hits, dealing 6 points of damage.\n",
+      "[1/2/2024 10:00:08 AM] Mira attacks Wolf with Ice Arrow and hits, dealing 30 points of damage.\n",
+      "[1/2/2024 10:00:10 AM] Mira attacks Wolf and hits, dealing 5 points of damage.\n",
+    );
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-02.txt"), text).unwrap();
+
+    let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let time = |h, m, s| NaiveDateTime::new(date, NaiveTime::from_hms_opt(h, m, s).unwrap());
+    let span = Span {
+      begin: time(10, 0, 0),
+      end: time(10, 1, 0),
+    };
+    let tally = futures::executor::block_on(tally_dps(
+      vec![log_path.to_path_buf()],
+      "Mira".into(),
+      span,
+      Cancel::default(),
+      AppState::default(),
+    ));
+
+    // The totals are unchanged by the breakdown.
+    assert_eq!(tally.avatar, 110);
+    assert_eq!(tally.pet, 8);
+    assert_eq!(tally.avatar_taken, 6);
+    assert_eq!(tally.secs, 11);
+
+    // The attacks add up to the avatar's total damage.
+    let total: u64 = tally.attacks.iter().map(|attack| attack.damage).sum();
+    assert_eq!(total, tally.avatar);
+
+    let attack = |name: &str, damage, hits, max_hit| AttackTally {
+      name: name.into(),
+      damage,
+      hits,
+      max_hit,
+    };
+    assert_eq!(
+      tally.attacks,
+      [
+        attack("Fire Arrow", 65, 2, 40),
+        attack("Ice Arrow", 30, 1, 30),
+        attack(AUTO_ATTACK, 15, 2, 10),
+      ]
+    );
+  }
+
+  /// Tally the DPS for Mira from a single day of log text.
+  fn tally_log_text(name: &str, text: &str) -> DPSTally {
+    tally_avatar_log_text(name, "Mira", text)
+  }
+
+  /// Tally the DPS for an avatar from a single day of log text.
+  fn tally_avatar_log_text(name: &str, avatar: &str, text: &str) -> DPSTally {
+    let log_path = TempDir::new(name);
+    fs::write(log_path.join(format!("SotAChatLog_{avatar}_2024-01-02.txt")), text).unwrap();
+
+    let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let span = Span {
+      begin: date.and_hms_opt(10, 0, 0).unwrap(),
+      end: date.and_hms_opt(11, 0, 0).unwrap(),
+    };
+    futures::executor::block_on(tally_dps(
+      vec![log_path.to_path_buf()],
+      avatar.into(),
+      span,
+      Cancel::default(),
+      AppState::default(),
+    ))
+  }
+
+  #[test]
+  fn test_tally_special_name() {
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+      "[1/2/2024 10:00:01 AM] M.ra attacks Wolf and hits, dealing 7 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Wolf attacks Mira and hits, dealing 3 points of damage.\n",
+      "[1/2/2024 10:00:03 AM] Wolf attacks M.ra and hits, dealing 4 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Mira heals Mira for 5 points of health.\n",
+      "[1/2/2024 10:00:05 AM] M.ra heals M.ra for 2 points of health.\n",
+    );
+
+    // The totals for a plain name are unchanged by the escaping.
+    let tally = tally_avatar_log_text("plain_name", "Mira", text);
+    assert_eq!(tally.avatar, 10);
+    assert_eq!(tally.avatar_taken, 3);
+    assert_eq!(tally.healing_done, 5);
+    assert_eq!(tally.healing_received, 5);
+
+    // The '.' only matches itself.
+    let tally = tally_avatar_log_text("special_name", "M.ra", text);
+    assert_eq!(tally.avatar, 7);
+    assert_eq!(tally.avatar_taken, 4);
+    assert_eq!(tally.healing_done, 2);
+    assert_eq!(tally.healing_received, 2);
+  }
+
+  fn pet(name: &str, damage: u64, hits: u64, max_hit: u64) -> AttackTally {
+    AttackTally {
+      name: name.into(),
+      damage,
+      hits,
+      max_hit,
+    }
+  }
+
+  #[test]
+  fn test_tally_pets() {
+    // A summon that's shown by name only.
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+      "[1/2/2024 10:00:01 AM] Earth Elemental attacks Wolf and hits, dealing 7 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Wolf attacks Earth Elemental and hits, dealing 3 points of damage.\n",
+      "[1/2/2024 10:00:03 AM] Wolf attacks Mira and hits, dealing 4 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Earth Elemental attacks Wolf with Slam and hits, dealing 9 points of damage.\n",
+      "[1/2/2024 10:00:05 AM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+    );
+    let tally = tally_log_text("named_pet", text);
+    assert_eq!(tally.avatar, 20);
+    assert_eq!(tally.pet, 16);
+    assert_eq!(tally.avatar_taken, 4);
+    assert_eq!(tally.pet_taken, 3);
+    assert_eq!(tally.pets, [pet("Earth Elemental", 16, 2, 9)]);
+
+    // The bracketed form, including the same pet shown by name only.
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Fluffy<Mira> attacks Bear with Bite and hits, dealing 8 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Bear attacks Fluffy<Mira> and hits, dealing 5 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Fluffy attacks Bear and hits, dealing 6 points of damage.\n",
+      "[1/2/2024 10:00:06 AM] <Mira> attacks Bear and hits, dealing 2 points of damage.\n",
+    );
+    let tally = tally_log_text("bracketed_pet", text);
+    assert_eq!(tally.avatar, 0);
+    assert_eq!(tally.pet, 16);
+    assert_eq!(tally.pet_taken, 5);
+    assert_eq!(tally.secs, 7);
+    assert_eq!(tally.pets, [pet("Fluffy", 14, 2, 8), pet(UNNAMED_PET, 2, 1, 2)]);
+
+    // Both, with another player's pet and an enemy that attacks the pet.
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Skeleton and hits, dealing 20 points of damage.\n",
+      "[1/2/2024 10:00:01 AM] Fluffy<Mira> attacks Skeleton and hits, dealing 5 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Fire Elemental attacks Skeleton and hits, dealing 12 points of damage.\n",
+      "[1/2/2024 10:00:03 AM] Rover<Dane> attacks Skeleton and hits, dealing 9 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Skeleton attacks Fire Elemental and hits, dealing 6 points of damage.\n",
+      "[1/2/2024 10:00:05 AM] Zombie attacks Fluffy<Mira> and misses.\n",
+      "[1/2/2024 10:00:06 AM] Zombie attacks Fire Elemental and hits, dealing 4 points of damage.\n",
+      "[1/2/2024 10:00:07 AM] Fire Elemental attacks Skeleton and hits, dealing 12 points of damage.\n",
+    );
+    let tally = tally_log_text("both_pets", text);
+    assert_eq!(tally.avatar, 20);
+    assert_eq!(tally.pet, 29);
+    assert_eq!(tally.pet_taken, 10);
+    assert_eq!(tally.secs, 8);
+    assert_eq!(tally.pets, [pet("Fire Elemental", 24, 2, 12), pet("Fluffy", 5, 1, 5)]);
+
+    // Another player fighting the same enemy, who isn't attacked by the avatar's hostiles.
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Skeleton and hits, dealing 20 points of damage.\n",
+      "[1/2/2024 10:00:01 AM] Dane attacks Skeleton and hits, dealing 15 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Skeleton attacks Mira and hits, dealing 6 points of damage.\n",
+      "[1/2/2024 10:00:03 AM] Zombie attacks Dane and hits, dealing 4 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Dane attacks Skeleton and hits, dealing 15 points of damage.\n",
+    );
+    let tally = tally_log_text("other_player", text);
+    assert_eq!(tally.avatar, 20);
+    assert_eq!(tally.pet, 0);
+    assert_eq!(tally.pet_taken, 0);
+    assert!(tally.pets.is_empty());
+  }
+
+  #[test]
+  fn test_tally_across_midnight() {
+    let log_path = TempDir::new("midnight");
+    // A fight that continues into the next day's log file.
+    let text = concat!(
+      "[1/2/2024 11:59:50 PM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+      "[1/2/2024 11:59:58 PM] Mira attacks Wolf and hits, dealing 20 points of damage.\n",
+    );
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-02.txt"), text).unwrap();
+    let text = concat!(
+      "[1/3/2024 12:00:05 AM] Mira attacks Wolf and hits, dealing 30 points of damage.\n",
+      "[1/3/2024 12:00:30 AM] Mira attacks Bear and hits, dealing 40 points of damage.\n",
+    );
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-03.txt"), text).unwrap();
+
+    let first = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let second = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+    let span = Span {
+      begin: first.and_hms_opt(23, 59, 0).unwrap(),
+      end: second.and_hms_opt(0, 0, 10).unwrap(),
+    };
+    let tally = futures::executor::block_on(tally_dps(
+      vec![log_path.to_path_buf()],
+      "Mira".into(),
+      span,
+      Cancel::default(),
+      AppState::default(),
+    ));
+
+    assert_eq!(tally.avatar, 60);
+    assert_eq!(tally.secs, 16);
+    assert_eq!(tally.span.begin, first.and_hms_opt(23, 59, 50).unwrap());
+    assert_eq!(tally.span.end, second.and_hms_opt(0, 0, 5).unwrap());
+  }
+
+  #[test]
+  fn test_read_invalid_utf8() {
+    let log_path = TempDir::new("log");
+    // "Jos\xE9" is Windows-1252 for "José".
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"[1/2/2024 10:00:00 AM] Jos\xE9 says hello\n");
+    bytes.extend_from_slice(b"[1/2/2024 10:01:00 AM] AdventurerLevel: 100 Strength: 30.5\n");
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-02.txt"), bytes).unwrap();
+
+    let log_paths = vec![log_path.to_path_buf()];
+    let state = AppState::default();
+    let timestamps = futures::executor::block_on(get_stats_timestamps(
+      log_paths.clone(),
+      "Mira".into(),
+      Cancel::default(),
+      None,
+      None,
+      state.clone(),
+    ));
+    assert_eq!(timestamps.len(), 1);
+
+    // The progress is cleared when the task completes.
+    assert!(state.progress().is_none());
+
+    let stats = futures::executor::block_on(get_stats(
+      log_paths.clone(),
+      "Mira".into(),
+      timestamps[0],
+      Cancel::default(),
+    ));
+    let stats: Vec<_> = stats.iter().collect();
+    assert_eq!(stats, [("AdventurerLevel", 100.0), ("Strength", 30.5)]);
+
+    let search = Search::String {
+      find: "hello".into(),
+      ignore_case: false,
+    };
+    let mut text = String::new();
+    futures::executor::block_on(find_log_entries(
+      log_paths,
+      Some("Mira".into()),
+      search,
+      DateRange::default(),
+      Cancel::default(),
+      state.clone(),
+      |result| text.push_str(&result),
+    ));
+    assert!(text.contains("Jos\u{FFFD} says hello"));
+    assert!(state.progress().is_none());
+  }
+
+  #[test]
+  fn test_archived_logs() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    let log_path = TempDir::new("archive");
+    // Gzip compressed log.
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+      .write_all(b"[1/2/2024 10:00:00 AM] AdventurerLevel: 100 Strength: 30\n")
+      .unwrap();
+    fs::write(
+      log_path.join("SotAChatLog_Mira_2024-01-02.txt.gz"),
+      encoder.finish().unwrap(),
+    )
+    .unwrap();
+
+    // Zip archive containing a single log.
+    let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
+    zip
+      .start_file("SotAChatLog_Mira_2024-01-03.txt", SimpleFileOptions::default())
+      .unwrap();
+    zip
+      .write_all(b"[1/3/2024 10:00:00 AM] AdventurerLevel: 101 Strength: 31\n")
+      .unwrap();
+    fs::write(
+      log_path.join("SotAChatLog_Mira_2024-01-03.zip"),
+      zip.finish().unwrap().into_inner(),
+    )
+    .unwrap();
+
+    // A plain log is preferred over an archived copy.
+    let text = "[1/4/2024 10:00:00 AM] AdventurerLevel: 102\n[1/4/2024 11:00:00 AM] AdventurerLevel: 103\n";
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-04.txt"), text).unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+      .write_all(b"[1/4/2024 10:00:00 AM] AdventurerLevel: 102\n")
+      .unwrap();
+    fs::write(
+      log_path.join("SotAChatLog_Mira_2024-01-04.txt.gz"),
+      encoder.finish().unwrap(),
+    )
+    .unwrap();
+
+    // Corrupted archives are skipped.
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-05.txt.gz"), b"not gzip").unwrap();
+    fs::write(log_path.join("SotAChatLog_Mira_2024-01-06.zip"), b"not zip").unwrap();
+
+    let log_paths = vec![log_path.to_path_buf()];
+    let paths = get_sorted_log_filenames(&log_paths, Some("Mira"), DateRange::default());
+    let names: Vec<&str> = paths.iter().map(|path| get_file_name(path)).collect();
+    assert_eq!(
+      names,
+      [
+        "SotAChatLog_Mira_2024-01-06.zip",
+        "SotAChatLog_Mira_2024-01-05.txt.gz",
+        "SotAChatLog_Mira_2024-01-04.txt",
+        "SotAChatLog_Mira_2024-01-03.zip",
+        "SotAChatLog_Mira_2024-01-02.txt.gz",
+      ]
+    );
+    assert_eq!(get_log_file_date(&paths[0]), NaiveDate::from_ymd_opt(2024, 1, 6));
+    assert_eq!(get_log_file_date(&paths[1]), NaiveDate::from_ymd_opt(2024, 1, 5));
+
+    let timestamps = futures::executor::block_on(get_stats_timestamps(
+      log_paths.clone(),
+      "Mira".into(),
+      Cancel::default(),
+      None,
+      None,
+      AppState::default(),
+    ));
+    assert_eq!(timestamps.len(), 4);
+
+    let date = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+    let ts = NaiveDateTime::new(date, NaiveTime::from_hms_opt(10, 0, 0).unwrap());
+    let stats = futures::executor::block_on(get_stats(
+      log_paths,
+      "Mira".into(),
+      util::local_to_timestamp(ts, None).unwrap(),
+      Cancel::default(),
+    ));
+    let stats: Vec<_> = stats.iter().collect();
+    assert_eq!(stats, [("AdventurerLevel", 101.0), ("Strength", 31.0)]);
+  }
+
+  #[test]
+  fn test_active_avatar() {
+    let dir = TempDir::new("active");
+    let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+    let set_modified = |name: &str, modified: SystemTime| {
+      let path = dir.join(name);
+      fs::write(&path, "").unwrap();
+      File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    };
+
+    // The newest file of each avatar is used.
+    set_modified("SotAChatLog_Mira_2024-01-01.txt", time);
+    set_modified(
+      "SotAChatLog_Mira_2024-01-02.txt",
+      time + std::time::Duration::from_secs(60),
+    );
+    set_modified(
+      "SotAChatLog_Dane_2024-01-03.txt",
+      time + std::time::Duration::from_secs(30),
+    );
+    let (avatars, _) = futures::executor::block_on(get_avatars(vec![dir.to_path_buf()], Cancel::default()));
+    assert_eq!(avatars[1].modified, Some(time + std::time::Duration::from_secs(60)));
+    assert_eq!(find_active_avatar(&avatars), Some("Mira"));
+
+    // Equal times go to the first avatar, which is alphabetical.
+    set_modified(
+      "SotAChatLog_Dane_2024-01-03.txt",
+      time + std::time::Duration::from_secs(60),
+    );
+    let (avatars, _) = futures::executor::block_on(get_avatars(vec![dir.to_path_buf()], Cancel::default()));
+    assert_eq!(find_active_avatar(&avatars), Some("Dane"));
+
+    // Avatars without a modification time are never active.
+    let avatar = |name: &str, modified| AvatarLogs {
+      name: String::from(name),
+      modified,
+    };
+    let avatars = [avatar("Dane", None), avatar("Mira", Some(time))];
+    assert_eq!(find_active_avatar(&avatars), Some("Mira"));
+    assert_eq!(find_active_avatar(&[avatar("Dane", None)]), None);
+    assert_eq!(find_active_avatar(&[]), None);
+  }
+
+  #[test]
+  fn test_multiple_log_folders() {
+    let root = TempDir::new("folders");
+    let first = root.join("first");
+    let second = root.join("second");
+    fs::create_dir_all(&first).unwrap();
+    fs::create_dir_all(&second).unwrap();
+
+    // The same day was logged on both machines, the larger file should win.
+    let text = "[1/2/2024 10:00:00 AM] AdventurerLevel: 100\n";
+    fs::write(first.join("SotAChatLog_Mira_2024-01-02.txt"), text).unwrap();
+    let text = "[1/2/2024 10:00:00 AM] AdventurerLevel: 100\n[1/2/2024 11:00:00 AM] AdventurerLevel: 101\n";
+    fs::write(second.join("SotAChatLog_Mira_2024-01-02.txt"), text).unwrap();
+    fs::write(second.join("SotAChatLog_Dane_2024-01-03.txt"), "").unwrap();
+
+    let log_paths = vec![first.clone(), second.clone()];
+    let paths = get_log_filenames(&log_paths, Some("Mira"), None);
+    assert_eq!(paths, [second.join("SotAChatLog_Mira_2024-01-02.txt")]);
+
+    // The log file for a snapshot's date.
+    let ts = NaiveDate::from_ymd_opt(2024, 1, 2)
+      .unwrap()
+      .and_hms_opt(11, 0, 0)
+      .unwrap();
+    let ts = util::local_to_timestamp(ts, None).unwrap();
+    let path = get_log_file(&log_paths, "Mira", ts);
+    assert_eq!(path, Some(second.join("SotAChatLog_Mira_2024-01-02.txt")));
+    assert_eq!(get_log_file(&log_paths, "Dane", ts), None);
+
+    let (avatars, _) = futures::executor::block_on(get_avatars(log_paths.clone(), Cancel::default()));
+    let names: Vec<&str> = avatars.iter().map(|avatar| avatar.name.as_str()).collect();
+    assert_eq!(names, ["Dane", "Mira"]);
+
+    // A missing folder is reported, an empty one isn't.
+    let empty = root.join("empty");
+    fs::create_dir_all(&empty).unwrap();
+    let avatars = futures::executor::block_on(get_avatars(vec![empty], Cancel::default()));
+    assert_eq!(avatars, (Vec::new(), None));
+
+    // The other folders are still scanned.
+    let missing = root.join("missing");
+    let (avatars, inaccessible) =
+      futures::executor::block_on(get_avatars(vec![missing.clone(), second.clone()], Cancel::default()));
+    let names: Vec<&str> = avatars.iter().map(|avatar| avatar.name.as_str()).collect();
+    assert_eq!(names, ["Dane", "Mira"]);
+    assert_eq!(inaccessible, Some(missing));
+
+    let timestamps = futures::executor::block_on(get_stats_timestamps(
+      log_paths,
+      "Mira".into(),
+      Cancel::default(),
+      None,
+      None,
+      AppState::default(),
+    ));
+    assert_eq!(timestamps.len(), 2);
+  }
+
+  #[test]
+  fn test_stat_history() {
+    let log_path = TempDir::new("history");
+    let text = "[5/1/2024 8:00:00 PM] AdventurerLevel: 100 Strength: 30.5\n\
+      [5/1/2024 8:30:00 PM] Dane says Strength: 99\n\
+      [5/1/2024 9:00:00 PM] AdventurerLevel: 100\n Strength: 31\n\
+      [5/1/2024 9:30:00 PM] AdventurerLevel: 100 Dexterity: 20\n";
+    fs::write(log_path.join("SotAChatLog_Mira_2024-05-01.txt"), text).unwrap();
+    let text = "[4/30/2024 8:00:00 PM] AdventurerLevel: 99 Strength: 29\n";
+    fs::write(log_path.join("SotAChatLog_Mira_2024-04-30.txt"), text).unwrap();
+
+    let history = futures::executor::block_on(get_stat_history(
+      vec![log_path.to_path_buf()],
+      "Mira".into(),
+      "Strength".into(),
+      Cancel::default(),
+      None,
+      AppState::default(),
+    ));
+
+    // Oldest first, continued lines are included and the snapshot without strength is skipped.
+    let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let ts = |date: NaiveDate, hour| util::local_to_timestamp(date.and_hms_opt(hour, 0, 0).unwrap(), None).unwrap();
+    assert_eq!(
+      history,
+      [
+        (ts(date.pred_opt().unwrap(), 20), 29.0),
+        (ts(date, 20), 30.5),
+        (ts(date, 21), 31.0)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_avatars_exp() {
+    let log_path = TempDir::new("exp");
+    let text = "[5/1/2024 8:00:00 PM] Adventurer Experience: 1,000\n\
+      [5/1/2024 8:00:00 PM] Producer Experience: 500\n\
+      [5/1/2024 9:00:00 PM] Adventurer Experience: 2,000\n";
+    fs::write(log_path.join("SotAChatLog_Mira_2024-05-01.txt"), text).unwrap();
+    fs::write(
+      log_path.join("SotAChatLog_Dane_2024-05-01.txt"),
+      "[5/1/2024 8:00:00 PM] Hello\n",
+    )
+    .unwrap();
+
+    let avatars = vec![String::from("Mira"), String::from("Dane")];
+    let threads = ThreadPool::builder().pool_size(2).create().unwrap();
+    let results = futures::executor::block_on(get_avatars_exp(
+      vec![log_path.to_path_buf()],
+      avatars,
+      Cancel::default(),
+      Some(threads),
+      AppState::default(),
+    ));
+
+    // Sorted by name, the most recent values are used and the timestamp is from the latest entry.
+    assert_eq!(results.len(), 2);
+    let (name, exp) = &results[0];
+    assert_eq!(name, "Dane");
+    assert_eq!((exp.adv, exp.prd, exp.ts), (None, None, None));
+
+    let (name, exp) = &results[1];
+    let ts = NaiveDate::from_ymd_opt(2024, 5, 1)
+      .unwrap()
+      .and_hms_opt(21, 0, 0)
+      .unwrap();
+    assert_eq!(name, "Mira");
+    assert_eq!(
+      (exp.adv, exp.prd, exp.ts),
+      (Some(2000), Some(500), Some(util::local_to_timestamp(ts, None).unwrap()))
+    );
+  }
+
+  #[test]
+  fn test_stats_cache() {
+    let root = TempDir::new("stats_cache");
+    let log_path = root.join("logs");
+    fs::create_dir_all(&log_path).unwrap();
+
+    // Files with a /stats entry after some chatter.
+    let chatter = "[1/2/2024 10:00:00 AM] Dane says hello\n".repeat(20);
+    const FILES: usize = 20;
+    for day in 0..FILES {
+      let date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap() + chrono::Days::new(day as u64);
+      let text = format!(
+        "{chatter}[{} 10:30:00 AM] AdventurerLevel: 100\n",
+        date.format("%-m/%-d/%Y")
+      );
+      fs::write(
+        log_path.join(format!("SotAChatLog_Mira_{}.txt", date.format("%F"))),
+        text,
+      )
+      .unwrap();
+    }
+
+    let cache_path = root.join("stats_cache.ron");
+    let scan = |cache: &StatsCache| {
+      futures::executor::block_on(get_stats_timestamps(
+        vec![log_path.clone()],
+        "Mira".into(),
+        Cancel::default(),
+        None,
+        Some(cache.clone()),
+        AppState::default(),
+      ))
+    };
+
+    let cache = StatsCache::new(cache_path.clone()).unwrap();
+    let cold = scan(&cache);
+    let warm = scan(&cache);
+    assert_eq!(cold.len(), FILES);
+    assert_eq!(warm, cold);
+
+    // A file with the same size and modification time isn't parsed again.
+    let path = log_path.join("SotAChatLog_Mira_2023-01-01.txt");
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    let text = fs::read_to_string(&path)
+      .unwrap()
+      .replace("AdventurerLevel", "AdventurerLevex");
+    fs::write(&path, text).unwrap();
+    File::options()
+      .write(true)
+      .open(&path)
+      .unwrap()
+      .set_modified(modified)
+      .unwrap();
+    assert_eq!(scan(&cache).len(), FILES);
+
+    // A file that grows is parsed again.
+    let mut text = fs::read_to_string(&path).unwrap();
+    text.push_str("[1/1/2023 11:00:00 AM] AdventurerLevel: 101\n");
+    text.push_str("[1/1/2023 11:30:00 AM] AdventurerLevel: 101\n");
+    fs::write(&path, text).unwrap();
+    assert_eq!(scan(&cache).len(), FILES + 1);
+
+    // The cache is persisted.
+    drop(cache);
+    assert!(cache_path.is_file());
+    let cache = StatsCache::new(cache_path.clone()).unwrap();
+    assert_eq!(scan(&cache).len(), FILES + 1);
+    drop(cache);
+
+    // Deleting the cache is safe.
+    fs::remove_file(&cache_path).unwrap();
+    let cache = StatsCache::new(cache_path).unwrap();
+    assert_eq!(scan(&cache).len(), FILES + 1);
+    drop(cache);
+  }
+
+  #[test]
+  fn test_partial_lines() {
+    let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let ts = util::local_to_timestamp(date.and_hms_opt(20, 1, 0).unwrap(), None).unwrap();
+
+    // Stats lines that were cut off in the date/time.
+    let line = "[5/1/2024 8:01:00 PM] AdventurerLevel: 100 Strength: 30.5";
+    assert_eq!(get_stats_timestamp(line, date), Some(ts));
+    assert_eq!(
+      get_stats_text(line, ts, date),
+      Some(" AdventurerLevel: 100 Strength: 30.5")
+    );
+    for line in [
+      "[5/1/2024 8:01:00 P",
+      "[5/1/2024 8:0",
+      "[5/1/2024 8:0[5/1/2024 8:01:00 PM] AdventurerLevel: 1",
+    ] {
+      assert_eq!(get_log_date(line), None);
+      assert_eq!(get_stats_timestamp(line, date), None);
+      assert_eq!(get_stats_text(line, ts, date), None);
+    }
+
+    // Damage that was cut off at the end of the line.
+    let line = " Mira attacks Wolf with Fire Arrow and hits, dealing 123 points of damage.";
+    assert_eq!(parse_attack(line), Some(("Mira", "Wolf", Some(123))));
+    let search = Regex::new("^ Mira attacks .+ and hits, dealing [0-9]+ ").unwrap();
+    assert_eq!(find_value(&search, line), Some(123));
+    for line in [
+      " Mira attacks Wolf with Fire Arrow and hits, dealing 12",
+      " Mira attacks Wolf with Fire Arrow and hits, dealing ",
+    ] {
+      assert_eq!(parse_attack(line), None);
+      assert_eq!(find_value(&search, line), None);
+    }
+
+    let text = concat!(
+      "[1/2/2024 10:00:00 AM] Mira attacks Wolf and hits, dealing 10 points of damage.\n",
+      "[1/2/2024 10:00:02 AM] Fluffy<Mira> attacks Wolf and hits, dealing 8 points of damage.\n",
+      "[1/2/2024 10:00:04 AM] Mira attacks Wolf and hits, dealing 12",
+    );
+    let tally = tally_log_text("partial_dps", text);
+    assert_eq!(tally.avatar, 10);
+    assert_eq!(tally.pet, 8);
+    assert_eq!(tally.secs, 3);
+
+    // The partially written line isn't part of the last fight either.
+    let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    let span = find_last_fight(text, "Mira", date, 30).unwrap();
+    assert_eq!(span.end, date.and_hms_opt(10, 0, 2).unwrap());
+
+    // Experience and stats that are still being written.
+    let log_path = TempDir::new("partial");
+    let file_path = log_path.join("SotAChatLog_Mira_2024-05-01.txt");
+    let read_exp =
+      || futures::executor::block_on(get_exp(vec![log_path.to_path_buf()], "Mira".into(), Cancel::default()));
+    let read_stats = || {
+      let future = get_stats(vec![log_path.to_path_buf()], "Mira".into(), ts, Cancel::default());
+      let stats = futures::executor::block_on(future);
+      stats
+        .iter()
+        .map(|(name, value)| (name.to_owned(), value))
+        .collect::<Vec<_>>()
+    };
+
+    let text = "[5/1/2024 8:00:00 PM] Adventurer Experience: 1,234,567\n\
+      [5/1/2024 8:01:00 PM] Adventurer Experience: 1,2";
+    fs::write(&file_path, text).unwrap();
+    assert_eq!(read_exp().adv, Some(1234567));
+
+    let text = "[5/1/2024 8:01:00 PM] AdventurerLevel: 100 Strength: 30.5\n Intelligence: 4";
+    fs::write(&file_path, text).unwrap();
+    assert!(read_stats().is_empty());
+
+    let text = "[5/1/2024 8:01:00 PM] AdventurerLevel: 100 Strength: 30.5\n\
+      [5/1/2024 8:02:00 PM] Mira says hi";
+    fs::write(&file_path, text).unwrap();
+    assert_eq!(
+      read_stats(),
+      [
+        (String::from("AdventurerLevel"), 100.0),
+        (String::from("Strength"), 30.5)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_localized_stats() {
+    let stats = |text| StatsIter::new(text).collect::<Vec<_>>();
+    assert_eq!(
+      stats(" AdventurerLevel: 100 Strength: 30.5 CriticalHitDamage: 1,234.5"),
+      [
+        ("AdventurerLevel", 100.0),
+        ("Strength", 30.5),
+        ("CriticalHitDamage", 1234.5)
+      ]
+    );
+
+    // German.
+    assert_eq!(
+      stats(" AdventurerLevel: 100 Strength: 30,5 CriticalHitDamage: 1.234,5"),
+      [
+        ("AdventurerLevel", 100.0),
+        ("Strength", 30.5),
+        ("CriticalHitDamage", 1234.5)
+      ]
+    );
+
+    // French, with non-breaking and ordinary spaces.
+    assert_eq!(
+      stats(" Strength: 30,5 CriticalHitDamage: 1\u{a0}234,56 MaxHealth: 12 345 Mana: 7"),
+      [
+        ("Strength", 30.5),
+        ("CriticalHitDamage", 1234.56),
+        ("MaxHealth", 12345.0),
+        ("Mana", 7.0)
+      ]
+    );
+
+    // Lone separators are resolved by the other values.
+    assert_eq!(
+      stats(" Strength: 30,5 MaxHealth: 1.234 Focus: 1,234"),
+      [("Strength", 30.5), ("MaxHealth", 1234.0), ("Focus", 1.234)]
+    );
+    assert_eq!(
+      stats(" Strength: 30.5 MaxHealth: 1,234"),
+      [("Strength", 30.5), ("MaxHealth", 1234.0)]
+    );
+
+    // A space after a number only continues it with a group of three digits.
+    assert_eq!(stats(" Level: 12 34"), [("Level", 12.0)]);
+
+    // Experience.
+    let line = "[5/1/2024 8:00:00 PM] Adventurer Experience: 1.234.567";
+    assert_eq!(get_xp(line, ADV_EXP_KEY), Some(1234567));
+    let line = "[5/1/2024 8:00:00 PM] Adventurer Experience: 1\u{a0}234\u{a0}567";
+    assert_eq!(get_xp(line, ADV_EXP_KEY), Some(1234567));
+    let line = "[5/1/2024 8:00:00 PM] Producer Experience: 1,234";
+    assert_eq!(get_xp(line, PRD_EXP_KEY), Some(1234));
+  }
+
+  #[test]
+  fn test_exp_gain() {
+    assert_eq!(
+      get_exp_gain("[5/1/2024 8:00:00 PM] You gained 1,234 experience."),
+      Some(1234)
+    );
+    assert_eq!(
+      get_exp_gain("[5/1/2024 8:00:00 PM] You gain 50 Adventurer Experience."),
+      Some(50)
+    );
+    assert_eq!(
+      get_exp_gain("[5/1/2024 8:00:00 PM] [20:00] You gained 7 experience."),
+      Some(7)
+    );
+
+    // Not adventurer experience gains.
+    assert_eq!(
+      get_exp_gain("[5/1/2024 8:00:00 PM] You gained 30 Producer Experience."),
+      None
+    );
+    assert_eq!(get_exp_gain("[5/1/2024 8:00:00 PM] You gained 3 gold."), None);
+    assert_eq!(
+      get_exp_gain("[5/1/2024 8:00:00 PM] Mira says You gained 5 experience."),
+      None
+    );
+    assert_eq!(get_exp_gain("[5/1/2024 8:00:00 PM] Adventurer Experience: 1,000"), None);
+    assert_eq!(get_exp_gain("You gained 5 experience."), None);
+  }
+
+  #[test]
+  fn test_session_exp() {
+    let log_path = TempDir::new("session");
+    let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let ts =
+      |date: NaiveDate, hour, min| util::local_to_timestamp(date.and_hms_opt(hour, min, 0).unwrap(), None).unwrap();
+    let scan = |since, path, offset| {
+      futures::executor::block_on(get_session_exp(
+        vec![log_path.to_path_buf()],
+        "Mira".into(),
+        since,
+        path,
+        offset,
+        Cancel::default(),
+      ))
+    };
+
+    // Gains before the start of the session are skipped, as is the partially written last line.
+    let first = log_path.join("SotAChatLog_Mira_2024-05-01.txt");
+    let text = "[5/1/2024 7:00:00 PM] You gained 100 experience.\n\
+      [5/1/2024 8:00:00 PM] You gained 200 experience.\n\
+      [5/1/2024 8:30:00 PM] You gained 30";
+    fs::write(&first, text).unwrap();
+    let session = scan(ts(date, 19, 30), None, 0);
+    assert_eq!(session.gains, [(ts(date, 20, 0), 200)]);
+    assert_eq!(session.path.as_ref(), Some(&first));
+    assert_eq!(session.offset, text.rfind('\n').unwrap() as u64 + 1);
+
+    // Only new lines are read.
+    let text = format!("{text}0 experience.\n[5/1/2024 11:59:00 PM] You gained 5 experience.\n");
+    fs::write(&first, &text).unwrap();
+    let session = scan(ts(date, 19, 30), session.path, session.offset);
+    assert_eq!(session.gains, [(ts(date, 20, 30), 300), (ts(date, 23, 59), 5)]);
+    assert_eq!(session.offset, text.len() as u64);
+
+    // The rest of the previous log is read when a new one is started.
+    let text = format!("{text}[5/1/2024 11:59:30 PM] You gained 6 experience.\n");
+    fs::write(&first, &text).unwrap();
+    let second = log_path.join("SotAChatLog_Mira_2024-05-02.txt");
+    fs::write(&second, "[5/2/2024 12:01:00 AM] You gained 7 experience.\n").unwrap();
+    let next = date.succ_opt().unwrap();
+    let session = scan(ts(date, 19, 30), session.path, session.offset);
+    assert_eq!(session.gains, [(ts(date, 23, 59) + 30, 6), (ts(next, 0, 1), 7)]);
+    assert_eq!(session.path.as_ref(), Some(&second));
+  }
+
+  #[test]
+  fn test_log_filename_regex_all() {
+    let regex = get_log_filename_regex(None, None).unwrap();
+    assert!(regex.is_match("SotAChatLog_Mira_2024-01-02.txt"));
+    assert!(regex.is_match("SotAChatLog_Mira Dane_2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog__2024-01-02.txt"));
+    assert!(!regex.is_match("SotAChatLog_Mira_2024-1-2.txt"));
+  }
+}
@@ -1,12 +1,18 @@
-use crate::{log_data, util};
+use crate::{log_data, theme::Color, util};
 use eframe::{
-  egui::{scroll_area::ScrollBarVisibility, Context, Key, RichText, ScrollArea, TextEdit, TextFormat, Ui, Window},
+  egui::{
+    scroll_area::ScrollBarVisibility, text::CCursor, Button, Context, Key, Layout, Modifiers, RichText, ScrollArea,
+    TextEdit, TextFormat, Ui, Window,
+  },
+  emath::Align,
   emath::Align2,
   epaint::{
     text::{LayoutJob, LayoutSection},
     Color32, FontFamily, FontId,
   },
 };
+use num_format::{Locale, ToFormattedString};
+use std::{fs, ops::Range, path::PathBuf};
 use util::{AppState, Cancel, Search};
 
 pub struct LogDlg {
@@ -15,19 +21,35 @@ pub struct LogDlg {
   cancel: Option<Cancel>,
   status: RichText,
   layout: Option<LayoutJob>,
+  find: Option<Find>,
+  search: Option<Search>,
+  count: usize,
+  searching: bool,
+  locale: Locale,
+  all_avatars: bool,
+  file_dlg: Option<egui_file::FileDialog>,
+  error: Option<String>,
   visible: bool,
   init: bool,
 }
 
 /// Dialog window for showing log search results.
 impl LogDlg {
-  pub fn new(state: AppState) -> Self {
+  pub fn new(state: AppState, locale: Locale) -> Self {
     Self {
       title: String::new(),
       state,
       cancel: None,
       status: Default::default(),
       layout: None,
+      find: None,
+      search: None,
+      count: 0,
+      searching: false,
+      locale,
+      all_avatars: false,
+      file_dlg: None,
+      error: None,
       visible: false,
       init: false,
     }
@@ -55,6 +77,18 @@ impl LogDlg {
               });
             });
           } else if let Some(layout_job) = &self.layout {
+            if self.find.as_mut().is_some_and(|find| !find.show(ui, layout_job)) {
+              self.find = None;
+            }
+
+            // Show the find highlights if there are any.
+            let scroll = self.find.as_mut().and_then(Find::take_scroll);
+            let layout_job = self
+              .find
+              .as_ref()
+              .and_then(|find| find.layout.as_ref())
+              .unwrap_or(layout_job);
+
             // Display the text as selectable but not editable.
             let mut text = layout_job.text.as_str();
             if self.init {
@@ -66,14 +100,29 @@ impl LogDlg {
             .max_height(available.height() * 0.75)
             .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
             .show(ui, |ui| {
-              ui.add_sized(
-                ui.available_size(),
-                TextEdit::multiline(&mut text).layouter(&mut |ui: &Ui, _text: &str, wrap: f32| {
-                  let mut layout_job = layout_job.clone();
-                  layout_job.wrap.max_width = wrap;
-                  ui.fonts(|fonts| fonts.layout_job(layout_job))
-                }),
-              );
+              let layout = Layout::centered_and_justified(ui.layout().main_dir());
+              let output = ui
+                .allocate_ui_with_layout(ui.available_size(), layout, |ui| {
+                  let mut layouter = |ui: &Ui, _text: &str, wrap: f32| {
+                    let mut layout_job = layout_job.clone();
+                    layout_job.wrap.max_width = wrap;
+                    ui.fonts(|fonts| fonts.layout_job(layout_job))
+                  };
+                  TextEdit::multiline(&mut text).layouter(&mut layouter).show(ui)
+                })
+                .inner;
+
+              // Scroll to the current find match.
+              if let Some(range) = scroll {
+                let index = get_galley_index(layout_job, range.start);
+                let rect = output.galley.pos_from_ccursor(CCursor::new(index));
+                ui.scroll_to_rect(rect.translate(output.galley_pos.to_vec2()), Some(Align::Center));
+              }
+            });
+          }
+          if let Some(error) = &self.error {
+            ui.vertical_centered(|ui| {
+              ui.label(RichText::from(error).color(Color::Error.get()));
             });
           }
           ui.separator();
@@ -81,65 +130,420 @@ impl LogDlg {
             if ui.button("Close").clicked() {
               self.close();
             }
+
+            ui.add_enabled_ui(
+              !self.searching && self.layout.is_some() && self.file_dlg.is_none(),
+              |ui| {
+                if ui.button("Export...").clicked() {
+                  self.choose_export_path(ctx);
+                }
+              },
+            );
+
+            if self.searching && ui.button("Stop").clicked() {
+              self.stop();
+            }
+
+            // Running count of the matching lines.
+            if self.count > 0 {
+              ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let count = self.count.to_formatted_string(&self.locale);
+                let text = if self.searching {
+                  format!("{count} found, searching...")
+                } else {
+                  format!("{count} found")
+                };
+                ui.label(text);
+              });
+            }
           });
         });
       if !open {
         self.close();
       }
+
+      // Export file dialog.
+      if let Some(file_dlg) = &mut self.file_dlg {
+        if !file_dlg.show(ctx).visible() {
+          if file_dlg.selected() {
+            if let Some(path) = file_dlg.path() {
+              let path = path.to_owned();
+              self.export(path);
+            }
+          }
+          self.file_dlg = None;
+        }
+      }
     }
   }
 
-  pub fn open(&mut self, avatar: &str, cancel: Cancel) {
+  pub fn visible(&self) -> bool {
+    self.visible
+  }
+
+  /// Open the dialog. If `avatar` is `None` then the results are for all avatars.
+  pub fn open(&mut self, avatar: Option<&str>, search: Search, cancel: Cancel) {
     if !self.visible {
       self.state.set_disabled(false);
-      self.title = format!("🗊  Search Results ({avatar})");
+      self.title = format!("🗊  Search Results ({})", avatar.unwrap_or("All Avatars"));
+      self.all_avatars = avatar.is_none();
       self.status = RichText::from("Processing...").color(Color32::from_rgb(229, 187, 123));
+      self.find = None;
+      self.search = Some(search);
+      self.count = 0;
+      self.searching = true;
       self.cancel = Some(cancel);
       self.visible = true;
       self.init = true;
     }
   }
 
-  pub fn set_text(&mut self, text: String, search: Search, ctx: &Context) {
-    if self.visible {
-      if text.is_empty() {
-        self.layout = None;
+  /// Append results to the search results.
+  pub fn append_text(&mut self, text: String, ctx: &Context) {
+    if !self.visible || !self.searching || text.is_empty() {
+      return;
+    }
+
+    let Some(search) = &self.search else {
+      return;
+    };
+
+    let font = FontId::new(14.0, FontFamily::Monospace);
+    let color = ctx.style().visuals.text_color();
+    self.count += text.lines().count();
+
+    let layout_job = layout_text(text, search, font, color, self.all_avatars);
+    match &mut self.layout {
+      Some(layout) => {
+        // Offset the new sections to the end of the existing text.
+        let offset = layout.text.len();
+        layout.text.push_str(&layout_job.text);
+        layout
+          .sections
+          .extend(layout_job.sections.into_iter().map(|mut section| {
+            section.byte_range = section.byte_range.start + offset..section.byte_range.end + offset;
+            section
+          }));
+      }
+      None => self.layout = Some(layout_job),
+    }
+
+    // Find again in the new results.
+    if let Some(find) = &mut self.find {
+      find.reset();
+    }
+
+    self.status = Default::default();
+    ctx.request_repaint();
+  }
+
+  /// The search is complete.
+  pub fn finish(&mut self, ctx: &Context) {
+    if self.visible && self.searching {
+      self.searching = false;
+      self.cancel = None;
+      if self.layout.is_none() {
         self.status = RichText::from("Nothing Found").color(Color32::from_rgb(229, 187, 123));
-      } else {
-        let font = FontId::new(14.0, FontFamily::Monospace);
-        let color = ctx.style().visuals.text_color();
-        self.layout = Some(layout_text(text, search, font, color));
-        self.status = Default::default();
       }
       ctx.request_repaint();
     }
   }
 
+  /// Stop the search, keeping the results found so far.
+  fn stop(&mut self) {
+    if let Some(mut cancel) = self.cancel.take() {
+      cancel.cancel();
+    }
+
+    self.state.set_busy(false);
+    self.searching = false;
+    if self.layout.is_none() {
+      self.status = RichText::from("Nothing Found").color(Color32::from_rgb(229, 187, 123));
+    }
+  }
+
   fn close(&mut self) {
     if self.visible {
-      if let Some(mut cancel) = self.cancel.take() {
+      if self.searching {
         // Cancel the search if it's still outstanding.
-        cancel.cancel();
+        self.stop();
       }
 
       self.state.set_disabled(false);
       self.status = Default::default();
       self.layout = None;
+      self.find = None;
+      self.search = None;
+      self.file_dlg = None;
+      self.error = None;
       self.visible = false;
     }
   }
 
+  fn choose_export_path(&mut self, ctx: &Context) {
+    let path = dirs::home_dir().map(|path| path.join("search_results.txt"));
+    let available = ctx.available_rect().size();
+    let mut file_dlg = egui_file::FileDialog::save_file(path)
+      .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+      .current_pos([0.0, 24.0])
+      .default_size([available.x, available.y * 0.5])
+      .show_new_folder(false)
+      .resizable(false);
+    file_dlg.open();
+
+    self.error = None;
+    self.file_dlg = Some(file_dlg);
+  }
+
+  /// Write the search results to a text file.
+  fn export(&mut self, path: PathBuf) {
+    let Some(layout_job) = &self.layout else {
+      return;
+    };
+
+    // Use the "txt" extension if there isn't one.
+    let path = if path.extension().is_none() {
+      path.with_extension("txt")
+    } else {
+      path
+    };
+
+    if let Err(err) = fs::write(&path, &layout_job.text) {
+      self.error = Some(format!("Unable to export to {path:?}: {err}"));
+    }
+  }
+
   fn handle_hotkeys(&mut self, ctx: &Context) {
+    // Let the file dialog handle the keys while it's open.
+    if self.file_dlg.is_some() {
+      return;
+    }
+
+    if self.layout.is_some() && ctx.input_mut(|state| state.consume_key(Modifiers::COMMAND, Key::F)) {
+      self.find.get_or_insert_with(Find::default).focus = true;
+      return;
+    }
+
     if ctx.input(|state| state.key_pressed(Key::Escape)) {
-      self.close();
+      // Close the find bar first.
+      if self.find.take().is_none() {
+        self.close();
+      }
+    }
+  }
+}
+
+/// Find bar for finding text within the results.
+#[derive(Default)]
+struct Find {
+  text: String,
+  matches: Vec<Range<usize>>,
+  current: usize,
+
+  /// Results with the matches highlighted. Needs to be updated if `None`.
+  layout: Option<LayoutJob>,
+  updated: bool,
+  scroll: bool,
+  focus: bool,
+}
+
+impl Find {
+  /// Show the find bar above the results. Returns false if it was closed.
+  fn show(&mut self, ui: &mut Ui, layout_job: &LayoutJob) -> bool {
+    if !self.updated {
+      self.updated = true;
+      self.matches = find_all(&layout_job.text, &self.text);
+      self.current = self.current.min(self.matches.len().saturating_sub(1));
+      self.layout = None;
+    }
+
+    let mut step = 0;
+    let mut open = true;
+    ui.horizontal(|ui| {
+      ui.label("Find");
+      let response = ui.add(TextEdit::singleline(&mut self.text).desired_width(200.0));
+      if self.focus {
+        self.focus = false;
+        response.request_focus();
+      }
+
+      if response.changed() {
+        self.matches = find_all(&layout_job.text, &self.text);
+        self.current = 0;
+        self.layout = None;
+        self.scroll = true;
+      }
+
+      // Enter goes to the next match and shift+enter goes to the previous one.
+      if response.lost_focus() && ui.input(|state| state.key_pressed(Key::Enter)) {
+        step = if ui.input(|state| state.modifiers.shift) { -1 } else { 1 };
+        response.request_focus();
+      }
+
+      let enabled = !self.matches.is_empty();
+      if ui
+        .add_enabled(enabled, Button::new("⏶"))
+        .on_hover_text("Previous (Shift+Enter)")
+        .clicked()
+      {
+        step = -1;
+      }
+
+      if ui
+        .add_enabled(enabled, Button::new("⏷"))
+        .on_hover_text("Next (Enter)")
+        .clicked()
+      {
+        step = 1;
+      }
+
+      if !self.text.is_empty() {
+        let text = if self.matches.is_empty() {
+          String::from("No matches")
+        } else {
+          format!("{} of {}", self.current + 1, self.matches.len())
+        };
+        ui.label(text);
+      }
+
+      ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+        if ui.button("✖").on_hover_text("Close the find bar (Esc)").clicked() {
+          open = false;
+        }
+      });
+    });
+
+    let count = self.matches.len();
+    if step != 0 && count > 0 {
+      self.current = (self.current as isize + step).rem_euclid(count as isize) as usize;
+      self.layout = None;
+      self.scroll = true;
+    }
+
+    if self.layout.is_none() && !self.matches.is_empty() {
+      self.layout = Some(highlight_matches(layout_job, &self.matches, self.current));
+    }
+
+    open
+  }
+
+  /// Find the matches again without scrolling, e.g. when there are new results.
+  fn reset(&mut self) {
+    self.current = 0;
+    self.updated = false;
+    self.scroll = false;
+  }
+
+  /// Take the match to scroll to, if there is one.
+  fn take_scroll(&mut self) -> Option<Range<usize>> {
+    if !std::mem::take(&mut self.scroll) {
+      return None;
+    }
+    self.matches.get(self.current).cloned()
+  }
+}
+
+/// Find all occurrences of `term` (ignoring case) in `text`.
+fn find_all(text: &str, term: &str) -> Vec<Range<usize>> {
+  let search = Search::String {
+    find: term.to_owned(),
+    ignore_case: true,
+  };
+
+  let mut matches = Vec::new();
+  let mut pos = 0;
+  while let Some(range) = search.find_in(&text[pos..]) {
+    if range.is_empty() {
+      break;
+    }
+
+    let range = pos + range.start..pos + range.end;
+    pos = range.end;
+    matches.push(range);
+  }
+  matches
+}
+
+/// Split the sections at the matches and give the matches a background, keeping the original text colors.
+fn highlight_matches(layout_job: &LayoutJob, matches: &[Range<usize>], current: usize) -> LayoutJob {
+  const MATCH_COLOR: Color32 = Color32::from_rgb(80, 70, 30);
+  const CURRENT_COLOR: Color32 = Color32::from_rgb(150, 110, 30);
+  let mut sections = Vec::with_capacity(layout_job.sections.len() + matches.len() * 2);
+  let mut matches = matches.iter().enumerate().peekable();
+  for section in &layout_job.sections {
+    let (mut start, end) = (section.byte_range.start, section.byte_range.end);
+    while start < end {
+      // Skip the matches that are before this point.
+      while matches.next_if(|(_, range)| range.end <= start).is_some() {}
+
+      let (next, background) = match matches.peek() {
+        Some((index, range)) if range.start <= start => {
+          let color = if *index == current { CURRENT_COLOR } else { MATCH_COLOR };
+          (range.end.min(end), color)
+        }
+        Some((_, range)) if range.start < end => (range.start, section.format.background),
+        _ => (end, section.format.background),
+      };
+
+      let leading_space = if start == section.byte_range.start {
+        section.leading_space
+      } else {
+        0.0
+      };
+      let format = TextFormat {
+        background,
+        ..section.format.clone()
+      };
+      sections.push(LayoutSection {
+        leading_space,
+        byte_range: start..next,
+        format,
+      });
+      start = next;
     }
   }
+
+  LayoutJob {
+    sections,
+    ..layout_job.clone()
+  }
+}
+
+/// Get the character index in the laid out text for a byte position. Text that isn't in a section isn't laid out.
+fn get_galley_index(layout_job: &LayoutJob, pos: usize) -> usize {
+  let text = &layout_job.text;
+  layout_job
+    .sections
+    .iter()
+    .take_while(|section| section.byte_range.start < pos)
+    .map(|section| {
+      text[section.byte_range.start..section.byte_range.end.min(pos)]
+        .chars()
+        .count()
+    })
+    .sum()
 }
 
-/// Construct a `LayoutJob` for highlighted results.
-fn layout_text(text: String, search: Search, font: FontId, color: Color32) -> LayoutJob {
+/// Construct a `LayoutJob` for highlighted results. If `all_avatars` is true then each line is prefixed with the
+/// avatar's name.
+fn layout_text(text: String, search: &Search, font: FontId, color: Color32, all_avatars: bool) -> LayoutJob {
   let mut sections = Vec::new();
   for line in text.lines() {
+    // Highlight the avatar name.
+    let line = match line.split_once(log_data::AVATAR_PREFIX_END) {
+      Some((avatar, rest)) if all_avatars => {
+        const AVATAR_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+        let pos = util::offset(&text, avatar).unwrap();
+        sections.push(LayoutSection {
+          leading_space: 0.0,
+          byte_range: pos..pos + avatar.len() + log_data::AVATAR_PREFIX_END.len(),
+          format: TextFormat::simple(font.clone(), AVATAR_COLOR),
+        });
+        rest
+      }
+      _ => line,
+    };
+
     // Highlight the date/time.
     if let Some(date) = log_data::get_log_date(line) {
       const DATE_COLOR: Color32 = Color32::from_rgb(180, 154, 102);
@@ -198,3 +602,69 @@ fn layout_text(text: String, search: Search, font: FontId, color: Color32) -> La
     ..Default::default()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_find_all() {
+    assert_eq!(find_all("Hit the Orc, orc hits", "orc"), vec![8..11, 13..16]);
+    assert_eq!(find_all("aaaa", "aa"), vec![0..2, 2..4]);
+    assert_eq!(find_all("Fünf fünf", "fünf"), vec![0..5, 6..11]);
+    assert_eq!(find_all("FÜNF Über", "ü"), vec![1..3, 6..8]);
+    assert!(find_all("text", "").is_empty());
+    assert!(find_all("te", "text").is_empty());
+  }
+
+  #[test]
+  fn test_highlight_matches() {
+    let font = FontId::new(14.0, FontFamily::Monospace);
+    let section = |range: Range<usize>, color| LayoutSection {
+      leading_space: 0.0,
+      byte_range: range,
+      format: TextFormat::simple(font.clone(), color),
+    };
+
+    // The date/time is colored and there's a gap that isn't laid out.
+    let layout_job = LayoutJob {
+      text: String::from("[12:00] x Orc hits orc\n"),
+      sections: vec![section(0..7, Color32::GOLD), section(10..23, Color32::WHITE)],
+      ..Default::default()
+    };
+
+    let matches = find_all(&layout_job.text, "orc");
+    assert_eq!(matches, vec![10..13, 19..22]);
+
+    let highlighted = highlight_matches(&layout_job, &matches, 1);
+    let ranges: Vec<_> = highlighted
+      .sections
+      .iter()
+      .map(|section| section.byte_range.clone())
+      .collect();
+    assert_eq!(ranges, vec![0..7, 10..13, 13..19, 19..22, 22..23]);
+
+    // The original colors are kept.
+    assert_eq!(highlighted.sections[0].format.color, Color32::GOLD);
+    assert!(highlighted.sections[1..]
+      .iter()
+      .all(|section| section.format.color == Color32::WHITE));
+
+    // Only the matches have a background, and the current one is different.
+    let backgrounds: Vec<_> = highlighted
+      .sections
+      .iter()
+      .map(|section| section.format.background)
+      .collect();
+    assert_eq!(backgrounds[0], Color32::TRANSPARENT);
+    assert_eq!(backgrounds[2], Color32::TRANSPARENT);
+    assert_eq!(backgrounds[4], Color32::TRANSPARENT);
+    assert_ne!(backgrounds[1], Color32::TRANSPARENT);
+    assert_ne!(backgrounds[3], Color32::TRANSPARENT);
+    assert_ne!(backgrounds[1], backgrounds[3]);
+
+    // The gap isn't counted.
+    assert_eq!(get_galley_index(&highlighted, 10), 7);
+    assert_eq!(get_galley_index(&highlighted, 19), 16);
+  }
+}
@@ -0,0 +1,323 @@
+use crate::{log_data, util::Cancel};
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+  thread::{self, JoinHandle},
+  time::{Duration, Instant, SystemTime},
+};
+
+/// Text that was appended to an avatar's chat log.
+#[derive(Clone)]
+pub struct LogChange {
+  pub avatar: String,
+  pub text: String,
+}
+
+/// Event reported by the log watcher.
+#[derive(Clone)]
+pub enum LogEvent {
+  /// Text was appended to an avatar's chat log.
+  Change(LogChange),
+
+  /// The log folders can be read again after being inaccessible (e.g. a network share that was down).
+  Available,
+}
+
+/// Watches the log folders for chat log changes. One polling thread reports the changes to all the subscribers.
+pub struct LogWatcher {
+  subscribers: Arc<Mutex<Subscribers>>,
+  cancel: Option<Cancel>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl LogWatcher {
+  pub fn new(log_paths: Vec<PathBuf>) -> Self {
+    let subscribers = Arc::new(Mutex::new(Subscribers::default()));
+    let cancel = Cancel::default();
+    let thread = Some(watch(log_paths, subscribers.clone(), cancel.clone()));
+    Self {
+      subscribers,
+      cancel: Some(cancel),
+      thread,
+    }
+  }
+
+  /// Report log events to `notify` until the subscription is dropped. `notify` is called without the subscribers
+  /// locked, so an event that was already on its way can still arrive right after the subscription is dropped.
+  pub fn subscribe<F: Fn(LogEvent) + Send + Sync + 'static>(&self, notify: F) -> LogSubscription {
+    let mut subscribers = self.subscribers.lock().unwrap();
+    let id = subscribers.next_id;
+    subscribers.next_id += 1;
+    subscribers.map.insert(
+      id,
+      Subscriber {
+        notify: Arc::new(notify),
+        held: None,
+      },
+    );
+
+    LogSubscription {
+      id,
+      subscribers: self.subscribers.clone(),
+    }
+  }
+
+  /// Watch different log folders, keeping the subscribers.
+  pub fn set_log_paths(&mut self, log_paths: Vec<PathBuf>) {
+    self.stop();
+
+    let cancel = Cancel::default();
+    self.thread = Some(watch(log_paths, self.subscribers.clone(), cancel.clone()));
+    self.cancel = Some(cancel);
+  }
+
+  pub fn stop(&mut self) {
+    // Cancel the watcher thread.
+    if let Some(mut cancel) = self.cancel.take() {
+      cancel.cancel();
+    }
+
+    // Wait for it to join.
+    if let Some(thread) = self.thread.take() {
+      thread.join().unwrap();
+    }
+  }
+}
+
+impl Drop for LogWatcher {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+/// Receives log events from a [`LogWatcher`] until dropped.
+pub struct LogSubscription {
+  id: u64,
+  subscribers: Arc<Mutex<Subscribers>>,
+}
+
+impl LogSubscription {
+  /// Hold back events until resumed. Events from while paused are reported after resuming.
+  pub fn pause(&self, paused: bool) {
+    loop {
+      let (notify, held) = {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let Some(subscriber) = subscribers.map.get_mut(&self.id) else {
+          return;
+        };
+
+        if paused {
+          subscriber.held.get_or_insert_with(Vec::new);
+          return;
+        }
+
+        // Stay paused until nothing more was held back while delivering, so that the events stay in order.
+        match &mut subscriber.held {
+          Some(held) if !held.is_empty() => (subscriber.notify.clone(), std::mem::take(held)),
+          _ => {
+            subscriber.held = None;
+            return;
+          }
+        }
+      };
+
+      for event in held {
+        notify(event);
+      }
+    }
+  }
+}
+
+impl Drop for LogSubscription {
+  fn drop(&mut self) {
+    self.subscribers.lock().unwrap().map.remove(&self.id);
+  }
+}
+
+type Notify = Arc<dyn Fn(LogEvent) + Send + Sync>;
+
+struct Subscriber {
+  notify: Notify,
+
+  /// Events held back while paused.
+  held: Option<Vec<LogEvent>>,
+}
+
+#[derive(Default)]
+struct Subscribers {
+  next_id: u64,
+  map: HashMap<u64, Subscriber>,
+}
+
+impl Subscribers {
+  /// Hold back the event for the paused subscribers. Returns the callbacks of the others, which are called after
+  /// unlocking.
+  fn notify(&mut self, event: &LogEvent) -> Vec<Notify> {
+    let mut result = Vec::new();
+    for subscriber in self.map.values_mut() {
+      let Some(held) = &mut subscriber.held else {
+        result.push(subscriber.notify.clone());
+        continue;
+      };
+
+      // Combine text appended to the same chat log while paused.
+      if let (Some(LogEvent::Change(last)), LogEvent::Change(change)) = (held.last_mut(), event) {
+        if last.avatar == change.avatar {
+          last.text.push_str(&change.text);
+          continue;
+        }
+      }
+      held.push(event.clone());
+    }
+    result
+  }
+}
+
+/// Start the polling thread.
+fn watch(log_paths: Vec<PathBuf>, subscribers: Arc<Mutex<Subscribers>>, cancel: Cancel) -> JoinHandle<()> {
+  thread::spawn(move || {
+    let notify = |event: LogEvent| {
+      let notify = subscribers.lock().unwrap().notify(&event);
+      for notify in notify {
+        notify(event.clone());
+      }
+    };
+
+    // Start with the current file lengths so that only new text is reported.
+    let mut files = get_watched(&log_paths);
+    let mut accessible = log_data::find_inaccessible_log_path(&log_paths).is_none();
+    let mut pending = None;
+
+    loop {
+      // Wait for the poll interval.
+      let instant = Instant::now();
+      while instant.elapsed() < POLL_INTERVAL {
+        if cancel.is_canceled() {
+          return;
+        }
+
+        // We need to sleep for some actual amount of time or this thread will peg one of the cores.
+        thread::sleep(Duration::from_millis(10));
+      }
+
+      // Keep the watched files while a folder can't be read, otherwise they would all be reported as new.
+      if log_data::find_inaccessible_log_path(&log_paths).is_some() {
+        accessible = false;
+        continue;
+      }
+
+      if !accessible {
+        // Start over with the current file lengths.
+        accessible = true;
+        files = get_watched(&log_paths);
+        pending = None;
+        notify(LogEvent::Available);
+        continue;
+      }
+
+      // Check for new or modified files.
+      let current = get_file_info(&log_paths);
+      let mut changed = false;
+      files.retain(|path, _| current.contains_key(path));
+      for (path, info) in current {
+        if let Some(watched) = files.get_mut(&path) {
+          if watched.info != info {
+            watched.info = info;
+            changed = true;
+          }
+        } else {
+          files.insert(path, Watched { offset: 0, info });
+          changed = true;
+        }
+      }
+
+      if changed && pending.is_none() {
+        pending = Some(Instant::now());
+      }
+
+      // Wait for writes to settle, but don't wait forever if the log is constantly being written.
+      let Some(since) = pending else { continue };
+      if changed && since.elapsed() < MAX_DELAY {
+        continue;
+      }
+
+      pending = None;
+      for (path, watched) in &mut files {
+        let len = watched.info.0;
+        if len == watched.offset {
+          continue;
+        }
+
+        // The file was truncated or replaced, start from the beginning.
+        if len < watched.offset {
+          watched.offset = 0;
+        }
+
+        if let Some(avatar) = log_data::get_log_file_avatar(log_data::get_file_name(path)) {
+          if let Some(text) = read_range(path, watched.offset, len) {
+            notify(LogEvent::Change(LogChange {
+              avatar: avatar.to_owned(),
+              text,
+            }));
+          }
+        }
+
+        watched.offset = len;
+      }
+    }
+  })
+}
+
+/// How often the log folders are checked. Changes must settle for this long before being reported.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum amount of time to wait for changes to settle.
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// File length and modification time.
+type FileInfo = (u64, Option<SystemTime>);
+
+struct Watched {
+  /// Length of the file that has been reported.
+  offset: u64,
+
+  /// Most recent file info.
+  info: FileInfo,
+}
+
+/// Get the chat logs in the folders, with their current lengths as the reported offsets.
+fn get_watched(log_paths: &[PathBuf]) -> HashMap<PathBuf, Watched> {
+  get_file_info(log_paths)
+    .into_iter()
+    .map(|(path, info)| (path, Watched { offset: info.0, info }))
+    .collect()
+}
+
+/// Get the file info for all the chat logs in the folders.
+fn get_file_info(log_paths: &[PathBuf]) -> HashMap<PathBuf, FileInfo> {
+  let mut files = HashMap::new();
+  for path in log_data::get_log_filenames(log_paths, None, None) {
+    // Archived logs aren't written to.
+    if log_data::is_log_archive(&path) {
+      continue;
+    }
+
+    if let Ok(metadata) = path.metadata() {
+      files.insert(path, (metadata.len(), metadata.modified().ok()));
+    }
+  }
+  files
+}
+
+/// Read the text between the specified offsets.
+fn read_range(path: &Path, begin: u64, end: u64) -> Option<String> {
+  let mut file = ok!(File::open(path), None);
+  ok!(file.seek(SeekFrom::Start(begin)), None);
+
+  let mut bytes = Vec::new();
+  ok!(file.take(end - begin).read_to_end(&mut bytes), None);
+  Some(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -6,52 +6,96 @@ mod util;
 
 mod about_dlg;
 mod app;
+mod changes_dlg;
 mod chronometer;
+mod cli;
 mod config;
 mod confirm_dlg;
+mod decks_dlg;
 mod dps_dlg;
 mod ethos;
 mod experience;
 mod farming;
+mod folders_dlg;
 mod game_data;
+mod game_folders;
+mod history_dlg;
 mod items_dlg;
+mod keymap;
+mod keymap_dlg;
 mod log_data;
 mod log_dlg;
+mod log_watcher;
+mod message_dlg;
 mod notes_dlg;
+mod ntp;
 mod offline;
+mod override_dlg;
 mod plant_dlg;
 mod plant_info;
+mod planting_dlg;
+mod rift_dlg;
 mod search_dlg;
 mod skill_info;
 mod stats;
+mod stats_cache;
 mod storage;
+mod theme;
 mod towns_dlg;
+mod update;
+mod virtues_dlg;
 
 use app::App;
 use config::Config;
 use eframe::{
   egui::{IconData, ViewportBuilder},
+  epaint::Vec2,
   AppCreator,
 };
+use std::process::ExitCode;
 use util::{APP_ICON, APP_NAME, APP_TITLE};
 
-fn main() {
-  let config = Config::new().unwrap();
+fn main() -> ExitCode {
+  // Store the configuration next to the executable with `--portable`.
+  let portable = std::env::args().skip(1).any(|arg| arg == "--portable");
+  let config = Config::new(portable).unwrap();
+
+  // Run headless commands without showing the window.
+  match cli::parse_args(std::env::args().skip(1)) {
+    Ok(Some(invocation)) => return cli::run(invocation, &config),
+    Ok(None) => (),
+    Err(err) => {
+      eprintln!("{APP_NAME}: {err}");
+      return ExitCode::FAILURE;
+    }
+  }
+
   let icon = image::load_from_memory(APP_ICON).unwrap();
   let icon = IconData {
     width: icon.width(),
     height: icon.height(),
     rgba: icon.into_rgba8().into_raw(),
   };
-  let viewport = ViewportBuilder::default()
-    .with_resizable(false)
-    .with_maximize_button(false)
-    .with_inner_size(App::inner_window_size())
-    .with_max_inner_size(App::inner_window_size())
-    .with_min_inner_size(App::inner_window_size())
+
+  // Restore the last window size, but don't go below the minimum.
+  let min_size = App::inner_window_size();
+  let size = match config.get_window_size() {
+    Some((width, height)) => Vec2::new(width, height).max(min_size),
+    None => min_size,
+  };
+
+  let mut viewport = ViewportBuilder::default()
+    .with_inner_size(size)
+    .with_min_inner_size(min_size)
     .with_title(APP_TITLE)
     .with_icon(icon);
 
+  // Restore the last window position if it could still be on a screen. The app checks the monitor once the window is
+  // shown.
+  if let Some((pos, _)) = config.get_window_pos().filter(|&(pos, _)| App::is_window_pos_sane(pos)) {
+    viewport = viewport.with_position(pos);
+  }
+
   let options = eframe::NativeOptions {
     viewport,
     ..Default::default()
@@ -59,4 +103,5 @@ fn main() {
 
   let creator: AppCreator = Box::new(move |cc| Ok(Box::new(App::new(cc, config))));
   eframe::run_native(APP_NAME, options, creator).unwrap();
+  ExitCode::SUCCESS
 }
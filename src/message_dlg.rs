@@ -0,0 +1,70 @@
+use crate::{theme::Color, util::AppState};
+use eframe::{
+  egui::{Context, Key, RichText, Window},
+  emath::Align2,
+};
+
+/// Dialog window that shows a warning message.
+pub struct MessageDlg {
+  state: AppState,
+  title: String,
+  text: String,
+  visible: bool,
+}
+
+impl MessageDlg {
+  pub fn new(state: AppState) -> Self {
+    Self {
+      state,
+      title: String::new(),
+      text: String::new(),
+      visible: false,
+    }
+  }
+
+  pub fn show(&mut self, ctx: &Context) {
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      let mut open = true;
+      Window::new(RichText::from(format!("⚠  {}", self.title)).strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          ui.label(RichText::from(&self.text).color(Color::Error.get()));
+          ui.separator();
+          if ui.button("OK").clicked() {
+            self.close();
+          }
+        });
+      if !open {
+        self.close();
+      }
+    }
+  }
+
+  pub fn open(&mut self, title: &str, text: String) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      title.clone_into(&mut self.title);
+      self.text = text;
+      self.visible = true;
+    }
+  }
+
+  fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
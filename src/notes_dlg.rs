@@ -1,30 +1,34 @@
-use crate::util::AppState;
+use crate::{config::Config, util::AppState};
 use eframe::{
   egui::{
-    scroll_area::ScrollBarVisibility, text::CCursor, text_selection::CCursorRange, Context, Key, RichText, ScrollArea,
-    TextEdit, Window,
+    scroll_area::ScrollBarVisibility, text::CCursor, text_selection::CCursorRange, Button, Context, Key, RichText,
+    ScrollArea, TextEdit, TextStyle, Ui, Window,
   },
   emath::Align2,
 };
 
 pub struct NotesDlg {
+  config: Config,
   state: AppState,
   title: String,
   text: String,
   result: Option<String>,
   visible: bool,
+  preview: bool,
   init: bool,
 }
 
 // Dialog window for editing avatar notes.
 impl NotesDlg {
-  pub fn new(state: AppState) -> Self {
+  pub fn new(config: Config, state: AppState) -> Self {
     Self {
+      config,
       state,
       title: String::new(),
       text: String::new(),
       result: None,
       visible: false,
+      preview: false,
       init: false,
     }
   }
@@ -52,6 +56,11 @@ impl NotesDlg {
           .max_height(available.height() * 0.5)
           .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
           .show(ui, |ui| {
+            if self.preview {
+              show_preview(ui, &self.text);
+              return;
+            }
+
             let response = ui.add_sized(ui.available_size(), TextEdit::multiline(&mut self.text).code_editor());
 
             if self.init {
@@ -76,6 +85,24 @@ impl NotesDlg {
             if ui.button("Cancel").clicked() {
               self.reject();
             }
+
+            ui.separator();
+            ui.selectable_value(&mut self.preview, false, "Edit");
+            ui.selectable_value(&mut self.preview, true, "View");
+
+            ui.separator();
+            if ui.button("New from Template").clicked() {
+              self.insert_template();
+            }
+
+            let enabled = !self.text.is_empty();
+            let button = Button::new("Save as Template");
+            let response = ui
+              .add_enabled(enabled, button)
+              .on_hover_text("Use these notes as the template");
+            if response.clicked() {
+              self.config.set_notes_template(self.text.clone());
+            }
           });
         });
       if !open {
@@ -92,6 +119,7 @@ impl NotesDlg {
       self.text = text;
       self.result = None;
       self.visible = true;
+      self.preview = false;
       self.init = true;
     }
   }
@@ -100,6 +128,19 @@ impl NotesDlg {
     self.result.take()
   }
 
+  fn insert_template(&mut self) {
+    let template = self.config.get_notes_template();
+    if !self.text.is_empty() {
+      // Separate the template from the existing notes with a blank line.
+      if !self.text.ends_with('\n') {
+        self.text.push('\n');
+      }
+      self.text.push('\n');
+    }
+    self.text.push_str(&template);
+    self.preview = false;
+  }
+
   fn accept(&mut self) {
     if self.visible {
       self.state.set_disabled(false);
@@ -124,3 +165,200 @@ impl NotesDlg {
     }
   }
 }
+
+/// Render the notes with basic structure: headings, bullet lists and links.
+fn show_preview(ui: &mut Ui, text: &str) {
+  ui.set_min_width(ui.available_width());
+  for line in text.lines() {
+    match parse_block(line) {
+      Block::Heading(level, text) => {
+        let text = RichText::from(text).strong();
+        ui.label(match level {
+          1 => text.text_style(TextStyle::Heading),
+          2 => text.size(TextStyle::Heading.resolve(ui.style()).size * 0.85),
+          _ => text,
+        });
+      }
+      Block::Bullet(indent, text) => {
+        ui.horizontal_wrapped(|ui| {
+          ui.add_space(indent as f32 * 8.0);
+          ui.label("•");
+          show_spans(ui, text);
+        });
+      }
+      Block::Text(text) => {
+        ui.horizontal_wrapped(|ui| show_spans(ui, text));
+      }
+      Block::Blank => {
+        ui.add_space(ui.spacing().item_spacing.y);
+      }
+    }
+  }
+}
+
+fn show_spans(ui: &mut Ui, text: &str) {
+  ui.spacing_mut().item_spacing.x = 0.0;
+  for span in parse_spans(text) {
+    match span {
+      Span::Text(text) => {
+        ui.label(text);
+      }
+      Span::Link(text, url) => {
+        // Egui asks the OS to open the link.
+        ui.hyperlink_to(text, url).on_hover_text(url);
+      }
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum Block<'a> {
+  /// Heading level and text.
+  Heading(usize, &'a str),
+
+  /// Indent level and text.
+  Bullet(usize, &'a str),
+  Text(&'a str),
+  Blank,
+}
+
+fn parse_block(line: &str) -> Block<'_> {
+  let trimmed = line.trim_start();
+  if trimmed.is_empty() {
+    return Block::Blank;
+  }
+
+  let level = trimmed.chars().take_while(|&ch| ch == '#').count();
+  if level > 0 {
+    if let Some(text) = trimmed[level..].strip_prefix(' ') {
+      return Block::Heading(level, text.trim());
+    }
+  }
+
+  for prefix in ["- ", "* "] {
+    if let Some(text) = trimmed.strip_prefix(prefix) {
+      // Two spaces or a tab per indent level.
+      let indent = line[..line.len() - trimmed.len()]
+        .chars()
+        .map(|ch| if ch == '\t' { 2 } else { 1 })
+        .sum::<usize>()
+        / 2;
+      return Block::Bullet(indent, text);
+    }
+  }
+
+  Block::Text(line)
+}
+
+#[derive(Debug, PartialEq)]
+enum Span<'a> {
+  Text(&'a str),
+
+  /// Link text and URL.
+  Link(&'a str, &'a str),
+}
+
+/// Split text into plain text and links. Links are either bare URLs or `[text](url)`.
+fn parse_spans(mut text: &str) -> Vec<Span<'_>> {
+  let mut spans = Vec::new();
+  while !text.is_empty() {
+    let Some((pos, len, span)) = find_link(text) else {
+      spans.push(Span::Text(text));
+      break;
+    };
+
+    if pos > 0 {
+      spans.push(Span::Text(&text[..pos]));
+    }
+    spans.push(span);
+    text = &text[pos + len..];
+  }
+  spans
+}
+
+/// Find the first link in the text. Returns the position, length and the link.
+fn find_link(text: &str) -> Option<(usize, usize, Span<'_>)> {
+  let url_pos = ["https://", "http://"]
+    .iter()
+    .filter_map(|scheme| text.find(scheme))
+    .min();
+  let named = find_named_link(text);
+  if let Some((pos, _, _)) = named {
+    if url_pos.is_none_or(|url_pos| pos < url_pos) {
+      return named;
+    }
+  }
+
+  let pos = url_pos?;
+  let len = text[pos..].find(char::is_whitespace).unwrap_or(text.len() - pos);
+
+  // Trailing punctuation is most likely not part of the URL.
+  let url = text[pos..pos + len].trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+  Some((pos, url.len(), Span::Link(url, url)))
+}
+
+fn find_named_link(text: &str) -> Option<(usize, usize, Span<'_>)> {
+  let mut start = 0;
+  while let Some(offset) = text[start..].find('[') {
+    let pos = start + offset;
+    start = pos + 1;
+
+    let rest = &text[start..];
+    let Some(end) = rest.find("](") else { continue };
+    let (name, rest) = (&rest[..end], &rest[end + 2..]);
+    let Some(url_end) = rest.find(')') else { continue };
+    let url = &rest[..url_end];
+    if name.is_empty() || !(url.starts_with("https://") || url.starts_with("http://")) {
+      continue;
+    }
+
+    let len = 1 + end + 2 + url_end + 1;
+    return Some((pos, len, Span::Link(name, url)));
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_block() {
+    assert_eq!(parse_block("# Build"), Block::Heading(1, "Build"));
+    assert_eq!(parse_block("### Sub "), Block::Heading(3, "Sub"));
+    assert_eq!(parse_block("#hashtag"), Block::Text("#hashtag"));
+    assert_eq!(parse_block("- Ginseng"), Block::Bullet(0, "Ginseng"));
+    assert_eq!(parse_block("  * Silk"), Block::Bullet(1, "Silk"));
+    assert_eq!(parse_block("\t- Garlic"), Block::Bullet(1, "Garlic"));
+    assert_eq!(parse_block("   "), Block::Blank);
+    assert_eq!(parse_block("Plain text"), Block::Text("Plain text"));
+  }
+
+  #[test]
+  fn test_parse_spans() {
+    assert_eq!(parse_spans("No links"), vec![Span::Text("No links")]);
+    assert_eq!(
+      parse_spans("See https://example.com/a, then stop."),
+      vec![
+        Span::Text("See "),
+        Span::Link("https://example.com/a", "https://example.com/a"),
+        Span::Text(", then stop."),
+      ]
+    );
+    assert_eq!(
+      parse_spans("[Wiki](https://example.com) and [x] (not a link)"),
+      vec![
+        Span::Link("Wiki", "https://example.com"),
+        Span::Text(" and [x] (not a link)"),
+      ]
+    );
+    assert_eq!(
+      parse_spans("http://a.b [B](http://c.d)"),
+      vec![
+        Span::Link("http://a.b", "http://a.b"),
+        Span::Text(" "),
+        Span::Link("B", "http://c.d"),
+      ]
+    );
+  }
+}
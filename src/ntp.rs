@@ -0,0 +1,164 @@
+use std::{
+  borrow::Cow,
+  net::UdpSocket,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Query a network time server for the offset of the system clock, in seconds. A positive offset means that the
+/// system clock is behind network time. This blocks, so it should be called from a pooled thread.
+pub fn get_clock_offset() -> Result<f64, Cow<'static, str>> {
+  let unreachable = |_| Cow::from(format!("Unable to reach {SERVER}"));
+  let socket = UdpSocket::bind("0.0.0.0:0").map_err(unreachable)?;
+  socket.set_read_timeout(Some(TIMEOUT)).map_err(unreachable)?;
+  socket.set_write_timeout(Some(TIMEOUT)).map_err(unreachable)?;
+  socket.connect(SERVER).map_err(unreachable)?;
+
+  // SNTP client request (version 4, mode 3). The server echoes the transmit timestamp back as the origin timestamp,
+  // which is used to match the response to this request.
+  let sent = SystemTime::now();
+  let origin = to_timestamp(sent);
+  let mut request = [0; PACKET_LEN];
+  request[0] = 0x23;
+  request[40..48].copy_from_slice(&origin.to_be_bytes());
+  socket.send(&request).map_err(unreachable)?;
+
+  let mut response = [0; PACKET_LEN];
+  let len = socket.recv(&mut response).map_err(|err| match err.kind() {
+    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => Cow::from(format!("{SERVER} didn't respond")),
+    _ => Cow::from(format!("Unable to reach {SERVER}")),
+  })?;
+  let received = SystemTime::now();
+
+  let (server_received, server_sent) =
+    parse_response(&response[..len], origin).ok_or(Cow::from(format!("Unexpected response from {SERVER}")))?;
+  Ok(get_offset(
+    to_secs(sent),
+    server_received,
+    server_sent,
+    to_secs(received),
+  ))
+}
+
+/// Get the clock offset from the client send time, server receive time, server send time and client receive time.
+fn get_offset(sent: f64, server_received: f64, server_sent: f64, received: f64) -> f64 {
+  // The network delay is assumed to be the same in both directions.
+  ((server_received - sent) + (server_sent - received)) / 2.0
+}
+
+/// Get the server receive and send times, in seconds since the Unix epoch, from an SNTP response. Returns `None` if
+/// it isn't a valid response to the request with the specified transmit timestamp.
+fn parse_response(packet: &[u8], origin: u64) -> Option<(f64, f64)> {
+  if packet.len() < PACKET_LEN {
+    return None;
+  }
+
+  // Mode 4 is a server response and stratum 0 is a "kiss-o'-death" (e.g. rate limited).
+  let mode = packet[0] & 0x07;
+  let stratum = packet[1];
+  if mode != 4 || stratum == 0 || stratum > 15 {
+    return None;
+  }
+
+  let timestamp = |pos: usize| u64::from_be_bytes(packet[pos..pos + 8].try_into().unwrap());
+  if timestamp(24) != origin {
+    return None;
+  }
+
+  let (received, sent) = (timestamp(32), timestamp(40));
+  if received == 0 || sent == 0 {
+    return None;
+  }
+  Some((from_timestamp(received), from_timestamp(sent)))
+}
+
+/// NTP timestamp: seconds since 1900 in the high 32 bits and the fraction in the low 32 bits.
+fn to_timestamp(time: SystemTime) -> u64 {
+  let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+  let secs = (since.as_secs() + EPOCH_OFFSET) & 0xFFFF_FFFF;
+  let frac = (since.subsec_nanos() as u64) * (1 << 32) / 1_000_000_000;
+  secs << 32 | frac
+}
+
+/// Convert an NTP timestamp to seconds since the Unix epoch.
+fn from_timestamp(timestamp: u64) -> f64 {
+  let mut secs = timestamp >> 32;
+
+  // Timestamps roll over in 2036, so the high bit being clear means the next era.
+  if secs & 0x8000_0000 == 0 {
+    secs += 1 << 32;
+  }
+
+  let frac = (timestamp & 0xFFFF_FFFF) as f64 / (1u64 << 32) as f64;
+  (secs as f64 - EPOCH_OFFSET as f64) + frac
+}
+
+fn to_secs(time: SystemTime) -> f64 {
+  time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+const SERVER: &str = "pool.ntp.org:123";
+const TIMEOUT: Duration = Duration::from_secs(5);
+const PACKET_LEN: usize = 48;
+
+/// Seconds from 1900 to 1970.
+const EPOCH_OFFSET: u64 = 2_208_988_800;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn response(origin: u64, received: u64, sent: u64) -> [u8; PACKET_LEN] {
+    let mut packet = [0; PACKET_LEN];
+    packet[0] = 0x24;
+    packet[1] = 2;
+    packet[24..32].copy_from_slice(&origin.to_be_bytes());
+    packet[32..40].copy_from_slice(&received.to_be_bytes());
+    packet[40..48].copy_from_slice(&sent.to_be_bytes());
+    packet
+  }
+
+  #[test]
+  fn test_offset() {
+    // The system clock is 37 seconds behind, with 100ms of delay each way and 10ms spent on the server.
+    let offset = get_offset(1000.0, 1037.1, 1037.11, 1000.21);
+    assert!((offset - 37.0).abs() < 1e-9, "{offset}");
+
+    // Slow responses don't matter if the delay is symmetric.
+    let offset = get_offset(1000.0, 997.0, 997.0, 1006.0);
+    assert!((offset + 6.0).abs() < 1e-9, "{offset}");
+  }
+
+  #[test]
+  fn test_parse_response() {
+    let time = UNIX_EPOCH + Duration::from_millis(1_714_579_200_250);
+    let origin = to_timestamp(time);
+    let received = to_timestamp(time + Duration::from_secs(37));
+    let sent = to_timestamp(time + Duration::from_millis(37_010));
+    let (server_received, server_sent) = parse_response(&response(origin, received, sent), origin).unwrap();
+    assert!((server_received - (to_secs(time) + 37.0)).abs() < 1e-6);
+    assert!((server_sent - (to_secs(time) + 37.01)).abs() < 1e-6);
+
+    // A response to a different request.
+    assert_eq!(parse_response(&response(origin + 1, received, sent), origin), None);
+
+    // Kiss-o'-death.
+    let mut packet = response(origin, received, sent);
+    packet[1] = 0;
+    assert_eq!(parse_response(&packet, origin), None);
+
+    // Not a server response.
+    let mut packet = response(origin, received, sent);
+    packet[0] = 0x23;
+    assert_eq!(parse_response(&packet, origin), None);
+    assert_eq!(parse_response(&packet[..40], origin), None);
+  }
+
+  #[test]
+  fn test_timestamp_era() {
+    // After the 2036 rollover.
+    let time = UNIX_EPOCH + Duration::from_secs(2_100_000_000);
+    assert_eq!(from_timestamp(to_timestamp(time)), to_secs(time));
+    let time = UNIX_EPOCH + Duration::from_secs(1_714_579_200);
+    assert_eq!(from_timestamp(to_timestamp(time)), to_secs(time));
+  }
+}
@@ -1,50 +1,127 @@
-use self::inner::GameInfo;
+use self::inner::{GameInfo, SkillCopy};
 use crate::{
-  game_data::GameData,
+  changes_dlg::ChangesDlg,
+  config::Config,
+  decks_dlg::DecksDlg,
+  game_data::{self, GameData, Section},
   items_dlg::ItemsDlg,
-  util::{AppState, Picture, APP_NAME, LVL_RANGE},
+  skill_info::SkillOverride,
+  theme::Color,
+  util::{self, AppState, Cancel, ExpandedGroups, Picture, APP_NAME, LVL_RANGE},
+  virtues_dlg::VirtuesDlg,
 };
+use chrono::NaiveDateTime;
 use eframe::{egui, epaint::Color32};
-use egui::{Button, DragValue, RichText, Ui, WidgetText};
-use std::{borrow::Cow, path::PathBuf};
+use egui::{Align2, Button, Context, DragValue, Key, RichText, TextEdit, Ui, WidgetText, Window};
+use futures::{channel::mpsc, executor::ThreadPool, StreamExt};
+use std::{
+  borrow::Cow,
+  ffi::OsStr,
+  fs, mem,
+  path::{Path, PathBuf},
+  time::Duration,
+};
 
 pub struct Offline {
+  ctx: Context,
+  config: Config,
+  state: AppState,
+  threads: ThreadPool,
+  channel: Channel,
+  pending: Option<Pending>,
   load_icon: Picture,
   store_icon: Picture,
   items_dlg: ItemsDlg,
+  virtues_dlg: VirtuesDlg,
+  decks_dlg: DecksDlg,
+  changes_dlg: ChangesDlg,
+  skill_override: SkillOverride,
   game: Option<GameInfo>,
   error: Option<Cow<'static, str>>,
   changed: bool,
   load_request: bool,
+  copy_request: bool,
+  modified_request: bool,
+  skill_copy: Option<SkillCopy>,
+  filter: String,
+  show_exp: bool,
+  expanded: ExpandedGroups,
 }
 
 impl Offline {
-  pub fn new(state: AppState) -> Self {
+  pub fn new(
+    ctx: Context,
+    threads: ThreadPool,
+    config: Config,
+    state: AppState,
+    skill_override: SkillOverride,
+  ) -> Self {
     let load_icon = Picture::new(format!("{APP_NAME}_load_icon"), include_bytes!("../res/load.png"));
     let store_icon = Picture::new(format!("{APP_NAME}_store_icon"), include_bytes!("../res/store.png"));
     let game = None;
     let error = None;
     let changed = false;
     let load_request = false;
+    let (tx, rx) = mpsc::unbounded();
+    let channel = Channel { tx, rx, cancel: None };
 
     Offline {
+      ctx,
+      config,
+      threads,
+      channel,
+      pending: None,
       load_icon,
       store_icon,
-      items_dlg: ItemsDlg::new(state),
+      items_dlg: ItemsDlg::new(state.clone()),
+      virtues_dlg: VirtuesDlg::new(state.clone()),
+      decks_dlg: DecksDlg::new(state.clone()),
+      changes_dlg: ChangesDlg::new(state.clone()),
+      state,
+      skill_override,
       game,
       error,
       changed,
       load_request,
+      copy_request: false,
+      modified_request: false,
+      skill_copy: None,
+      filter: String::new(),
+      show_exp: false,
+      expanded: ExpandedGroups::default(),
     }
   }
 
   pub fn show(&mut self, ui: &mut Ui) {
+    while let Ok(msg) = self.channel.rx.try_recv() {
+      self.handle_message(msg);
+    }
+
+    // Nothing can be edited while the save-game is being loaded or stored.
+    if self.pending.is_some() {
+      ui.disable();
+    }
+
     if let Some(game) = &mut self.game {
       if self.items_dlg.show(game.items_mut(), ui.ctx()) {
-        self.changed = game.changed();
+        self.changed = game.edited();
+      }
+
+      if self.virtues_dlg.show(game.virtues_mut(), ui.ctx()) {
+        self.changed = game.edited();
+      }
+
+      // Slots can only be validated if the skills are loaded.
+      let editable = game.report().is_loaded(Section::Skills);
+      let (decks, skills) = game.decks_and_skills();
+      if self.decks_dlg.show(decks, &skills, editable, ui.ctx()) {
+        self.changed = game.edited();
       }
     }
 
+    self.changes_dlg.show(ui.ctx());
+    self.show_skill_copy(ui.ctx());
+
     // Tool bar.
     ui.horizontal(|ui| {
       let response = ui.add_sized([23.0, 22.0], Button::image(self.load_icon.image(ui.ctx())));
@@ -61,12 +138,54 @@ impl Offline {
 
       ui.separator();
 
+      let response = ui.add_enabled(self.changed(), Button::new("Review Changes..."));
+      if response.on_hover_text("List the edits that will be stored").clicked() {
+        self.changes_dlg.open(self.changes());
+      }
+
+      ui.separator();
+
       ui.horizontal(|ui| {
-        ui.add_enabled_ui(self.game.is_some(), |ui| {
-          if ui.button("Items").clicked() {
+        let enabled = self.is_editable(Section::Inventory);
+        ui.add_enabled_ui(enabled, |ui| {
+          let response = ui.button("Items");
+          let response = match self.section_error(Section::Inventory) {
+            Some(err) => response.on_disabled_hover_text(err),
+            None => response,
+          };
+          if response.clicked() {
             self.items_dlg.open();
           }
         });
+
+        let has_virtues = self.game.as_ref().is_some_and(|game| !game.virtues().is_empty());
+        ui.add_enabled_ui(has_virtues, |ui| {
+          if ui.button("Virtues").clicked() {
+            self.virtues_dlg.open();
+          }
+        });
+
+        let has_decks = self.game.as_ref().is_some_and(|game| !game.decks().is_empty());
+        ui.add_enabled_ui(has_decks, |ui| {
+          let response = ui.button("Decks");
+          let response = match self.section_error(Section::Decks) {
+            Some(err) => response.on_disabled_hover_text(err),
+            None => response,
+          };
+          if response.clicked() {
+            self.decks_dlg.open();
+          }
+        });
+
+        let enabled = self.is_editable(Section::Skills);
+        ui.add_enabled_ui(enabled, |ui| {
+          let response = ui
+            .button("Copy Skills")
+            .on_hover_text("Copy the skills from another save-game");
+          if response.clicked() {
+            self.copy_request = true;
+          }
+        });
       });
 
       ui.separator();
@@ -75,87 +194,314 @@ impl Offline {
         const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
 
         ui.label(RichText::from("Adv Lvl").color(LABEL_COLOR));
-        if let Some(game) = &mut self.game {
-          let mut level = game.adv_level();
-          let widget = DragValue::new(&mut level).range(LVL_RANGE);
-          if ui.add(widget).changed() {
-            game.set_adv_level(level);
-            self.changed = game.changed();
+        match &mut self.game {
+          Some(game) if game.report().is_loaded(Section::Levels) => {
+            let mut level = game.adv_level();
+            let widget = DragValue::new(&mut level).range(LVL_RANGE);
+            if ui.add(widget).changed() {
+              game.set_adv_level(level);
+              self.changed = game.edited();
+            }
           }
-        } else {
-          ui.add_enabled_ui(false, |ui| {
-            ui.add_sized(ui.spacing().interact_size, Button::new(RichText::default()));
-          });
+          game => show_unavailable(ui, game.as_ref(), Section::Levels),
         }
 
         ui.label(RichText::from("Prd Lvl").color(LABEL_COLOR));
-        if let Some(game) = &mut self.game {
-          let mut level = game.prd_level();
-          let widget = DragValue::new(&mut level).range(LVL_RANGE);
-          if ui.add(widget).changed() {
-            game.set_prd_level(level);
-            self.changed = game.changed();
+        match &mut self.game {
+          Some(game) if game.report().is_loaded(Section::Levels) => {
+            let mut level = game.prd_level();
+            let widget = DragValue::new(&mut level).range(LVL_RANGE);
+            if ui.add(widget).changed() {
+              game.set_prd_level(level);
+              self.changed = game.edited();
+            }
           }
-        } else {
-          ui.add_enabled_ui(false, |ui| {
-            ui.add_sized(ui.spacing().interact_size, Button::new(RichText::default()));
-          });
+          game => show_unavailable(ui, game.as_ref(), Section::Levels),
         }
 
         ui.label(RichText::from("Gold").color(LABEL_COLOR));
-        if let Some(game) = &mut self.game {
-          let mut gold = game.gold();
-          let speed = (gold as f64 / 100.0).max(1.0);
-          let range = 0..=MAX_GOLD;
-          let widget = DragValue::new(&mut gold).speed(speed).range(range);
-          if ui.add(widget).changed() {
-            game.set_gold(gold);
-            self.changed = game.changed();
+        match &mut self.game {
+          Some(game) if game.report().is_loaded(Section::Gold) => {
+            let mut gold = game.gold();
+            let speed = (gold as f64 / 100.0).max(1.0);
+            let widget = DragValue::new(&mut gold).speed(speed).range(0..=i64::MAX);
+            if ui.add(widget).changed() {
+              game.set_gold(gold);
+              self.changed = game.edited();
+            }
           }
-        } else {
-          ui.add_enabled_ui(false, |ui| {
-            ui.add_sized(ui.spacing().interact_size, Button::new(RichText::default()));
-          });
+          game => show_unavailable(ui, game.as_ref(), Section::Gold),
         }
       });
     });
 
     ui.separator();
 
+    // Skill filter.
+    ui.add_enabled_ui(self.is_editable(Section::Skills), |ui| {
+      ui.horizontal(|ui| {
+        const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+        ui.checkbox(&mut self.show_exp, "XP")
+          .on_hover_text("Show and edit the raw skill experience");
+        ui.label(RichText::from("Filter").color(LABEL_COLOR));
+        let widget = TextEdit::singleline(&mut self.filter).hint_text("skill name");
+        ui.add_sized([ui.available_width(), ui.spacing().interact_size.y], widget);
+      });
+    });
+
+    // Automatic skill adjustments.
+    if let Some(game) = &self.game {
+      if let Some(text) = game.adjustment_text() {
+        ui.label(RichText::from(text).color(ADJUSTED_COLOR));
+        ui.ctx().request_repaint_after(ADJUSTMENT_DURATION);
+      }
+    }
+
     // Skills.
     if let Some(game) = &mut self.game {
-      if game.show_skills(ui) {
-        self.changed = game.changed();
+      if let Some(err) = game.report().error(Section::Skills) {
+        ui.add_enabled_ui(false, |ui| {
+          ui.label(format!("Skills are unavailable: {err}"));
+        });
+      }
+
+      let filter = util::get_name_filter(&self.filter);
+      if game.show_skills(ui, filter.as_ref(), self.show_exp, &mut self.expanded) {
+        self.changed = game.edited();
+      }
+
+      if self.expanded.take_changed() {
+        self.config.set_offline_expanded(self.expanded.names());
       }
+
+      // Add the edits to the undo history once they're finished.
+      game.commit_edits(ui.ctx());
     }
   }
 
   pub fn show_status(&mut self, ui: &mut Ui) {
     ui.centered_and_justified(|ui| {
-      if let Some(error) = &self.error {
-        ui.label(WidgetText::from(error.as_ref()).color(Color32::LIGHT_RED));
+      if let Some(pending) = &self.pending {
+        ui.label(pending.text());
+      } else if let Some(error) = &self.error {
+        ui.label(WidgetText::from(error.as_ref()).color(Color::Error.get()));
       } else if let Some(game) = self.game.as_ref() {
         let file_name = game.get_file_name();
         let changed = if self.changed() { "*" } else { "" };
-        ui.label(format!("Editing {} - {}{}", game.avatar_name(), changed, file_name));
+        let text = format!("Editing {} - {}{}", game.avatar_name(), changed, file_name);
+        let report = game.report();
+        if report.is_complete() {
+          ui.label(text);
+        } else {
+          // Partially loaded, list the sections that can't be edited.
+          let failed: Vec<String> = report
+            .failed()
+            .iter()
+            .map(|(section, err)| format!("{}: {err}", section.name()))
+            .collect();
+          let text = format!("{text} (unable to edit {})", failed.join("; "));
+          ui.label(RichText::from(text).color(Color::Error.get()));
+        }
       }
     });
   }
 
-  pub fn load(&mut self, path: PathBuf) -> bool {
-    self.changed = false;
-    match GameData::load(path) {
-      Ok(game) => {
-        self.game = Some(GameInfo::new(game));
-        self.error = None;
-        true
+  /// Load a save-game on a pooled thread.
+  pub fn load(&mut self, path: PathBuf) {
+    let pending = Pending::Load(get_file_name(&path));
+    self.spawn(pending, move || Message::Loaded {
+      result: GameData::load(path),
+      restored: None,
+    });
+  }
+
+  /// Load another save-game to compare the skills with, then ask for confirmation before copying them.
+  pub fn copy_skills_from(&mut self, path: PathBuf) {
+    if self.game.is_none() {
+      return;
+    }
+
+    let pending = Pending::Load(get_file_name(&path));
+    self.spawn(pending, move || Message::CopyFrom(GameData::load(path)));
+  }
+
+  fn handle_message(&mut self, msg: Message) {
+    self.pending = None;
+    self.channel.cancel = None;
+    self.state.set_busy(false);
+    match msg {
+      Message::Loaded { result, restored } => match result {
+        Ok(game) => {
+          if restored.is_none() {
+            let folder = game.get_file_path().with_file_name(String::default());
+            self.config.set_save_game_path(&folder);
+          }
+
+          self.set_game(game);
+
+          // A restored backup replaces the save-game when it's stored.
+          if let (Some(game), Some(backup)) = (&mut self.game, restored) {
+            game.set_restored(backup);
+          }
+          self.changed = self.game.as_ref().is_some_and(|game| game.changed());
+        }
+        Err(err) => {
+          if restored.is_none() {
+            self.game = None;
+            self.changed = false;
+          }
+          self.error = Some(err);
+        }
+      },
+      Message::CopyFrom(result) => {
+        let Some(game) = &self.game else { return };
+        match result {
+          Ok(data) => match data.report().error(Section::Skills) {
+            Some(err) => self.error = Some(Cow::from(format!("Unable to copy skills: {err}"))),
+            None => {
+              self.skill_copy = Some(game.plan_skill_copy(&data));
+
+              // Keep the UI disabled while the confirmation is showing.
+              self.state.set_disabled(true);
+            }
+          },
+          Err(err) => self.error = Some(err),
+        }
       }
-      Err(err) => {
-        self.game = None;
-        self.error = Some(err);
-        false
+      Message::Stored(result) => match result {
+        Ok(()) => {
+          if let Some(game) = &mut self.game {
+            game.end_store();
+          }
+          self.changed = false;
+        }
+        Err(err) => self.error = Some(err),
+      },
+    }
+  }
+
+  /// Run a save-game file operation on a pooled thread. Only one can run at a time.
+  fn spawn<F>(&mut self, pending: Pending, task: F)
+  where
+    F: FnOnce() -> Message + Send + 'static,
+  {
+    if self.pending.is_some() {
+      return;
+    }
+
+    let cancel = Cancel::default();
+    self.channel.cancel = Some(cancel.clone());
+    self.pending = Some(pending);
+    self.error = None;
+
+    // Show the busy cursor.
+    self.state.set_busy(true);
+
+    // Setup the future.
+    let tx = self.channel.tx.clone();
+    let ctx = self.ctx.clone();
+    let future = async move {
+      let msg = task();
+      if !cancel.is_canceled() {
+        tx.unbounded_send(msg).unwrap();
+        ctx.request_repaint();
       }
+    };
+
+    // Execute the future on a pooled thread.
+    self.threads.spawn_ok(future);
+  }
+
+  /// Check if a save-game is being loaded or stored.
+  pub fn is_busy(&self) -> bool {
+    self.pending.is_some()
+  }
+
+  fn show_skill_copy(&mut self, ctx: &Context) {
+    let Some(copy) = &self.skill_copy else { return };
+    let mut apply = None;
+    let mut open = true;
+    Window::new(RichText::from("⚠  Copy Skills").strong())
+      .open(&mut open)
+      .collapsible(false)
+      .resizable(false)
+      .current_pos([0.0, 24.0])
+      .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+      .show(ctx, |ui| {
+        ui.add_space(8.0);
+        ui.vertical_centered(|ui| {
+          ui.label(copy.summary());
+          ui.label("Levels and gold are not changed.");
+        });
+        ui.add_space(8.0);
+        ui.separator();
+        ui.horizontal(|ui| {
+          if ui.add_enabled(!copy.is_empty(), Button::new("Apply")).clicked() {
+            apply = Some(true);
+          }
+          if ui.button("Cancel").clicked() {
+            apply = Some(false);
+          }
+        });
+      });
+
+    if ctx.input(|state| state.key_pressed(Key::Escape)) || !open {
+      apply = Some(false);
     }
+
+    let Some(apply) = apply else { return };
+    if let Some(copy) = self.skill_copy.take() {
+      if let Some(game) = &mut self.game {
+        if apply {
+          game.copy_skills(&copy);
+          self.changed = game.edited();
+        }
+      }
+    }
+    self.state.set_disabled(false);
+  }
+
+  /// Backups of the loaded save-game, newest first.
+  pub fn backups(&self) -> Vec<(NaiveDateTime, PathBuf)> {
+    match self.file_path() {
+      Some(path) => game_data::get_backups(&path),
+      None => Vec::new(),
+    }
+  }
+
+  /// Reload the save-game from a backup. Storing it will replace the current save-game file.
+  pub fn restore_backup(&mut self, backup: &Path) {
+    let Some(path) = self.file_path() else { return };
+    let backup = backup.to_owned();
+    let file_name = get_file_name(&backup);
+    let pending = Pending::Load(file_name.clone());
+    self.spawn(pending, move || Message::Loaded {
+      result: GameData::load_backup(path, &backup),
+      restored: Some(file_name),
+    });
+  }
+
+  fn set_game(&mut self, game: GameData) {
+    let game = GameInfo::new(game, self.skill_override);
+    if game.report().is_loaded(Section::Skills) {
+      let expanded = self.config.get_offline_expanded();
+      if self.expanded.load(expanded, game.group_names()) {
+        // Forget groups that no longer exist.
+        self.config.set_offline_expanded(self.expanded.names());
+      }
+    }
+
+    self.game = Some(game);
+    self.error = None;
+  }
+
+  /// Check if a save-game is loaded and the section can be edited.
+  fn is_editable(&self, section: Section) -> bool {
+    self.game.as_ref().is_some_and(|game| game.report().is_loaded(section))
+  }
+
+  fn section_error(&self, section: Section) -> Option<&str> {
+    self.game.as_ref()?.report().error(section)
   }
 
   pub fn is_loaded(&self) -> bool {
@@ -166,21 +512,118 @@ impl Offline {
     self.changed
   }
 
+  /// Get the differences between the edits and the loaded save-game.
+  pub fn changes(&self) -> Vec<ChangeGroup> {
+    match &self.game {
+      Some(game) => game.get_changes(),
+      None => Vec::new(),
+    }
+  }
+
   pub fn store(&mut self) {
-    let Some(game) = &mut self.game else { return };
-    if let Err(err) = game.store() {
-      self.error = Some(err);
-    } else {
-      self.changed = false;
+    let Some(game) = &self.game else { return };
+    if game.file_modified() {
+      // The file is newer than what was loaded, ask what to do first.
+      self.modified_request = true;
+      return;
     }
+
+    self.write(false);
+  }
+
+  /// Store the save-game even though the file was modified after it was loaded.
+  pub fn overwrite(&mut self) {
+    self.write(true);
+  }
+
+  /// Load the save-game again from its file, dropping any changes.
+  pub fn reload(&mut self) {
+    if let Some(path) = self.file_path() {
+      self.load(path);
+    }
+  }
+
+  fn write(&mut self, overwrite: bool) {
+    if self.pending.is_some() {
+      return;
+    }
+
+    let Some(game) = &mut self.game else { return };
+    let Some(data) = game.begin_store() else { return };
+    let backups = self.config.get_save_backups();
+    let pending = Pending::Store(game.get_file_name());
+    self.spawn(pending, move || Message::Stored(data.store(backups, overwrite)));
   }
 
   pub fn store_as(&mut self, path: PathBuf) {
+    if self.pending.is_some() {
+      return;
+    }
+
+    // Make sure the extension is "sota".
+    let path = if path.extension() != Some(OsStr::new("sota")) {
+      path.with_extension("sota")
+    } else {
+      path
+    };
+
     let Some(game) = &mut self.game else { return };
-    if let Err(err) = game.store_as(path) {
-      self.error = Some(err);
+    let Some(data) = game.begin_store() else { return };
+    let backups = self.config.get_save_backups();
+    let pending = Pending::Store(get_file_name(&path));
+    self.spawn(pending, move || Message::Stored(data.store_as(path, backups)));
+  }
+
+  /// Default file name for exporting the character data.
+  pub fn export_file_name(&self) -> String {
+    let name = self.game.as_ref().map(|game| game.avatar_name()).unwrap_or_default();
+    format!("{name}.json")
+  }
+
+  /// Export the character data, including any edits that haven't been stored, as JSON.
+  pub fn export_character(&mut self, path: PathBuf) {
+    let Some(game) = &self.game else { return };
+
+    // Use the "json" extension if there isn't one.
+    let path = if path.extension().is_none() {
+      path.with_extension("json")
     } else {
-      self.changed = false;
+      path
+    };
+
+    let text = format!("{:#}", game.get_export());
+    if let Err(err) = fs::write(&path, text) {
+      self.error = Some(Cow::from(format!("Unable to save to {path:?}: {err}")));
+    }
+  }
+
+  pub fn can_undo(&self) -> bool {
+    self.pending.is_none() && self.game.as_ref().is_some_and(|game| game.can_undo())
+  }
+
+  pub fn can_redo(&self) -> bool {
+    self.pending.is_none() && self.game.as_ref().is_some_and(|game| game.can_redo())
+  }
+
+  pub fn undo(&mut self) {
+    if !self.can_undo() {
+      return;
+    }
+
+    let Some(game) = &mut self.game else { return };
+    if game.undo() {
+      self.changed = game.changed();
+    }
+  }
+
+  pub fn redo(&mut self) {
+    if !self.can_redo() {
+      return;
+    }
+
+    let Some(game) = &mut self.game else { return };
+    if game.redo() {
+      self.changed = game.changed();
     }
   }
 
@@ -200,6 +643,15 @@ impl Offline {
     Some(game.get_file_path())
   }
 
+  /// Check if storing was held back because the file was modified after it was loaded.
+  pub fn modified_request(&mut self) -> bool {
+    mem::take(&mut self.modified_request)
+  }
+
+  pub fn copy_request(&mut self) -> bool {
+    mem::take(&mut self.copy_request)
+  }
+
   pub fn load_request(&mut self) -> bool {
     let load_request = self.load_request;
     self.load_request = false;
@@ -207,29 +659,137 @@ impl Offline {
   }
 
   pub fn on_close_event(&mut self) {
+    self.changes_dlg.close();
     self.items_dlg.close();
+    self.virtues_dlg.close();
+    self.decks_dlg.close();
+  }
+
+  pub fn on_exit(&mut self) {
+    match self.pending {
+      // Don't leave a partially written save-game behind.
+      Some(Pending::Store(_)) => {
+        if let Some(msg) = futures::executor::block_on(self.channel.rx.next()) {
+          self.handle_message(msg);
+        }
+      }
+      Some(Pending::Load(_)) => {
+        if let Some(mut cancel) = self.channel.cancel.take() {
+          cancel.cancel();
+        }
+      }
+      None => (),
+    }
+  }
+}
+
+/// A value that differs from the loaded save-game.
+pub struct Change {
+  pub name: String,
+  pub old: String,
+  pub new: String,
+}
+
+impl Change {
+  fn new(name: impl Into<String>, old: impl ToString, new: impl ToString) -> Self {
+    Self {
+      name: name.into(),
+      old: old.to_string(),
+      new: new.to_string(),
+    }
+  }
+}
+
+/// Changes of the same kind (e.g. adventurer skills).
+pub struct ChangeGroup {
+  pub name: &'static str,
+  pub changes: Vec<Change>,
+}
+
+/// Save-game file operation that's running on a pooled thread.
+enum Pending {
+  Load(String),
+  Store(String),
+}
+
+impl Pending {
+  fn text(&self) -> String {
+    match self {
+      Pending::Load(file_name) => format!("Loading {file_name}..."),
+      Pending::Store(file_name) => format!("Storing {file_name}..."),
+    }
   }
 }
 
-const MAX_GOLD: i32 = i32::MAX / 2;
+enum Message {
+  /// A save-game or backup was loaded.
+  Loaded {
+    result: Result<GameData, Cow<'static, str>>,
+    /// File name of the backup, if a backup was loaded in place of the save-game.
+    restored: Option<String>,
+  },
+
+  /// A save-game to copy skills from was loaded.
+  CopyFrom(Result<GameData, Cow<'static, str>>),
+  Stored(Result<(), Cow<'static, str>>),
+}
+
+struct Channel {
+  tx: mpsc::UnboundedSender<Message>,
+  rx: mpsc::UnboundedReceiver<Message>,
+  cancel: Option<Cancel>,
+}
+
+fn get_file_name(path: &Path) -> String {
+  path
+    .file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default()
+}
+
+/// Show a disabled placeholder for a value that can't be edited.
+fn show_unavailable(ui: &mut Ui, game: Option<&GameInfo>, section: Section) {
+  ui.add_enabled_ui(false, |ui| {
+    let response = ui.add_sized(ui.spacing().interact_size, Button::new(RichText::default()));
+    if let Some(err) = game.and_then(|game| game.report().error(section)) {
+      response.on_disabled_hover_text(err);
+    }
+  });
+}
+
+/// Color used to highlight automatically adjusted skills.
+const ADJUSTED_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+
+/// How long automatic skill adjustments are highlighted.
+const ADJUSTMENT_DURATION: Duration = Duration::from_secs(5);
+
+/// Maximum number of undo steps.
+const UNDO_LIMIT: usize = 100;
+
+/// Schema version of the exported character data. Increment it when fields are renamed, removed or change meaning.
+const EXPORT_VERSION: u32 = 1;
 
 mod inner {
+  use super::{Change, ChangeGroup, ADJUSTED_COLOR, ADJUSTMENT_DURATION, EXPORT_VERSION, UNDO_LIMIT};
   use crate::{
-    game_data::{GameData, Item, SkillLvl, SkillLvlGroup},
-    skill_info::SkillCategory,
-    util,
+    game_data::{Deck, Durability, GameData, Item, ItemGroup, LoadReport, Section, SkillLvl, SkillLvlGroup, VirtueLvl},
+    skill_info::{SkillCategory, SkillOverride},
+    theme::Color,
+    util::{self, ExpandedGroups, Search, APP_NAME, APP_VERSION, LEVEL_EXP, LVL_RANGE},
   };
   use eframe::{
-    egui::{scroll_area::ScrollBarVisibility, CollapsingHeader, DragValue, Layout, RichText, ScrollArea, Ui},
+    egui::{scroll_area::ScrollBarVisibility, CollapsingHeader, Context, DragValue, Layout, RichText, ScrollArea, Ui},
     emath::Align,
-    epaint::{Color32, Vec2},
+    epaint::Color32,
   };
   use egui_extras::{Column, TableBuilder};
+  use serde_json::{json, Value};
   use std::{
-    borrow::Cow,
+    cmp::Ordering,
     collections::{HashMap, HashSet},
-    ffi::OsStr,
     path::PathBuf,
+    sync::Arc,
+    time::Instant,
   };
 
   struct SkillsIdx {
@@ -243,14 +803,20 @@ mod inner {
     prd: Vec<SkillLvlGroup>,
     map: HashMap<u32, SkillsIdx>,
     tree: HashMap<u32, HashSet<u32>>,
+    names: HashMap<u32, &'static str>,
+
+    /// Target levels for the "Apply to group" controls.
+    adv_targets: Vec<i32>,
+    prd_targets: Vec<i32>,
   }
 
   impl Skills {
-    fn new(data: &GameData) -> Self {
-      let adv = data.get_skills(SkillCategory::Adventurer);
-      let prd = data.get_skills(SkillCategory::Producer);
+    fn new(data: &GameData, skill_override: SkillOverride) -> Self {
+      let adv = data.get_skills(SkillCategory::Adventurer, skill_override);
+      let prd = data.get_skills(SkillCategory::Producer, skill_override);
       let mut map = HashMap::new();
       let mut tree = HashMap::new();
+      let mut names = HashMap::new();
       for cat in [SkillCategory::Adventurer, SkillCategory::Producer] {
         let vec = match cat {
           SkillCategory::Adventurer => &adv,
@@ -259,6 +825,8 @@ mod inner {
 
         for (group_idx, group) in vec.iter().enumerate() {
           for (skill_idx, skill) in group.skills.iter().enumerate() {
+            names.insert(skill.info.id, skill.info.name);
+
             // A map of skill IDs to indexes.
             map.insert(
               skill.info.id,
@@ -283,7 +851,17 @@ mod inner {
         }
       }
 
-      Self { adv, prd, map, tree }
+      let adv_targets = vec![0; adv.len()];
+      let prd_targets = vec![0; prd.len()];
+      Self {
+        adv,
+        prd,
+        map,
+        tree,
+        names,
+        adv_targets,
+        prd_targets,
+      }
     }
 
     fn get(&self, id: u32) -> Option<&SkillLvl> {
@@ -304,95 +882,344 @@ mod inner {
   }
 
   pub struct GameInfo {
-    data: GameData,
+    // Shared with the thread that stores it.
+    data: Arc<GameData>,
+    skill_override: SkillOverride,
     skills: Skills,
-    items: Vec<Item>,
+    items: Vec<ItemGroup>,
+    virtues: Vec<VirtueLvl>,
+    decks: Vec<Deck>,
     adv_lvl_cmp: i32,
     adv_lvl: i32,
     prd_lvl_cmp: i32,
     prd_lvl: i32,
-    gold_cmp: i32,
-    gold: i32,
+    gold_cmp: i64,
+    gold: i64,
+    adjustment: Option<Adjustment>,
+
+    // Undo history. `current` is the state as of the last committed edit.
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    current: Snapshot,
+    pending: bool,
+
+    // File name of the backup that was loaded in place of the save-game. The save-game file still has its own
+    // contents, so it differs from what's loaded until stored, regardless of edits.
+    restored: Option<String>,
+  }
+
+  /// Editable state for the undo history.
+  struct Snapshot {
+    skills: Vec<SkillLvl>,
+    items: Vec<Vec<Item>>,
+    virtues: Vec<VirtueLvl>,
+    decks: Vec<Vec<Option<u32>>>,
+    adv_lvl: i32,
+    prd_lvl: i32,
+    gold: i64,
+  }
+
+  /// Skills to copy from another save-game.
+  pub struct SkillCopy {
+    file_name: String,
+    exp: HashMap<u32, Option<i64>>,
+    raised: usize,
+    lowered: usize,
+    added: usize,
+  }
+
+  impl SkillCopy {
+    pub fn is_empty(&self) -> bool {
+      self.exp.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+      if self.is_empty() {
+        return format!("The skills in {} are the same.", self.file_name);
+      }
+
+      format!(
+        "Copying the skills from {} will raise {}, lower {} and add {} skills.",
+        self.file_name, self.raised, self.lowered, self.added
+      )
+    }
+  }
+
+  /// Skills that were automatically adjusted to satisfy requirements.
+  #[derive(Default)]
+  struct Adjustment {
+    ids: HashSet<u32>,
+    notes: Vec<String>,
+    time: Option<Instant>,
   }
 
   impl GameInfo {
-    pub fn new(data: GameData) -> Self {
-      let skills = Skills::new(&data);
+    pub fn new(data: GameData, skill_override: SkillOverride) -> Self {
+      let skills = Skills::new(&data, skill_override);
       let items = data.get_inventory_items();
-      let adv_lvl = data.get_adv_lvl();
-      let prd_lvl = data.get_prd_lvl();
+      let virtues = data.get_virtues();
+      let decks = data.get_decks();
+      let adv_lvl = data.get_adv_lvl().unwrap_or(*LVL_RANGE.start());
+      let prd_lvl = data.get_prd_lvl().unwrap_or(*LVL_RANGE.start());
       let gold = data.get_gold().unwrap_or(0);
 
-      GameInfo {
-        data,
+      let mut game = GameInfo {
+        data: Arc::new(data),
+        skill_override,
         skills,
         items,
+        virtues,
+        decks,
         adv_lvl_cmp: adv_lvl,
         adv_lvl,
         prd_lvl_cmp: prd_lvl,
         prd_lvl,
         gold_cmp: gold,
         gold,
+        adjustment: None,
+        undo: Vec::new(),
+        redo: Vec::new(),
+        current: Snapshot {
+          skills: Vec::new(),
+          items: Vec::new(),
+          virtues: Vec::new(),
+          decks: Vec::new(),
+          adv_lvl,
+          prd_lvl,
+          gold,
+        },
+        pending: false,
+        restored: None,
+      };
+      game.current = game.snapshot();
+      game
+    }
+
+    /// Note that an edit was made and return whether anything differs from the loaded save-game.
+    pub fn edited(&mut self) -> bool {
+      self.pending = true;
+      self.changed()
+    }
+
+    /// Add pending edits to the undo history. Edits are held back while a widget is being dragged or typed into so
+    /// that each drag, along with any automatic skill adjustments, is a single undo step.
+    pub fn commit_edits(&mut self, ctx: &Context) {
+      if self.pending && !ctx.input(|state| state.pointer.any_down()) && !ctx.wants_keyboard_input() {
+        self.commit();
       }
     }
 
-    pub fn show_skills(&mut self, ui: &mut Ui) -> bool {
-      // Divide the space evenly between adventurer and producer.
-      let available = ui.available_size();
-      let spacing = ui.spacing().item_spacing.y;
-      let size = Vec2::new(available.x, available.y * 0.5 - spacing * 4.0);
+    pub fn can_undo(&self) -> bool {
+      self.pending || !self.undo.is_empty()
+    }
 
-      ui.add_space(spacing);
+    pub fn can_redo(&self) -> bool {
+      !self.pending && !self.redo.is_empty()
+    }
 
-      // Adventurer skills.
-      let mut changed = false;
-      ui.allocate_ui(size, |ui| {
-        if self.show_skill_category(ui, SkillCategory::Adventurer) {
-          changed = true;
-        }
-      });
+    pub fn undo(&mut self) -> bool {
+      self.commit();
+      let Some(snapshot) = self.undo.pop() else {
+        return false;
+      };
 
-      ui.add_space(spacing);
-      ui.separator();
-      ui.add_space(spacing);
+      self.restore(&snapshot);
+      self.redo.push(std::mem::replace(&mut self.current, snapshot));
+      true
+    }
+
+    pub fn redo(&mut self) -> bool {
+      self.commit();
+      let Some(snapshot) = self.redo.pop() else {
+        return false;
+      };
+
+      self.restore(&snapshot);
+      self.undo.push(std::mem::replace(&mut self.current, snapshot));
+      true
+    }
+
+    fn commit(&mut self) {
+      if !self.pending {
+        return;
+      }
 
-      // Producer skills.
-      ui.allocate_ui(size, |ui| {
-        if self.show_skill_category(ui, SkillCategory::Producer) {
+      self.pending = false;
+      let snapshot = self.snapshot();
+      self.undo.push(std::mem::replace(&mut self.current, snapshot));
+      if self.undo.len() > UNDO_LIMIT {
+        self.undo.remove(0);
+      }
+      self.redo.clear();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+      Snapshot {
+        skills: self.all_skills().cloned().collect(),
+        items: self.items.iter().map(|group| group.items.clone()).collect(),
+        virtues: self.virtues.clone(),
+        decks: self.decks.iter().map(|deck| deck.slots.clone()).collect(),
+        adv_lvl: self.adv_lvl,
+        prd_lvl: self.prd_lvl,
+        gold: self.gold,
+      }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+      let skills = self.skills.adv.iter_mut().chain(self.skills.prd.iter_mut());
+      for (skill, saved) in skills.flat_map(|group| group.skills.iter_mut()).zip(&snapshot.skills) {
+        saved.clone_into(skill);
+      }
+      for (group, saved) in self.items.iter_mut().zip(&snapshot.items) {
+        saved.clone_into(&mut group.items);
+      }
+      snapshot.virtues.clone_into(&mut self.virtues);
+      for (deck, saved) in self.decks.iter_mut().zip(&snapshot.decks) {
+        saved.clone_into(&mut deck.slots);
+      }
+      self.adv_lvl = snapshot.adv_lvl;
+      self.prd_lvl = snapshot.prd_lvl;
+      self.gold = snapshot.gold;
+      self.adjustment = None;
+    }
+
+    /// Start a new undo history from the current state.
+    fn reset_history(&mut self) {
+      self.undo.clear();
+      self.redo.clear();
+      self.pending = false;
+      self.current = self.snapshot();
+    }
+
+    fn all_skills(&self) -> impl Iterator<Item = &SkillLvl> {
+      let groups = self.skills.adv.iter().chain(self.skills.prd.iter());
+      groups.flat_map(|group| group.skills.iter())
+    }
+
+    pub fn group_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+      let groups = self.skills.adv.iter().chain(self.skills.prd.iter());
+      groups.map(|group| group.name)
+    }
+
+    pub fn show_skills(
+      &mut self,
+      ui: &mut Ui,
+      filter: Option<&Search>,
+      show_exp: bool,
+      expanded: &mut ExpandedGroups,
+    ) -> bool {
+      // Divide the space evenly between adventurer and producer.
+      let mut changed = false;
+      util::split_evenly(ui, |ui, index| {
+        let category = if index == 0 {
+          SkillCategory::Adventurer
+        } else {
+          SkillCategory::Producer
+        };
+        if self.show_skill_category(ui, category, filter, show_exp, expanded) {
           changed = true;
         }
       });
 
+      // The expanded groups only need to be restored once, and not while filtering.
+      if filter.is_none() {
+        expanded.restored();
+      }
+
       changed
     }
 
-    fn show_skill_category(&mut self, ui: &mut Ui, category: SkillCategory) -> bool {
-      let (scroll_id, groups) = match category {
-        SkillCategory::Adventurer => ("offline_adventurer_skills", &mut self.skills.adv),
-        SkillCategory::Producer => ("offline_producer_skills", &mut self.skills.prd),
+    fn show_skill_category(
+      &mut self,
+      ui: &mut Ui,
+      category: SkillCategory,
+      filter: Option<&Search>,
+      show_exp: bool,
+      expanded: &mut ExpandedGroups,
+    ) -> bool {
+      // Snapshot the levels for the requirement tooltips.
+      let levels: HashMap<u32, i32> = self.all_skills().map(|skill| (skill.info.id, skill.level)).collect();
+      let adjusted = self
+        .adjustment()
+        .map(|adjustment| adjustment.ids.clone())
+        .unwrap_or_default();
+
+      let Skills {
+        adv,
+        prd,
+        tree,
+        names,
+        adv_targets,
+        prd_targets,
+        ..
+      } = &mut self.skills;
+      let (scroll_id, groups, targets) = match category {
+        SkillCategory::Adventurer => ("offline_adventurer_skills", adv, adv_targets),
+        SkillCategory::Producer => ("offline_producer_skills", prd, prd_targets),
       };
 
       let mut changed = None;
+      let mut apply = None;
       ui.vertical(|ui| {
         ScrollArea::vertical()
           .id_salt(scroll_id)
           .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
           .show(ui, |ui| {
-            for skill_group in groups {
+            let matches = |skill: &SkillLvl| filter.is_none_or(|filter| filter.find_in(skill.info.name).is_some());
+            for (skill_group, target) in groups.iter_mut().zip(targets.iter_mut()) {
+              // Skip groups that don't contain any matches.
+              if !skill_group.skills.iter().any(matches) {
+                continue;
+              }
+
+              // Use a different ID while filtering so that the groups with matches are expanded without disturbing
+              // their normal state.
+              let id_salt = if filter.is_some() {
+                format!("{}_offline_filtered", skill_group.name.to_lowercase())
+              } else {
+                format!("{}_offline", skill_group.name.to_lowercase())
+              };
+
               // Use a single column in order to force the scroll area to fill the entire available width.
               ui.columns(1, |col| {
-                CollapsingHeader::new(skill_group.name)
-                  .id_salt(format!("{}_offline", skill_group.name.to_lowercase()))
+                let open = if filter.is_some() {
+                  None
+                } else {
+                  expanded.open(skill_group.name)
+                };
+                let response = CollapsingHeader::new(skill_group.name)
+                  .id_salt(id_salt)
+                  .default_open(filter.is_some())
+                  .open(open)
                   .show(&mut col[0], |ui| {
+                    ui.horizontal(|ui| {
+                      ui.add(DragValue::new(target).range(0..=200));
+                      // While filtering, only the skills that are shown are changed.
+                      let text = if filter.is_some() {
+                        "Apply to matches"
+                      } else {
+                        "Apply to group"
+                      };
+                      if ui.button(text).clicked() {
+                        let ids = skill_group.skills.iter().filter(|skill| matches(skill));
+                        apply = Some((ids.map(|skill| skill.info.id).collect::<Vec<_>>(), *target));
+                      }
+                    });
+
                     let spacing = ui.spacing().item_spacing;
                     let row_size = util::button_size(ui) + spacing[1] * 2.0;
                     let available_width = ui.available_width();
-                    TableBuilder::new(ui)
+                    let name_width = if show_exp { 0.4 } else { 0.64 };
+                    let mut table = TableBuilder::new(ui)
                       .cell_layout(Layout::left_to_right(Align::Center))
                       .striped(true)
                       .vscroll(false)
-                      .column(Column::exact(available_width * 0.64 - spacing[0]))
-                      .column(Column::exact(available_width * 0.18 - spacing[0]))
+                      .column(Column::exact(available_width * name_width - spacing[0]))
+                      .column(Column::exact(available_width * 0.18 - spacing[0]));
+                    if show_exp {
+                      table = table.column(Column::exact(available_width * 0.24 - spacing[0]));
+                    }
+                    table
                       .column(Column::remainder())
                       .header(row_size, |mut header| {
                         const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
@@ -402,29 +1229,57 @@ mod inner {
                         header.col(|ui| {
                           ui.label(RichText::from("Level").color(HEADER_COLOR));
                         });
+                        if show_exp {
+                          header.col(|ui| {
+                            ui.label(RichText::from("XP").color(HEADER_COLOR));
+                          });
+                        }
                         header.col(|ui| {
                           ui.label(RichText::from("ID").color(HEADER_COLOR));
                         });
                       })
                       .body(|mut body| {
                         for skill in &mut skill_group.skills {
+                          if !matches(skill) {
+                            continue;
+                          }
+
                           body.row(row_size, |mut row| {
                             row.col(|ui| {
-                              let color = if skill.level > 0 {
-                                const NAME_COLOR: Color32 = Color32::from_rgb(102, 154, 180);
-                                NAME_COLOR
+                              let color = if adjusted.contains(&skill.info.id) {
+                                ADJUSTED_COLOR
+                              } else if skill.level > 0 {
+                                Color::SkillName.get()
                               } else {
-                                const SUBDUED_NAME_COLOR: Color32 = Color32::from_rgb(80, 120, 140);
-                                SUBDUED_NAME_COLOR
+                                Color::SubduedSkillName.get()
                               };
-                              ui.label(RichText::from(skill.info.name).color(color));
+                              let response = ui.label(RichText::from(skill.info.name).color(color));
+                              if let Some(tip) = get_requirements_tip(skill, tree, names, &levels) {
+                                response.on_hover_text(tip);
+                              }
                             });
+                            // Edits are made to a copy so that they can be rejected.
                             row.col(|ui| {
-                              let widget = DragValue::new(&mut skill.level).range(0..=200);
+                              let mut level = skill.level;
+                              let widget = DragValue::new(&mut level).range(0..=skill.info.level_cap());
                               if ui.add(widget).changed() {
-                                changed = Some(skill.info.id);
+                                let mut edit = skill.clone();
+                                edit.level = level;
+                                changed = Some(edit);
                               }
                             });
+                            if show_exp {
+                              row.col(|ui| {
+                                let mut exp = skill.exp().unwrap_or(0);
+                                let speed = (exp as f64 / 1000.0).max(1.0);
+                                let widget = DragValue::new(&mut exp).speed(speed).range(0..=skill.max_exp());
+                                if ui.add(widget).changed() {
+                                  let mut edit = skill.clone();
+                                  edit.set_exp(exp);
+                                  changed = Some(edit);
+                                }
+                              });
+                            }
                             row.col(|ui| {
                               ui.label(format!("{}", skill.info.id));
                             });
@@ -432,27 +1287,147 @@ mod inner {
                         }
                       });
                   });
+
+                // Keep track of the expanded groups.
+                if filter.is_none() && response.header_response.clicked() {
+                  expanded.toggle(skill_group.name);
+                }
               });
             }
           });
       });
 
+      if let Some((ids, level)) = apply {
+        self.begin_adjustment();
+        self.set_skills_level(&ids, level);
+        self.end_adjustment();
+        return true;
+      }
+
       match changed.take() {
-        Some(id) => {
+        Some(edit) => {
+          let id = edit.info.id;
+          self.begin_adjustment();
+
+          // Reject the edit if a required skill would need to go past its cap.
+          if let Some(note) = self.find_capped_requirement(id, edit.level) {
+            self.note_adjustment(id, note);
+            self.end_adjustment();
+            return false;
+          }
+
+          *self.skills.get_mut(id).unwrap() = edit;
+
           // Make sure this skill meets the minimum level for skills that require it.
-          let min = self.get_skill_min_level(id);
-          let skill = self.skills.get_mut(id).unwrap();
-          skill.level = skill.level.max(min);
+          self.apply_min_level(id, self.skills.get(id).unwrap().level);
 
           // Clone the skill so that we can borrow self as mutable again.
-          let skill = skill.clone();
+          let skill = self.skills.get(id).unwrap().clone();
           self.check_skill_requirements(&skill);
+          self.end_adjustment();
           true
         }
         None => false,
       }
     }
 
+    /// Get the text describing the most recent automatic skill adjustments.
+    pub fn adjustment_text(&self) -> Option<String> {
+      let adjustment = self.adjustment()?;
+      Some(adjustment.notes.join("; "))
+    }
+
+    /// Get the most recent automatic skill adjustments if they're still being shown.
+    fn adjustment(&self) -> Option<&Adjustment> {
+      let adjustment = self.adjustment.as_ref()?;
+      let time = adjustment.time?;
+      (time.elapsed() < ADJUSTMENT_DURATION).then_some(adjustment)
+    }
+
+    /// Start collecting automatic skill adjustments.
+    fn begin_adjustment(&mut self) {
+      self.adjustment = Some(Adjustment::default());
+    }
+
+    /// Keep the collected adjustments if there are any.
+    fn end_adjustment(&mut self) {
+      match &mut self.adjustment {
+        Some(adjustment) if !adjustment.notes.is_empty() => adjustment.time = Some(Instant::now()),
+        _ => self.adjustment = None,
+      }
+    }
+
+    fn note_adjustment(&mut self, id: u32, note: String) {
+      if let Some(adjustment) = &mut self.adjustment {
+        adjustment.ids.insert(id);
+        adjustment.notes.push(note);
+      }
+    }
+
+    /// Set a skill's level, but not below the minimum level required by dependent skills.
+    fn apply_min_level(&mut self, id: u32, level: i32) {
+      let min = self.get_skill_min_level(id);
+      let skill = self.skills.get_mut(id).unwrap();
+      skill.level = level.max(min);
+      if level < min {
+        let name = skill.info.name;
+        let note = format!("{name} kept at {min} (required by {})", self.get_dependents(id, min));
+        self.note_adjustment(id, note);
+      }
+    }
+
+    /// Check if raising a skill to `level` would need a required skill past its level cap. Returns a note describing
+    /// the requirement if so.
+    fn find_capped_requirement(&self, id: u32, level: i32) -> Option<String> {
+      let info = &self.skills.get(id)?.info;
+      if level == 0 {
+        return None;
+      }
+
+      for req in &info.reqs {
+        let req_skill = self.skills.get(req.id)?;
+        let cap = req_skill.info.level_cap();
+        if req.lvl > cap {
+          let name = req_skill.info.name;
+          return Some(format!(
+            "{} needs {name} {} but it's capped at {cap}",
+            info.name, req.lvl
+          ));
+        }
+
+        // Skills that would be enabled have their own requirements.
+        if req_skill.level == 0 {
+          if let Some(note) = self.find_capped_requirement(req.id, req.lvl) {
+            return Some(note);
+          }
+        }
+      }
+      None
+    }
+
+    /// Get the names of the leveled skills that require at least `level` of the specified skill.
+    fn get_dependents(&self, id: u32, level: i32) -> String {
+      let mut names = Vec::new();
+      if let Some(set) = self.skills.tree.get(&id) {
+        for child_id in set {
+          let skill = self.skills.get(*child_id).unwrap();
+          if skill.level > 0 && skill.info.reqs.iter().any(|req| req.id == id && req.lvl >= level) {
+            names.push(skill.info.name);
+          }
+        }
+      }
+      names.sort_unstable();
+      names.join(", ")
+    }
+
+    pub fn report(&self) -> &LoadReport {
+      self.data.report()
+    }
+
+    pub fn file_modified(&self) -> bool {
+      self.data.file_modified()
+    }
+
     pub fn get_file_path(&self) -> PathBuf {
       self.data.get_file_path()
     }
@@ -461,14 +1436,99 @@ mod inner {
       self.data.get_file_name()
     }
 
+    /// Compare the skills with those from another save-game.
+    pub fn plan_skill_copy(&self, data: &GameData) -> SkillCopy {
+      let mut copy = SkillCopy {
+        file_name: data.get_file_name(),
+        exp: HashMap::new(),
+        raised: 0,
+        lowered: 0,
+        added: 0,
+      };
+
+      let categories = [SkillCategory::Adventurer, SkillCategory::Producer];
+      let groups = categories
+        .into_iter()
+        .flat_map(|category| data.get_skills(category, self.skill_override));
+      for skill in groups.flat_map(|group| group.skills) {
+        let Some(current) = self.skills.get(skill.info.id) else {
+          continue;
+        };
+
+        let exp = skill.exp();
+        let current_exp = current.exp();
+        if current.level == 0 && skill.level > 0 {
+          copy.added += 1;
+        } else {
+          match (skill.level, exp).cmp(&(current.level, current_exp)) {
+            Ordering::Greater => copy.raised += 1,
+            Ordering::Less => copy.lowered += 1,
+            Ordering::Equal => continue,
+          }
+        }
+
+        copy.exp.insert(skill.info.id, exp);
+      }
+
+      copy
+    }
+
+    /// Copy skills from another save-game. Levels and gold are not changed. Required skills are raised the same as
+    /// when editing, in case the other save-game's skills differ from the skill data.
+    pub fn copy_skills(&mut self, copy: &SkillCopy) {
+      self.begin_adjustment();
+      for (&id, &exp) in &copy.exp {
+        let Some(skill) = self.skills.get_mut(id) else {
+          continue;
+        };
+
+        match exp {
+          Some(exp) => skill.set_exp(exp),
+          None => skill.level = 0,
+        }
+      }
+
+      // Lowered skills can be required by skills that weren't copied, so check all of them.
+      let mut ids: Vec<u32> = self
+        .all_skills()
+        .filter(|skill| skill.level > 0)
+        .map(|skill| skill.info.id)
+        .collect();
+      ids.sort_unstable();
+      for id in ids {
+        let skill = self.skills.get(id).unwrap().clone();
+        self.check_skill_requirements(&skill);
+      }
+      self.end_adjustment();
+    }
+
     pub fn avatar_name(&self) -> &str {
       self.data.avatar_name()
     }
 
-    pub fn items_mut(&mut self) -> &mut Vec<Item> {
+    pub fn items_mut(&mut self) -> &mut Vec<ItemGroup> {
       &mut self.items
     }
 
+    pub fn virtues(&self) -> &[VirtueLvl] {
+      &self.virtues
+    }
+
+    pub fn virtues_mut(&mut self) -> &mut [VirtueLvl] {
+      &mut self.virtues
+    }
+
+    pub fn decks(&self) -> &[Deck] {
+      &self.decks
+    }
+
+    /// Get the decks for editing, along with all the skills that can be assigned to them.
+    pub fn decks_and_skills(&mut self) -> (&mut [Deck], Vec<&SkillLvl>) {
+      let groups = self.skills.adv.iter().chain(self.skills.prd.iter());
+      let skills = groups.flat_map(|group| group.skills.iter()).collect();
+      (&mut self.decks, skills)
+    }
+
     pub fn adv_level(&self) -> i32 {
       self.adv_lvl
     }
@@ -485,93 +1545,250 @@ mod inner {
       self.prd_lvl = level
     }
 
-    pub fn gold(&self) -> i32 {
+    pub fn gold(&self) -> i64 {
       self.gold
     }
 
-    pub fn set_gold(&mut self, gold: i32) {
+    pub fn set_gold(&mut self, gold: i64) {
       self.gold = gold;
     }
 
-    pub fn store(&mut self) -> Result<(), Cow<'static, str>> {
-      self.update_json();
-      let result = self.data.store();
-      if result.is_ok() {
-        self.accept_changes();
+    /// Get the character data, including any edits that haven't been stored, as a JSON document for external tools.
+    /// Sections that didn't load are `null`.
+    pub fn get_export(&self) -> Value {
+      let report = self.report();
+      let levels = |level: i32, level_cmp: i32, exp: Option<i64>| {
+        // A changed level starts at the beginning of the level, same as when it's stored.
+        let exp = match exp {
+          Some(exp) if level == level_cmp => exp,
+          _ => LEVEL_EXP[level as usize - 1],
+        };
+        json!({ "level": level, "experience": exp })
+      };
+
+      let loaded = report.is_loaded(Section::Levels);
+      let adventurer = loaded.then(|| levels(self.adv_lvl, self.adv_lvl_cmp, self.data.get_adv_exp()));
+      let producer = loaded.then(|| levels(self.prd_lvl, self.prd_lvl_cmp, self.data.get_prd_exp()));
+      let gold = report.is_loaded(Section::Gold).then_some(self.gold);
+
+      let skills = report.is_loaded(Section::Skills).then(|| {
+        let categories = [("adventurer", &self.skills.adv), ("producer", &self.skills.prd)];
+        let mut skills = Vec::new();
+        for (category, groups) in categories {
+          for group in groups {
+            for skill in &group.skills {
+              skills.push(json!({
+                "id": skill.info.id,
+                "name": skill.info.name,
+                "category": category,
+                "group": group.name,
+                "level": skill.level,
+                "experience": skill.exp(),
+              }));
+            }
+          }
+        }
+        skills
+      });
+
+      let inventory = report.is_loaded(Section::Inventory).then(|| {
+        let item_json = |item: &Item| {
+          let durability = item
+            .durability()
+            .map(|dur| json!({ "current": dur.minor, "primary": dur.major }));
+          json!({
+            "id": item.id(),
+            "name": item.name(),
+            "count": item.count(),
+            "durability": durability,
+            "container": item.is_container(),
+          })
+        };
+
+        let groups = self.items.iter().map(|group| {
+          let items: Vec<Value> = group.items.iter().map(item_json).collect();
+          json!({ "name": group.name, "items": items })
+        });
+        groups.collect::<Vec<_>>()
+      });
+
+      json!({
+        "version": EXPORT_VERSION,
+        "generator": format!("{APP_NAME} {APP_VERSION}"),
+        "avatar": self.avatar_name(),
+        "adventurer": adventurer,
+        "producer": producer,
+        "gold": gold,
+        "skills": skills,
+        "inventory": inventory,
+      })
+    }
+
+    /// Update the save-game data with the edits and get it for storing on another thread. Returns `None` if it's
+    /// still being stored.
+    pub fn begin_store(&mut self) -> Option<Arc<GameData>> {
+      if !self.update_json() {
+        return None;
       }
-      result
+      Some(self.data.clone())
     }
 
-    pub fn store_as(&mut self, path: PathBuf) -> Result<(), Cow<'static, str>> {
-      // Make sure the extension is "sota".
-      let path = if path.extension() != Some(OsStr::new("sota")) {
-        path.with_extension("sota")
-      } else {
-        path
-      };
+    /// The save-game data was stored, so the edits are now the loaded values.
+    pub fn end_store(&mut self) {
+      self.accept_changes();
+    }
 
-      self.update_json();
-      let result = self.data.store_as(path);
-      if result.is_ok() {
-        self.accept_changes();
-      }
-      result
+    /// Note that this was loaded from a backup, so the save-game file is replaced when stored.
+    pub fn set_restored(&mut self, backup: String) {
+      self.restored = Some(backup);
     }
 
     pub fn changed(&self) -> bool {
-      self.adv_lvl != self.adv_lvl_cmp
+      self.restored.is_some()
+        || self.adv_lvl != self.adv_lvl_cmp
         || self.prd_lvl != self.prd_lvl_cmp
-        || self.gold_changed()
+        || self.gold != self.gold_cmp
         || self.items_changed()
+        || self.virtues.iter().any(|virtue| virtue.changed())
+        || self.decks.iter().any(|deck| deck.changed())
         || changed(&self.skills.adv)
         || changed(&self.skills.prd)
     }
 
+    /// Get the differences from the loaded save-game, grouped by kind. Groups without differences are left out.
+    pub fn get_changes(&self) -> Vec<ChangeGroup> {
+      let mut groups = Vec::new();
+      let mut add = |name, changes: Vec<Change>| {
+        if !changes.is_empty() {
+          groups.push(ChangeGroup { name, changes });
+        }
+      };
+
+      if let Some(backup) = &self.restored {
+        add("Save-Game", vec![Change::new("Contents", self.get_file_name(), backup)]);
+      }
+
+      let mut changes = Vec::new();
+      if self.adv_lvl != self.adv_lvl_cmp {
+        changes.push(Change::new("Adventurer Level", self.adv_lvl_cmp, self.adv_lvl));
+      }
+      if self.prd_lvl != self.prd_lvl_cmp {
+        changes.push(Change::new("Producer Level", self.prd_lvl_cmp, self.prd_lvl));
+      }
+      if self.gold != self.gold_cmp {
+        changes.push(Change::new("Gold", self.gold_cmp, self.gold));
+      }
+      add("Levels and Gold", changes);
+      add("Adventurer Skills", get_skill_changes(&self.skills.adv));
+      add("Producer Skills", get_skill_changes(&self.skills.prd));
+
+      let mut changes = Vec::new();
+      for group in &self.items {
+        let name = |item: &Item| format!("{} ({})", item.name(), group.name);
+        let loaded: HashMap<&str, &Item> = group.loaded_items().iter().map(|item| (item.id(), item)).collect();
+        for item in &group.items {
+          match loaded.get(item.id()) {
+            Some(_) if item.changed() => {
+              let old = get_item_text(item.loaded_count(), item.loaded_durability());
+              let new = get_item_text(item.count(), item.durability());
+              changes.push(Change::new(name(item), old, new));
+            }
+            Some(_) => (),
+            None => changes.push(Change::new(
+              name(item),
+              NONE,
+              get_item_text(item.count(), item.durability()),
+            )),
+          }
+        }
+
+        let ids: HashSet<&str> = group.items.iter().map(|item| item.id()).collect();
+        for item in group.loaded_items().iter().filter(|item| !ids.contains(item.id())) {
+          let old = get_item_text(item.loaded_count(), item.loaded_durability());
+          changes.push(Change::new(name(item), old, NONE));
+        }
+      }
+      add("Items", changes);
+
+      let changes = self.virtues.iter().filter(|virtue| virtue.changed());
+      let changes = changes.map(|virtue| Change::new(&virtue.name, virtue.loaded_value(), virtue.value));
+      add("Virtues", changes.collect());
+
+      let mut changes = Vec::new();
+      let skill_name = |id: Option<u32>| match id {
+        Some(id) => self.skills.names.get(&id).copied().unwrap_or("?"),
+        None => NONE,
+      };
+      for deck in self.decks.iter().filter(|deck| deck.changed()) {
+        let slots = deck.loaded_slots().iter().zip(&deck.slots).enumerate();
+        for (index, (&old, &new)) in slots.filter(|(_, (old, new))| old != new) {
+          let name = format!("{} Slot {}", deck.name, index + 1);
+          changes.push(Change::new(name, skill_name(old), skill_name(new)));
+        }
+      }
+      add("Decks", changes);
+
+      groups
+    }
+
     pub fn discard_changes(&mut self) {
-      for item in &mut self.items {
-        item.discard();
+      for group in &mut self.items {
+        group.discard();
+      }
+      for virtue in &mut self.virtues {
+        virtue.discard();
+      }
+      for deck in &mut self.decks {
+        deck.discard();
       }
       self.adv_lvl = self.adv_lvl_cmp;
       self.prd_lvl = self.prd_lvl_cmp;
       self.gold = self.gold_cmp;
       discard_changes(&mut self.skills.adv);
       discard_changes(&mut self.skills.prd);
+      self.reset_history();
     }
 
     fn accept_changes(&mut self) {
-      // Since gold can be larger than the editor maximum, we need to check here.
-      if self.gold_changed() {
-        self.gold_cmp = self.gold;
+      for group in &mut self.items {
+        group.accept();
       }
-
-      for item in &mut self.items {
-        item.accept();
+      for virtue in &mut self.virtues {
+        virtue.accept();
+      }
+      for deck in &mut self.decks {
+        deck.accept();
       }
       self.adv_lvl_cmp = self.adv_lvl;
       self.prd_lvl_cmp = self.prd_lvl;
+      self.gold_cmp = self.gold;
       accept_changes(&mut self.skills.adv);
       accept_changes(&mut self.skills.prd);
-    }
+      self.restored = None;
 
-    fn update_json(&mut self) {
-      self.data.set_inventory_items(&self.items);
-      self.data.set_adv_lvl(self.adv_lvl);
-      self.data.set_prd_lvl(self.prd_lvl);
-      self.data.set_gold(self.gold);
-      self.data.set_skills(&self.skills.adv);
-      self.data.set_skills(&self.skills.prd);
+      // The stored state is the new baseline for comparisons, so older snapshots would be stale.
+      self.reset_history();
     }
 
-    fn gold_changed(&self) -> bool {
-      if self.gold != self.gold_cmp {
-        return self.gold_cmp < self.gold || self.gold != super::MAX_GOLD;
-      }
-      false
+    fn update_json(&mut self) -> bool {
+      let Some(data) = Arc::get_mut(&mut self.data) else {
+        return false;
+      };
+
+      data.set_inventory_items(&self.items);
+      data.set_virtues(&self.virtues);
+      data.set_decks(&self.decks);
+      data.set_adv_lvl(self.adv_lvl);
+      data.set_prd_lvl(self.prd_lvl);
+      data.set_gold(self.gold);
+      data.set_skills(&self.skills.adv);
+      data.set_skills(&self.skills.prd);
+      true
     }
 
     fn items_changed(&self) -> bool {
-      for item in &self.items {
-        if item.changed() {
+      for group in &self.items {
+        if group.changed() {
           return true;
         }
       }
@@ -596,6 +1813,46 @@ mod inner {
       min
     }
 
+    fn set_skills_level(&mut self, ids: &[u32], level: i32) {
+      // Skills in the group can depend on each other, so keep going until nothing else changes. Lowering a skill can
+      // only reduce the minimum level of other skills, so this will settle.
+      let mut rejected = HashSet::new();
+      loop {
+        let mut modified = false;
+        for &id in ids {
+          if rejected.contains(&id) {
+            continue;
+          }
+
+          // Don't go past the skill's cap or below the minimum level required by dependent skills.
+          let min = self.get_skill_min_level(id);
+          let skill = self.skills.get_mut(id).unwrap();
+          let level = level.min(skill.info.level_cap());
+          if skill.level == level.max(min) {
+            continue;
+          }
+
+          // Skip skills that would need a required skill past its cap.
+          if let Some(note) = self.find_capped_requirement(id, level.max(min)) {
+            rejected.insert(id);
+            self.note_adjustment(id, note);
+            continue;
+          }
+
+          self.apply_min_level(id, level);
+          modified = true;
+
+          // Clone the skill so that we can borrow self as mutable again.
+          let skill = self.skills.get(id).unwrap().clone();
+          self.check_skill_requirements(&skill);
+        }
+
+        if !modified {
+          break;
+        }
+      }
+    }
+
     fn check_skill_requirements(&mut self, skill: &SkillLvl) {
       if skill.level == 0 {
         return;
@@ -607,16 +1864,78 @@ mod inner {
           let enabling = req_skill.level == 0;
           req_skill.level = req.lvl;
 
+          // We need to clone the required skill here so that we can recurse with mutable self.
+          let req_skill = req_skill.clone();
+          let note = format!(
+            "{} raised to {} (required by {})",
+            req_skill.info.name, req.lvl, skill.info.name
+          );
+          self.note_adjustment(req.id, note);
+
           if enabling {
-            // We need to clone the required skill here so that we can recurse with mutable self.
-            let skill = req_skill.clone();
-            self.check_skill_requirements(&skill);
+            self.check_skill_requirements(&req_skill);
           }
         }
       }
     }
   }
 
+  /// Describe a skill's requirements and the leveled skills that depend on it.
+  fn get_requirements_tip(
+    skill: &SkillLvl,
+    tree: &HashMap<u32, HashSet<u32>>,
+    names: &HashMap<u32, &'static str>,
+    levels: &HashMap<u32, i32>,
+  ) -> Option<String> {
+    let mut lines = Vec::new();
+    for req in &skill.info.reqs {
+      let name = names.get(&req.id).copied().unwrap_or_default();
+      lines.push(format!("Requires {name} {}", req.lvl));
+    }
+
+    if let Some(set) = tree.get(&skill.info.id) {
+      let mut dependents: Vec<_> = set
+        .iter()
+        .filter(|id| levels.get(id).is_some_and(|level| *level > 0))
+        .filter_map(|id| names.get(id).copied())
+        .collect();
+      dependents.sort_unstable();
+      for name in dependents {
+        lines.push(format!("Required by {name}"));
+      }
+    }
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+  }
+
+  /// Placeholder for a value that was added or removed.
+  const NONE: &str = "—";
+
+  fn get_skill_changes(groups: &[SkillLvlGroup]) -> Vec<Change> {
+    let skills = groups.iter().flat_map(|group| group.skills.iter());
+    skills
+      .filter(|skill| skill.changed())
+      .map(|skill| {
+        let (old, new) = (skill.loaded_level(), skill.level);
+        if old == new {
+          // Only the experience within the level changed.
+          let exp = |exp: Option<i64>| format!("{old} ({} XP)", exp.unwrap_or(0));
+          Change::new(skill.info.name, exp(skill.loaded_exp()), exp(skill.exp()))
+        } else {
+          Change::new(skill.info.name, old, new)
+        }
+      })
+      .collect()
+  }
+
+  /// Get the count and durability of an item as text.
+  fn get_item_text(count: u64, dur: Option<&Durability>) -> String {
+    match dur {
+      Some(dur) => format!("{count} ({}/{})", dur.minor, dur.major),
+      None => count.to_string(),
+    }
+  }
+
   fn changed(groups: &Vec<SkillLvlGroup>) -> bool {
     for group in groups {
       if group.changed() {
@@ -638,3 +1957,250 @@ mod inner {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    game_data::{self, ItemGroup, TEST_AVATAR, TEST_USER},
+    skill_info::{self, SkillCategory, SkillInfo},
+  };
+  use util::{TempDir, LEVEL_EXP};
+
+  const BACKPACK: &str = "00000000000000000000000b";
+  const PEARL: &str = "00000000000000000000000c";
+
+  fn first_skill() -> SkillInfo {
+    skill_info::parse_skill_info_groups(SkillCategory::Adventurer, SkillOverride::default())
+      .remove(0)
+      .skills
+      .remove(0)
+  }
+
+  /// Save-game text for Mira, with one skill and one item.
+  fn save_game_text(exp: i64) -> String {
+    let skill = first_skill();
+    let sheet = format!(
+      r#"{{"ae":{exp},"pe":0,"sk2":{{"{}":{{"m":0,"t":"2024-01-02","x":1000}}}}}}"#,
+      skill.id
+    );
+    let items = format!(r#"{{"in":{{"{PEARL}":{{"in":{{"an":"Items/Reagents/BlackPearl","qn":5}}}}}}}}"#);
+    let character = format!(r#"{{"mainbp":"{BACKPACK}"}}"#);
+    game_data::test_save_game(&[
+      ("Character", TEST_AVATAR, &character),
+      ("ItemStore", BACKPACK, &items),
+      ("CharacterSheet", TEST_AVATAR, &sheet),
+      ("UserGold", TEST_USER, r#"{"g":100}"#),
+    ])
+  }
+
+  #[test]
+  fn test_export() {
+    let skill = first_skill();
+    let exp = LEVEL_EXP[9] + 5;
+    let dir = TempDir::new("export");
+    let path = dir.join("export.sota");
+    fs::write(&path, save_game_text(exp)).unwrap();
+    let mut game = GameInfo::new(GameData::load(path).unwrap(), SkillOverride::default());
+
+    let export = game.get_export();
+    assert_eq!(export["version"], EXPORT_VERSION);
+    assert_eq!(export["avatar"], "Mira");
+    assert_eq!(
+      export["adventurer"],
+      serde_json::json!({ "level": 10, "experience": exp })
+    );
+    assert_eq!(export["gold"], 100);
+
+    // Skills that haven't been learned are included.
+    let skills = export["skills"].as_array().unwrap();
+    assert!(skills.len() > 1);
+    assert_eq!(skills[0]["id"], skill.id);
+    assert_eq!(skills[0]["name"], skill.name);
+    assert_eq!(skills[0]["category"], "adventurer");
+    assert_eq!(skills[0]["experience"], 1000);
+    assert!(skills[1]["experience"].is_null());
+
+    let items = &export["inventory"][0];
+    assert_eq!(items["name"], "Backpack");
+    assert_eq!(
+      items["items"][0],
+      serde_json::json!({ "id": PEARL, "name": "BlackPearl", "count": 5, "durability": null, "container": false })
+    );
+
+    // The export has the edits that haven't been stored.
+    game.set_adv_level(12);
+    game.set_gold(250);
+    *game.items_mut()[0].items[0].count_mut() = 7;
+    let export = game.get_export();
+    assert_eq!(export["adventurer"]["experience"], LEVEL_EXP[11]);
+    assert_eq!(export["gold"], 250);
+    assert_eq!(export["inventory"][0]["items"][0]["count"], 7);
+  }
+
+  #[test]
+  fn test_get_changes() {
+    let skill = first_skill();
+    let dir = TempDir::new("changes");
+    let load = |name: &str, text: String| {
+      let path = dir.join(name);
+      fs::write(&path, text).unwrap();
+      GameData::load(path).unwrap()
+    };
+
+    let knowledge =
+      format!(r#"<collection name="UserKnowledge"><record Id="{TEST_AVATAR}">{{"Valor":25}}</record></collection>"#);
+    let mut game = GameInfo::new(
+      load("changes.sota", save_game_text(LEVEL_EXP[9]) + &knowledge),
+      SkillOverride::default(),
+    );
+    let higher = load(
+      "higher.sota",
+      save_game_text(LEVEL_EXP[9]).replace(r#""x":1000"#, r#""x":90000"#),
+    );
+    let partial = load(
+      "partial.sota",
+      save_game_text(LEVEL_EXP[9]).replace(r#""x":1000"#, r#""x":1001"#),
+    );
+    assert!(game.get_changes().is_empty());
+
+    let changes = |game: &GameInfo| {
+      let groups = game.get_changes();
+      let mut result = Vec::new();
+      for group in &groups {
+        for change in &group.changes {
+          result.push((group.name, change.name.clone(), change.old.clone(), change.new.clone()));
+        }
+      }
+      result
+    };
+    let change = |group, name: &str, old: &str, new: &str| (group, name.to_owned(), old.to_owned(), new.to_owned());
+
+    // Only the experience within the level. Copying also raises the skills that it requires.
+    let level = game
+      .decks_and_skills()
+      .1
+      .iter()
+      .find(|lvl| lvl.info.id == skill.id)
+      .unwrap()
+      .level;
+    game.copy_skills(&game.plan_skill_copy(&partial));
+    assert!(changes(&game).contains(&change(
+      "Adventurer Skills",
+      skill.name,
+      &format!("{level} (1000 XP)"),
+      &format!("{level} (1001 XP)")
+    )));
+
+    // The level.
+    game.discard_changes();
+    game.copy_skills(&game.plan_skill_copy(&higher));
+    let raised = game
+      .decks_and_skills()
+      .1
+      .iter()
+      .find(|lvl| lvl.info.id == skill.id)
+      .unwrap()
+      .level;
+    assert!(raised > level);
+    assert!(changes(&game).contains(&change(
+      "Adventurer Skills",
+      skill.name,
+      &level.to_string(),
+      &raised.to_string()
+    )));
+
+    // Levels, gold, items and virtues.
+    game.discard_changes();
+    game.set_adv_level(12);
+    game.set_gold(250);
+    let id = ItemGroup::new_item_id(game.items_mut());
+    let group = &mut game.items_mut()[0];
+    *group.items[0].count_mut() = 7;
+    group.add_item("Items/Reagents/Nightshade", id).unwrap();
+    game.virtues_mut()[0].value = 40.0;
+    assert_eq!(
+      changes(&game),
+      [
+        change("Levels and Gold", "Adventurer Level", "10", "12"),
+        change("Levels and Gold", "Gold", "100", "250"),
+        change("Items", "BlackPearl (Backpack)", "5", "7"),
+        change("Items", "Nightshade (Backpack)", "—", "1"),
+        change("Virtues", "Valor", "25", "40"),
+      ]
+    );
+
+    // Removed items.
+    game.discard_changes();
+    game.items_mut()[0].remove_item(0);
+    assert_eq!(changes(&game), [change("Items", "BlackPearl (Backpack)", "5", "—")]);
+
+    // Decks, which this save-game doesn't have.
+    game.discard_changes();
+    assert!(game.decks().is_empty());
+    assert!(game.get_changes().is_empty());
+  }
+
+  #[test]
+  fn test_edit_decks() {
+    let skill = first_skill();
+    let dir = TempDir::new("edit_decks");
+    let path = dir.join("decks.sota");
+    let text = save_game_text(LEVEL_EXP[9]).replace(r#""pe":0,"#, r#""pe":0,"dk":{"1":{"n":"Fire","s":[0,0]}},"#);
+    fs::write(&path, text).unwrap();
+    let mut game = GameInfo::new(GameData::load(path).unwrap(), SkillOverride::default());
+
+    // Assign the trained skill to the second slot.
+    let (decks, skills) = game.decks_and_skills();
+    assert!(skills.iter().any(|lvl| lvl.info.id == skill.id && lvl.level > 0));
+    decks[0].slots[1] = Some(skill.id);
+    assert!(game.edited());
+    let changes = game.get_changes();
+    assert_eq!(changes[0].name, "Decks");
+    assert_eq!(changes[0].changes[0].name, "Fire Slot 2");
+    assert_eq!(changes[0].changes[0].new, skill.name);
+
+    // Undo and redo.
+    assert!(game.undo());
+    assert_eq!(game.decks()[0].slots, [None, None]);
+    assert!(!game.changed());
+    assert!(game.redo());
+    assert_eq!(game.decks()[0].slots, [None, Some(skill.id)]);
+
+    // Storing writes only the changed slot.
+    let copy = dir.join("decks_copy.sota");
+    game.begin_store().unwrap().store_as(copy.clone(), 0).unwrap();
+    let stored = GameData::load(copy).unwrap();
+    assert_eq!(stored.get_decks()[0].slots, [None, Some(skill.id)]);
+  }
+
+  #[test]
+  fn test_restore_backup() {
+    let dir = TempDir::new("restore");
+    let path = dir.join("restore.sota");
+    let backup = dir.join("restore.sota.bak-20240101-153000.000");
+    fs::write(&path, save_game_text(LEVEL_EXP[9])).unwrap();
+    fs::write(&backup, save_game_text(LEVEL_EXP[9])).unwrap();
+    let mut game = GameInfo::new(GameData::load_backup(path, &backup).unwrap(), SkillOverride::default());
+    game.set_restored(get_file_name(&backup));
+
+    // The backup replaces the save-game file, even if its values are the same.
+    assert!(game.changed());
+    let changes = game.get_changes();
+    assert_eq!(changes[0].name, "Save-Game");
+    assert_eq!(changes[0].changes[0].new, "restore.sota.bak-20240101-153000.000");
+
+    // Edits and undo don't lose that.
+    game.set_gold(250);
+    assert!(game.edited());
+    assert!(game.undo());
+    assert!(game.changed());
+    game.discard_changes();
+    assert!(game.changed());
+
+    // Storing makes it the baseline.
+    game.end_store();
+    assert!(!game.changed());
+    assert!(game.get_changes().is_empty());
+  }
+}
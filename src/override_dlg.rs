@@ -0,0 +1,107 @@
+use crate::{
+  skill_info::{self, SkillOverride},
+  theme::Color,
+  util::AppState,
+};
+use eframe::{
+  egui::{Context, Grid, Key, RichText, ScrollArea, Window},
+  emath::Align2,
+  epaint::Color32,
+};
+use std::path::PathBuf;
+
+/// Dialog window that reports the skill data override entries, or why the override couldn't be loaded.
+pub struct OverrideDlg {
+  state: AppState,
+  path: PathBuf,
+  error: Option<String>,
+  entries: Vec<skill_info::OverrideInfo>,
+  visible: bool,
+}
+
+impl OverrideDlg {
+  /// Create the dialog for the override loaded from `path`.
+  pub fn new(state: AppState, path: PathBuf, error: Option<String>, skill_override: SkillOverride) -> Self {
+    Self {
+      state,
+      path,
+      error,
+      entries: skill_info::get_override_info(skill_override),
+      visible: false,
+    }
+  }
+
+  pub fn show(&mut self, ctx: &Context) -> bool {
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      let mut open = true;
+      Window::new(RichText::from("🛠  Skill Data Override").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          ui.label(format!("Override file: {}", self.path.display()));
+          ui.add_space(ui.spacing().item_spacing.y);
+          if let Some(error) = &self.error {
+            ui.label(RichText::from(error).color(Color::Error.get()));
+            ui.label("The bundled skill data is being used.");
+          } else if self.entries.is_empty() {
+            ui.label("No override entries are active.");
+          } else {
+            ScrollArea::vertical()
+              .max_height(ui.ctx().available_rect().height() * 0.6)
+              .show(ui, |ui| {
+                Grid::new("override_grid").striped(true).show(ui, |ui| {
+                  const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+                  ui.label(RichText::from("Category").color(HEADER_COLOR));
+                  ui.label(RichText::from("Id").color(HEADER_COLOR));
+                  ui.label(RichText::from("Skill").color(HEADER_COLOR));
+                  ui.label(RichText::from("Change").color(HEADER_COLOR));
+                  ui.end_row();
+
+                  for entry in &self.entries {
+                    ui.label(entry.category.name());
+                    ui.label(entry.id.to_string());
+                    ui.label(&entry.name);
+                    ui.label(&entry.change);
+                    ui.end_row();
+                  }
+                });
+              });
+          }
+
+          ui.separator();
+          if ui.button("Close").clicked() {
+            self.close();
+          }
+        });
+      if !open {
+        self.close();
+      }
+    }
+    self.visible
+  }
+
+  pub fn open(&mut self) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      self.visible = true;
+    }
+  }
+
+  fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
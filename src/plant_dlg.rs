@@ -1,12 +1,16 @@
-use crate::{config::Config, plant_info, util::AppState};
+use crate::{
+  config::Config,
+  plant_info,
+  util::{AppState, HOUR_SECS},
+};
 use chrono::{Local, NaiveDate, NaiveTime, Timelike};
 use eframe::{
-  egui::{ComboBox, Context, DragValue, Key, PointerButton, RichText, ScrollArea, TextEdit, Window},
+  egui::{Checkbox, ComboBox, Context, DragValue, Key, PointerButton, RichText, ScrollArea, TextEdit, Window},
   emath::Align2,
   epaint::Color32,
 };
 use egui_extras::DatePickerButton;
-use plant_info::{CropTimer, Environment, Seed};
+use plant_info::{CropLocation, CropTimer, Environment, Seed};
 use std::{collections::BTreeSet, mem};
 
 // #[derive(Default)]
@@ -19,9 +23,15 @@ pub struct PlantDlg {
   seed_names: Vec<&'static str>,
   seed_index: Option<usize>,
   environment: Option<Environment>,
+  growth_hours: Option<f64>,
+  waterings: Option<usize>,
   description: String,
-  descriptions: Descriptions,
+  descriptions: Choices,
+  lot: String,
+  bed: Option<u32>,
+  lots: Choices,
   result: Option<CropTimer>,
+  editing: bool,
   visible: bool,
 }
 
@@ -39,9 +49,15 @@ impl PlantDlg {
       seed_names,
       seed_index: None,
       environment: None,
+      growth_hours: None,
+      waterings: None,
       description: String::new(),
-      descriptions: Descriptions::load(config),
+      descriptions: Choices::descriptions(config.clone()),
+      lot: String::new(),
+      bed: None,
+      lots: Choices::lots(config),
       result: None,
+      editing: false,
       visible: false,
     }
   }
@@ -51,9 +67,43 @@ impl PlantDlg {
       let now = Local::now();
       self.date = now.date_naive();
       self.description = String::new();
+      self.lot = String::new();
+      self.bed = None;
+      self.growth_hours = None;
+      self.waterings = None;
       self.hour = now.hour();
       self.min = now.minute();
       self.result = None;
+      self.editing = false;
+      self.state.set_disabled(true);
+      self.visible = true;
+    }
+  }
+
+  /// Open the dialog populated from an existing timer.
+  pub fn open_edit(&mut self, timer: &CropTimer) {
+    if !self.visible {
+      let date_time = timer.date_time();
+      self.date = date_time.date();
+      self.hour = date_time.hour();
+      self.min = date_time.minute();
+      self.seed_index = self.seed_names.iter().position(|name| *name == timer.seed_name());
+      self.environment = Some(timer.environment());
+      self.growth_hours = timer.growth_secs().map(|secs| secs as f64 / HOUR_SECS as f64);
+      self.waterings = timer.waterings();
+      timer.description().clone_into(&mut self.description);
+      match timer.location() {
+        Some(location) => {
+          location.lot.clone_into(&mut self.lot);
+          self.bed = location.bed;
+        }
+        None => {
+          self.lot = String::new();
+          self.bed = None;
+        }
+      }
+      self.result = None;
+      self.editing = true;
       self.state.set_disabled(true);
       self.visible = true;
     }
@@ -66,7 +116,13 @@ impl PlantDlg {
       let available = ctx.available_rect();
       let mut open = true;
 
-      Window::new(RichText::from("⏰  Add Crop Timer").strong())
+      let title = if self.editing {
+        "⏰  Edit Crop Timer"
+      } else {
+        "⏰  Add Crop Timer"
+      };
+
+      Window::new(RichText::from(title).strong())
         .open(&mut open)
         .collapsible(false)
         .current_pos([0.0, 24.0])
@@ -156,6 +212,100 @@ impl PlantDlg {
 
           ui.add_space(3.0);
 
+          ui.horizontal(|ui| {
+            // Growth time override.
+            let mut custom = self.growth_hours.is_some();
+            ui.spacing_mut().item_spacing.x = item_spacing.x * 0.5;
+            if ui.add(Checkbox::new(&mut custom, "")).changed() {
+              self.growth_hours = custom.then(|| self.default_growth_hours());
+            }
+
+            ui.label(RichText::from("Growth Hours").color(LABEL_COLOR));
+            ui.add_enabled_ui(custom, |ui| {
+              let mut hours = self.growth_hours.unwrap_or_else(|| self.default_growth_hours());
+              let widget = DragValue::new(&mut hours)
+                .range(0.1..=2400.0)
+                .speed(0.1)
+                .max_decimals(1);
+              if ui.add(widget).changed() {
+                self.growth_hours = Some(hours);
+              }
+            });
+
+            ui.separator();
+
+            // Watering count override.
+            let mut custom = self.waterings.is_some();
+            if ui.add(Checkbox::new(&mut custom, "")).changed() {
+              self.waterings = custom.then_some(plant_info::DEFAULT_WATERINGS);
+            }
+
+            ui.label(RichText::from("Waterings").color(LABEL_COLOR));
+            ui.add_enabled_ui(custom, |ui| {
+              let mut waterings = self.waterings.unwrap_or(plant_info::DEFAULT_WATERINGS);
+              let widget = DragValue::new(&mut waterings).range(0..=10).speed(0.125);
+              if ui.add(widget).changed() {
+                self.waterings = Some(waterings);
+              }
+            });
+          });
+
+          ui.add_space(3.0);
+
+          ui.horizontal(|ui| {
+            // Lot.
+            ui.spacing_mut().item_spacing.x = item_spacing.x * 0.5;
+            ui.label(RichText::from("Lot").color(LABEL_COLOR));
+            let widget = TextEdit::singleline(&mut self.lot)
+              .hint_text("unassigned")
+              .desired_width(157.0);
+            ui.add(widget);
+
+            // Lot list.
+            let mut remove = None;
+            ui.add_enabled_ui(!self.lots.list.is_empty(), |ui| {
+              ui.menu_button("⏷", |ui| {
+                for lot in &self.lots.list {
+                  ui.horizontal(|ui| {
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                      remove = Some(lot.to_owned());
+                    }
+
+                    if ui.selectable_label(self.lot == *lot, lot).clicked() {
+                      lot.clone_into(&mut self.lot);
+                      ui.close_menu();
+                    }
+                  });
+                }
+              });
+            });
+
+            if let Some(remove) = remove.take() {
+              self.lots.remove(&remove);
+            }
+
+            ui.spacing_mut().item_spacing.x = item_spacing.x;
+            ui.separator();
+
+            // Bed number.
+            let mut numbered = self.bed.is_some();
+            ui.spacing_mut().item_spacing.x = item_spacing.x * 0.5;
+            if ui.add(Checkbox::new(&mut numbered, "")).changed() {
+              self.bed = numbered.then_some(1);
+            }
+
+            ui.label(RichText::from("Bed").color(LABEL_COLOR));
+            ui.add_enabled_ui(numbered, |ui| {
+              let mut bed = self.bed.unwrap_or(1);
+              let widget = DragValue::new(&mut bed).range(1..=99).speed(0.125);
+              if ui.add(widget).changed() {
+                self.bed = Some(bed);
+              }
+            });
+          });
+
+          ui.add_space(3.0);
+
           ui.horizontal(|ui| {
             // Additional information.
             let widget = TextEdit::singleline(&mut self.description).hint_text("additional info");
@@ -217,6 +367,15 @@ impl PlantDlg {
     self.result.take()
   }
 
+  /// Get the default growth time, in hours, for the current seed and environment.
+  fn default_growth_hours(&self) -> f64 {
+    let (Some(index), Some(environment)) = (self.seed_index, self.environment) else {
+      return 1.0;
+    };
+
+    environment.growth_secs(self.seed_types[index]) as f64 / HOUR_SECS as f64
+  }
+
   fn accept(&mut self) {
     if self.visible {
       let Some(index) = self.seed_index else { return };
@@ -225,13 +384,28 @@ impl PlantDlg {
       };
       let time = NaiveTime::from_hms_opt(self.hour, self.min, 0).unwrap();
       self.descriptions.insert(self.description.clone());
-      self.result = Some(CropTimer::new(
+
+      // A timer without a lot is unassigned.
+      let lot = self.lot.trim();
+      let location = (!lot.is_empty()).then(|| CropLocation {
+        lot: lot.to_owned(),
+        bed: self.bed,
+      });
+      if let Some(location) = &location {
+        self.lots.insert(location.lot.clone());
+      }
+
+      let mut timer = CropTimer::new(
         mem::take(&mut self.description),
         self.date.and_time(time),
         self.seed_names[index].to_owned(),
         self.seed_types[index],
         environment,
-      ));
+        self.growth_hours.map(|hours| (hours * HOUR_SECS as f64) as i64),
+        self.waterings,
+      );
+      timer.set_location(location);
+      self.result = Some(timer);
       self.state.set_disabled(false);
       self.visible = false;
     }
@@ -253,27 +427,35 @@ impl PlantDlg {
   }
 }
 
-struct Descriptions {
-  #[allow(unused)]
+/// Previously entered text that's persisted in the config.
+struct Choices {
   config: Config,
   list: BTreeSet<String>,
+  store: fn(&mut Config, &BTreeSet<String>),
 }
 
-impl Descriptions {
-  fn load(config: Config) -> Self {
+impl Choices {
+  fn descriptions(config: Config) -> Self {
     let list = config.get_crop_descriptions().unwrap_or_default();
-    Descriptions { config, list }
+    let store = Config::set_crop_descriptions;
+    Choices { config, list, store }
+  }
+
+  fn lots(config: Config) -> Self {
+    let list = config.get_crop_lots().unwrap_or_default();
+    let store = Config::set_crop_lots;
+    Choices { config, list, store }
   }
 
   fn insert(&mut self, text: String) {
     if !text.is_empty() && self.list.insert(text) {
-      self.config.set_crop_descriptions(&self.list);
+      (self.store)(&mut self.config, &self.list);
     }
   }
 
   fn remove(&mut self, text: &str) {
     if self.list.remove(text) {
-      self.config.set_crop_descriptions(&self.list);
+      (self.store)(&mut self.config, &self.list);
     }
   }
 }
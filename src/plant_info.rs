@@ -1,6 +1,7 @@
 use crate::util::HOUR_SECS;
-use chrono::{Duration, Local, NaiveDateTime};
-use serde::{Deserialize, Serialize};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{borrow::Cow, fmt};
 
 #[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Seed {
@@ -40,6 +41,222 @@ pub enum Environment {
   Inside = 240 * HOUR_SECS as isize / 3,
 }
 
+impl Environment {
+  /// Default total growth time, in seconds, for a seed type in this environment.
+  pub fn growth_secs(self, seed_type: Seed) -> i64 {
+    seed_type as i64 * self as i64 * (DEFAULT_WATERINGS as i64 + 1)
+  }
+}
+
+/// Crop timers parsed from pasted planting notes.
+#[derive(Default)]
+pub struct PlantingNotes {
+  pub timers: Vec<CropTimer>,
+  pub errors: Vec<NoteError>,
+}
+
+/// A planting note line that couldn't be parsed.
+#[derive(Debug, PartialEq)]
+pub struct NoteError {
+  /// One based line number.
+  pub line: usize,
+  pub text: String,
+  pub reason: Cow<'static, str>,
+}
+
+/// Parse planting notes, one planting per line, such as `Cotton x6, greenhouse, 14:30`. The fields are separated by
+/// commas and can be in any order:
+/// - seed name (case-insensitive) with an optional count, e.g. `Cotton x6`, `6x Cotton` or `Cotton 6`
+/// - environment: greenhouse, outside or inside
+/// - planting time (`14:30` or `2:30 PM`), optionally preceded by a date (`2024-05-01 14:30`)
+///
+/// Anything else becomes the timer's description. Without a time, the timers are planted `now`; a time without a date
+/// that's later than `now` is taken to be from the previous day. Blank lines and lines starting with `#` are ignored.
+pub fn parse_planting_notes(text: &str, seeds: &[(&'static str, Seed)], now: NaiveDateTime) -> PlantingNotes {
+  let mut notes = PlantingNotes::default();
+  for (index, line) in text.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    match parse_planting_note(line, seeds, now) {
+      Ok(note) => {
+        for _ in 0..note.count {
+          notes.timers.push(CropTimer::new(
+            note.description.clone(),
+            note.date_time,
+            note.seed_name.to_owned(),
+            note.seed_type,
+            note.environment,
+            None,
+            None,
+          ));
+        }
+      }
+      Err(reason) => notes.errors.push(NoteError {
+        line: index + 1,
+        text: line.to_owned(),
+        reason,
+      }),
+    }
+  }
+  notes
+}
+
+struct PlantingNote {
+  count: usize,
+  seed_name: &'static str,
+  seed_type: Seed,
+  environment: Environment,
+  date_time: NaiveDateTime,
+  description: String,
+}
+
+fn parse_planting_note(
+  line: &str,
+  seeds: &[(&'static str, Seed)],
+  now: NaiveDateTime,
+) -> Result<PlantingNote, Cow<'static, str>> {
+  let mut seed = None;
+  let mut environment = None;
+  let mut date_time = None;
+  let mut unknown = Vec::new();
+  for field in line.split(',').map(str::trim).filter(|field| !field.is_empty()) {
+    if let Some(env) = parse_environment(field) {
+      if environment.replace(env).is_some() {
+        return Err(Cow::from("more than one environment"));
+      }
+    } else if looks_like_time(field) {
+      let value = parse_planting_time(field, now).ok_or_else(|| Cow::from(format!("invalid time \"{field}\"")))?;
+      if date_time.replace(value).is_some() {
+        return Err(Cow::from("more than one time"));
+      }
+    } else if let Some(value) = parse_seed_count(field, seeds)? {
+      if seed.replace(value).is_some() {
+        return Err(Cow::from("more than one seed"));
+      }
+    } else {
+      unknown.push(field);
+    }
+  }
+
+  let Some((seed_name, seed_type, count)) = seed else {
+    return Err(match unknown.first() {
+      Some(field) => Cow::from(format!("unknown seed \"{field}\"")),
+      None => Cow::from("missing seed"),
+    });
+  };
+
+  let Some(environment) = environment else {
+    return Err(Cow::from("missing environment (greenhouse, outside or inside)"));
+  };
+
+  Ok(PlantingNote {
+    count,
+    seed_name,
+    seed_type,
+    environment,
+    date_time: date_time.unwrap_or_else(|| now.with_second(0).unwrap_or(now)),
+    description: unknown.join(", "),
+  })
+}
+
+fn parse_environment(field: &str) -> Option<Environment> {
+  match field.to_lowercase().as_str() {
+    "greenhouse" => Some(Environment::Greenhouse),
+    "outside" | "outdoors" => Some(Environment::Outside),
+    "inside" | "indoors" => Some(Environment::Inside),
+    _ => None,
+  }
+}
+
+/// Check if a field is meant to be a date and/or time.
+fn looks_like_time(field: &str) -> bool {
+  field.starts_with(|ch: char| ch.is_ascii_digit()) && (field.contains(':') || field.contains('-'))
+}
+
+fn parse_planting_time(field: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+  let (date, time) = match field.split_once(char::is_whitespace) {
+    Some((date, time)) if date.contains('-') => (Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?), time.trim()),
+    _ if !field.contains(':') => return Some(NaiveDate::parse_from_str(field, "%Y-%m-%d").ok()?.and_time(now.time())),
+    _ => (None, field),
+  };
+
+  const FORMATS: [&str; 4] = ["%H:%M", "%H:%M:%S", "%I:%M %p", "%I:%M%p"];
+  let time = FORMATS
+    .iter()
+    .find_map(|format| NaiveTime::parse_from_str(time, format).ok())?;
+
+  match date {
+    Some(date) => Some(date.and_time(time)),
+    None => {
+      // A planting time later than now is from yesterday.
+      let date_time = now.date().and_time(time);
+      if date_time > now {
+        return Some(date_time - Duration::days(1));
+      }
+      Some(date_time)
+    }
+  }
+}
+
+/// Parse a seed name with an optional count. Returns `None` if the field isn't a known seed.
+fn parse_seed_count(
+  field: &str,
+  seeds: &[(&'static str, Seed)],
+) -> Result<Option<(&'static str, Seed, usize)>, Cow<'static, str>> {
+  let mut count = None;
+  let mut words = Vec::new();
+  for word in field.split_whitespace() {
+    let digits = word
+      .strip_prefix(['x', 'X', '*', '×'])
+      .or_else(|| word.strip_suffix(['x', 'X']))
+      .unwrap_or(word);
+    if !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_digit()) {
+      if count.replace(digits).is_some() {
+        return Err(Cow::from("more than one count"));
+      }
+    } else if !matches!(word, "x" | "X" | "*" | "×") {
+      words.push(word);
+    }
+  }
+
+  let name = words.join(" ");
+  let Some(&(seed_name, seed_type)) = seeds
+    .iter()
+    .find(|(seed_name, _)| seed_name.eq_ignore_ascii_case(&name))
+  else {
+    return Ok(None);
+  };
+
+  let count = match count {
+    Some(digits) => match digits.parse() {
+      Ok(count) if (1..=MAX_PLANTING_COUNT).contains(&count) => count,
+      _ => return Err(Cow::from(format!("count must be from 1 to {MAX_PLANTING_COUNT}"))),
+    },
+    None => 1,
+  };
+  Ok(Some((seed_name, seed_type, count)))
+}
+
+/// Maximum number of timers that a single planting note can add.
+const MAX_PLANTING_COUNT: usize = 100;
+
+/// Default number of times that a crop needs to be watered.
+pub const DEFAULT_WATERINGS: usize = 2;
+
+/// Display order for the crop timer list.
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CropOrder {
+  /// Soonest pending event first.
+  #[default]
+  NextEvent,
+
+  /// Oldest planting first.
+  Planted,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Event {
   None,
@@ -47,14 +264,40 @@ pub enum Event {
   Harvest,
 }
 
+/// Where a crop is planted.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CropLocation {
+  pub lot: String,
+
+  /// Plant bed number within the lot.
+  #[serde(default)]
+  pub bed: Option<u32>,
+}
+
+impl fmt::Display for CropLocation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.bed {
+      Some(bed) => write!(f, "{}, Bed {bed}", self.lot),
+      None => write!(f, "{}", self.lot),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CropTimer {
   description: String,
+  #[serde(default)]
+  location: Option<CropLocation>,
   date_time: NaiveDateTime,
   seed_name: String,
   seed_type: Seed,
   environment: Environment,
-  events: [Option<bool>; 3],
+  #[serde(deserialize_with = "deserialize_events")]
+  events: Vec<Option<bool>>,
+  #[serde(default)]
+  growth_secs: Option<i64>,
+  #[serde(default)]
+  waterings: Option<usize>,
 }
 
 impl CropTimer {
@@ -64,14 +307,20 @@ impl CropTimer {
     seed_name: String,
     seed_type: Seed,
     environment: Environment,
+    growth_secs: Option<i64>,
+    waterings: Option<usize>,
   ) -> Self {
+    let count = waterings.unwrap_or(DEFAULT_WATERINGS) + 1;
     Self {
       description,
+      location: None,
       date_time,
       seed_name,
       seed_type,
       environment,
-      events: [Some(false); 3],
+      events: vec![Some(false); count],
+      growth_secs,
+      waterings,
     }
   }
 
@@ -79,6 +328,14 @@ impl CropTimer {
     &self.description
   }
 
+  pub fn location(&self) -> Option<&CropLocation> {
+    self.location.as_ref()
+  }
+
+  pub fn set_location(&mut self, location: Option<CropLocation>) {
+    self.location = location;
+  }
+
   pub fn date_time(&self) -> NaiveDateTime {
     self.date_time
   }
@@ -91,31 +348,61 @@ impl CropTimer {
     self.environment
   }
 
+  pub fn growth_secs(&self) -> Option<i64> {
+    self.growth_secs
+  }
+
+  pub fn waterings(&self) -> Option<usize> {
+    self.waterings
+  }
+
+  /// Keep the event flags from another timer if the timing is the same.
+  pub fn keep_events(&mut self, other: &CropTimer) {
+    if self.date_time == other.date_time
+      && self.seed_type == other.seed_type
+      && self.environment == other.environment
+      && self.growth_secs == other.growth_secs
+      && self.events.len() == other.events.len()
+    {
+      self.events.clone_from(&other.events);
+    }
+  }
+
   /// Get the current event.
   pub fn current_event(&self) -> Event {
-    if self.events[2] == Some(true) {
+    let Some((harvest, waterings)) = self.events.split_last() else {
+      return Event::None;
+    };
+
+    if *harvest == Some(true) {
       return Event::Harvest;
     }
 
-    if self.events[0] == Some(true) || self.events[1] == Some(true) {
+    if waterings.contains(&Some(true)) {
       return Event::Water;
     }
 
     Event::None
   }
 
+  /// Date and time that the current event became due.
+  pub fn event_date_time(&self) -> Option<NaiveDateTime> {
+    let index = self.events.iter().position(|event| *event == Some(true))?;
+    let seconds = Duration::try_seconds(self.event_timeout(index + 1))?;
+    Some(self.date_time + seconds)
+  }
+
   /// Get information about the remaining events.
   pub fn remaining_events(&self) -> Vec<(Event, NaiveDateTime)> {
     let elapsed = (Local::now().naive_local() - self.date_time).num_seconds();
-    let interval = self.seed_type as i64 * self.environment as i64;
     let mut events = Vec::with_capacity(self.events.len());
 
     for count in 1..=self.events.len() {
-      let timeout = interval * count as i64;
+      let timeout = self.event_timeout(count);
       if elapsed < timeout {
         if let Some(seconds) = Duration::try_seconds(timeout) {
           let date_time = self.date_time + seconds;
-          if count < 3 {
+          if count < self.events.len() {
             events.push((Event::Water, date_time));
           } else {
             events.push((Event::Harvest, date_time));
@@ -131,11 +418,10 @@ impl CropTimer {
   /// Check and update events.
   pub fn check(&mut self) -> bool {
     let elapsed = (Local::now().naive_local() - self.date_time).num_seconds();
-    let interval = self.seed_type as i64 * self.environment as i64;
 
     // Check the last event first.
     for count in (0..self.events.len()).rev() {
-      if elapsed > interval * (count as i64 + 1) {
+      if elapsed > self.event_timeout(count + 1) {
         if self.events[count] == Some(false) {
           // Flag this event.
           self.events[count] = Some(true);
@@ -164,4 +450,152 @@ impl CropTimer {
       }
     }
   }
+
+  /// Number of seconds from planting until the specified event (one based).
+  fn event_timeout(&self, count: usize) -> i64 {
+    let total = self.events.len() as i64;
+    let growth_secs = match self.growth_secs {
+      Some(secs) => secs,
+      None => self.environment.growth_secs(self.seed_type),
+    };
+    growth_secs * count as i64 / total
+  }
+}
+
+/// Deserialize the event flags, accepting the fixed size array used by older configurations.
+fn deserialize_events<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Option<bool>>, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Events {
+    Legacy([Option<bool>; DEFAULT_WATERINGS + 1]),
+    List(Vec<Option<bool>>),
+  }
+
+  Ok(match Events::deserialize(deserializer)? {
+    Events::Legacy(events) => events.to_vec(),
+    Events::List(events) => events,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date_time(text: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+  }
+
+  #[test]
+  fn test_parse_planting_notes() {
+    let seeds = parse_seeds();
+    let now = date_time("2024-05-01 16:45:30");
+    let text = concat!(
+      "Cotton x6, greenhouse, 14:30\n",
+      "\n",
+      "# Back yard\n",
+      "outside, 2x blue rose, 2024-04-30 9:15 PM, by the well\n",
+      "Carrot, inside\n",
+      "pumpkin 3, Outdoors, 18:00\n",
+    );
+    let notes = parse_planting_notes(text, &seeds, now);
+    assert!(notes.errors.is_empty());
+
+    let summary: Vec<_> = notes
+      .timers
+      .iter()
+      .map(|timer| {
+        (
+          timer.seed_name(),
+          timer.environment(),
+          timer.date_time(),
+          timer.description(),
+        )
+      })
+      .collect();
+    let cotton = ("Cotton", Environment::Greenhouse, date_time("2024-05-01 14:30:00"), "");
+    let rose = (
+      "Blue Rose",
+      Environment::Outside,
+      date_time("2024-04-30 21:15:00"),
+      "by the well",
+    );
+    let carrot = ("Carrot", Environment::Inside, date_time("2024-05-01 16:45:00"), "");
+
+    // A time later than now is from the previous day.
+    let pumpkin = ("Pumpkin", Environment::Outside, date_time("2024-04-30 18:00:00"), "");
+    let mut expected = vec![cotton; 6];
+    expected.extend([rose, rose, carrot, pumpkin, pumpkin, pumpkin]);
+    assert_eq!(summary, expected);
+  }
+
+  #[test]
+  fn test_crop_location() {
+    // Timers stored before locations were added are unassigned.
+    let text = concat!(
+      r#"{"description":"","date_time":"2024-05-01T14:30:00","seed_name":"Corn","seed_type":"Low","#,
+      r#""environment":"Outside","events":[false,false,false]}"#
+    );
+    let timer: CropTimer = serde_json::from_str(text).unwrap();
+    assert_eq!(timer.location(), None);
+
+    let location = CropLocation {
+      lot: String::from("Back Yard"),
+      bed: Some(2),
+    };
+    let mut timer = CropTimer::new(
+      String::new(),
+      timer.date_time(),
+      String::from("Corn"),
+      Seed::Low,
+      Environment::Outside,
+      None,
+      None,
+    );
+    timer.set_location(Some(location.clone()));
+    let text = serde_json::to_string(&timer).unwrap();
+    let timer: CropTimer = serde_json::from_str(&text).unwrap();
+    assert_eq!(timer.location(), Some(&location));
+    assert_eq!(location.to_string(), "Back Yard, Bed 2");
+
+    let location = CropLocation { bed: None, ..location };
+    assert_eq!(location.to_string(), "Back Yard");
+  }
+
+  #[test]
+  fn test_planting_note_errors() {
+    let seeds = parse_seeds();
+    let now = date_time("2024-05-01 16:45:30");
+    let text = concat!(
+      "Coton x6, greenhouse\n",
+      "Cotton x6\n",
+      "Cotton x0, greenhouse\n",
+      "Cotton, greenhouse, 25:00\n",
+      "Cotton, greenhouse, inside\n",
+      "Corn, outside\n",
+      "greenhouse\n",
+    );
+    let notes = parse_planting_notes(text, &seeds, now);
+
+    // The good line is kept.
+    assert_eq!(notes.timers.len(), 1);
+    assert_eq!(notes.timers[0].seed_name(), "Corn");
+
+    let errors: Vec<_> = notes
+      .errors
+      .iter()
+      .map(|error| (error.line, error.reason.as_ref()))
+      .collect();
+    assert_eq!(
+      errors,
+      [
+        (1, "unknown seed \"Coton x6\""),
+        (2, "missing environment (greenhouse, outside or inside)"),
+        (3, "count must be from 1 to 100"),
+        (4, "invalid time \"25:00\""),
+        (5, "more than one environment"),
+        (7, "missing seed"),
+      ]
+    );
+    assert_eq!(notes.errors[0].text, "Coton x6, greenhouse");
+  }
 }
@@ -0,0 +1,173 @@
+use crate::{
+  plant_info::{self, CropTimer, PlantingNotes, Seed},
+  theme::Color,
+  util::AppState,
+};
+use chrono::Local;
+use eframe::{
+  egui::{Button, Context, Key, RichText, ScrollArea, TextEdit, Window},
+  emath::Align2,
+};
+use std::mem;
+
+/// Dialog window for adding crop timers from pasted planting notes.
+pub struct PlantingDlg {
+  state: AppState,
+  seeds: Vec<(&'static str, Seed)>,
+  text: String,
+  result: Vec<CropTimer>,
+  added: usize,
+  visible: bool,
+}
+
+impl PlantingDlg {
+  pub fn new(state: AppState) -> Self {
+    Self {
+      state,
+      seeds: plant_info::parse_seeds(),
+      text: String::new(),
+      result: Vec::new(),
+      added: 0,
+      visible: false,
+    }
+  }
+
+  pub fn open(&mut self) {
+    if !self.visible {
+      self.text.clear();
+      self.result.clear();
+      self.added = 0;
+      self.state.set_disabled(true);
+      self.visible = true;
+    }
+  }
+
+  pub fn show(&mut self, ctx: &Context) -> bool {
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      let mut open = true;
+      Window::new(RichText::from("⏰  Add Crop Timers from Notes").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          ui.label("One planting per line: seed and count, environment and time, separated by commas.");
+          ui.add_space(ui.spacing().item_spacing.y);
+
+          let widget = TextEdit::multiline(&mut self.text)
+            .hint_text("Cotton x6, greenhouse, 14:30")
+            .desired_width(NOTES_WIDTH)
+            .desired_rows(8);
+          ui.add(widget);
+
+          let notes = plant_info::parse_planting_notes(&self.text, &self.seeds, Local::now().naive_local());
+          if !notes.errors.is_empty() {
+            ScrollArea::vertical().max_height(ERRORS_HEIGHT).show(ui, |ui| {
+              for error in &notes.errors {
+                let text = format!("Line {}: {}", error.line, error.reason);
+                ui.label(RichText::from(text).color(Color::Error.get()));
+              }
+            });
+          }
+
+          ui.separator();
+          ui.horizontal(|ui| {
+            let count = notes.timers.len();
+            let text = match count {
+              1 => String::from("Add 1 Timer"),
+              count => format!("Add {count} Timers"),
+            };
+            if ui.add_enabled(count > 0, Button::new(text)).clicked() {
+              self.add(notes);
+            }
+
+            // Added timers are kept, so don't offer to cancel once there are any.
+            let text = if self.added > 0 { "Close" } else { "Cancel" };
+            if ui.button(text).clicked() {
+              self.close();
+            }
+
+            if self.added > 0 {
+              let text = match self.added {
+                1 => String::from("1 timer added"),
+                count => format!("{count} timers added"),
+              };
+              ui.label(text);
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+
+    self.visible
+  }
+
+  /// Take the timers that were added. Timers are available as soon as they're added, not just when the dialog closes.
+  pub fn take_result(&mut self) -> Vec<CropTimer> {
+    mem::take(&mut self.result)
+  }
+
+  fn add(&mut self, notes: PlantingNotes) {
+    // Only the lines that failed are left.
+    let failed: Vec<&str> = notes.errors.iter().map(|error| error.text.as_str()).collect();
+    self.text = failed.join("\n");
+    self.added += notes.timers.len();
+    self.result.extend(notes.timers);
+    if failed.is_empty() {
+      self.close();
+    }
+  }
+
+  fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
+
+const NOTES_WIDTH: f32 = 480.0;
+const ERRORS_HEIGHT: f32 = 120.0;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDateTime;
+
+  #[test]
+  fn test_add_twice() {
+    let mut dlg = PlantingDlg::new(AppState::default());
+    let now = NaiveDateTime::parse_from_str("2024-05-01 16:45:30", "%Y-%m-%d %H:%M:%S").unwrap();
+    dlg.open();
+
+    // The second line fails, so the dialog stays open for another add.
+    let notes = plant_info::parse_planting_notes("Cotton, greenhouse, 14:30\nNot a seed", &dlg.seeds, now);
+    dlg.add(notes);
+    assert!(dlg.visible);
+    assert_eq!(dlg.text, "Not a seed");
+
+    let notes = plant_info::parse_planting_notes("Carrot, inside", &dlg.seeds, now);
+    dlg.add(notes);
+    assert!(!dlg.visible);
+    assert_eq!(dlg.added, 2);
+
+    let seeds: Vec<_> = dlg
+      .take_result()
+      .iter()
+      .map(|timer| timer.seed_name().to_owned())
+      .collect();
+    assert_eq!(seeds, ["Cotton", "Carrot"]);
+    assert!(dlg.take_result().is_empty());
+  }
+}
@@ -0,0 +1,105 @@
+use crate::util;
+use eframe::{
+  egui::{Context, Grid, Key, Layout, RichText, Window},
+  emath::{Align, Align2},
+  epaint::Color32,
+};
+use util::AppState;
+
+pub struct RiftDlg {
+  state: AppState,
+  rift: Option<usize>,
+}
+
+impl RiftDlg {
+  pub fn new(state: AppState) -> Self {
+    Self { state, rift: None }
+  }
+
+  pub fn open(&mut self, rift: usize) {
+    self.state.set_disabled(true);
+    self.rift = Some(rift);
+  }
+
+  /// Index of the rift being shown, if visible.
+  pub fn rift(&self) -> Option<usize> {
+    self.rift
+  }
+
+  /// Show the dialog. `openings` contains the open and close times, in seconds from now, for the rift.
+  pub fn show(&mut self, ctx: &Context, name: &str, openings: &[(i32, i32)], clock_12h: bool) {
+    if self.rift.is_none() {
+      return;
+    }
+
+    self.handle_hotkeys(ctx);
+
+    let mut open = true;
+    Window::new(RichText::from(format!("🌙  {name} Openings")).strong())
+      .open(&mut open)
+      .collapsible(false)
+      .current_pos([0.0, 24.0])
+      .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+      .default_size([ctx.available_rect().width(), 0.0])
+      .resizable(false)
+      .show(ctx, |ui| {
+        Grid::new("rift_openings_grid")
+          .min_col_width((ui.available_width() - ui.spacing().item_spacing.x * 2.0) / 3.0)
+          .show(ui, |ui| {
+            // Header.
+            const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+            ui.label(RichText::from("Opens").color(HEADER_COLOR));
+            ui.centered_and_justified(|ui| {
+              ui.label(RichText::from("Closes").color(HEADER_COLOR));
+            });
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+              ui.label(RichText::from("Status").color(HEADER_COLOR));
+            });
+            ui.end_row();
+
+            for &(begin, end) in openings {
+              let (color, status) = if begin <= 0 {
+                (
+                  Color32::from_rgb(154, 229, 255),
+                  util::get_countdown_text("Closes: ", end),
+                )
+              } else {
+                (
+                  Color32::from_rgb(102, 154, 180),
+                  util::get_countdown_text("Opens: ", begin),
+                )
+              };
+
+              ui.label(RichText::from(util::get_clock_text(begin, clock_12h)).color(color));
+              ui.centered_and_justified(|ui| {
+                ui.label(RichText::from(util::get_clock_text(end, clock_12h)).color(color));
+              });
+              ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                ui.label(status);
+              });
+              ui.end_row();
+            }
+          });
+        ui.separator();
+        ui.horizontal(|ui| {
+          if ui.button("Close").clicked() {
+            self.close();
+          }
+        });
+      });
+    if !open {
+      self.close();
+    }
+  }
+
+  pub fn close(&mut self) {
+    self.state.set_disabled(false);
+    self.rift = None;
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Enter) || state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
@@ -1,37 +1,81 @@
-use crate::util::{AppState, Search};
+use crate::{
+  config::Config,
+  log_data::DateRange,
+  stats::StatsQuery,
+  theme::Color,
+  util::{AppState, Search},
+};
+use chrono::{Local, NaiveDate};
 use eframe::{
-  egui::{Context, Key, Layout, RichText, Window},
+  egui::{ComboBox, Context, Key, Layout, RichText, Ui, Window},
   emath::{Align, Align2},
   epaint::Color32,
 };
+use egui_extras::DatePickerButton;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 pub struct SearchDlg {
+  config: Config,
   state: AppState,
   title: String,
   text: String,
   error: String,
   search: Option<Search>,
   search_type: SearchType,
+  log_search: Option<LogSearch>,
+  stats_filter: bool,
+  query: Option<StatsQuery>,
+  history: Vec<SearchTerm>,
   visible: bool,
   focus: bool,
 }
 
 // Dialog window for inputting search term.
 impl SearchDlg {
-  pub fn new(state: AppState) -> Self {
+  pub fn new(config: Config, state: AppState) -> Self {
+    let history = config.get_filter_history().unwrap_or_default();
     Self {
+      config,
       state,
       title: String::new(),
       text: String::new(),
       error: String::new(),
       search: None,
       search_type: SearchType::Default,
+      log_search: None,
+      stats_filter: false,
+      query: None,
+      history,
       visible: false,
       focus: false,
     }
   }
 
+  /// Create a dialog that also has the options of searching the logs of all avatars and limiting the dates.
+  pub fn new_log_search(config: Config, state: AppState) -> Self {
+    let today = Local::now().date_naive();
+    let history = config.get_log_search_history().unwrap_or_default();
+    Self {
+      log_search: Some(LogSearch {
+        all_avatars: false,
+        begin: (false, today),
+        end: (false, today),
+      }),
+      history,
+      ..Self::new(config, state)
+    }
+  }
+
+  /// Create a dialog that accepts a stats query: space-separated terms that must all match, where terms like ">=100"
+  /// compare the value.
+  pub fn new_stats_filter(config: Config, state: AppState) -> Self {
+    Self {
+      stats_filter: true,
+      ..Self::new(config, state)
+    }
+  }
+
   pub fn show(&mut self, ctx: &Context) -> bool {
     if self.visible {
       self.handle_hotkeys(ctx);
@@ -54,9 +98,35 @@ impl SearchDlg {
               response.request_focus();
             }
           });
+          if !self.history.is_empty() {
+            // Recent search terms.
+            let mut selected = None;
+            ComboBox::from_id_salt("search_history_combo")
+              .selected_text("Recent")
+              .width(ui.available_width())
+              .show_ui(ui, |ui| {
+                for term in &self.history {
+                  if ui.selectable_label(false, term.label()).clicked() {
+                    selected = Some((term.text.clone(), term.search_type));
+                  }
+                }
+              });
+            if let Some((text, search_type)) = selected {
+              self.text = text;
+              self.search_type = search_type;
+              self.focus = true;
+            }
+          }
+          if let Some(log_search) = &mut self.log_search {
+            ui.horizontal(|ui| {
+              show_date(ui, "From", &mut log_search.begin, "search_begin_date_picker");
+              ui.separator();
+              show_date(ui, "To", &mut log_search.end, "search_end_date_picker");
+            });
+          }
           if !self.error.is_empty() {
             ui.vertical_centered(|ui| {
-              let error = RichText::new(&self.error).color(Color32::LIGHT_RED);
+              let error = RichText::new(&self.error).color(Color::Error.get());
               ui.label(error);
             });
           }
@@ -73,6 +143,10 @@ impl SearchDlg {
             }
 
             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+              if let Some(log_search) = &mut self.log_search {
+                ui.checkbox(&mut log_search.all_avatars, "All Avatars");
+              }
+
               let widget = ui.radio(self.search_type == SearchType::Regex, "Regex");
               if widget.clicked() {
                 self.search_type = match self.search_type {
@@ -102,7 +176,9 @@ impl SearchDlg {
     if !self.visible {
       self.state.set_disabled(true);
       self.title = title;
+      self.error.clear();
       self.search = None;
+      self.query = None;
       self.visible = true;
       self.focus = true;
     }
@@ -112,37 +188,96 @@ impl SearchDlg {
     self.search.take()
   }
 
+  pub fn take_stats_query(&mut self) -> Option<StatsQuery> {
+    self.query.take()
+  }
+
+  /// Check if the logs of all avatars should be searched.
+  pub fn all_avatars(&self) -> bool {
+    self
+      .log_search
+      .as_ref()
+      .is_some_and(|log_search| log_search.all_avatars)
+  }
+
+  /// Get the date range for log searches.
+  pub fn date_range(&self) -> DateRange {
+    let Some(log_search) = &self.log_search else {
+      return DateRange::default();
+    };
+
+    let (use_begin, begin) = log_search.begin;
+    let (use_end, end) = log_search.end;
+    DateRange {
+      begin: use_begin.then_some(begin),
+      end: use_end.then_some(end),
+    }
+  }
+
   fn accept(&mut self) {
     if self.visible {
       if self.text.is_empty() {
         return;
       }
 
-      self.search = match self.search_type {
-        SearchType::Default | SearchType::NoCase => {
-          let ignore_case = self.search_type == SearchType::NoCase;
-          let mut find = String::new();
-          std::mem::swap(&mut find, &mut self.text);
-          Some(Search::String { find, ignore_case })
-        }
-        SearchType::Regex => match Regex::new(&self.text) {
-          Ok(regex) => {
+      let term = SearchTerm {
+        text: self.text.clone(),
+        search_type: self.search_type,
+      };
+
+      if self.stats_filter {
+        let search_type = self.search_type;
+        match StatsQuery::parse(&self.text, |term| make_search(term, search_type)) {
+          Ok(query) => {
             self.text.clear();
-            Some(Search::Regex(regex))
+            self.query = Some(query);
           }
           Err(err) => {
-            self.text = format!("{err:?}");
+            self.error = err;
             return;
           }
-        },
-      };
+        }
+      } else {
+        self.search = match self.search_type {
+          SearchType::Default | SearchType::NoCase => {
+            let ignore_case = self.search_type == SearchType::NoCase;
+            let mut find = String::new();
+            std::mem::swap(&mut find, &mut self.text);
+            Some(Search::String { find, ignore_case })
+          }
+          SearchType::Regex => match Regex::new(&self.text) {
+            Ok(regex) => {
+              self.text.clear();
+              Some(Search::Regex(regex))
+            }
+            Err(err) => {
+              self.text = format!("{err:?}");
+              return;
+            }
+          },
+        };
+      }
 
+      self.add_history(term);
       self.state.set_disabled(false);
       self.title.clear();
       self.visible = false;
     }
   }
 
+  /// Move the search term to the front of the history.
+  fn add_history(&mut self, term: SearchTerm) {
+    self.history.retain(|item| *item != term);
+    self.history.insert(0, term);
+    self.history.truncate(HISTORY_SIZE);
+
+    if self.log_search.is_some() {
+      self.config.set_log_search_history(&self.history);
+    } else {
+      self.config.set_filter_history(&self.history);
+    }
+  }
+
   fn reject(&mut self) {
     if self.visible {
       self.state.set_disabled(false);
@@ -161,7 +296,57 @@ impl SearchDlg {
   }
 }
 
-#[derive(Eq, PartialEq)]
+/// Create the search for a stats query's name term.
+fn make_search(term: &str, search_type: SearchType) -> Result<Search, String> {
+  match search_type {
+    SearchType::Default | SearchType::NoCase => Ok(Search::String {
+      find: term.to_owned(),
+      ignore_case: search_type == SearchType::NoCase,
+    }),
+    SearchType::Regex => Regex::new(term).map(Search::Regex).map_err(|err| err.to_string()),
+  }
+}
+
+/// Show a checkbox and date picker for one end of the date range.
+fn show_date(ui: &mut Ui, label: &str, date: &mut (bool, NaiveDate), id: &str) {
+  const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+  let x_spacing = ui.spacing().item_spacing.x;
+  ui.spacing_mut().item_spacing.x *= 0.5;
+  ui.checkbox(&mut date.0, RichText::from(label).color(LABEL_COLOR));
+  ui.spacing_mut().item_spacing.x = x_spacing;
+  ui.add_enabled_ui(date.0, |ui| {
+    ui.add(DatePickerButton::new(&mut date.1).id_salt(id).show_icon(false));
+  });
+}
+
+/// Additional options for log searches.
+struct LogSearch {
+  all_avatars: bool,
+  begin: (bool, NaiveDate),
+  end: (bool, NaiveDate),
+}
+
+/// Maximum number of search terms to remember.
+const HISTORY_SIZE: usize = 20;
+
+/// Previously accepted search term.
+#[derive(Eq, PartialEq, Serialize, Deserialize)]
+pub struct SearchTerm {
+  text: String,
+  search_type: SearchType,
+}
+
+impl SearchTerm {
+  fn label(&self) -> String {
+    match self.search_type {
+      SearchType::Default => self.text.clone(),
+      SearchType::NoCase => format!("{} (ignore case)", self.text),
+      SearchType::Regex => format!("{} (regex)", self.text),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 enum SearchType {
   Default,
   NoCase,
@@ -1,9 +1,22 @@
-#[derive(Clone, Copy, Debug)]
+use crate::util::LVL_RANGE;
+use serde::Deserialize;
+use std::{collections::HashSet, fs, io, path::Path};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SkillCategory {
   Adventurer,
   Producer,
 }
 
+impl SkillCategory {
+  pub fn name(self) -> &'static str {
+    match self {
+      SkillCategory::Adventurer => "Adventurer",
+      SkillCategory::Producer => "Producer",
+    }
+  }
+}
+
 #[derive(Clone, Default)]
 pub struct Requires {
   pub id: u32,
@@ -16,6 +29,27 @@ pub struct SkillInfo {
   pub mul: f64,
   pub id: u32,
   pub reqs: Vec<Requires>,
+
+  /// Highest level the game allows for this skill, if it's lower than the usual maximum.
+  pub max_lvl: Option<i32>,
+
+  /// Multiplier when trained from the pooled experience instead of by crafting, if it's different.
+  pub pool_mul: Option<f64>,
+}
+
+impl SkillInfo {
+  /// Get the experience multiplier, either for training from the pooled experience or the usual one.
+  pub fn get_mul(&self, pooled: bool) -> f64 {
+    match self.pool_mul {
+      Some(pool_mul) if pooled => pool_mul,
+      _ => self.mul,
+    }
+  }
+
+  /// Get the highest level the skill can be trained to.
+  pub fn level_cap(&self) -> i32 {
+    self.max_lvl.unwrap_or(*LVL_RANGE.end())
+  }
 }
 
 #[derive(Default)]
@@ -33,8 +67,18 @@ impl SkillInfoGroup {
   }
 }
 
-/// Parse the CSV for adventurer or producer skills.
-pub fn parse_skill_info_groups(category: SkillCategory) -> Vec<SkillInfoGroup> {
+/// Parse the CSV for adventurer or producer skills, with the skill data override (if any) merged in.
+pub fn parse_skill_info_groups(category: SkillCategory, skill_override: SkillOverride) -> Vec<SkillInfoGroup> {
+  let mut groups = parse_bundled_groups(category);
+  if let Some(file) = skill_override.file {
+    apply_override(&mut groups, file.entries(category), category);
+  }
+  groups
+}
+
+/// Parse the bundled CSV for adventurer or producer skills.
+fn parse_bundled_groups(category: SkillCategory) -> Vec<SkillInfoGroup> {
+  let pool_mul = |mul: f64| (category == SkillCategory::Producer).then_some(mul * PRODUCER_POOL_PENALTY);
   let text = match category {
     SkillCategory::Adventurer => include_str!("../res/adventurer_skills.csv"),
     SkillCategory::Producer => include_str!("../res/producer_skills.csv"),
@@ -69,7 +113,14 @@ pub fn parse_skill_info_groups(category: SkillCategory) -> Vec<SkillInfoGroup> {
       let reqs = tmp_reqs.clone();
       tmp_reqs.clear();
 
-      skill_group.skills.push(SkillInfo { name, mul, id, reqs });
+      skill_group.skills.push(SkillInfo {
+        name,
+        mul,
+        id,
+        reqs,
+        max_lvl: None,
+        pool_mul: pool_mul(mul),
+      });
     }
   }
 
@@ -79,3 +130,394 @@ pub fn parse_skill_info_groups(category: SkillCategory) -> Vec<SkillInfoGroup> {
 
   skill_groups
 }
+
+/// Skill data override file, which lets skills be added, changed or hidden without a new release.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverrideFile {
+  #[serde(default)]
+  adventurer: Vec<OverrideSkill>,
+  #[serde(default)]
+  producer: Vec<OverrideSkill>,
+}
+
+impl OverrideFile {
+  fn entries(&self, category: SkillCategory) -> &[OverrideSkill] {
+    match category {
+      SkillCategory::Adventurer => &self.adventurer,
+      SkillCategory::Producer => &self.producer,
+    }
+  }
+}
+
+/// Skill override entry. Existing skills are matched by `id`, anything else is added.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverrideSkill {
+  id: u32,
+
+  /// Group to add a new skill to. It's created if it doesn't exist.
+  group: Option<String>,
+  name: Option<String>,
+  mul: Option<f64>,
+
+  /// Multiplier when trained from the pooled experience.
+  pool_mul: Option<f64>,
+
+  /// Required skill ids and levels.
+  reqs: Option<Vec<(u32, i32)>>,
+
+  /// Highest level the skill can be trained to.
+  max_lvl: Option<i32>,
+
+  /// Remove the skill.
+  #[serde(default)]
+  hidden: bool,
+}
+
+/// Description of an active skill override entry.
+pub struct OverrideInfo {
+  pub category: SkillCategory,
+  pub id: u32,
+  pub name: String,
+  pub change: String,
+}
+
+/// Skill data override, which is passed to everything that parses the skills. The default is no override.
+#[derive(Clone, Copy, Default)]
+pub struct SkillOverride {
+  // Loaded once at startup and kept for as long as the skills that borrow its names.
+  file: Option<&'static OverrideFile>,
+}
+
+/// Load the skill data override file, if it exists.
+pub fn load_override(path: &Path) -> Result<SkillOverride, String> {
+  let file = match fs::read_to_string(path) {
+    Ok(text) => parse_override(&text).map_err(|err| format!("{}: {err}", path.display()))?,
+    Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(SkillOverride::default()),
+    Err(err) => return Err(format!("Unable to read {}: {err}", path.display())),
+  };
+
+  let file: &'static OverrideFile = Box::leak(Box::new(file));
+  Ok(SkillOverride { file: Some(file) })
+}
+
+/// Describe the active skill override entries.
+pub fn get_override_info(skill_override: SkillOverride) -> Vec<OverrideInfo> {
+  match skill_override.file {
+    Some(file) => describe_override(file),
+    None => Vec::new(),
+  }
+}
+
+fn parse_override(text: &str) -> Result<OverrideFile, String> {
+  let file: OverrideFile = serde_json::from_str(text).map_err(|err| err.to_string())?;
+  for category in [SkillCategory::Adventurer, SkillCategory::Producer] {
+    let groups = parse_bundled_groups(category);
+    validate_override(file.entries(category), &groups).map_err(|err| format!("{} skills: {err}", category.name()))?;
+  }
+  Ok(file)
+}
+
+/// Check that the entries can be applied to the bundled skills.
+fn validate_override(entries: &[OverrideSkill], groups: &[SkillInfoGroup]) -> Result<(), String> {
+  let bundled: HashSet<u32> = groups
+    .iter()
+    .flat_map(|group| &group.skills)
+    .map(|skill| skill.id)
+    .collect();
+  let mut ids = HashSet::new();
+  for entry in entries {
+    let id = entry.id;
+    if !ids.insert(id) {
+      return Err(format!("skill {id} is listed more than once"));
+    }
+
+    if let Some(mul) = entry.mul {
+      if !mul.is_finite() || mul <= 0.0 {
+        return Err(format!("skill {id} has an invalid mul ({mul})"));
+      }
+    }
+
+    if let Some(pool_mul) = entry.pool_mul {
+      if !pool_mul.is_finite() || pool_mul <= 0.0 {
+        return Err(format!("skill {id} has an invalid pool_mul ({pool_mul})"));
+      }
+    }
+
+    for &(req, lvl) in entry.reqs.iter().flatten() {
+      if !LVL_RANGE.contains(&lvl) {
+        return Err(format!("skill {id} requires an invalid level ({lvl}) of skill {req}"));
+      }
+    }
+
+    if let Some(max_lvl) = entry.max_lvl {
+      if !LVL_RANGE.contains(&max_lvl) {
+        return Err(format!("skill {id} has an invalid max_lvl ({max_lvl})"));
+      }
+    }
+
+    if bundled.contains(&id) {
+      continue;
+    }
+
+    // New skills need everything.
+    if entry.hidden {
+      return Err(format!("skill {id} can't be hidden because it doesn't exist"));
+    }
+
+    for (field, missing) in [
+      ("group", entry.group.is_none()),
+      ("name", entry.name.is_none()),
+      ("mul", entry.mul.is_none()),
+    ] {
+      if missing {
+        return Err(format!("new skill {id} needs a {field}"));
+      }
+    }
+  }
+
+  // Required skills need to exist after the override is applied.
+  let hidden: HashSet<u32> = entries
+    .iter()
+    .filter(|entry| entry.hidden)
+    .map(|entry| entry.id)
+    .collect();
+  for entry in entries.iter().filter(|entry| !entry.hidden) {
+    for &(req, _) in entry.reqs.iter().flatten() {
+      let id = entry.id;
+      if hidden.contains(&req) {
+        return Err(format!("skill {id} requires skill {req}, which is hidden"));
+      }
+      if !bundled.contains(&req) && !ids.contains(&req) {
+        return Err(format!("skill {id} requires skill {req}, which doesn't exist"));
+      }
+    }
+  }
+
+  // Including for the bundled skills that keep their requirements.
+  let changed: HashSet<u32> = entries
+    .iter()
+    .filter(|entry| entry.hidden || entry.reqs.is_some())
+    .map(|entry| entry.id)
+    .collect();
+  for skill in groups.iter().flat_map(|group| &group.skills) {
+    if changed.contains(&skill.id) {
+      continue;
+    }
+
+    if let Some(req) = skill.reqs.iter().find(|req| hidden.contains(&req.id)) {
+      return Err(format!(
+        "skill {} can't be hidden because skill {} requires it",
+        req.id, skill.id
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Merge validated override entries into the skill groups.
+fn apply_override(groups: &mut Vec<SkillInfoGroup>, entries: &'static [OverrideSkill], category: SkillCategory) {
+  let default_pool_mul = |mul: f64| (category == SkillCategory::Producer).then_some(mul * PRODUCER_POOL_PENALTY);
+  for entry in entries {
+    let existing = groups.iter_mut().find_map(|group| {
+      let index = group.skills.iter().position(|skill| skill.id == entry.id)?;
+      Some((group, index))
+    });
+
+    if let Some((group, index)) = existing {
+      if entry.hidden {
+        group.skills.remove(index);
+        continue;
+      }
+
+      let skill = &mut group.skills[index];
+      if let Some(name) = &entry.name {
+        skill.name = name;
+      }
+      if let Some(mul) = entry.mul {
+        skill.mul = mul;
+        skill.pool_mul = default_pool_mul(mul);
+      }
+      if entry.pool_mul.is_some() {
+        skill.pool_mul = entry.pool_mul;
+      }
+      if let Some(reqs) = &entry.reqs {
+        skill.reqs = get_reqs(reqs);
+      }
+      if entry.max_lvl.is_some() {
+        skill.max_lvl = entry.max_lvl;
+      }
+      continue;
+    }
+
+    // Add the new skill, creating the group if needed.
+    let (Some(group_name), Some(name), Some(mul)) = (&entry.group, &entry.name, entry.mul) else {
+      continue;
+    };
+
+    let index = match groups.iter().position(|group| group.name == group_name) {
+      Some(index) => index,
+      None => {
+        groups.push(SkillInfoGroup::new(group_name));
+        groups.len() - 1
+      }
+    };
+
+    let reqs = entry.reqs.as_deref().map(get_reqs).unwrap_or_default();
+    groups[index].skills.push(SkillInfo {
+      name,
+      mul,
+      id: entry.id,
+      reqs,
+      max_lvl: entry.max_lvl,
+      pool_mul: entry.pool_mul.or(default_pool_mul(mul)),
+    });
+  }
+
+  // Don't leave empty groups behind.
+  groups.retain(|group| !group.skills.is_empty());
+}
+
+fn describe_override(file: &OverrideFile) -> Vec<OverrideInfo> {
+  let mut info = Vec::new();
+  for category in [SkillCategory::Adventurer, SkillCategory::Producer] {
+    let groups = parse_bundled_groups(category);
+    for entry in file.entries(category) {
+      let bundled = groups
+        .iter()
+        .flat_map(|group| &group.skills)
+        .find(|skill| skill.id == entry.id);
+
+      let change = if bundled.is_none() {
+        String::from("Added")
+      } else if entry.hidden {
+        String::from("Hidden")
+      } else {
+        let changed: Vec<&str> = [
+          entry.name.as_ref().map(|_| "name"),
+          entry.mul.map(|_| "mul"),
+          entry.pool_mul.map(|_| "pooled mul"),
+          entry.reqs.as_ref().map(|_| "requirements"),
+          entry.max_lvl.map(|_| "max level"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        format!("Changed {}", changed.join(", "))
+      };
+
+      let name = entry.name.as_deref().or(bundled.map(|skill| skill.name));
+      info.push(OverrideInfo {
+        category,
+        id: entry.id,
+        name: name.unwrap_or_default().to_owned(),
+        change,
+      });
+    }
+  }
+  info
+}
+
+fn get_reqs(reqs: &[(u32, i32)]) -> Vec<Requires> {
+  reqs.iter().map(|&(id, lvl)| Requires { id, lvl }).collect()
+}
+
+/// Producer skills cost twice the experience when trained from the pooled experience.
+const PRODUCER_POOL_PENALTY: f64 = 2.0;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_apply_override() {
+    let text = r#"{
+      "adventurer": [
+        { "id": 5, "mul": 1.5, "reqs": [[6, 20]], "max_lvl": 40 },
+        { "id": 7, "hidden": true },
+        { "id": 90001, "group": "Air", "name": "Gust", "mul": 2, "reqs": [[5, 40]] },
+        { "id": 90002, "group": "Storms", "name": "Squall", "mul": 1 }
+      ]
+    }"#;
+    let file: &'static OverrideFile = Box::leak(Box::new(parse_override(text).unwrap()));
+    let mut groups = parse_bundled_groups(SkillCategory::Adventurer);
+    let count = groups.len();
+    apply_override(
+      &mut groups,
+      file.entries(SkillCategory::Adventurer),
+      SkillCategory::Adventurer,
+    );
+    let find = |id| {
+      groups
+        .iter()
+        .flat_map(|group| &group.skills)
+        .find(|skill| skill.id == id)
+    };
+
+    // Changed.
+    let skill = find(5).unwrap();
+    assert_eq!(skill.name, "Air's Embrace");
+    assert_eq!(skill.mul, 1.5);
+    assert_eq!((skill.reqs[0].id, skill.reqs[0].lvl), (6, 20));
+    assert_eq!(skill.level_cap(), 40);
+
+    // Hidden.
+    assert!(find(7).is_none());
+
+    // Added to an existing group and to a new one.
+    let air = groups.iter().find(|group| group.name == "Air").unwrap();
+    assert!(air.skills.iter().any(|skill| skill.id == 90001 && skill.name == "Gust"));
+    assert_eq!(find(90001).unwrap().level_cap(), 200);
+    assert_eq!(groups.len(), count + 1);
+    assert_eq!(groups[count].name, "Storms");
+
+    let info = describe_override(file);
+    let changes: Vec<&str> = info.iter().map(|info| info.change.as_str()).collect();
+    assert_eq!(
+      changes,
+      ["Changed mul, requirements, max level", "Hidden", "Added", "Added"]
+    );
+    assert_eq!(info[1].name, "Blink");
+  }
+
+  #[test]
+  fn test_override_errors() {
+    // Syntax and unknown fields.
+    assert!(parse_override("{").is_err());
+    assert!(parse_override(r#"{ "adventurers": [] }"#).is_err());
+    assert!(parse_override(r#"{ "adventurer": [{ "id": 5, "multiplier": 2 }] }"#).is_err());
+
+    // Bad values.
+    assert!(parse_override(r#"{ "adventurer": [{ "id": 5, "mul": 0 }] }"#).is_err());
+    assert!(parse_override(r#"{ "adventurer": [{ "id": 5, "reqs": [[7, 201]] }] }"#).is_err());
+    assert!(parse_override(r#"{ "adventurer": [{ "id": 5 }, { "id": 5 }] }"#).is_err());
+    assert!(parse_override(r#"{ "adventurer": [{ "id": 5, "max_lvl": 0 }] }"#).is_err());
+
+    // New skills need a group, name and mul.
+    let err = parse_override(r#"{ "producer": [{ "id": 90001, "name": "Whittling", "mul": 1 }] }"#).err();
+    assert_eq!(err.as_deref(), Some("Producer skills: new skill 90001 needs a group"));
+    assert!(parse_override(r#"{ "producer": [{ "id": 90001, "hidden": true }] }"#).is_err());
+
+    // Required skills need to exist.
+    let err = parse_override(r#"{ "adventurer": [{ "id": 5, "reqs": [[90001, 10]] }] }"#).err();
+    assert_eq!(
+      err.as_deref(),
+      Some("Adventurer skills: skill 5 requires skill 90001, which doesn't exist")
+    );
+    let err =
+      parse_override(r#"{ "adventurer": [{ "id": 5, "reqs": [[7, 10]] }, { "id": 7, "hidden": true }] }"#).err();
+    assert_eq!(
+      err.as_deref(),
+      Some("Adventurer skills: skill 5 requires skill 7, which is hidden")
+    );
+    assert!(parse_override(r#"{ "adventurer": [{ "id": 5, "hidden": true }] }"#).is_err());
+
+    // Including ones that are added.
+    let text = r#"{ "adventurer": [{ "id": 5, "reqs": [[90001, 10]] }, { "id": 90001, "group": "Air", "name": "Gust", "mul": 2 }] }"#;
+    assert!(parse_override(text).is_ok());
+
+    // Empty is fine.
+    assert!(parse_override("{}").is_ok());
+  }
+}
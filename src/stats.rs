@@ -1,45 +1,63 @@
 use crate::{
-  config::Config, dps_dlg::DPSDlg, log_data, log_dlg::LogDlg, notes_dlg::NotesDlg, search_dlg::SearchDlg, util,
+  config::Config,
+  dps_dlg::DPSDlg,
+  history_dlg::HistoryDlg,
+  log_data,
+  log_dlg::LogDlg,
+  log_watcher::{LogChange, LogEvent, LogSubscription, LogWatcher},
+  notes_dlg::NotesDlg,
+  search_dlg::SearchDlg,
+  stats_cache::StatsCache,
+  theme::Color,
+  util,
 };
+use chrono::{Local, NaiveDate};
 use eframe::{
-  egui::{ComboBox, Context, Layout, RichText, Ui},
+  egui::{Button, CollapsingHeader, ComboBox, Context, Label, Layout, ProgressBar, RichText, Sense, Ui},
   emath::Align,
   epaint::Color32,
 };
 use egui_extras::{Column, TableBuilder};
 use futures::{channel::mpsc, executor::ThreadPool};
-use log_data::StatsData;
+use log_data::{AvatarLogs, DateRange, StatsData};
 use num_format::Locale;
 use std::{
-  collections::HashMap,
-  mem,
-  path::{Path, PathBuf},
+  collections::{BTreeMap, HashMap, HashSet},
+  fs, mem,
+  path::PathBuf,
+  time::Duration,
 };
 use util::{AppState, Cancel, Search};
 
 pub struct Stats {
   config: Config,
-  resist_stats: HashMap<&'static str, (Resist, f64)>,
 
   // Threading.
   threads: ThreadPool,
   channel: Channel,
+  watcher: Option<LogSubscription>,
+  stats_cache: Option<StatsCache>,
 
   // State.
   locale: Locale,
-  log_path: PathBuf,
+  log_paths: Vec<PathBuf>,
+  log_path_error: Option<PathBuf>,
+  choose_folder_request: bool,
   state: AppState,
 
   // Collections.
   avatars: Vec<String>,
+  active_avatar: Option<String>,
   dates: Vec<i64>,
 
   // Current selection.
   avatar: String,
   date: Option<i64>,
+  compare: Option<i64>,
 
   // Stats.
   stats: StatsData,
+  compare_stats: StatsData,
   filter: StatsFilter,
 
   // Dialog windows.
@@ -48,35 +66,22 @@ pub struct Stats {
   notes_dlg: NotesDlg,
   log_dlg: LogDlg,
   dps_dlg: DPSDlg,
+  history_dlg: HistoryDlg,
+
+  // Set when the user selects a different avatar.
+  avatar_changed: bool,
+
+  // CSV export.
+  csv_request: bool,
+  error: Option<String>,
+  open_error: Option<String>,
 
   // initialize on first view.
   init: bool,
 }
 
 impl Stats {
-  pub fn new(log_path: PathBuf, threads: ThreadPool, config: Config, state: AppState, locale: Locale) -> Self {
-    let resist_stats = HashMap::from([
-      ("AirAttunement", (Resist::Air, 0.5)),
-      ("AirResistance", (Resist::Air, 1.0)),
-      ("ChaosAttunement", (Resist::Chaos, 0.5)),
-      ("ChaosResistance", (Resist::Chaos, 1.0)),
-      ("DeathAttunement", (Resist::Death, 0.5)),
-      ("DeathResistance", (Resist::Death, 1.0)),
-      ("EarthAttunement", (Resist::Earth, 0.5)),
-      ("EarthResistance", (Resist::Earth, 1.0)),
-      ("FireAttunement", (Resist::Fire, 0.5)),
-      ("FireResistance", (Resist::Fire, 1.0)),
-      ("LifeAttunement", (Resist::Life, 0.5)),
-      ("LifeResistance", (Resist::Life, 1.0)),
-      ("MoonAttunement", (Resist::Moon, 0.5)),
-      ("MoonResistance", (Resist::Moon, 1.0)),
-      ("SunAttunement", (Resist::Sun, 0.5)),
-      ("SunResistance", (Resist::Sun, 1.0)),
-      ("WaterAttunement", (Resist::Water, 0.5)),
-      ("WaterResistance", (Resist::Water, 1.0)),
-      ("MagicResistance", (Resist::Magic, 1.0)),
-    ]);
-
+  pub fn new(log_paths: Vec<PathBuf>, threads: ThreadPool, config: Config, state: AppState, locale: Locale) -> Self {
     // Threading.
     let (tx, rx) = mpsc::unbounded();
     let channel = Channel {
@@ -85,8 +90,11 @@ impl Stats {
       cancel_avatars: None,
       cancel_dates: None,
       cancel_stats: None,
+      cancel_compare: None,
       cancel_search: None,
+      cancel_history: None,
     };
+    let stats_cache = StatsCache::new(config.stats_cache_path());
 
     // Collections
     let avatars = Vec::new();
@@ -95,37 +103,51 @@ impl Stats {
     // Current selection.
     let avatar = String::new();
     let date = None;
+    let compare = None;
 
     // Stats.
     let stats = StatsData::default();
+    let compare_stats = StatsData::default();
     let filter = StatsFilter::None;
 
     // Dialog windows.
-    let filter_dlg = SearchDlg::new(state.clone());
-    let search_dlg = SearchDlg::new(state.clone());
-    let notes_dlg = NotesDlg::new(state.clone());
-    let log_dlg = LogDlg::new(state.clone());
-    let dps_dlg = DPSDlg::new(state.clone(), threads.clone(), locale);
+    let filter_dlg = SearchDlg::new_stats_filter(config.clone(), state.clone());
+    let search_dlg = SearchDlg::new_log_search(config.clone(), state.clone());
+    let notes_dlg = NotesDlg::new(config.clone(), state.clone());
+    let log_dlg = LogDlg::new(state.clone(), locale);
+    let dps_dlg = DPSDlg::new(config.clone(), state.clone(), threads.clone(), locale);
+    let history_dlg = HistoryDlg::new(state.clone(), locale);
 
     Stats {
       config,
-      resist_stats,
       threads,
       channel,
+      watcher: None,
+      stats_cache,
       locale,
-      log_path,
+      log_paths,
+      log_path_error: None,
+      choose_folder_request: false,
       state,
       avatars,
+      active_avatar: None,
       dates,
       avatar,
       date,
+      compare,
       stats,
+      compare_stats,
       filter,
       filter_dlg,
       search_dlg,
       notes_dlg,
       log_dlg,
       dps_dlg,
+      history_dlg,
+      avatar_changed: false,
+      csv_request: false,
+      error: None,
+      open_error: None,
       init: true,
     }
   }
@@ -136,14 +158,16 @@ impl Stats {
     }
 
     if !self.filter_dlg.show(ui.ctx()) {
-      if let Some(search) = self.filter_dlg.take_search_term() {
-        self.filter = StatsFilter::Search { search };
+      if let Some(query) = self.filter_dlg.take_stats_query() {
+        self.filter = StatsFilter::Search { query };
       }
     }
 
     if !self.search_dlg.show(ui.ctx()) {
       if let Some(search) = self.search_dlg.take_search_term() {
-        self.search_logs(ui.ctx(), search);
+        let all_avatars = self.search_dlg.all_avatars();
+        let range = self.search_dlg.date_range();
+        self.search_logs(ui.ctx(), search, all_avatars, range);
       }
     }
 
@@ -157,14 +181,23 @@ impl Stats {
 
     self.log_dlg.show(ui.ctx());
     self.dps_dlg.show(ui.ctx());
+    self.history_dlg.show(ui.ctx());
 
     // Collect messages.
-    while let Ok(Some(msg)) = self.channel.rx.try_next() {
+    while let Ok(msg) = self.channel.rx.try_recv() {
       match msg {
-        Message::Avatars(avatars) => {
-          self.avatars = avatars;
+        Message::Avatars((avatars, inaccessible)) => {
+          // Avatars from the other folders are still shown.
+          self.log_path_error = inaccessible;
+          self.active_avatar = log_data::find_active_avatar(&avatars).map(String::from);
+          self.avatars = avatars.into_iter().map(|avatar| avatar.name).collect();
           self.avatar.clear();
 
+          // Clean up the UI state for avatars that no longer have logs.
+          if !self.avatars.is_empty() {
+            self.config.remove_avatar_states(&self.avatars);
+          }
+
           // Determine the current avatar.
           if let Some(first) = self.avatars.first() {
             // Check if the avatar is in the configuration.
@@ -174,43 +207,76 @@ impl Stats {
               }
             }
 
-            // If the avatar wasn't set then use the first avatar.
+            // If the avatar wasn't set then use the most recently active avatar, or the first one.
             if self.avatar.is_empty() {
-              self.config.set_stats_avatar(first.clone());
-              self.avatar.clone_from(first);
+              let avatar = self.active_avatar.as_ref().unwrap_or(first);
+              self.config.set_stats_avatar(avatar.clone());
+              self.avatar.clone_from(avatar);
             }
           }
 
           // Get the dates for the current avatar.
-          self.request_dates(ui.ctx());
+          self.request_dates(ui.ctx(), false);
         }
-        Message::Dates(dates) => {
+        Message::Dates((dates, newest)) => {
           self.dates = dates;
-          self.date = self.dates.first().copied();
+
+          // Use the avatar's last selected date if it's still available, unless new stats were just logged.
+          let date = self.config.get_avatar_state(&self.avatar).stats_date;
+          self.date = match date {
+            Some(date) if !newest && self.dates.contains(&date) => Some(date),
+            _ => self.dates.first().copied(),
+          };
+          if newest {
+            self.config.set_avatar_stats_date(&self.avatar, self.date);
+          }
           self.request_stats(ui.ctx());
         }
         Message::Stats(stats) => {
           self.state.set_busy(false);
           self.stats = stats;
         }
-        Message::Search(text, search) => {
+        Message::CompareStats(stats) => {
+          self.state.set_busy(false);
+          self.compare_stats = stats;
+        }
+        Message::History(history) => {
           self.state.set_busy(false);
-          self.log_dlg.set_text(text, search, ui.ctx());
+          self.history_dlg.set_history(history);
+        }
+        Message::SearchResult(text) => {
+          self.log_dlg.append_text(text, ui.ctx());
+        }
+        Message::SearchDone => {
+          self.state.set_busy(false);
+          self.log_dlg.finish(ui.ctx());
+        }
+        Message::LogChange(change) => {
+          // Refresh the dates if `/stats` was used by the current avatar.
+          if change.avatar == self.avatar && log_data::has_stats(&change.text) {
+            self.request_dates(ui.ctx(), true);
+          }
+        }
+        Message::LogsAvailable => {
+          // The log folders are back, so load everything again.
+          self.request_avatars(ui.ctx());
         }
       }
     }
 
     // Tool bar.
+    let toolbar_width = ui.available_width();
     ui.horizontal(|ui| {
       // Avatar combo-box.
       ui.add_enabled_ui(!self.avatars.is_empty(), |ui| {
         let mut avatar_changed = false;
         ComboBox::from_id_salt("avatar_combo")
           .selected_text(&self.avatar)
-          .width(250.0)
+          .width(toolbar_width * 0.54)
           .show_ui(ui, |ui| {
             for avatar in &self.avatars {
-              if ui.selectable_label(self.avatar == *avatar, avatar).clicked() && self.avatar != *avatar {
+              let text = util::avatar_text(ui, avatar, self.active_avatar.as_deref());
+              if ui.selectable_label(self.avatar == *avatar, text).clicked() && self.avatar != *avatar {
                 self.config.set_stats_avatar(avatar.clone());
                 self.avatar.clone_from(avatar);
                 avatar_changed = true;
@@ -218,28 +284,71 @@ impl Stats {
             }
           });
         if avatar_changed {
-          self.request_dates(ui.ctx());
+          self.avatar_changed = true;
+          self.request_dates(ui.ctx(), false);
         }
       });
 
-      // Date combo-box.
+      // Date combo-box, grouped by day.
       ui.add_enabled_ui(!self.dates.is_empty(), |ui| {
         let mut date_changed = false;
+        let now = Local::now().naive_local();
+        let selected_day = self.date.and_then(util::timestamp_to_date);
         ComboBox::from_id_salt("date_combo")
-          .selected_text(util::timestamp_to_string(self.date))
+          .selected_text(util::relative_timestamp_text(self.date, now))
           .show_ui(ui, |ui| {
             // This is here to keep the date text from wrapping when the scroll bar is visible.
-            ui.set_min_width(137.0);
-            for date in &self.dates {
-              let date = Some(*date);
-              let text = util::timestamp_to_string(date);
-              if ui.selectable_label(self.date == date, text).clicked() && self.date != date {
-                self.date = date;
-                date_changed = true;
-              }
+            ui.set_min_width(toolbar_width * 0.3);
+            for (day, dates) in group_by_day(&self.dates) {
+              let text = match day {
+                Some(day) => format!("{} ({})", util::relative_day_text(day, now.date()), dates.len()),
+                None => String::from("Unknown"),
+              };
+              CollapsingHeader::new(text)
+                .id_salt(("stats_day", day))
+                .default_open(day == selected_day)
+                .show(ui, |ui| {
+                  for &date in dates {
+                    let text = util::timestamp_to_local(date).map(|dt| dt.format("%H:%M:%S").to_string());
+                    let date = Some(date);
+                    let text = text.unwrap_or_default();
+                    if ui.selectable_label(self.date == date, text).clicked() && self.date != date {
+                      self.date = date;
+                      date_changed = true;
+                    }
+                  }
+                });
             }
           });
+
+        // Step through the snapshots without opening the combo-box. The dates are newest first.
+        let index = self
+          .date
+          .and_then(|date| self.dates.iter().position(|&item| item == date));
+        let older = index.and_then(|index| self.dates.get(index + 1)).copied();
+        let newer = index
+          .and_then(|index| index.checked_sub(1))
+          .and_then(|index| self.dates.get(index))
+          .copied();
+        if ui
+          .add_enabled(older.is_some(), Button::new("⏴"))
+          .on_hover_text("Previous snapshot")
+          .clicked()
+        {
+          self.date = older;
+          date_changed = true;
+        }
+        if ui
+          .add_enabled(newer.is_some(), Button::new("⏵"))
+          .on_hover_text("Next snapshot")
+          .clicked()
+        {
+          self.date = newer;
+          date_changed = true;
+        }
+
         if date_changed {
+          self.config.set_avatar_stats_date(&self.avatar, self.date);
           self.request_stats(ui.ctx());
         }
       });
@@ -252,9 +361,94 @@ impl Stats {
           self.notes_dlg.open(&self.avatar, text);
         }
       });
+
+      // Log folder and file buttons.
+      ui.add_enabled_ui(!self.log_paths.is_empty(), |ui| {
+        if ui.button("🗁").on_hover_text("Open the log folder").clicked() {
+          self.open_log_folder();
+        }
+      });
+      ui.add_enabled_ui(self.can_open_log_file(), |ui| {
+        if ui
+          .button("🗋")
+          .on_hover_text("Open the log file for this snapshot")
+          .clicked()
+        {
+          self.open_log_file();
+        }
+      });
+
+      // CSV menu.
+      ui.add_enabled_ui(self.has_rows(), |ui| {
+        ui.menu_button("CSV", |ui| {
+          if ui.button("Copy as CSV").clicked() {
+            self.error = None;
+            util::set_clipboard_contents(self.get_csv());
+            ui.close_menu();
+          }
+
+          if ui.button("Save as CSV...").clicked() {
+            self.error = None;
+            self.csv_request = true;
+            ui.close_menu();
+          }
+        });
+      });
     });
 
+    if let Some(error) = &self.error {
+      ui.label(RichText::from(error).color(Color::Error.get()));
+    }
+
+    // Compare date combo-box.
+    ui.horizontal(|ui| {
+      ui.add_enabled_ui(self.dates.len() > 1, |ui| {
+        const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+        ui.label(RichText::from("Compare with").color(LABEL_COLOR));
+
+        let mut compare_changed = false;
+        let text = if self.compare.is_some() {
+          util::timestamp_to_string(self.compare)
+        } else {
+          String::from("None")
+        };
+        ComboBox::from_id_salt("compare_combo")
+          .selected_text(text)
+          .show_ui(ui, |ui| {
+            // This is here to keep the date text from wrapping when the scroll bar is visible.
+            ui.set_min_width(toolbar_width * 0.3);
+            if ui.selectable_label(self.compare.is_none(), "None").clicked() && self.compare.is_some() {
+              self.compare = None;
+              compare_changed = true;
+            }
+
+            for date in &self.dates {
+              let date = Some(*date);
+              if date == self.date {
+                continue;
+              }
+
+              let text = util::timestamp_to_string(date);
+              if ui.selectable_label(self.compare == date, text).clicked() && self.compare != date {
+                self.compare = date;
+                compare_changed = true;
+              }
+            }
+          });
+        if compare_changed {
+          self.request_compare_stats(ui.ctx());
+        }
+      });
+    });
+
+    // Show the differences between the two snapshots.
+    if self.is_comparing() {
+      self.show_compare(ui);
+      return;
+    }
+
     // Stats.
+    let mut history_request = None;
     ui.add_enabled_ui(!self.stats.is_empty(), |ui| {
       const NAME_COLOR: Color32 = Color32::from_rgb(102, 154, 180);
       let spacing = ui.spacing().item_spacing;
@@ -279,73 +473,37 @@ impl Stats {
             for (name, value) in self.stats.iter() {
               body.row(row_size, |mut row| {
                 row.col(|ui| {
-                  ui.label(RichText::from(name).color(NAME_COLOR));
+                  show_stat_name(ui, name, NAME_COLOR, &mut history_request);
                 });
                 row.col(|ui| {
-                  ui.label(f64_to_string!(value, 6, self.locale));
+                  ui.label(util::format_f64(value, 6, &self.locale));
                 });
               });
             }
           }
           StatsFilter::Resists => {
-            // Collect and sum the resistances.
-            let mut resist_values: HashMap<Resist, f64> = HashMap::new();
-            for (name, value) in self.stats.iter() {
-              if let Some((key, mul)) = self.resist_stats.get(name) {
-                if let Some(resist) = resist_values.get_mut(key) {
-                  *resist += value * mul;
-                } else {
-                  resist_values.insert(*key, value * mul);
-                }
-              }
-            }
-
-            // Add-in magic resistance.
-            if let Some(magic) = resist_values.remove(&Resist::Magic) {
-              for (key, resist) in &mut resist_values {
-                // Chaos is not affected by magic resistance.
-                if *key != Resist::Chaos {
-                  *resist += magic;
-                }
-              }
-            }
-
-            const RESIST_KEYS: [(Resist, &str); 9] = [
-              (Resist::Air, "Air"),
-              (Resist::Chaos, "Chaos"),
-              (Resist::Death, "Death"),
-              (Resist::Earth, "Earth"),
-              (Resist::Fire, "Fire"),
-              (Resist::Life, "Life"),
-              (Resist::Moon, "Moon"),
-              (Resist::Sun, "Sun"),
-              (Resist::Water, "Water"),
-            ];
-
-            for (key, name) in RESIST_KEYS {
-              if let Some(value) = resist_values.get(&key) {
-                let value = f64_to_string!(*value, 6, self.locale);
-                body.row(row_size, |mut row| {
-                  row.col(|ui| {
-                    const RESIST_COLOR: Color32 = Color32::from_rgb(154, 120, 180);
-                    ui.label(RichText::from(name).color(RESIST_COLOR));
-                  });
-                  row.col(|ui| {
-                    ui.label(value);
-                  });
+            for (name, value) in self.get_resists() {
+              let value = util::format_f64(value, 6, &self.locale);
+              body.row(row_size, |mut row| {
+                row.col(|ui| {
+                  const RESIST_COLOR: Color32 = Color32::from_rgb(154, 120, 180);
+                  ui.label(RichText::from(name).color(RESIST_COLOR));
                 });
-              }
+                row.col(|ui| {
+                  ui.label(value);
+                });
+              });
             }
           }
-          StatsFilter::Search { search: filter } => {
+          StatsFilter::Search { query } => {
             for (name, value) in self.stats.iter() {
-              if filter.find_in(name).is_some() {
+              if query.matches(name, Some(value)) {
                 body.row(row_size, |mut row| {
                   row.col(|ui| {
-                    ui.label(RichText::from(name).color(NAME_COLOR));
+                    show_stat_name(ui, name, NAME_COLOR, &mut history_request);
                   });
                   row.col(|ui| {
-                    ui.label(f64_to_string!(value, 6, self.locale));
+                    ui.label(util::format_f64(value, 6, &self.locale));
                   });
                 });
               }
@@ -353,9 +511,41 @@ impl Stats {
           }
         });
     });
+
+    if let Some(name) = history_request {
+      self.request_history(ui.ctx(), name);
+    }
   }
 
-  pub fn show_status(&self, ui: &mut Ui) {
+  pub fn show_status(&mut self, ui: &mut Ui) {
+    if let Some(progress) = self.state.progress() {
+      // The progress is updated from other threads, so keep repainting while it's shown.
+      ui.ctx().request_repaint_after(PROGRESS_REPAINT);
+      ui.centered_and_justified(|ui| {
+        ui.add(ProgressBar::new(progress.fraction()).text(progress.text()));
+      });
+      return;
+    }
+
+    if let Some(error) = &self.open_error {
+      let mut dismiss = false;
+      ui.horizontal_centered(|ui| {
+        ui.label(RichText::from(error).color(Color::Error.get()));
+        dismiss = ui.button("Dismiss").clicked();
+      });
+      if dismiss {
+        self.open_error = None;
+      }
+      return;
+    }
+
+    if let Some(path) = &self.log_path_error {
+      if util::show_log_path_error(ui, path) {
+        self.choose_folder_request = true;
+      }
+      return;
+    }
+
     if self.avatar.is_empty() {
       return;
     }
@@ -365,21 +555,245 @@ impl Stats {
       return;
     }
 
-    ui.centered_and_justified(|ui| {
-      ui.label(match self.filter {
+    let text = if self.is_comparing() {
+      let compare = util::timestamp_to_string(self.compare);
+      format!("Comparing {date} with {compare}")
+    } else {
+      match self.filter {
         StatsFilter::None => format!("Stats for {} from {}", self.avatar, date),
         StatsFilter::Resists => format!("Effective resists for {} from {}", self.avatar, date),
-        StatsFilter::Search { search: _ } => {
+        StatsFilter::Search { query: _ } => {
           format!("Filtered stats for {} from {}", self.avatar, date)
         }
-      });
+      }
+    };
+
+    ui.centered_and_justified(|ui| {
+      let response = ui.label(text);
+      if self.log_paths.len() > 1 {
+        // List the log folders that the stats are collected from.
+        let folders: Vec<_> = self.log_paths.iter().map(|path| path.to_string_lossy()).collect();
+        response.on_hover_text(format!("Log folders:\n{}", folders.join("\n")));
+      }
     });
   }
 
+  /// Pair up the values from both snapshots, keeping the order of the selected snapshot.
+  fn get_compare_rows(&self) -> Vec<(&str, Option<f64>, Option<f64>)> {
+    let compare: HashMap<&str, f64> = self.compare_stats.iter().collect();
+    let mut rows: Vec<(&str, Option<f64>, Option<f64>)> = Vec::new();
+    for (name, value) in self.stats.iter() {
+      rows.push((name, Some(value), compare.get(name).copied()));
+    }
+
+    // Add the stats that are only in the compared snapshot.
+    let names: HashSet<&str> = rows.iter().map(|(name, _, _)| *name).collect();
+    for (name, value) in self.compare_stats.iter() {
+      if !names.contains(name) {
+        rows.push((name, None, Some(value)));
+      }
+    }
+
+    // Remove rows that don't match the search filter.
+    if let StatsFilter::Search { query } = &self.filter {
+      rows.retain(|(name, value, compare)| query.matches(name, value.or(*compare)));
+    }
+
+    rows
+  }
+
+  fn show_compare(&self, ui: &mut Ui) {
+    let rows = self.get_compare_rows();
+    ui.add_enabled_ui(!rows.is_empty(), |ui| {
+      const NAME_COLOR: Color32 = Color32::from_rgb(102, 154, 180);
+      const MISSING: &str = "-";
+      let spacing = ui.spacing().item_spacing;
+      let row_size = util::text_size(ui) + spacing[1] * 2.0;
+      let available_width = ui.available_width();
+      let compare_date = util::timestamp_to_string(self.compare);
+      TableBuilder::new(ui)
+        .cell_layout(Layout::left_to_right(Align::Center))
+        .striped(true)
+        .column(Column::exact(available_width * 0.6 - spacing[0]))
+        .column(Column::exact(available_width * 0.2 - spacing[0]))
+        .column(Column::remainder())
+        .header(row_size, |mut header| {
+          const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+          header.col(|ui| {
+            ui.label(RichText::from("Name").color(HEADER_COLOR));
+          });
+          header.col(|ui| {
+            ui.label(RichText::from("Value").color(HEADER_COLOR));
+          });
+          header.col(|ui| {
+            ui.label(RichText::from("Δ").color(HEADER_COLOR));
+          });
+        })
+        .body(|mut body| {
+          for (name, value, compare) in rows {
+            body.row(row_size, |mut row| {
+              row.col(|ui| {
+                ui.label(RichText::from(name).color(NAME_COLOR));
+              });
+              row.col(|ui| {
+                match value {
+                  Some(value) => ui.label(util::format_f64(value, 6, &self.locale)),
+                  None => ui.label(MISSING),
+                };
+              });
+              row.col(|ui| {
+                let response = match (value, compare) {
+                  (Some(value), Some(compare)) => {
+                    let delta = value - compare;
+                    let text = util::format_f64(delta, 6, &self.locale);
+                    if delta > 0.0 {
+                      ui.label(RichText::from(format!("+{text}")).color(Color::Increase.get()))
+                    } else if delta < 0.0 {
+                      ui.label(RichText::from(text).color(Color::Decrease.get()))
+                    } else {
+                      ui.label(text)
+                    }
+                  }
+                  _ => ui.label(MISSING),
+                };
+
+                // Show the compared value when hovering.
+                let text = match compare {
+                  Some(compare) => util::format_f64(compare, 6, &self.locale),
+                  None => MISSING.into(),
+                };
+                response.on_hover_text(format!("{compare_date}: {text}"));
+              });
+            });
+          }
+        });
+    });
+  }
+
+  /// Get the effective resists from the current stats.
+  fn get_resists(&self) -> Vec<(&'static str, f64)> {
+    let resists = compute_resists(self.stats.iter());
+    resists
+      .into_iter()
+      .map(|(resist, value)| (resist.name(), value))
+      .collect()
+  }
+
+  /// Get the visible rows as CSV text. Numbers always use '.' as the decimal separator so that spreadsheets can parse
+  /// them.
+  fn get_csv(&self) -> String {
+    let mut csv = String::new();
+    if self.is_comparing() {
+      let to_text = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+      csv.push_str("Name,Value,Compare,Delta\n");
+      for (name, value, compare) in self.get_compare_rows() {
+        let delta = value.zip(compare).map(|(value, compare)| value - compare);
+        let line = [csv_field(name), to_text(value), to_text(compare), to_text(delta)].join(",");
+        csv.push_str(&line);
+        csv.push('\n');
+      }
+      return csv;
+    }
+
+    let rows: Vec<(&str, f64)> = match &self.filter {
+      StatsFilter::None => self.stats.iter().collect(),
+      StatsFilter::Resists => self.get_resists(),
+      StatsFilter::Search { query } => self
+        .stats
+        .iter()
+        .filter(|&(name, value)| query.matches(name, Some(value)))
+        .collect(),
+    };
+
+    csv.push_str("Name,Value\n");
+    for (name, value) in rows {
+      csv.push_str(&format!("{},{value}\n", csv_field(name)));
+    }
+    csv
+  }
+
+  /// Check if there are any rows to export.
+  fn has_rows(&self) -> bool {
+    !self.stats.is_empty() || (self.is_comparing() && !self.compare_stats.is_empty())
+  }
+
+  /// Check if the user requested choosing a different log folder.
+  pub fn choose_folder_request(&mut self) -> bool {
+    mem::take(&mut self.choose_folder_request)
+  }
+
+  /// Check if the user requested saving the stats as CSV.
+  pub fn csv_request(&mut self) -> bool {
+    mem::take(&mut self.csv_request)
+  }
+
+  /// Default file name for saving the stats as CSV.
+  pub fn csv_file_name(&self) -> String {
+    let date = self.date.and_then(util::timestamp_to_local);
+    match date {
+      Some(date) => format!("{}_{}.csv", self.avatar, date.format("%Y-%m-%d")),
+      None => format!("{}.csv", self.avatar),
+    }
+  }
+
+  /// Save the visible rows to a CSV file.
+  pub fn save_csv(&mut self, path: PathBuf) {
+    // Use the "csv" extension if there isn't one.
+    let path = if path.extension().is_none() {
+      path.with_extension("csv")
+    } else {
+      path
+    };
+
+    if let Err(err) = fs::write(&path, self.get_csv()) {
+      self.error = Some(format!("Unable to save to {path:?}: {err}"));
+    }
+  }
+
+  /// Check if the user requested saving a stat's history as CSV.
+  pub fn history_csv_request(&mut self) -> bool {
+    self.history_dlg.csv_request()
+  }
+
+  /// Default file name for saving a stat's history as CSV.
+  pub fn history_csv_file_name(&self) -> String {
+    self.history_dlg.csv_file_name()
+  }
+
+  /// Save the stat's history to a CSV file.
+  pub fn save_history_csv(&mut self, path: PathBuf) {
+    self.history_dlg.save_csv(path);
+  }
+
+  /// Check if two snapshots are being compared.
+  fn is_comparing(&self) -> bool {
+    self.compare.is_some() && !self.filter.is_resists()
+  }
+
   pub fn avatar(&self) -> &str {
     &self.avatar
   }
 
+  /// Check if the user selected a different avatar.
+  pub fn take_avatar_changed(&mut self) -> bool {
+    mem::take(&mut self.avatar_changed)
+  }
+
+  /// Select an avatar if it's in the list.
+  pub fn select_avatar(&mut self, ctx: &Context, avatar: &str) -> bool {
+    if self.avatar == avatar {
+      return true;
+    }
+
+    if self.avatars.iter().any(|name| name == avatar) {
+      self.config.set_stats_avatar(avatar.to_owned());
+      avatar.clone_into(&mut self.avatar);
+      self.request_dates(ctx, false);
+      return true;
+    }
+    false
+  }
+
   pub fn stats(&self) -> &StatsData {
     &self.stats
   }
@@ -392,6 +806,39 @@ impl Stats {
     self.filter = filter;
   }
 
+  /// Open the (first) log folder in the file manager.
+  pub fn open_log_folder(&mut self) {
+    if let Some(log_path) = self.log_paths.first() {
+      self.open_error = util::open_path(log_path).err().map(String::from);
+    }
+  }
+
+  /// Check if there's a log file for the selected avatar and date.
+  pub fn can_open_log_file(&self) -> bool {
+    !self.avatar.is_empty() && self.date.is_some()
+  }
+
+  /// Open the log file for the selected avatar and date with its default application.
+  pub fn open_log_file(&mut self) {
+    let Some(ts) = self.date.filter(|_| !self.avatar.is_empty()) else {
+      return;
+    };
+
+    self.open_error = match log_data::get_log_file(&self.log_paths, &self.avatar, ts) {
+      Some(path) => util::open_path(&path).err().map(String::from),
+      None => Some(format!(
+        "No log file found for {} on {}",
+        self.avatar,
+        util::timestamp_to_string(Some(ts))
+      )),
+    };
+  }
+
+  /// Check if the log search results are showing.
+  pub fn is_showing_results(&self) -> bool {
+    self.log_dlg.visible()
+  }
+
   pub fn show_filter_dlg(&mut self) {
     let title = "⚙  Filter Stats".into();
     self.filter_dlg.open(title);
@@ -403,15 +850,20 @@ impl Stats {
   }
 
   pub fn show_dps_dlg(&mut self) {
-    self.dps_dlg.open(&self.avatar, &self.log_path);
+    self.dps_dlg.open(&self.avatar, &self.log_paths);
+  }
+
+  pub fn log_paths(&self) -> &[PathBuf] {
+    &self.log_paths
   }
 
-  pub fn log_path(&self) -> &Path {
-    &self.log_path
+  /// Refresh from the chat log changes reported by the log watcher.
+  pub fn watch_logs(&mut self, ctx: &Context, watcher: &LogWatcher) {
+    self.watcher = Some(watch_logs(ctx, &self.channel, watcher));
   }
 
-  pub fn set_log_path(&mut self, ctx: &Context, log_path: PathBuf) {
-    self.log_path = log_path;
+  pub fn set_log_paths(&mut self, ctx: &Context, log_paths: Vec<PathBuf>) {
+    self.log_paths = log_paths;
     self.request_avatars(ctx);
   }
 
@@ -425,12 +877,17 @@ impl Stats {
       self.channel.cancel_avatars.take(),
       self.channel.cancel_dates.take(),
       self.channel.cancel_stats.take(),
+      self.channel.cancel_compare.take(),
       self.channel.cancel_search.take(),
+      self.channel.cancel_history.take(),
     ];
 
     for mut cancel in cancelers.into_iter().flatten() {
       cancel.cancel();
     }
+
+    // Stop receiving log changes.
+    self.watcher = None;
   }
 
   fn request_avatars(&mut self, ctx: &Context) {
@@ -439,7 +896,9 @@ impl Stats {
     self.avatar.clear();
     self.dates.clear();
     self.date = None;
+    self.compare = None;
     self.stats = StatsData::default();
+    self.compare_stats = StatsData::default();
 
     // Cancel any previous request.
     if let Some(mut cancel) = self.channel.cancel_avatars.take() {
@@ -455,7 +914,7 @@ impl Stats {
     // Setup the future.
     let tx = self.channel.tx.clone();
     let ctx = ctx.clone();
-    let future = log_data::get_avatars(self.log_path.clone(), cancel);
+    let future = log_data::get_avatars(self.log_paths.clone(), cancel);
     let future = async move {
       let msg = Message::Avatars(future.await);
       tx.unbounded_send(msg).unwrap();
@@ -466,11 +925,14 @@ impl Stats {
     self.threads.spawn_ok(future);
   }
 
-  fn request_dates(&mut self, ctx: &Context) {
+  /// Get the avatar's dates. If `newest` is true then the newest date is selected rather than the last selected one.
+  fn request_dates(&mut self, ctx: &Context, newest: bool) {
     // Clear these.
     self.dates.clear();
     self.date = None;
+    self.compare = None;
     self.stats = StatsData::default();
+    self.compare_stats = StatsData::default();
 
     // Cancel any previous request.
     if let Some(mut cancel) = self.channel.cancel_dates.take() {
@@ -485,14 +947,16 @@ impl Stats {
       self.state.set_busy(true);
 
       // Setup the future.
-      let log_path = self.log_path.clone();
+      let log_paths = self.log_paths.clone();
       let avatar = self.avatar.clone();
       let threads = self.threads.clone();
-      let future = log_data::get_stats_timestamps(log_path, avatar, cancel, Some(threads));
+      let cache = self.stats_cache.clone();
+      let state = self.state.clone();
+      let future = log_data::get_stats_timestamps(log_paths, avatar, cancel, Some(threads), cache, state);
       let tx = self.channel.tx.clone();
       let ctx = ctx.clone();
       let future = async move {
-        let msg = Message::Dates(future.await);
+        let msg = Message::Dates((future.await, newest));
         tx.unbounded_send(msg).unwrap();
         ctx.request_repaint();
       };
@@ -505,6 +969,45 @@ impl Stats {
     self.state.set_busy(false);
   }
 
+  fn request_history(&mut self, ctx: &Context, name: String) {
+    // Cancel any previous request.
+    if let Some(mut cancel) = self.channel.cancel_history.take() {
+      cancel.cancel();
+    }
+
+    if self.avatar.is_empty() {
+      return;
+    }
+
+    let cancel = Cancel::default();
+    self.channel.cancel_history = Some(cancel.clone());
+    self.history_dlg.open(&self.avatar, &name, cancel.clone());
+
+    // Show the busy cursor.
+    self.state.set_busy(true);
+
+    // Setup the future.
+    let log_paths = self.log_paths.clone();
+    let avatar = self.avatar.clone();
+    let threads = self.threads.clone();
+    let state = self.state.clone();
+    let future = log_data::get_stat_history(log_paths, avatar, name, cancel.clone(), Some(threads), state);
+    let tx = self.channel.tx.clone();
+    let ctx = ctx.clone();
+    let future = async move {
+      let history = future.await;
+
+      // Don't replace the results of a newer request.
+      if !cancel.is_canceled() {
+        tx.unbounded_send(Message::History(history)).unwrap();
+        ctx.request_repaint();
+      }
+    };
+
+    // Execute the future on a pooled thread.
+    self.threads.spawn_ok(future);
+  }
+
   fn request_stats(&mut self, ctx: &Context) {
     // Clear this.
     self.stats = StatsData::default();
@@ -525,7 +1028,7 @@ impl Stats {
         // Setup the future.
         let tx = self.channel.tx.clone();
         let ctx = ctx.clone();
-        let future = log_data::get_stats(self.log_path.clone(), self.avatar.clone(), date, cancel);
+        let future = log_data::get_stats(self.log_paths.clone(), self.avatar.clone(), date, cancel);
         let future = async move {
           let msg = Message::Stats(future.await);
           tx.unbounded_send(msg).unwrap();
@@ -541,14 +1044,52 @@ impl Stats {
     self.state.set_busy(false);
   }
 
-  fn search_logs(&mut self, ctx: &Context, search: Search) {
-    if self.avatar.is_empty() {
+  fn request_compare_stats(&mut self, ctx: &Context) {
+    // Clear this.
+    self.compare_stats = StatsData::default();
+
+    // Cancel any previous request.
+    if let Some(mut cancel) = self.channel.cancel_compare.take() {
+      cancel.cancel();
+    }
+
+    if let Some(date) = self.compare {
+      if !self.avatar.is_empty() {
+        let cancel = Cancel::default();
+        self.channel.cancel_compare = Some(cancel.clone());
+
+        // Show the busy cursor.
+        self.state.set_busy(true);
+
+        // Setup the future.
+        let tx = self.channel.tx.clone();
+        let ctx = ctx.clone();
+        let future = log_data::get_stats(self.log_paths.clone(), self.avatar.clone(), date, cancel);
+        let future = async move {
+          let msg = Message::CompareStats(future.await);
+          tx.unbounded_send(msg).unwrap();
+          ctx.request_repaint();
+        };
+
+        // Execute the future on a pooled thread.
+        self.threads.spawn_ok(future);
+        return;
+      }
+    }
+
+    self.state.set_busy(false);
+  }
+
+  fn search_logs(&mut self, ctx: &Context, search: Search, all_avatars: bool, range: DateRange) {
+    // Search the current avatar's logs unless searching all of them.
+    let avatar = (!all_avatars).then(|| self.avatar.clone());
+    if avatar.as_ref().is_some_and(|avatar| avatar.is_empty()) {
       return;
     }
 
     let cancel = Cancel::default();
     self.channel.cancel_search = Some(cancel.clone());
-    self.log_dlg.open(&self.avatar, cancel.clone());
+    self.log_dlg.open(avatar.as_deref(), search.clone(), cancel.clone());
 
     // Show the busy cursor.
     self.state.set_busy(true);
@@ -556,13 +1097,22 @@ impl Stats {
     // Setup the future.
     let tx = self.channel.tx.clone();
     let ctx = ctx.clone();
-    let log_path = self.log_path.clone();
-    let avatar = self.avatar.clone();
-    let future = log_data::find_log_entries(log_path, avatar, search.clone(), cancel);
+    let log_paths = self.log_paths.clone();
+    let state = self.state.clone();
     let future = async move {
-      let msg = Message::Search(future.await, search);
-      tx.unbounded_send(msg).unwrap();
-      ctx.request_repaint();
+      // Send the results from each log file as they're found.
+      log_data::find_log_entries(log_paths, avatar, search, range, cancel.clone(), state, |text| {
+        if !cancel.is_canceled() {
+          tx.unbounded_send(Message::SearchResult(text)).unwrap();
+          ctx.request_repaint();
+        }
+      })
+      .await;
+
+      if !cancel.is_canceled() {
+        tx.unbounded_send(Message::SearchDone).unwrap();
+        ctx.request_repaint();
+      }
     };
 
     // Execute the future on a pooled thread.
@@ -570,8 +1120,92 @@ impl Stats {
   }
 }
 
-#[derive(Hash, Eq, PartialEq, Copy, Clone)]
-enum Resist {
+/// Subscribe to the log watcher and forward changes as messages.
+fn watch_logs(ctx: &Context, channel: &Channel, watcher: &LogWatcher) -> LogSubscription {
+  let tx = channel.tx.clone();
+  let ctx = ctx.clone();
+  watcher.subscribe(move |event| {
+    let msg = match event {
+      LogEvent::Change(change) => Message::LogChange(change),
+      LogEvent::Available => Message::LogsAvailable,
+    };
+    tx.unbounded_send(msg).unwrap();
+    ctx.request_repaint();
+  })
+}
+
+/// Group timestamps by day, keeping their order.
+fn group_by_day(dates: &[i64]) -> Vec<(Option<NaiveDate>, &[i64])> {
+  let mut groups = Vec::new();
+  let mut rest = dates;
+  while let Some(&first) = rest.first() {
+    let day = util::timestamp_to_date(first);
+    let len = rest
+      .iter()
+      .take_while(|&&date| util::timestamp_to_date(date) == day)
+      .count();
+    let (group, remaining) = rest.split_at(len);
+    groups.push((day, group));
+    rest = remaining;
+  }
+  groups
+}
+
+/// Compute the effective resists from stat names and values.
+///
+/// Attunements count for half of a resistance and magic resistance is added to every resist except chaos.
+pub fn compute_resists<'a>(iter: impl Iterator<Item = (&'a str, f64)>) -> BTreeMap<Resist, f64> {
+  // Collect and sum the resistances.
+  let mut resists: BTreeMap<Resist, f64> = BTreeMap::new();
+  for (name, value) in iter {
+    if let Some((resist, mul)) = get_resist_stat(name) {
+      *resists.entry(resist).or_default() += value * mul;
+    }
+  }
+
+  // Add-in magic resistance.
+  if let Some(magic) = resists.remove(&Resist::Magic) {
+    for (resist, value) in &mut resists {
+      // Chaos is not affected by magic resistance.
+      if *resist != Resist::Chaos {
+        *value += magic;
+      }
+    }
+  }
+
+  resists
+}
+
+/// Get the resist and multiplier for a stat name.
+fn get_resist_stat(name: &str) -> Option<(Resist, f64)> {
+  const ATTUNEMENT: f64 = 0.5;
+  let (resist, mul) = match name {
+    "AirAttunement" => (Resist::Air, ATTUNEMENT),
+    "AirResistance" => (Resist::Air, 1.0),
+    "ChaosAttunement" => (Resist::Chaos, ATTUNEMENT),
+    "ChaosResistance" => (Resist::Chaos, 1.0),
+    "DeathAttunement" => (Resist::Death, ATTUNEMENT),
+    "DeathResistance" => (Resist::Death, 1.0),
+    "EarthAttunement" => (Resist::Earth, ATTUNEMENT),
+    "EarthResistance" => (Resist::Earth, 1.0),
+    "FireAttunement" => (Resist::Fire, ATTUNEMENT),
+    "FireResistance" => (Resist::Fire, 1.0),
+    "LifeAttunement" => (Resist::Life, ATTUNEMENT),
+    "LifeResistance" => (Resist::Life, 1.0),
+    "MoonAttunement" => (Resist::Moon, ATTUNEMENT),
+    "MoonResistance" => (Resist::Moon, 1.0),
+    "SunAttunement" => (Resist::Sun, ATTUNEMENT),
+    "SunResistance" => (Resist::Sun, 1.0),
+    "WaterAttunement" => (Resist::Water, ATTUNEMENT),
+    "WaterResistance" => (Resist::Water, 1.0),
+    "MagicResistance" => (Resist::Magic, 1.0),
+    _ => return None,
+  };
+  Some((resist, mul))
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
+pub enum Resist {
   Air,
   Chaos,
   Death,
@@ -584,6 +1218,46 @@ enum Resist {
   Magic,
 }
 
+impl Resist {
+  pub fn name(self) -> &'static str {
+    match self {
+      Resist::Air => "Air",
+      Resist::Chaos => "Chaos",
+      Resist::Death => "Death",
+      Resist::Earth => "Earth",
+      Resist::Fire => "Fire",
+      Resist::Life => "Life",
+      Resist::Moon => "Moon",
+      Resist::Sun => "Sun",
+      Resist::Water => "Water",
+      Resist::Magic => "Magic",
+    }
+  }
+}
+
+/// How often the status bar is repainted while progress is shown.
+const PROGRESS_REPAINT: Duration = Duration::from_millis(250);
+
+/// Show a stat's name with a context menu for showing its history.
+fn show_stat_name(ui: &mut Ui, name: &str, color: Color32, history_request: &mut Option<String>) {
+  let label = Label::new(RichText::from(name).color(color)).sense(Sense::click());
+  ui.add(label).context_menu(|ui| {
+    if ui.button("History...").clicked() {
+      *history_request = Some(name.to_owned());
+      ui.close_menu();
+    }
+  });
+}
+
+/// Quote a CSV field if it contains special characters.
+fn csv_field(text: &str) -> String {
+  if text.contains([',', '"', '\n']) {
+    format!("\"{}\"", text.replace('"', "\"\""))
+  } else {
+    text.to_owned()
+  }
+}
+
 #[derive(Clone)]
 pub enum StatsFilter {
   /// Show all stats.
@@ -592,8 +1266,8 @@ pub enum StatsFilter {
   /// Show effective resists.
   Resists,
 
-  /// Filter the stats using Search.
-  Search { search: Search },
+  /// Filter the stats using a query.
+  Search { query: StatsQuery },
 }
 
 impl StatsFilter {
@@ -606,11 +1280,115 @@ impl StatsFilter {
   }
 }
 
+/// Stats filter made of space-separated terms that must all match. Terms starting with a comparison (e.g. ">=100" or
+/// "< 5") apply to the value, the others are searched for in the name.
+#[derive(Clone)]
+pub struct StatsQuery {
+  names: Vec<Search>,
+  values: Vec<(Comparison, f64)>,
+}
+
+impl StatsQuery {
+  /// Parse the query text, using `make_search` to create the search for each name term.
+  pub fn parse<F>(text: &str, make_search: F) -> Result<Self, String>
+  where
+    F: Fn(&str) -> Result<Search, String>,
+  {
+    let mut names = Vec::new();
+    let mut values = Vec::new();
+    let mut terms = text.split_whitespace();
+    while let Some(term) = terms.next() {
+      let Some((comparison, operand)) = Comparison::parse(term) else {
+        names.push(make_search(term)?);
+        continue;
+      };
+
+      // The value can be separated from the comparison by a space.
+      let operand = if operand.is_empty() {
+        terms.next().ok_or_else(|| format!("Missing value after \"{term}\""))?
+      } else {
+        operand
+      };
+
+      let Ok(operand) = operand.parse() else {
+        return Err(format!("Invalid value: {operand}"));
+      };
+      values.push((comparison, operand));
+    }
+
+    if names.is_empty() && values.is_empty() {
+      return Err(String::from("Nothing to filter by"));
+    }
+
+    Ok(Self { names, values })
+  }
+
+  /// Check if a stat matches all the terms. Value comparisons fail if there's no value.
+  pub fn matches(&self, name: &str, value: Option<f64>) -> bool {
+    if !self.names.iter().all(|search| search.find_in(name).is_some()) {
+      return false;
+    }
+
+    match value {
+      Some(value) => self
+        .values
+        .iter()
+        .all(|&(comparison, operand)| comparison.eval(value, operand)),
+      None => self.values.is_empty(),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+  Less,
+  LessEqual,
+  Equal,
+  NotEqual,
+  GreaterEqual,
+  Greater,
+}
+
+impl Comparison {
+  /// Split a comparison from the front of a term.
+  fn parse(term: &str) -> Option<(Self, &str)> {
+    // Two character operators first.
+    const OPERATORS: [(&str, Comparison); 6] = [
+      ("<=", Comparison::LessEqual),
+      (">=", Comparison::GreaterEqual),
+      ("!=", Comparison::NotEqual),
+      ("<", Comparison::Less),
+      (">", Comparison::Greater),
+      ("=", Comparison::Equal),
+    ];
+
+    OPERATORS
+      .iter()
+      .find_map(|&(operator, comparison)| Some((comparison, term.strip_prefix(operator)?)))
+  }
+
+  fn eval(self, value: f64, operand: f64) -> bool {
+    match self {
+      Comparison::Less => value < operand,
+      Comparison::LessEqual => value <= operand,
+      Comparison::Equal => value == operand,
+      Comparison::NotEqual => value != operand,
+      Comparison::GreaterEqual => value >= operand,
+      Comparison::Greater => value > operand,
+    }
+  }
+}
+
 enum Message {
-  Avatars(Vec<String>),
-  Dates(Vec<i64>),
+  Avatars((Vec<AvatarLogs>, Option<PathBuf>)),
+  Dates((Vec<i64>, bool)),
   Stats(StatsData),
-  Search(String, Search),
+  CompareStats(StatsData),
+  History(Vec<(i64, f64)>),
+  SearchResult(String),
+  SearchDone,
+  LogChange(LogChange),
+  LogsAvailable,
 }
 
 struct Channel {
@@ -619,5 +1397,85 @@ struct Channel {
   cancel_avatars: Option<Cancel>,
   cancel_dates: Option<Cancel>,
   cancel_stats: Option<Cancel>,
+  cancel_compare: Option<Cancel>,
   cancel_search: Option<Cancel>,
+  cancel_history: Option<Cancel>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use regex::Regex;
+
+  #[test]
+  fn test_stats_query() {
+    let string = |term: &str| {
+      Ok(Search::String {
+        find: term.to_owned(),
+        ignore_case: false,
+      })
+    };
+
+    // Every stat over 100.
+    let query = StatsQuery::parse(">100", string).unwrap();
+    assert!(query.matches("Health", Some(900.0)));
+    assert!(!query.matches("Health", Some(100.0)));
+    assert!(!query.matches("Health", None));
+
+    // Names and ranges all have to match.
+    let query = StatsQuery::parse("Attunement >= 50 <60", string).unwrap();
+    assert!(query.matches("FireAttunement", Some(50.0)));
+    assert!(!query.matches("FireAttunement", Some(60.0)));
+    assert!(!query.matches("FireResistance", Some(55.0)));
+    let query = StatsQuery::parse("Fire Attunement", string).unwrap();
+    assert!(query.matches("FireAttunement", None));
+    assert!(!query.matches("WaterAttunement", None));
+    assert!(StatsQuery::parse("Health !=0", string)
+      .unwrap()
+      .matches("Health", Some(1.0)));
+    assert!(StatsQuery::parse("=-2.5", string).unwrap().matches("Regen", Some(-2.5)));
+
+    // Name terms use the search that's passed in.
+    let regex = |term: &str| Regex::new(term).map(Search::Regex).map_err(|err| err.to_string());
+    let query = StatsQuery::parse("^(Fire|Water)Attunement$ >5", regex).unwrap();
+    assert!(query.matches("WaterAttunement", Some(6.0)));
+    assert!(!query.matches("AirAttunement", Some(6.0)));
+
+    // Errors.
+    assert!(StatsQuery::parse("", string).is_err());
+    assert!(StatsQuery::parse("Health >", string).is_err());
+    assert!(StatsQuery::parse("Health >=lots", string).is_err());
+    assert!(StatsQuery::parse("(Fire", regex).is_err());
+  }
+
+  #[test]
+  fn test_compute_resists() {
+    let stats = [
+      ("FireResistance", 10.0),
+      ("FireAttunement", 4.0),
+      ("WaterAttunement", 6.0),
+      ("ChaosResistance", 5.0),
+      ("MagicResistance", 2.0),
+      ("Health", 900.0),
+    ];
+    let resists = compute_resists(stats.into_iter());
+
+    // Attunements count for half and magic is added to everything but chaos.
+    assert_eq!(resists.get(&Resist::Fire), Some(&14.0));
+    assert_eq!(resists.get(&Resist::Chaos), Some(&5.0));
+
+    // Resists that only have an attunement still get magic resistance.
+    assert_eq!(resists.get(&Resist::Water), Some(&5.0));
+
+    // Missing resists and magic itself aren't listed.
+    assert_eq!(resists.get(&Resist::Air), None);
+    assert_eq!(resists.get(&Resist::Magic), None);
+    assert_eq!(resists.len(), 3);
+
+    // Without magic resistance, only attunements and resistances count.
+    let resists = compute_resists([("ChaosAttunement", 3.0), ("SunResistance", 1.5)].into_iter());
+    assert_eq!(resists.get(&Resist::Chaos), Some(&1.5));
+    assert_eq!(resists.get(&Resist::Sun), Some(&1.5));
+    assert!(compute_resists(std::iter::empty()).is_empty());
+  }
 }
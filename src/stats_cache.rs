@@ -0,0 +1,85 @@
+use crate::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  time::UNIX_EPOCH,
+};
+
+/// Size and modification time of a log file, used to check if cached timestamps are still valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+  size: u64,
+  secs: u64,
+  nanos: u32,
+}
+
+impl FileStamp {
+  pub fn new(path: &Path) -> Option<Self> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(Self {
+      size: metadata.len(),
+      secs: modified.as_secs(),
+      nanos: modified.subsec_nanos(),
+    })
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+  /// Entries from before log times were read as local time don't have a version.
+  #[serde(default)]
+  version: u32,
+  stamp: FileStamp,
+  timestamps: Vec<i64>,
+}
+
+/// On-disk cache of the `/stats` timestamps found in each log file, so that only new or changed files need to be
+/// parsed. The cache file can be deleted at any time.
+#[derive(Clone)]
+pub struct StatsCache {
+  storage: Storage,
+}
+
+impl StatsCache {
+  pub fn new(path: PathBuf) -> Option<Self> {
+    let mut storage = Storage::new(path)?;
+
+    // Forget files that no longer exist.
+    for key in storage.keys() {
+      if !Path::new(&key).is_file() {
+        storage.remove(&key);
+      }
+    }
+
+    Some(Self { storage })
+  }
+
+  /// Get the cached timestamps for a log file, if the file hasn't changed since they were cached.
+  pub fn get(&self, path: &Path, stamp: &FileStamp) -> Option<Vec<i64>> {
+    let entry: Entry = self.storage.get_as(path.to_str()?)?;
+    (entry.version == VERSION && entry.stamp == *stamp).then_some(entry.timestamps)
+  }
+
+  pub fn set(&mut self, path: &Path, stamp: FileStamp, timestamps: Vec<i64>) {
+    let Some(key) = path.to_str() else { return };
+    let version = VERSION;
+    self.storage.set_as(
+      key,
+      &Entry {
+        version,
+        stamp,
+        timestamps,
+      },
+    );
+  }
+
+  /// Persist changes.
+  pub fn persist(&self) {
+    self.storage.persist();
+  }
+}
+
+/// Version of the cached timestamps. Bump this when the way timestamps are parsed changes.
+const VERSION: u32 = 1;
@@ -48,19 +48,31 @@ impl Storage {
     self.items.write().unwrap().remove(key);
   }
 
+  /// Get all the keys.
+  pub fn keys(&self) -> Vec<String> {
+    self.items.read().unwrap().keys()
+  }
+
   /// Persist changes.
   pub fn persist(&self) {
     self.thread.persist();
   }
+
+  /// Take the warning about the storage file being damaged when it was loaded. This is only returned once.
+  pub fn take_warning(&self) -> Option<String> {
+    self.items.write().unwrap().take_warning()
+  }
 }
 
 mod inner {
   use std::{
     collections::HashMap,
+    ffi::OsString,
     fs::{self, File},
+    io::{self, Write},
     path::{Path, PathBuf},
     sync::{
-      atomic::{AtomicBool, Ordering},
+      atomic::{AtomicBool, AtomicUsize, Ordering},
       mpsc::{self, Sender},
       Arc, RwLock,
     },
@@ -71,37 +83,84 @@ mod inner {
     path: PathBuf,
     items: HashMap<String, String>,
     changed: AtomicBool,
+
+    /// The file on disk is a good version that can be backed up. Not the case if it was damaged.
+    backup: AtomicBool,
+    warning: Option<String>,
   }
 
   impl Items {
     pub fn load(path: PathBuf) -> Self {
-      let items = Self::load_items(&path);
+      let (items, backup, warning) = match Self::load_items(&path) {
+        Ok(items) => (items, true, None),
+        Err(err) => {
+          println!("{err}");
+
+          // Recover from the previous good version.
+          let name = path.file_name().unwrap_or_default().to_string_lossy();
+          match Self::load_items(&get_backup_path(&path)) {
+            Ok(items) if !items.is_empty() => {
+              let warning = format!("{name} was damaged, the previous settings were restored from the backup.");
+              (items, false, Some(warning))
+            }
+            _ => {
+              let warning = format!("{name} was damaged and couldn't be recovered, the default settings are used.");
+              (HashMap::new(), false, Some(warning))
+            }
+          }
+        }
+      };
       let changed = AtomicBool::new(false);
+      let backup = AtomicBool::new(backup);
 
-      Self { path, items, changed }
+      Self {
+        path,
+        items,
+        changed,
+        backup,
+        warning,
+      }
     }
 
-    fn load_items(path: &Path) -> HashMap<String, String> {
+    /// Load the items. A missing file just means nothing has been stored yet.
+    fn load_items(path: &Path) -> Result<HashMap<String, String>, String> {
       match fs::read(path) {
-        Ok(data) => match ron::de::from_bytes(&data) {
-          Ok(items) => return items,
-          Err(err) => println!("{err}"),
-        },
-        Err(err) => println!("{err}"),
+        Ok(data) => ron::de::from_bytes(&data).map_err(|err| format!("{}: {err}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(err) => Err(format!("{}: {err}", path.display())),
       }
-
-      HashMap::new()
     }
 
     fn persist(&self) {
       if self.changed.swap(false, Ordering::Relaxed) {
-        match File::create(&self.path) {
-          Ok(file) => ron::ser::to_writer_pretty(file, &self.items, Default::default()).unwrap(),
+        let text = match ron::ser::to_string_pretty(&self.items, Default::default()) {
+          Ok(text) => text,
+          Err(err) => {
+            println!("{err}");
+            return;
+          }
+        };
+
+        // Keep the previous version in case this one gets damaged.
+        let backup = self.backup.load(Ordering::Relaxed) && self.path.is_file();
+        if backup {
+          let result = fs::read(&self.path).and_then(|data| write_atomic(&get_backup_path(&self.path), &data));
+          if let Err(err) = result {
+            println!("{err}");
+          }
+        }
+
+        match write_atomic(&self.path, text.as_bytes()) {
+          Ok(()) => self.backup.store(true, Ordering::Relaxed),
           Err(err) => println!("{err}"),
         }
       }
     }
 
+    pub fn take_warning(&mut self) -> Option<String> {
+      self.warning.take()
+    }
+
     pub fn get(&self, key: &str) -> Option<&String> {
       self.items.get(key)
     }
@@ -118,6 +177,10 @@ mod inner {
         self.changed.store(true, Ordering::Relaxed);
       }
     }
+
+    pub fn keys(&self) -> Vec<String> {
+      self.items.keys().cloned().collect()
+    }
   }
 
   impl Drop for Items {
@@ -126,6 +189,42 @@ mod inner {
     }
   }
 
+  /// Path of the backup of the previous version.
+  pub fn get_backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".bak");
+    path.with_file_name(name)
+  }
+
+  /// Write to a temporary file in the same folder, sync it and then rename it over the target so that the target is
+  /// never partially written.
+  fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    // The temporary file name is unique so that storages writing to the same file don't use the same temporary file.
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(format!(".{}.{count}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(name);
+
+    let result = File::create(&tmp_path).and_then(|mut file| {
+      file.write_all(data)?;
+      file.sync_all()
+    });
+
+    if let Err(err) = result.and_then(|()| fs::rename(&tmp_path, path)) {
+      let _ = fs::remove_file(&tmp_path);
+      return Err(err);
+    }
+
+    // Sync the folder so that the rename is durable.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+      File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+  }
+
   pub struct PersistThread {
     thread: Option<JoinHandle<()>>,
     tx: Option<Sender<()>>,
@@ -165,3 +264,69 @@ mod inner {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::TempDir;
+  use std::fs;
+
+  #[test]
+  fn test_recovery() {
+    let dir = TempDir::new("storage");
+    let path = dir.join("cota.ron");
+    let backup_path = inner::get_backup_path(&path);
+
+    // The first version has nothing to back up.
+    let mut storage = Storage::new(path.clone()).unwrap();
+    assert_eq!(storage.take_warning(), None);
+    storage.set("notes", String::from("first"));
+    drop(storage);
+    assert!(path.is_file());
+    assert!(!backup_path.exists());
+
+    // The previous version is backed up before it's replaced.
+    let mut storage = Storage::new(path.clone()).unwrap();
+    assert_eq!(storage.get("notes").as_deref(), Some("first"));
+    storage.set("notes", String::from("second"));
+    drop(storage);
+    assert!(backup_path.is_file());
+
+    // Truncate the main file, as if power was lost while it was being written.
+    let data = fs::read(&path).unwrap();
+    fs::write(&path, &data[..data.len() / 2]).unwrap();
+
+    let mut storage = Storage::new(path.clone()).unwrap();
+    assert_eq!(storage.get("notes").as_deref(), Some("first"));
+    assert!(storage.take_warning().is_some());
+    assert_eq!(storage.take_warning(), None);
+
+    // The damaged file doesn't replace the good backup.
+    storage.set("crops", String::from("corn"));
+    drop(storage);
+    let storage = Storage::new(backup_path.clone()).unwrap();
+    assert_eq!(storage.get("notes").as_deref(), Some("first"));
+    assert_eq!(storage.get("crops"), None);
+    drop(storage);
+
+    let storage = Storage::new(path.clone()).unwrap();
+    assert_eq!(storage.get("notes").as_deref(), Some("first"));
+    assert_eq!(storage.get("crops").as_deref(), Some("corn"));
+    assert_eq!(storage.take_warning(), None);
+    drop(storage);
+
+    // Without a usable backup, the defaults are used.
+    fs::write(&path, "{\"notes\": \"trunc").unwrap();
+    fs::write(&backup_path, "").unwrap();
+    let storage = Storage::new(path.clone()).unwrap();
+    assert_eq!(storage.get("notes"), None);
+    assert!(storage.take_warning().is_some());
+    drop(storage);
+
+    // No temporary files are left behind.
+    for entry in fs::read_dir(&dir).unwrap() {
+      let name = entry.unwrap().file_name();
+      assert!(!name.to_string_lossy().ends_with(".tmp"), "{name:?}");
+    }
+  }
+}
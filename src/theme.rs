@@ -0,0 +1,75 @@
+use eframe::epaint::Color32;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Colors that have an alternative in the color-blind friendly palette.
+#[derive(Clone, Copy, Debug)]
+pub enum Color {
+  /// Error messages.
+  Error,
+
+  /// Crops that are still growing.
+  Growing,
+
+  /// Crops that need water.
+  Water,
+
+  /// Crops that are ready to harvest.
+  Harvest,
+
+  /// A stat that went up.
+  Increase,
+
+  /// A stat that went down.
+  Decrease,
+
+  /// Towns under siege by one, two or three or more cabalists.
+  SiegeLow,
+  SiegeMedium,
+  SiegeHigh,
+
+  /// Skills that have been trained.
+  SkillName,
+
+  /// Skills that haven't been trained.
+  SubduedSkillName,
+
+  /// Skills that are being un-trained and the experience recovered.
+  Untrain,
+}
+
+impl Color {
+  /// Get the color from the active palette.
+  pub fn get(self) -> Color32 {
+    let colors = match self {
+      Color::Error => (Color32::LIGHT_RED, Color32::from_rgb(240, 160, 60)),
+      Color::Growing => (Color32::from_rgb(220, 220, 220), Color32::from_rgb(220, 220, 220)),
+      Color::Water => (Color32::from_rgb(255, 255, 0), Color32::from_rgb(240, 160, 60)),
+      Color::Harvest => (Color32::from_rgb(0, 255, 0), Color32::from_rgb(86, 180, 233)),
+      Color::Increase => (Color32::from_rgb(102, 204, 102), Color32::from_rgb(86, 180, 233)),
+      Color::Decrease => (Color32::from_rgb(224, 96, 96), Color32::from_rgb(230, 159, 0)),
+      Color::SiegeLow => (Color32::from_rgb(192, 164, 24), Color32::from_rgb(240, 228, 66)),
+      Color::SiegeMedium => (Color32::from_rgb(208, 96, 32), Color32::from_rgb(230, 159, 0)),
+      Color::SiegeHigh => (Color32::from_rgb(224, 48, 48), Color32::from_rgb(213, 94, 0)),
+      Color::SkillName => (Color32::from_rgb(102, 154, 180), Color32::from_rgb(86, 180, 233)),
+      Color::SubduedSkillName => (Color32::from_rgb(80, 120, 140), Color32::from_gray(120)),
+      Color::Untrain => (Color32::from_rgb(229, 140, 102), Color32::from_rgb(204, 121, 167)),
+    };
+
+    if is_color_blind() {
+      colors.1
+    } else {
+      colors.0
+    }
+  }
+}
+
+/// Use the color-blind friendly (blue/orange) palette.
+pub fn set_color_blind(color_blind: bool) {
+  COLOR_BLIND.store(color_blind, Ordering::Relaxed);
+}
+
+pub fn is_color_blind() -> bool {
+  COLOR_BLIND.load(Ordering::Relaxed)
+}
+
+static COLOR_BLIND: AtomicBool = AtomicBool::new(false);
@@ -1,9 +1,11 @@
 use crate::{
+  chronometer::{self, SiegeForecast},
   ethos::{Siege, Virtue, CABALISTS, TOWNS, VIRTUES},
   util,
 };
+use chrono::{DateTime, Local, Utc};
 use eframe::{
-  egui::{Context, Grid, Key, Layout, RichText, WidgetText, Window},
+  egui::{ComboBox, Context, Grid, Key, Layout, RichText, WidgetText, Window},
   emath::{Align, Align2},
   epaint::Color32,
 };
@@ -12,12 +14,19 @@ use util::AppState;
 
 pub struct TownsDlg {
   state: AppState,
+  town: Virtue,
+  forecast: Vec<SiegeForecast>,
   visible: bool,
 }
 
 impl TownsDlg {
   pub fn new(state: AppState) -> Self {
-    Self { state, visible: false }
+    Self {
+      state,
+      town: Virtue::Honor,
+      forecast: Vec::new(),
+      visible: false,
+    }
   }
 
   pub fn open(&mut self) {
@@ -25,11 +34,16 @@ impl TownsDlg {
     self.visible = true;
   }
 
-  pub fn show(&mut self, ctx: &Context, sieges: &[Siege]) {
+  pub fn show(&mut self, ctx: &Context, sieges: &[Siege], now: DateTime<Utc>, clock_12h: bool) {
     if !self.visible {
       return;
     }
 
+    // Project the sieges again once the earliest one is over.
+    if self.forecast.first().is_none_or(|siege| siege.end <= now) {
+      self.forecast = chronometer::get_siege_forecast(now, self.town, FORECAST_COUNT);
+    }
+
     let towns = {
       // Convert the sieges into a by-town array.
       let mut towns: [(bool, [bool; CABALISTS.len()]); VIRTUES.len()] = Default::default();
@@ -107,6 +121,60 @@ impl TownsDlg {
             }
           });
         ui.separator();
+
+        // Forecast for a single town.
+        ui.horizontal(|ui| {
+          const LABEL_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+          ui.label(RichText::from("Forecast").color(LABEL_COLOR));
+          let town_text = |virtue: Virtue| format!("{} ({virtue:?})", TOWNS[virtue as usize]);
+          let mut town = self.town;
+          ComboBox::from_id_salt("forecast_town_combo")
+            .selected_text(town_text(town))
+            .show_ui(ui, |ui| {
+              for &virtue in VIRTUES.iter().filter(|&&virtue| virtue != Virtue::Ethos) {
+                ui.selectable_value(&mut town, virtue, town_text(virtue));
+              }
+            });
+          if town != self.town {
+            self.town = town;
+            self.forecast = chronometer::get_siege_forecast(now, town, FORECAST_COUNT);
+          }
+        });
+
+        Grid::new("siege_forecast_grid")
+          .min_col_width((ui.available_width() - ui.spacing().item_spacing.x * 2.0) / 3.0)
+          .show(ui, |ui| {
+            const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+            ui.label(RichText::from("Cabalist").color(HEADER_COLOR));
+            ui.centered_and_justified(|ui| {
+              ui.label(RichText::from("Starts").color(HEADER_COLOR));
+            });
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+              ui.label(RichText::from("Duration").color(HEADER_COLOR));
+            });
+            ui.end_row();
+
+            for siege in &self.forecast {
+              let color = if siege.begin <= now {
+                Color32::from_rgb(154, 229, 255)
+              } else {
+                Color32::from_rgb(102, 154, 180)
+              };
+
+              ui.label(RichText::from(CABALISTS[siege.cabalist]).color(color));
+              ui.centered_and_justified(|ui| {
+                let begin = siege.begin.with_timezone(&Local);
+                let format = format!("%a {}", util::get_time_format(clock_12h));
+                ui.label(RichText::from(begin.format(&format).to_string()).color(color));
+              });
+              ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let secs = (siege.end - siege.begin).num_seconds() as i32;
+                ui.label(util::get_countdown_text(Default::default(), secs));
+              });
+              ui.end_row();
+            }
+          });
+        ui.separator();
         ui.horizontal(|ui| {
           if ui.button("Close").clicked() {
             self.close();
@@ -129,3 +197,6 @@ impl TownsDlg {
     }
   }
 }
+
+/// Number of sieges in the forecast.
+const FORECAST_COUNT: usize = 10;
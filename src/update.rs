@@ -0,0 +1,200 @@
+use serde_json::Value;
+use std::{borrow::Cow, cmp::Ordering, process::Command};
+
+/// Latest published release.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Release {
+  /// Version without the `v` prefix.
+  pub version: String,
+
+  /// Release page.
+  pub url: String,
+}
+
+/// Fetch the latest release from GitHub. This blocks, so it should be called from a pooled thread.
+pub fn get_latest_release() -> Result<Release, Cow<'static, str>> {
+  // Use the system's curl so that an HTTP client and TLS stack don't need to be bundled.
+  let mut command = Command::new("curl");
+  command.args(["--silent", "--show-error", "--fail", "--location", "--max-time", "15"]);
+  command.args([
+    "--header",
+    "Accept: application/vnd.github+json",
+    "--user-agent",
+    "cota",
+  ]);
+  command.arg(RELEASES_URL);
+
+  // Don't flash a console window on Windows.
+  #[cfg(target_os = "windows")]
+  std::os::windows::process::CommandExt::creation_flags(&mut command, 0x0800_0000);
+
+  let output = command.output().map_err(|err| match err.kind() {
+    std::io::ErrorKind::NotFound => Cow::from("Checking for updates needs curl, which isn't installed"),
+    _ => Cow::from(format!("Unable to run curl to check for updates: {err}")),
+  })?;
+  if !output.status.success() {
+    return Err(Cow::from("Unable to reach GitHub"));
+  }
+
+  parse_release(&String::from_utf8_lossy(&output.stdout)).ok_or(Cow::from("Unexpected response from GitHub"))
+}
+
+/// Check if `version` is newer than `current`. Returns `None` if either isn't a semantic version.
+pub fn is_newer(version: &str, current: &str) -> Option<bool> {
+  let version = Version::parse(version)?;
+  let current = Version::parse(current)?;
+  Some(version > current)
+}
+
+/// Parse the GitHub releases API response.
+fn parse_release(text: &str) -> Option<Release> {
+  let value: Value = serde_json::from_str(text).ok()?;
+  let tag = value.get("tag_name")?.as_str()?;
+  let url = value.get("html_url")?.as_str()?;
+  Version::parse(tag)?;
+
+  Some(Release {
+    version: tag.trim_start_matches(['v', 'V']).to_owned(),
+    url: url.to_owned(),
+  })
+}
+
+/// Semantic version, compared as described at semver.org.
+#[derive(Debug, PartialEq, Eq)]
+struct Version<'a> {
+  core: [u64; 3],
+
+  /// Pre-release identifiers, e.g. `["beta", "2"]` for `1.0.0-beta.2`.
+  pre: Vec<&'a str>,
+}
+
+impl<'a> Version<'a> {
+  fn parse(text: &'a str) -> Option<Self> {
+    let text = text.trim().trim_start_matches(['v', 'V']);
+
+    // Build metadata doesn't affect precedence.
+    let text = text.split_once('+').map_or(text, |(text, _)| text);
+    let (core, pre) = match text.split_once('-') {
+      Some((core, pre)) => (core, pre.split('.').collect()),
+      None => (text, Vec::new()),
+    };
+
+    let is_ident = |ident: &&str| !ident.is_empty() && ident.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-');
+    if !pre.iter().all(is_ident) {
+      return None;
+    }
+
+    let mut parts = core.split('.');
+    let mut next = || -> Option<u64> {
+      let part = parts.next()?;
+      if part.is_empty() || !part.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+      }
+      part.parse().ok()
+    };
+
+    let core = [next()?, next()?, next()?];
+    if parts.next().is_some() {
+      return None;
+    }
+
+    Some(Self { core, pre })
+  }
+}
+
+impl Ord for Version<'_> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.core.cmp(&other.core).then_with(|| {
+      // A pre-release has lower precedence than the release.
+      match (self.pre.is_empty(), other.pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => cmp_pre(&self.pre, &other.pre),
+      }
+    })
+  }
+}
+
+impl PartialOrd for Version<'_> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// Compare pre-release identifiers. Numeric identifiers are compared numerically and are lower than alphanumeric ones.
+fn cmp_pre(a: &[&str], b: &[&str]) -> Ordering {
+  for (a, b) in a.iter().zip(b) {
+    let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+      (Ok(a), Ok(b)) => a.cmp(&b),
+      (Ok(_), Err(_)) => Ordering::Less,
+      (Err(_), Ok(_)) => Ordering::Greater,
+      (Err(_), Err(_)) => a.cmp(b),
+    };
+
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+
+  // More identifiers is higher when the rest are equal.
+  a.len().cmp(&b.len())
+}
+
+const RELEASES_URL: &str = "https://api.github.com/repos/Barugon/cota/releases/latest";
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_newer() {
+    assert_eq!(is_newer("v5.7.0", "5.6.9"), Some(true));
+    assert_eq!(is_newer("5.6.10", "5.6.9"), Some(true));
+    assert_eq!(is_newer("5.6.9", "5.6.9"), Some(false));
+    assert_eq!(is_newer("5.6.8", "5.6.9"), Some(false));
+    assert_eq!(is_newer("6.0.0-beta.1", "5.6.9"), Some(true));
+    assert_eq!(is_newer("5.6.9-rc.1", "5.6.9"), Some(false));
+    assert_eq!(is_newer("5.6.9", "5.6.9-rc.1"), Some(true));
+    assert_eq!(is_newer("5.6.9+build.7", "5.6.9"), Some(false));
+
+    // Pre-release precedence from semver.org.
+    let versions = [
+      "1.0.0-alpha",
+      "1.0.0-alpha.1",
+      "1.0.0-alpha.beta",
+      "1.0.0-beta",
+      "1.0.0-beta.2",
+      "1.0.0-beta.11",
+      "1.0.0-rc.1",
+      "1.0.0",
+    ];
+    for pair in versions.windows(2) {
+      assert_eq!(is_newer(pair[1], pair[0]), Some(true), "{} > {}", pair[1], pair[0]);
+    }
+
+    assert_eq!(is_newer("5.7", "5.6.9"), None);
+    assert_eq!(is_newer("5.7.0.1", "5.6.9"), None);
+    assert_eq!(is_newer("latest", "5.6.9"), None);
+    assert_eq!(is_newer("5.7.0-", "5.6.9"), None);
+  }
+
+  #[test]
+  fn test_parse_release() {
+    let text = r#"{"tag_name": "v5.7.0", "html_url": "https://github.com/Barugon/cota/releases/tag/v5.7.0"}"#;
+    assert_eq!(
+      parse_release(text),
+      Some(Release {
+        version: String::from("5.7.0"),
+        url: String::from("https://github.com/Barugon/cota/releases/tag/v5.7.0"),
+      })
+    );
+
+    assert_eq!(parse_release(r#"{"message": "Not Found"}"#), None);
+    assert_eq!(
+      parse_release(r#"{"tag_name": "nightly", "html_url": "https://github.com"}"#),
+      None
+    );
+    assert_eq!(parse_release("<html>"), None);
+  }
+}
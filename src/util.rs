@@ -1,19 +1,24 @@
-use chrono::{DateTime, TimeZone, Utc};
+use crate::theme::Color;
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use clipboard::{ClipboardContext, ClipboardProvider};
 use eframe::{
-  egui::{Context, Image, TextStyle, Ui},
+  egui::{text::LayoutJob, Context, Image, RichText, TextFormat, TextStyle, Ui, WidgetText},
   epaint::{ColorImage, TextureHandle, TextureId, Vec2},
 };
-use num_format::Locale;
+use num_format::{Locale, ToFormattedString};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
+  borrow::Cow,
   cell::RefCell,
+  collections::BTreeSet,
   mem,
   ops::{Range, RangeInclusive},
+  path::Path,
+  process::Command,
   sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
   },
 };
 
@@ -71,23 +76,6 @@ macro_rules! err {
   };
 }
 
-/// Nicely format a f64 for display.
-#[macro_export]
-macro_rules! f64_to_string {
-  ($value:expr, 2, $locale:expr) => {
-    format!("{:.2}", $value)
-      .trim_end_matches('0')
-      .trim_end_matches('.')
-      .replacen('.', $locale.decimal(), 1)
-  };
-  ($value:expr, 6, $locale:expr) => {
-    format!("{:.6}", $value)
-      .trim_end_matches('0')
-      .trim_end_matches('.')
-      .replacen('.', $locale.decimal(), 1)
-  };
-}
-
 pub struct Picture {
   name: String,
   size: Vec2,
@@ -124,7 +112,7 @@ impl Picture {
     texture.as_ref().unwrap().id()
   }
 
-  pub fn image(&self, ctx: &Context) -> Image {
+  pub fn image(&self, ctx: &Context) -> Image<'_> {
     Image::new((self.texture_id(ctx), self.size))
   }
 }
@@ -151,6 +139,16 @@ pub fn floor_search<T: Ord>(value: T, values: &[T]) -> Option<usize> {
   }
 }
 
+/// Get the level for the specified amount of experience along with the experience needed to reach the next level
+/// (`None` at the maximum level).
+pub fn get_level_info(exp: i64) -> (i32, Option<i64>) {
+  // The first entry is zero, so negative experience is the only way for this to fail.
+  let idx = floor_search(exp, LEVEL_EXP).unwrap_or(0);
+  let lvl = idx as i32 + 1;
+  let next = LEVEL_EXP.get(idx + 1).map(|next| next - exp.max(0));
+  (lvl, next)
+}
+
 pub fn set_clipboard_contents(text: String) {
   let mut ctx: ClipboardContext = ok!(ClipboardProvider::new());
   err!(ctx.set_contents(text));
@@ -162,8 +160,38 @@ pub fn get_epoch() -> DateTime<Utc> {
   Utc.with_ymd_and_hms(1997, 9, 2, 0, 0, 0).unwrap()
 }
 
-/// Get the remaining time in XXh XXm XXs format.
+/// Get the local clock time that is the specified number of seconds from now, in either 12 or 24 hour format.
+pub fn get_clock_text(secs: i32, clock_12h: bool) -> String {
+  let date_time = Local::now() + Duration::seconds(secs as i64);
+  get_time_text(date_time.time(), clock_12h)
+}
+
+/// Get a time of day in either 12 or 24 hour format.
+pub fn get_time_text(time: NaiveTime, clock_12h: bool) -> String {
+  time.format(get_time_format(clock_12h)).to_string()
+}
+
+/// Get the `chrono` format for hours and minutes in either 12 or 24 hour format.
+pub fn get_time_format(clock_12h: bool) -> &'static str {
+  if clock_12h {
+    "%-I:%M %p"
+  } else {
+    "%H:%M"
+  }
+}
+
+/// Get the remaining time in XXh XXm XXs format, or Xd XXh XXm format for more than a day. Negative values are
+/// treated as zero.
 pub fn get_countdown_text(prefix: &str, sec: i32) -> String {
+  const DAY_SECS: i32 = 24 * 60 * 60;
+  let sec = sec.max(0);
+  if sec > DAY_SECS {
+    let day = sec / DAY_SECS;
+    let hour = sec % DAY_SECS / 3600;
+    let min = sec % 3600 / 60;
+    return format!("{prefix}{day}d {hour:02}h {min:02}m");
+  }
+
   if sec >= 60 {
     let min = sec / 60;
     let sec = sec % 60;
@@ -184,6 +212,12 @@ struct State {
 
   /// Disable the main UI.
   disabled: AtomicBool,
+
+  /// Progress of the current long-running task, along with the ID of the tracker that owns it.
+  progress: Mutex<Option<(u64, Progress)>>,
+
+  /// ID for the next progress tracker.
+  next_progress_id: AtomicU64,
 }
 
 #[derive(Clone, Default)]
@@ -209,6 +243,83 @@ impl AppState {
   pub fn is_disabled(&self) -> bool {
     self.state.disabled.load(Ordering::Relaxed)
   }
+
+  /// Start reporting progress for a long-running task. The progress is cleared when the returned tracker is dropped.
+  pub fn begin_progress(&self, desc: &'static str, total: usize) -> ProgressTracker {
+    let id = self.state.next_progress_id.fetch_add(1, Ordering::Relaxed);
+    let progress = Progress { desc, done: 0, total };
+    *self.state.progress.lock().unwrap() = Some((id, progress));
+    ProgressTracker {
+      state: self.state.clone(),
+      id,
+    }
+  }
+
+  /// Get the progress of the current long-running task, if any.
+  #[must_use]
+  pub fn progress(&self) -> Option<Progress> {
+    let progress = self.state.progress.lock().unwrap();
+    progress.as_ref().map(|(_, progress)| progress.clone())
+  }
+}
+
+/// Progress of a long-running task.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+  pub desc: &'static str,
+  pub done: usize,
+  pub total: usize,
+}
+
+impl Progress {
+  #[must_use]
+  pub fn fraction(&self) -> f32 {
+    if self.total == 0 {
+      return 0.0;
+    }
+    self.done.min(self.total) as f32 / self.total as f32
+  }
+
+  #[must_use]
+  pub fn text(&self) -> String {
+    format!("{} {}/{}...", self.desc, self.done, self.total)
+  }
+}
+
+/// Updates the progress started by `AppState::begin_progress`.
+pub struct ProgressTracker {
+  state: Arc<State>,
+  id: u64,
+}
+
+impl ProgressTracker {
+  /// Call when a file (or other unit of work) is finished.
+  pub fn step(&self) {
+    self.update(|progress| progress.done += 1);
+  }
+
+  /// Set the number of files (or other units of work) that are finished.
+  pub fn set_done(&self, done: usize) {
+    self.update(|progress| progress.done = done);
+  }
+
+  fn update<F: FnOnce(&mut Progress)>(&self, f: F) {
+    // Another task may have started reporting progress since this one began.
+    if let Some((id, progress)) = self.state.progress.lock().unwrap().as_mut() {
+      if *id == self.id {
+        f(progress);
+      }
+    }
+  }
+}
+
+impl Drop for ProgressTracker {
+  fn drop(&mut self) {
+    let mut progress = self.state.progress.lock().unwrap();
+    if progress.as_ref().is_some_and(|(id, _)| *id == self.id) {
+      *progress = None;
+    }
+  }
 }
 
 #[derive(Clone, Default)]
@@ -227,6 +338,57 @@ impl Cancel {
   }
 }
 
+/// Expanded state of collapsing skill group headers, keyed by group name.
+#[derive(Default)]
+pub struct ExpandedGroups {
+  names: BTreeSet<String>,
+  restore: bool,
+  changed: bool,
+}
+
+impl ExpandedGroups {
+  /// Set the expanded groups, dropping names that aren't in `groups`. The state is applied the next time the groups
+  /// are shown. Returns true if any names were dropped.
+  pub fn load<'a>(&mut self, mut names: BTreeSet<String>, groups: impl IntoIterator<Item = &'a str>) -> bool {
+    let groups: BTreeSet<&str> = groups.into_iter().collect();
+    let count = names.len();
+    names.retain(|name| groups.contains(name.as_str()));
+    self.names = names;
+    self.restore = true;
+    self.changed = false;
+    self.names.len() != count
+  }
+
+  /// Open state to pass to `CollapsingHeader::open`. This is only `Some` while the state is being restored.
+  #[must_use]
+  pub fn open(&self, name: &str) -> Option<bool> {
+    self.restore.then(|| self.names.contains(name))
+  }
+
+  /// Call when a group's header is clicked.
+  pub fn toggle(&mut self, name: &str) {
+    if !self.names.remove(name) {
+      self.names.insert(name.to_owned());
+    }
+    self.changed = true;
+  }
+
+  /// Call after all the groups have been shown.
+  pub fn restored(&mut self) {
+    self.restore = false;
+  }
+
+  /// Check if a group was toggled since the last call.
+  pub fn take_changed(&mut self) -> bool {
+    mem::take(&mut self.changed)
+  }
+
+  #[must_use]
+  pub fn names(&self) -> &BTreeSet<String> {
+    &self.names
+  }
+}
+
 fn find_ignore_case(text: &str, find: &str) -> Option<Range<usize>> {
   if text.is_empty() || find.is_empty() {
     return None;
@@ -318,6 +480,33 @@ pub fn offset(text: &str, sub: &str) -> Option<usize> {
   None
 }
 
+/// Nicely format a f64 for display using the locale's digit grouping and decimal separator. Trailing zeros are removed
+/// from the fractional part.
+pub fn format_f64(value: f64, precision: usize, locale: &Locale) -> String {
+  let text = format!("{:.precision$}", value.abs());
+  let (int, frac) = match text.split_once('.') {
+    Some((int, frac)) => (int, frac.trim_end_matches('0')),
+    None => (text.as_str(), ""),
+  };
+
+  // Group the integer part. Fall back to the plain digits if it's not a finite number.
+  let mut result = match int.parse::<u128>() {
+    Ok(int) => int.to_formatted_string(locale),
+    Err(_) => int.to_owned(),
+  };
+
+  if !frac.is_empty() {
+    result.push_str(locale.decimal());
+    result.push_str(frac);
+  }
+
+  // Don't show negative zero.
+  if value.is_sign_negative() && result.chars().any(|ch| ch.is_ascii_digit() && ch != '0') {
+    result.insert_str(0, locale.minus_sign());
+  }
+  result
+}
+
 /// Get the system's locale.
 pub fn get_locale() -> Locale {
   if let Some(name) = sys_locale::get_locale() {
@@ -351,17 +540,183 @@ pub fn replace_decimal(text: &str) -> String {
   text.replacen([',', '\u{66b}'], ".", 1)
 }
 
-/// Remove all digit grouping separators (comma, period, single quote and non-breaking space).
+/// Remove all digit grouping separators (comma, period, arabic thousands, single quote and spaces).
 pub fn remove_separators(text: &str) -> String {
-  text.replace([',', '.', '\'', '\u{a0}'], Default::default())
+  text
+    .replace([',', '.', '\u{66c}', '\''], Default::default())
+    .replace(GROUP_SPACES, Default::default())
+}
+
+/// Parse a whole number that may have digit grouping (e.g. "1,234,567", "1.234.567" or "1 234 567").
+pub fn parse_grouped_int(text: &str) -> Option<i64> {
+  remove_separators(text.trim()).parse().ok()
 }
 
-/// Convert a timestamp into a date & time string.
+/// Parse a number as written by clients in different languages (e.g. "1,234.5", "1.234,5" or "1 234,5").
+///
+/// `decimal` is the client's decimal separator, if it's known (see `find_decimal_separator`). Otherwise the last comma
+/// or period is the decimal separator unless it's used more than once or is followed by exactly three digits, in which
+/// case it groups digits. This means that "1,234" is 1234 unless the decimal separator is known to be a comma.
+pub fn parse_decimal(text: &str, decimal: Option<char>) -> Option<f64> {
+  let text: String = text.trim().chars().filter(|ch| !GROUP_SPACES.contains(ch)).collect();
+  let decimal = match decimal {
+    Some(decimal) => text
+      .char_indices()
+      .find(|&(_, ch)| ch == decimal || ch == '\u{66b}')
+      .filter(|&(pos, ch)| !text[pos + ch.len_utf8()..].contains(ch)),
+    None => text
+      .char_indices()
+      .rev()
+      .find(|(_, ch)| matches!(ch, ',' | '.' | '\u{66b}'))
+      .filter(|&(pos, ch)| ch == '\u{66b}' || !text[..pos].contains(ch) && !is_lone_group(&text, pos)),
+  }
+  .map(|(pos, _)| pos);
+
+  let mut number = String::with_capacity(text.len());
+  let mut group = None;
+  for (pos, ch) in text.char_indices() {
+    if matches!(ch, ',' | '.' | '\u{66c}') && Some(pos) != decimal {
+      // Grouping separators must be between digits, all the same and can't be part of the fraction.
+      let before = text[..pos].chars().next_back().is_some_and(|ch| ch.is_ascii_digit());
+      let after = text[pos + ch.len_utf8()..]
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_ascii_digit());
+      if !before || !after || *group.get_or_insert(ch) != ch || decimal.is_some_and(|decimal| pos > decimal) {
+        return None;
+      }
+      continue;
+    }
+    number.push(ch);
+  }
+
+  replace_decimal(&number).parse().ok()
+}
+
+/// Find the decimal separator used by the numbers in a block of text, e.g. the values from one `/stats` output.
+/// Returns `None` if none of them show it, because every comma and period could be grouping digits.
+pub fn find_decimal_separator<'a>(words: impl IntoIterator<Item = &'a str>) -> Option<char> {
+  for word in words {
+    let Some(pos) = word.rfind([',', '.']) else {
+      continue;
+    };
+
+    let (ch, other) = if word[pos..].starts_with(',') {
+      (',', '.')
+    } else {
+      ('.', ',')
+    };
+    if word[..pos].contains(other) {
+      // Both are used, so the last one is the decimal separator.
+      return Some(ch);
+    }
+
+    if word[..pos].contains(ch) {
+      // Repeated, so it groups digits.
+      return Some(other);
+    }
+
+    let before = word[..pos].trim_start_matches('-');
+    if !before.is_empty() && before.chars().all(|ch| ch.is_ascii_digit()) && !is_lone_group(word, pos) {
+      return Some(ch);
+    }
+  }
+  None
+}
+
+/// Check if the comma or period at `pos` could be the only digit grouping separator: one to three digits, not starting
+/// with zero, before it and exactly three digits after it.
+fn is_lone_group(text: &str, pos: usize) -> bool {
+  let before = text[..pos].trim_start_matches('-');
+  let after = &text[pos + 1..];
+  (1..=3).contains(&before.len())
+    && !before.starts_with('0')
+    && before.chars().all(|ch| ch.is_ascii_digit())
+    && after.len() == 3
+    && after.chars().all(|ch| ch.is_ascii_digit())
+}
+
+/// Characters other than commas and periods that are used to group digits.
+const GROUP_SPACES: [char; 4] = [' ', '\u{a0}', '\u{202f}', '\''];
+
+/// Convert a local date & time, as written to the chat logs, into a timestamp. See `zoned_to_timestamp` for how
+/// times around DST changes are handled.
+pub fn local_to_timestamp(date_time: NaiveDateTime, after: Option<i64>) -> Option<i64> {
+  zoned_to_timestamp(&Local, date_time, after)
+}
+
+/// Convert a date & time in the time zone into a timestamp.
+///
+/// When the clocks go back, the repeated hour is ambiguous and the earlier mapping is used, unless it's before
+/// `after` (the timestamp of the previous log entry) and the later one isn't. Times that are skipped when the clocks
+/// go forward aren't normally logged; they're taken to still be in the old offset (e.g. 2:30 is the same as 3:30).
+fn zoned_to_timestamp<Tz: TimeZone>(tz: &Tz, date_time: NaiveDateTime, after: Option<i64>) -> Option<i64> {
+  match tz.from_local_datetime(&date_time) {
+    LocalResult::Single(dt) => Some(dt.timestamp()),
+    LocalResult::Ambiguous(earlier, later) => {
+      let (earlier, later) = (earlier.timestamp(), later.timestamp());
+      match after {
+        Some(after) if earlier < after && later >= after => Some(later),
+        _ => Some(earlier),
+      }
+    }
+    LocalResult::None => {
+      let date_time = date_time.checked_add_signed(Duration::hours(1))?;
+      Some(tz.from_local_datetime(&date_time).earliest()?.timestamp())
+    }
+  }
+}
+
+/// Convert a timestamp into a local date & time.
+pub fn timestamp_to_local(ts: i64) -> Option<NaiveDateTime> {
+  Some(DateTime::from_timestamp(ts, 0)?.with_timezone(&Local).naive_local())
+}
+
+/// Convert a timestamp into a local date & time string.
 pub fn timestamp_to_string(ts: Option<i64>) -> String {
-  let Some(ts) = ts else { return String::new() };
-  let Some(dt) = DateTime::from_timestamp(ts, 0) else {
+  let Some(dt) = ts.and_then(timestamp_to_local) else {
+    return String::new();
+  };
+  dt.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Get the local day of a stats timestamp.
+pub fn timestamp_to_date(ts: i64) -> Option<NaiveDate> {
+  Some(timestamp_to_local(ts)?.date())
+}
+
+/// Get text for a day relative to today: "Today", "Yesterday" or the date.
+pub fn relative_day_text(date: NaiveDate, today: NaiveDate) -> String {
+  if date == today {
+    String::from("Today")
+  } else if today.pred_opt() == Some(date) {
+    String::from("Yesterday")
+  } else {
+    date.format("%Y-%m-%d").to_string()
+  }
+}
+
+/// Get text for a stats timestamp relative to the current local time, e.g. "Today 14:32". Anything older than
+/// yesterday uses the full date and time.
+pub fn relative_timestamp_text(ts: Option<i64>, now: NaiveDateTime) -> String {
+  zoned_relative_timestamp_text(&Local, ts, now)
+}
+
+/// Get text for a timestamp relative to `now` in the time zone.
+fn zoned_relative_timestamp_text<Tz: TimeZone>(tz: &Tz, ts: Option<i64>, now: NaiveDateTime) -> String {
+  let Some(dt) = ts
+    .and_then(|ts| DateTime::from_timestamp(ts, 0))
+    .map(|dt| dt.with_timezone(tz).naive_local())
+  else {
     return String::new();
   };
+
+  // Days are compared by calendar date so that DST changes don't matter.
+  let today = now.date();
+  if dt.date() == today || today.pred_opt() == Some(dt.date()) {
+    let day = relative_day_text(dt.date(), today);
+    return format!("{day} {}", dt.format("%H:%M"));
+  }
   dt.format("%Y-%m-%d %H:%M:%S").to_string()
 }
 
@@ -371,6 +726,43 @@ pub fn scroll_bar_size(ui: &Ui) -> f32 {
   spacing.scroll.bar_inner_margin + spacing.scroll.bar_width + spacing.scroll.bar_outer_margin
 }
 
+/// Get a case-insensitive search for filtering by name, or `None` if the text is empty.
+pub fn get_name_filter(text: &str) -> Option<Search> {
+  if text.is_empty() {
+    return None;
+  }
+
+  Some(Search::String {
+    find: text.to_owned(),
+    ignore_case: true,
+  })
+}
+
+/// Divide the available space evenly between two panes. The panes are placed side by side if the space is wider
+/// than it is tall, otherwise one is placed above the other.
+pub fn split_evenly(ui: &mut Ui, mut add_pane: impl FnMut(&mut Ui, usize)) {
+  let available = ui.available_size();
+  let spacing = ui.spacing().item_spacing;
+  if available.x > available.y {
+    let size = Vec2::new(available.x * 0.5 - spacing.x * 4.0, available.y);
+    ui.horizontal(|ui| {
+      ui.allocate_ui(size, |ui| add_pane(ui, 0));
+      ui.add_space(spacing.x);
+      ui.separator();
+      ui.add_space(spacing.x);
+      ui.allocate_ui(size, |ui| add_pane(ui, 1));
+    });
+  } else {
+    let size = Vec2::new(available.x, available.y * 0.5 - spacing.y * 4.0);
+    ui.add_space(spacing.y);
+    ui.allocate_ui(size, |ui| add_pane(ui, 0));
+    ui.add_space(spacing.y);
+    ui.separator();
+    ui.add_space(spacing.y);
+    ui.allocate_ui(size, |ui| add_pane(ui, 1));
+  }
+}
+
 /// Get the size (height) of a button.
 pub fn button_size(ui: &Ui) -> f32 {
   text_size(ui) + ui.spacing().button_padding[1] * 2.0
@@ -381,9 +773,237 @@ pub fn text_size(ui: &Ui) -> f32 {
   TextStyle::Body.resolve(ui.style()).size
 }
 
+/// Open a file with its default application or a folder with the file manager.
+pub fn open_path(path: &Path) -> Result<(), Cow<'static, str>> {
+  if !path.exists() {
+    return Err(Cow::from(format!("{} doesn't exist", path.display())));
+  }
+
+  #[cfg(target_os = "windows")]
+  let program = "explorer";
+  #[cfg(target_os = "macos")]
+  let program = "open";
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  let program = "xdg-open";
+
+  match Command::new(program).arg(path).spawn() {
+    Ok(mut child) => {
+      // Reap the process when it exits. Explorer's exit code isn't meaningful, so it's ignored.
+      std::thread::spawn(move || child.wait());
+      Ok(())
+    }
+    Err(err) => Err(Cow::from(format!("Unable to open {}: {err}", path.display()))),
+  }
+}
+
+/// Text for an avatar in the avatar combo-boxes, with a subtle suffix for the most recently active avatar.
+pub fn avatar_text(ui: &Ui, avatar: &str, active: Option<&str>) -> WidgetText {
+  if active != Some(avatar) {
+    return avatar.into();
+  }
+
+  let font = TextStyle::Button.resolve(ui.style());
+  let mut job = LayoutJob::default();
+  job.append(avatar, 0.0, TextFormat::simple(font.clone(), ui.visuals().text_color()));
+  job.append(
+    " (active)",
+    0.0,
+    TextFormat::simple(font, ui.visuals().weak_text_color()),
+  );
+  job.into()
+}
+
+/// Show that a log folder can't be read in the status area. Returns true if the user wants to choose a different one.
+pub fn show_log_path_error(ui: &mut Ui, path: &Path) -> bool {
+  let mut choose = false;
+  ui.horizontal(|ui| {
+    let text = format!("Log folder not accessible: {}", path.display());
+    let hover_text = "The logs will be loaded again when the folder becomes available";
+    ui.label(RichText::from(text).color(Color::Error.get()))
+      .on_hover_text(hover_text);
+    choose = ui.button("Choose Folder...").clicked();
+  });
+  choose
+}
+
+/// Temporary folder for tests, which is removed when dropped.
+#[cfg(test)]
+pub struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+  pub fn new(name: &str) -> Self {
+    let path = std::env::temp_dir().join(format!("cota_{name}_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    Self(path)
+  }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.0
+  }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+  fn as_ref(&self) -> &Path {
+    &self.0
+  }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.0);
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use chrono::FixedOffset;
+
+  #[test]
+  fn test_relative_timestamp_text() {
+    let date_time = |text| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap();
+    let ts = |text| zoned_to_timestamp(&Eastern, date_time(text), None);
+    let relative_timestamp_text = |ts, now| zoned_relative_timestamp_text(&Eastern, ts, now);
+
+    let now = date_time("2024-05-02 10:00:00");
+    assert_eq!(relative_timestamp_text(ts("2024-05-02 00:00:00"), now), "Today 00:00");
+    assert_eq!(
+      relative_timestamp_text(ts("2024-05-01 23:59:59"), now),
+      "Yesterday 23:59"
+    );
+    assert_eq!(
+      relative_timestamp_text(ts("2024-05-01 00:00:00"), now),
+      "Yesterday 00:00"
+    );
+    assert_eq!(
+      relative_timestamp_text(ts("2024-04-30 23:59:59"), now),
+      "2024-04-30 23:59:59"
+    );
+    assert_eq!(relative_timestamp_text(None, now), "");
+
+    // Across month and year boundaries.
+    let now = date_time("2025-01-01 00:01:00");
+    assert_eq!(
+      relative_timestamp_text(ts("2024-12-31 14:32:00"), now),
+      "Yesterday 14:32"
+    );
+
+    // The 23 and 25 hour days around the 2024 US Eastern DST changes still go by the calendar date.
+    let now = date_time("2024-03-10 23:30:00");
+    assert_eq!(
+      relative_timestamp_text(ts("2024-03-09 00:30:00"), now),
+      "Yesterday 00:30"
+    );
+    let now = date_time("2024-11-04 00:30:00");
+    assert_eq!(
+      relative_timestamp_text(ts("2024-11-03 00:10:00"), now),
+      "Yesterday 00:10"
+    );
+    assert_eq!(
+      relative_timestamp_text(ts("2024-11-02 23:50:00"), now),
+      "2024-11-02 23:50:00"
+    );
+
+    let today = now.date();
+    assert_eq!(relative_day_text(today, today), "Today");
+    assert_eq!(relative_day_text(today.pred_opt().unwrap(), today), "Yesterday");
+    let ts = local_to_timestamp(date_time("2024-11-02 23:50:00"), None).unwrap();
+    assert_eq!(timestamp_to_date(ts), NaiveDate::from_ymd_opt(2024, 11, 2));
+  }
+
+  /// US Eastern time for 2024: DST from 2024-03-10 2:00 EST to 2024-11-03 2:00 EDT.
+  #[derive(Clone)]
+  struct Eastern;
+
+  impl Eastern {
+    fn dst_range() -> std::ops::Range<i64> {
+      let utc = |text| {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+          .unwrap()
+          .and_utc()
+          .timestamp()
+      };
+      utc("2024-03-10 07:00:00")..utc("2024-11-03 06:00:00")
+    }
+
+    fn offset(dst: bool) -> FixedOffset {
+      FixedOffset::west_opt(if dst { 4 * 3600 } else { 5 * 3600 }).unwrap()
+    }
+  }
+
+  impl TimeZone for Eastern {
+    type Offset = FixedOffset;
+
+    fn from_offset(_: &FixedOffset) -> Self {
+      Eastern
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+      self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+      // Try both offsets and keep the ones that map back to the same local time.
+      let fits = |dst| {
+        let ts = (*local - Eastern::offset(dst)).and_utc().timestamp();
+        Eastern::dst_range().contains(&ts) == dst
+      };
+      match (fits(true), fits(false)) {
+        (true, true) => LocalResult::Ambiguous(Eastern::offset(true), Eastern::offset(false)),
+        (true, false) => LocalResult::Single(Eastern::offset(true)),
+        (false, true) => LocalResult::Single(Eastern::offset(false)),
+        (false, false) => LocalResult::None,
+      }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+      self.offset_from_utc_datetime(&utc.and_hms_opt(12, 0, 0).unwrap())
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+      Eastern::offset(Eastern::dst_range().contains(&utc.and_utc().timestamp()))
+    }
+  }
+
+  #[test]
+  fn test_zoned_to_timestamp() {
+    let local = |text| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap();
+    let utc = |text| local(text).and_utc().timestamp();
+    let ts = |text, after| zoned_to_timestamp(&Eastern, local(text), after);
+
+    assert_eq!(ts("2024-05-01 20:00:00", None), Some(utc("2024-05-02 00:00:00")));
+    assert_eq!(ts("2024-12-01 20:00:00", None), Some(utc("2024-12-02 01:00:00")));
+
+    // Spring forward: a fight from 1:59:50 to 3:00:10 is 20 seconds long.
+    let begin = ts("2024-03-10 01:59:50", None).unwrap();
+    let end = ts("2024-03-10 03:00:10", Some(begin)).unwrap();
+    assert_eq!(end - begin, 20);
+
+    // The skipped hour is taken to be in the old offset.
+    assert_eq!(ts("2024-03-10 02:30:00", None), ts("2024-03-10 03:30:00", None));
+
+    // Fall back: the repeated hour uses the earlier mapping...
+    assert_eq!(ts("2024-11-03 01:30:00", None), Some(utc("2024-11-03 05:30:00")));
+
+    // ...unless the log has already moved past it, so a fight from 1:59:50 to the second 1:00:10 is 20 seconds long.
+    let begin = ts("2024-11-03 01:59:50", None).unwrap();
+    let end = ts("2024-11-03 01:00:10", Some(begin)).unwrap();
+    assert_eq!(end - begin, 20);
+
+    // Times within the first pass stay in it.
+    let begin = ts("2024-11-03 01:10:00", None).unwrap();
+    let end = ts("2024-11-03 01:20:00", Some(begin)).unwrap();
+    assert_eq!(end - begin, 600);
+  }
 
   #[test]
   fn test_replace_decimal() {
@@ -396,12 +1016,125 @@ mod tests {
     assert_eq!(".4", replace_decimal("\u{66b}4"));
   }
 
+  #[test]
+  fn test_get_level_info() {
+    // Level 1.
+    assert_eq!(get_level_info(0), (1, Some(1000)));
+    assert_eq!(get_level_info(-1), (1, Some(1000)));
+
+    // Level 2 starts at 1000.
+    assert_eq!(get_level_info(999), (1, Some(1)));
+    assert_eq!(get_level_info(1000), (2, Some(1100)));
+    assert_eq!(get_level_info(1001), (2, Some(1099)));
+
+    // Transition from 199 to 200.
+    let exp = LEVEL_EXP[199];
+    assert_eq!(get_level_info(LEVEL_EXP[198]), (199, Some(exp - LEVEL_EXP[198])));
+    assert_eq!(get_level_info(exp - 1), (199, Some(1)));
+    assert_eq!(get_level_info(exp), (200, None));
+    assert_eq!(get_level_info(exp + 1), (200, None));
+    assert_eq!(get_level_info(i64::MAX), (200, None));
+  }
+
+  #[test]
+  fn test_format_f64() {
+    // English style.
+    let locale = Locale::en;
+    assert_eq!("12,345.6", format_f64(12345.6, 6, &locale));
+    assert_eq!("1,234,567.89", format_f64(1234567.891, 2, &locale));
+    assert_eq!("100", format_f64(100.0, 2, &locale));
+    assert_eq!("100", format_f64(100.0, 0, &locale));
+    assert_eq!("0.5", format_f64(0.5, 2, &locale));
+    assert_eq!("-12,345.6", format_f64(-12345.6, 2, &locale));
+    assert_eq!("0", format_f64(-0.001, 2, &locale));
+
+    // German style.
+    let locale = Locale::de;
+    assert_eq!("12.345,6", format_f64(12345.6, 6, &locale));
+    assert_eq!("1.234.567,89", format_f64(1234567.891, 2, &locale));
+    assert_eq!("0,5", format_f64(0.5, 2, &locale));
+
+    // French style.
+    let locale = Locale::fr;
+    let sep = locale.separator();
+    assert_eq!(format!("12{sep}345,6"), format_f64(12345.6, 6, &locale));
+    assert_eq!(format!("1{sep}234{sep}567,89"), format_f64(1234567.891, 2, &locale));
+    assert_eq!("999,25", format_f64(999.25, 2, &locale));
+  }
+
   #[test]
   fn test_remove_separators() {
     assert_eq!("123456789", remove_separators("123,456,789"));
     assert_eq!("123456789", remove_separators("123.456.789"));
     assert_eq!("123456789", remove_separators("123'456'789"));
     assert_eq!("123456789", remove_separators("123\u{a0}456\u{a0}789"));
+    assert_eq!("123456789", remove_separators("123\u{202f}456 789"));
+    assert_eq!("123456789", remove_separators("123\u{66c}456\u{66c}789"));
+  }
+
+  #[test]
+  fn test_parse_grouped_int() {
+    assert_eq!(parse_grouped_int("1,234,567"), Some(1234567));
+    assert_eq!(parse_grouped_int("1.234.567"), Some(1234567));
+    assert_eq!(parse_grouped_int(" 1\u{a0}234\u{a0}567"), Some(1234567));
+    assert_eq!(parse_grouped_int("1,234"), Some(1234));
+    assert_eq!(parse_grouped_int("12a"), None);
+  }
+
+  #[test]
+  fn test_parse_decimal() {
+    // English.
+    assert_eq!(parse_decimal("30.5", None), Some(30.5));
+    assert_eq!(parse_decimal("1,234.5", None), Some(1234.5));
+    assert_eq!(parse_decimal("1,234,567", None), Some(1234567.0));
+    assert_eq!(parse_decimal("-12.25", None), Some(-12.25));
+
+    // German.
+    assert_eq!(parse_decimal("30,5", None), Some(30.5));
+    assert_eq!(parse_decimal("1.234,5", None), Some(1234.5));
+    assert_eq!(parse_decimal("1.234.567", None), Some(1234567.0));
+    assert_eq!(parse_decimal("1.234.567,89", None), Some(1234567.89));
+
+    // French and Swiss.
+    assert_eq!(parse_decimal("1\u{a0}234,56", None), Some(1234.56));
+    assert_eq!(parse_decimal("1\u{202f}234\u{202f}567,5", None), Some(1234567.5));
+    assert_eq!(parse_decimal("1 234,56", None), Some(1234.56));
+    assert_eq!(parse_decimal("1'234.56", None), Some(1234.56));
+
+    // Arabic.
+    assert_eq!(parse_decimal("1\u{66c}234\u{66b}5", None), Some(1234.5));
+
+    // A lone separator followed by three digits groups them unless the decimal separator is known.
+    assert_eq!(parse_decimal("1,234", None), Some(1234.0));
+    assert_eq!(parse_decimal("1,234", Some(',')), Some(1.234));
+    assert_eq!(parse_decimal("1,234", Some('.')), Some(1234.0));
+    assert_eq!(parse_decimal("0,234", None), Some(0.234));
+    assert_eq!(parse_decimal("123,", None), Some(123.0));
+    assert_eq!(parse_decimal(",4", None), Some(0.4));
+
+    // German integer with grouping.
+    assert_eq!(parse_decimal("1.234", Some(',')), Some(1234.0));
+    assert_eq!(parse_decimal("-12.345", Some(',')), Some(-12345.0));
+    assert_eq!(parse_decimal("1.234,5", Some(',')), Some(1234.5));
+
+    // Misplaced separators.
+    assert_eq!(parse_decimal("1,,234.5", None), None);
+    assert_eq!(parse_decimal(",1,234", None), None);
+    assert_eq!(parse_decimal("1.5,234,567", None), None);
+    assert_eq!(parse_decimal("abc", None), None);
+    assert_eq!(parse_decimal("", None), None);
+  }
+
+  #[test]
+  fn test_find_decimal_separator() {
+    let find = |text: &str| find_decimal_separator(text.split_whitespace());
+    assert_eq!(find("Level: 100 Strength: 30.5"), Some('.'));
+    assert_eq!(find("Level: 100 Strength: 30,5"), Some(','));
+    assert_eq!(find("Damage: 1,234 Health: 1,234,567"), Some('.'));
+    assert_eq!(find("Damage: 1.234 Health: 1.234.567"), Some(','));
+    assert_eq!(find("Damage: 1.234 Health: 12,5"), Some(','));
+    assert_eq!(find("Damage: 1,234.5"), Some('.'));
+    assert_eq!(find("Damage: 1,234 Health: 7"), None);
   }
 
   #[test]
@@ -429,4 +1162,59 @@ mod tests {
     let result = find_ignore_case(text, "abc");
     assert_eq!(result, Some(8..11));
   }
+
+  #[test]
+  fn test_countdown_text() {
+    assert_eq!(get_countdown_text("", 5), "05s");
+    assert_eq!(get_countdown_text("Opens: ", 65), "Opens: 01m 05s");
+    assert_eq!(get_countdown_text("", 3600 + 61), "01h 01m 01s");
+
+    // A full day still uses hours.
+    assert_eq!(get_countdown_text("", 24 * 3600), "24h 00m 00s");
+
+    // Longer than a day uses days and drops the seconds.
+    assert_eq!(get_countdown_text("", 24 * 3600 + 1), "1d 00h 00m");
+    assert_eq!(get_countdown_text("", 14 * 24 * 3600 - 1), "13d 23h 59m");
+
+    // Negative values are clamped.
+    assert_eq!(get_countdown_text("Closes: ", -30), "Closes: 00s");
+  }
+
+  #[test]
+  fn test_progress() {
+    let state = AppState::default();
+    let first = state.begin_progress("Scanning logs", 4);
+    first.step();
+    first.set_done(2);
+    let progress = state.progress().unwrap();
+    assert_eq!(progress.text(), "Scanning logs 2/4...");
+    assert_eq!(progress.fraction(), 0.5);
+
+    // A newer task takes over, and the older one can't update or clear it.
+    let second = state.begin_progress("Searching logs", 10);
+    first.step();
+    drop(first);
+    assert_eq!(state.progress().unwrap().text(), "Searching logs 0/10...");
+
+    drop(second);
+    assert!(state.progress().is_none());
+  }
+
+  #[test]
+  fn test_expanded_groups() {
+    let mut expanded = ExpandedGroups::default();
+    let names = ["Magic", "Removed"].map(String::from).into();
+    assert!(expanded.load(names, ["Magic", "Melee"]));
+    assert_eq!(expanded.open("Magic"), Some(true));
+    assert_eq!(expanded.open("Melee"), Some(false));
+
+    expanded.restored();
+    assert_eq!(expanded.open("Magic"), None);
+
+    expanded.toggle("Melee");
+    assert!(expanded.take_changed());
+    assert!(!expanded.take_changed());
+    assert!(expanded.names().contains("Melee"));
+    assert!(!expanded.names().contains("Removed"));
+  }
 }
@@ -0,0 +1,108 @@
+use crate::{game_data::VirtueLvl, util};
+use eframe::{
+  egui::{Context, DragValue, Key, Layout, RichText, Window},
+  emath::{Align, Align2},
+  epaint::Color32,
+};
+use egui_extras::{Column, TableBuilder};
+use std::ops::RangeInclusive;
+use util::AppState;
+
+pub struct VirtuesDlg {
+  state: AppState,
+  visible: bool,
+}
+
+impl VirtuesDlg {
+  pub fn new(state: AppState) -> Self {
+    Self { state, visible: false }
+  }
+
+  pub fn show(&mut self, virtues: &mut [VirtueLvl], ctx: &Context) -> bool {
+    let mut modified = false;
+    if self.visible {
+      self.handle_hotkeys(ctx);
+
+      let mut open = true;
+      Window::new(RichText::from("☯  Virtues").strong())
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .current_pos([0.0, 24.0])
+        .anchor(Align2::CENTER_TOP, [0.0, 0.0])
+        .show(ctx, |ui| {
+          let spacing = ui.spacing().item_spacing;
+          let row_size = util::button_size(ui) + spacing[1] * 2.0;
+          TableBuilder::new(ui)
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .striped(true)
+            .column(Column::exact(120.0))
+            .column(Column::exact(80.0))
+            .header(row_size, |mut header| {
+              const HEADER_COLOR: Color32 = Color32::from_rgb(229, 187, 123);
+              header.col(|ui| {
+                ui.label(RichText::from("Virtue").color(HEADER_COLOR));
+              });
+              header.col(|ui| {
+                ui.label(RichText::from("Value").color(HEADER_COLOR));
+              });
+            })
+            .body(|mut body| {
+              const NAME_COLOR: Color32 = Color32::from_rgb(154, 187, 154);
+              for virtue in virtues {
+                body.row(row_size, |mut row| {
+                  row.col(|ui| {
+                    ui.label(RichText::from(&virtue.name).color(NAME_COLOR));
+                  });
+                  row.col(|ui| {
+                    // Don't clamp values that are already outside of the range.
+                    let widget = DragValue::new(&mut virtue.value)
+                      .speed(1.0)
+                      .range(VIRTUE_RANGE)
+                      .clamp_existing_to_range(false);
+                    if ui.add(widget).changed() {
+                      modified = true;
+                    }
+                  });
+                });
+              }
+            });
+
+          ui.add_space(ui.spacing().item_spacing.y);
+          ui.separator();
+          ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+              self.close();
+            }
+          });
+        });
+      if !open {
+        self.close();
+      }
+    }
+    modified
+  }
+
+  pub fn open(&mut self) {
+    if !self.visible {
+      self.state.set_disabled(true);
+      self.visible = true;
+    }
+  }
+
+  pub fn close(&mut self) {
+    if self.visible {
+      self.state.set_disabled(false);
+      self.visible = false;
+    }
+  }
+
+  fn handle_hotkeys(&mut self, ctx: &Context) {
+    if ctx.input(|state| state.key_pressed(Key::Escape)) {
+      self.close();
+    }
+  }
+}
+
+/// Allowed range when editing virtue values.
+const VIRTUE_RANGE: RangeInclusive<f64> = -1000.0..=1000.0;